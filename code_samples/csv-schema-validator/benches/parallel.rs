@@ -0,0 +1,89 @@
+//! Sequential vs parallel validation of a generated file.
+//!
+//! ```shell
+//! cargo bench --features parallel --bench parallel [rows]
+//! ```
+
+use std::env;
+use std::fmt::Write as _;
+use std::time::Instant;
+
+use csv::ReaderBuilder;
+use csv_schema_validator::parallel::ParallelValidator;
+use csv_schema_validator::{validate_reader, ValidateCsv};
+use serde::Deserialize;
+
+#[allow(dead_code)]
+#[derive(Deserialize, ValidateCsv, Debug)]
+struct Order {
+    #[validate(uuid)]
+    id: String,
+    #[validate(email)]
+    email: String,
+    #[validate(range(min = 0.0, max = 20000.0))]
+    amount: f64,
+    #[validate(decimal(precision = 10, scale = 2))]
+    price: String,
+    #[validate(date)]
+    created: String,
+    #[validate(one_of("new", "paid", "shipped"))]
+    status: String,
+    #[validate(length(max = 200), not_blank)]
+    notes: String,
+}
+
+fn generate(rows: usize) -> Vec<u8> {
+    let mut data = String::from("id,email,amount,price,created,status,notes\n");
+    for i in 0..rows {
+        let status = ["new", "paid", "shipped", "lost"][i % 4];
+        let _ = writeln!(
+            data,
+            "{:08x}-0000-4000-8000-{:012x},user{}@example.com,{}.5,{}.{:02},2024-{:02}-{:02},{},\"line one\nline \"\"two\"\", {}\"",
+            i,
+            i,
+            i,
+            i % 20000,
+            i % 100000,
+            i % 100,
+            i % 12 + 1,
+            i % 28 + 1,
+            status,
+            i
+        );
+    }
+    data.into_bytes()
+}
+
+fn main() {
+    let rows = env::args()
+        .skip(1)
+        .find_map(|a| a.parse().ok())
+        .unwrap_or(500_000);
+    let data = generate(rows);
+    println!(
+        "{} rows, {:.1} MiB",
+        rows,
+        data.len() as f64 / (1 << 20) as f64
+    );
+
+    let start = Instant::now();
+    let sequential = validate_reader::<_, Order>(&mut ReaderBuilder::new().from_reader(&data[..]));
+    let sequential_time = start.elapsed();
+    println!("sequential: {:?}", sequential_time);
+
+    let start = Instant::now();
+    let parallel = ParallelValidator::new().validate_reader::<_, Order>(&data[..]);
+    let parallel_time = start.elapsed();
+    println!(
+        "parallel ({} threads): {:?}",
+        rayon::current_num_threads(),
+        parallel_time
+    );
+
+    assert_eq!(sequential, parallel);
+    println!(
+        "{} errors, speedup {:.2}x",
+        parallel.errors.len(),
+        sequential_time.as_secs_f64() / parallel_time.as_secs_f64()
+    );
+}
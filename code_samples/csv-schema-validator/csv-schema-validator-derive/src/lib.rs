@@ -1,8 +1,11 @@
 // csv-schema-validator-derive/src/lib.rs
 extern crate proc_macro;
 
+mod record;
+
 use proc_macro::TokenStream;
 use quote::quote;
+use record::{DatasetKey, RecordField, RecordValidation};
 use syn::{
     parse_macro_input, Data, DeriveInput, Expr, ExprLit, Fields, GenericArgument, Ident, Lit, Meta,
     PathArguments, Type,
@@ -25,6 +28,18 @@ enum Validation {
     NotBlank,
     OneOf { values: Vec<String> },
     NotIn { values: Vec<String> },    
+    // validadores tipados; valores String ou Option<String>
+    Date { format: String, min: Option<String>, max: Option<String> },
+    Email,
+    Url,
+    Uuid,
+    Cpf,
+    Cnpj,
+    Decimal { precision: u32, scale: u32 },
+    Ip,
+    // restrições de conjunto de dados, verificadas pelo DatasetValidator
+    Unique,
+    References { name: String },
 }
 
 impl Validation {
@@ -41,6 +56,26 @@ impl Validation {
                         validations.push(Validation::Required);
                     } else if path.is_ident("not_blank") {
                                validations.push(Validation::NotBlank);
+                    } else if path.is_ident("unique") {
+                        validations.push(Validation::Unique);
+                    } else if path.is_ident("date") {
+                        validations.push(Validation::Date {
+                            format: DEFAULT_DATE_FORMAT.to_string(),
+                            min: None,
+                            max: None,
+                        });
+                    } else if path.is_ident("email") {
+                        validations.push(Validation::Email);
+                    } else if path.is_ident("url") {
+                        validations.push(Validation::Url);
+                    } else if path.is_ident("uuid") {
+                        validations.push(Validation::Uuid);
+                    } else if path.is_ident("cpf") {
+                        validations.push(Validation::Cpf);
+                    } else if path.is_ident("cnpj") {
+                        validations.push(Validation::Cnpj);
+                    } else if path.is_ident("ip") {
+                        validations.push(Validation::Ip);
                     }
                 }
                 Meta::NameValue(mnv) => {
@@ -53,6 +88,15 @@ impl Validation {
                                 "Expected string literal for `regex`",
                             ));
                         }
+                    } else if mnv.path.is_ident("references") {
+                        if let Expr::Lit(ExprLit { lit: Lit::Str(s), .. }) = mnv.value {
+                            validations.push(Validation::References { name: s.value() });
+                        } else {
+                            return Err(syn::Error::new_spanned(
+                                mnv.value,
+                                "Expected string literal for `references`",
+                            ));
+                        }
                     } else if mnv.path.is_ident("custom") {
                         if let Expr::Lit(ExprLit { lit: Lit::Str(s), .. }) = mnv.value {
                             let path: syn::Path =
@@ -136,8 +180,8 @@ impl Validation {
 
                         for kv in items {
                             if kv.path.is_ident("min") {
-                                if let Expr::Lit(ExprLit { lit: Lit::Float(f), .. }) = kv.value {
-                                    min = Some(f.base10_parse::<f64>()?);
+                                if let Some(f) = float_literal(&kv.value)? {
+                                    min = Some(f);
                                 } else {
                                     return Err(syn::Error::new_spanned(
                                         kv.value,
@@ -145,8 +189,8 @@ impl Validation {
                                     ));
                                 }
                             } else if kv.path.is_ident("max") {
-                                if let Expr::Lit(ExprLit { lit: Lit::Float(f), .. }) = kv.value {
-                                    max = Some(f.base10_parse::<f64>()?);
+                                if let Some(f) = float_literal(&kv.value)? {
+                                    max = Some(f);
                                 } else {
                                     return Err(syn::Error::new_spanned(
                                         kv.value,
@@ -213,6 +257,59 @@ impl Validation {
                         }
                         validations.push(Validation::NotIn { values });
                     }
+                    // date(format = "%d/%m/%Y", min = "01/01/2000", max = "31/12/2099")
+                    else if meta_list.path.is_ident("date") {
+                        validations.push(parse_date(&meta_list)?);
+                    }
+                    // decimal(precision = 10, scale = 2)
+                    else if meta_list.path.is_ident("decimal") {
+                        let mut precision: Option<u32> = None;
+                        let mut scale: Option<u32> = None;
+
+                        let items: syn::punctuated::Punctuated<syn::MetaNameValue, syn::Token![,]> =
+                            meta_list.parse_args_with(
+                                syn::punctuated::Punctuated::parse_terminated,
+                            )?;
+
+                        for kv in items {
+                            let target = if kv.path.is_ident("precision") {
+                                &mut precision
+                            } else if kv.path.is_ident("scale") {
+                                &mut scale
+                            } else {
+                                return Err(syn::Error::new_spanned(
+                                    kv.path,
+                                    "`decimal` only accepts `precision` and `scale`",
+                                ));
+                            };
+                            if let Expr::Lit(ExprLit { lit: Lit::Int(i), .. }) = kv.value {
+                                *target = Some(i.base10_parse::<u32>()?);
+                            } else {
+                                return Err(syn::Error::new_spanned(
+                                    kv.value,
+                                    "`precision` and `scale` for `decimal` must be integer literals",
+                                ));
+                            }
+                        }
+
+                        let precision = match precision {
+                            Some(p) if p > 0 => p,
+                            _ => {
+                                return Err(syn::Error::new_spanned(
+                                    meta_list,
+                                    "`decimal` requires a `precision` greater than zero",
+                                ));
+                            }
+                        };
+                        let scale = scale.unwrap_or(0);
+                        if scale > precision {
+                            return Err(syn::Error::new_spanned(
+                                meta_list,
+                                "`scale` must be <= `precision` for `decimal`",
+                            ));
+                        }
+                        validations.push(Validation::Decimal { precision, scale });
+                    }
 
                 }
             }
@@ -222,6 +319,154 @@ impl Validation {
     }
 }
 
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+// date(format = "...", min = "...", max = "..."): formato e limites são
+// conferidos aqui, para que erros apareçam na compilação
+fn parse_date(meta_list: &syn::MetaList) -> syn::Result<Validation> {
+    use chrono::format::{Item, StrftimeItems};
+    use chrono::NaiveDate;
+
+    let mut format: Option<syn::LitStr> = None;
+    let mut min: Option<syn::LitStr> = None;
+    let mut max: Option<syn::LitStr> = None;
+
+    let items: syn::punctuated::Punctuated<syn::MetaNameValue, syn::Token![,]> =
+        meta_list.parse_args_with(syn::punctuated::Punctuated::parse_terminated)?;
+
+    for kv in items {
+        let target = if kv.path.is_ident("format") {
+            &mut format
+        } else if kv.path.is_ident("min") {
+            &mut min
+        } else if kv.path.is_ident("max") {
+            &mut max
+        } else {
+            return Err(syn::Error::new_spanned(
+                kv.path,
+                "`date` only accepts `format`, `min` and `max`",
+            ));
+        };
+        if let Expr::Lit(ExprLit { lit: Lit::Str(s), .. }) = kv.value {
+            *target = Some(s);
+        } else {
+            return Err(syn::Error::new_spanned(
+                kv.value,
+                "`format`, `min` and `max` for `date` must be string literals",
+            ));
+        }
+    }
+
+    let format_str = format
+        .as_ref()
+        .map_or_else(|| DEFAULT_DATE_FORMAT.to_string(), |f| f.value());
+    if StrftimeItems::new(&format_str).any(|item| matches!(item, Item::Error)) {
+        return Err(syn::Error::new_spanned(
+            format.as_ref().map_or_else(|| quote!(#meta_list), |f| quote!(#f)),
+            "invalid `format` for `date`",
+        ));
+    }
+
+    let mut bounds = Vec::new();
+    for lit in [&min, &max] {
+        bounds.push(match lit {
+            Some(lit) => match NaiveDate::parse_from_str(&lit.value(), &format_str) {
+                Ok(date) => Some((lit.value(), date)),
+                Err(e) => {
+                    return Err(syn::Error::new_spanned(
+                        lit,
+                        format!("`date` bound does not match the format {}: {}", format_str, e),
+                    ));
+                }
+            },
+            None => None,
+        });
+    }
+    if let (Some((_, a)), Some((_, b))) = (&bounds[0], &bounds[1]) {
+        if a > b {
+            return Err(syn::Error::new_spanned(
+                meta_list,
+                "`min` must be <= `max` for `date`",
+            ));
+        }
+    }
+
+    Ok(Validation::Date {
+        format: format_str,
+        min: bounds[0].take().map(|(s, _)| s),
+        max: bounds[1].take().map(|(s, _)| s),
+    })
+}
+
+// liga `value` ao campo (só quando Some para Option<T>) antes de `body`
+fn with_value(
+    field: &Ident,
+    is_option: bool,
+    body: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    if is_option {
+        quote! {
+            if let Some(value) = &self.#field {
+                #body
+            }
+        }
+    } else {
+        quote! {
+            let value = &self.#field;
+            #body
+        }
+    }
+}
+
+// validadores baseados em um padrão fixo, compilado uma única vez
+fn builtin_pattern(
+    field: &Ident,
+    field_name_str: &str,
+    is_option: bool,
+    pattern: proc_macro2::TokenStream,
+    code: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    with_value(
+        field,
+        is_option,
+        quote! {
+            use ::csv_schema_validator::__private::once_cell::sync::Lazy;
+            use ::csv_schema_validator::__private::regex;
+            static RE: Lazy<regex::Regex> =
+                Lazy::new(|| regex::Regex::new(#pattern).expect("built-in pattern"));
+
+            if !RE.is_match(value) {
+                errors.push(::csv_schema_validator::ValidationError::new(
+                    #field_name_str,
+                    ::csv_schema_validator::ErrorCode::#code { value: value.to_string() },
+                ));
+            }
+        },
+    )
+}
+
+// validadores baseados em uma função de `csv_schema_validator::validators`
+fn builtin_check(
+    field: &Ident,
+    field_name_str: &str,
+    is_option: bool,
+    check: proc_macro2::TokenStream,
+    code: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    with_value(
+        field,
+        is_option,
+        quote! {
+            if !::csv_schema_validator::validators::#check {
+                errors.push(::csv_schema_validator::ValidationError::new(
+                    #field_name_str,
+                    ::csv_schema_validator::ErrorCode::#code,
+                ));
+            }
+        },
+    )
+}
+
 // [FIX] helper para detectar Option<T>
 fn option_inner_type(ty: &Type) -> Option<&Type> {
     if let Type::Path(tp) = ty {
@@ -238,6 +483,70 @@ fn option_inner_type(ty: &Type) -> Option<&Type> {
     None
 }
 
+// literal float, opcionalmente negativo (`-5.0` é um Expr::Unary)
+fn float_literal(expr: &Expr) -> syn::Result<Option<f64>> {
+    match expr {
+        Expr::Lit(ExprLit { lit: Lit::Float(f), .. }) => Ok(Some(f.base10_parse::<f64>()?)),
+        Expr::Unary(syn::ExprUnary { op: syn::UnOp::Neg(_), expr, .. }) => {
+            Ok(float_literal(expr)?.map(|f| -f))
+        }
+        _ => Ok(None),
+    }
+}
+
+// quote! não aceita f64 infinito; range sem min/max usa ±INFINITY
+fn f64_tokens(value: f64) -> proc_macro2::TokenStream {
+    if value == f64::INFINITY {
+        quote!(::core::f64::INFINITY)
+    } else if value == f64::NEG_INFINITY {
+        quote!(::core::f64::NEG_INFINITY)
+    } else {
+        quote!(#value)
+    }
+}
+
+// Nome da coluna no CSV: respeita #[serde(rename = "...")] quando presente
+fn serde_column_name(field: &syn::Field) -> String {
+    for attr in &field.attrs {
+        if !attr.path().is_ident("serde") {
+            continue;
+        }
+        let items = match attr.parse_args_with(
+            syn::punctuated::Punctuated::<Meta, syn::Token![,]>::parse_terminated,
+        ) {
+            Ok(items) => items,
+            Err(_) => continue,
+        };
+        for meta in items {
+            match meta {
+                Meta::NameValue(mnv) if mnv.path.is_ident("rename") => {
+                    if let Expr::Lit(ExprLit { lit: Lit::Str(s), .. }) = mnv.value {
+                        return s.value();
+                    }
+                }
+                // rename(deserialize = "...")
+                Meta::List(list) if list.path.is_ident("rename") => {
+                    let inner = match list.parse_args_with(
+                        syn::punctuated::Punctuated::<syn::MetaNameValue, syn::Token![,]>::parse_terminated,
+                    ) {
+                        Ok(inner) => inner,
+                        Err(_) => continue,
+                    };
+                    for kv in inner {
+                        if kv.path.is_ident("deserialize") {
+                            if let Expr::Lit(ExprLit { lit: Lit::Str(s), .. }) = kv.value {
+                                return s.value();
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    field.ident.as_ref().unwrap().to_string()
+}
+
 #[proc_macro_derive(ValidateCsv, attributes(validate))]
 pub fn validate_csv_derive(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
@@ -263,10 +572,18 @@ pub fn validate_csv_derive(input: TokenStream) -> TokenStream {
     };
 
     let mut field_validations = Vec::new();
+    let mut csv_columns = Vec::new();
+    let mut record_fields = Vec::new();
+    let mut dataset_keys = Vec::new();
 
     for field in fields {
         let field_name = field.ident.as_ref().unwrap().clone();
+        csv_columns.push((field_name.to_string(), serde_column_name(field)));
         let is_option = option_inner_type(&field.ty).is_some(); // [FIX] capturamos se é Option<T>
+        record_fields.push(RecordField {
+            name: field_name.clone(),
+            is_option,
+        });
         let mut validations = Vec::new();
 
         for attr in &field.attrs {
@@ -278,6 +595,19 @@ pub fn validate_csv_derive(input: TokenStream) -> TokenStream {
             }
         }
 
+        for validation in &validations {
+            match validation {
+                Validation::Unique => dataset_keys.push(DatasetKey::Unique {
+                    fields: vec![field_name.clone()],
+                }),
+                Validation::References { name } => dataset_keys.push(DatasetKey::ForeignKey {
+                    field: field_name.clone(),
+                    references: name.clone(),
+                }),
+                _ => {}
+            }
+        }
+
         if !validations.is_empty() {
             field_validations.push(FieldValidation {
                 field_name,
@@ -287,6 +617,30 @@ pub fn validate_csv_derive(input: TokenStream) -> TokenStream {
         }
     }
 
+    // validações de registro (#[validate(...)] na própria struct)
+    let mut record_checks = Vec::new();
+    for attr in &input.attrs {
+        if attr.path().is_ident("validate") {
+            let validations = match attr.parse_args_with(RecordValidation::parse_validations) {
+                Ok(v) => v,
+                Err(e) => return e.to_compile_error().into(),
+            };
+            for validation in validations {
+                if let RecordValidation::PrimaryKey { fields } = &validation {
+                    match record::primary_key(fields, &record_fields) {
+                        Ok(key) => dataset_keys.push(key),
+                        Err(e) => return e.to_compile_error().into(),
+                    }
+                    continue;
+                }
+                match validation.expand(name, &record_fields) {
+                    Ok(tokens) => record_checks.push(tokens),
+                    Err(e) => return e.to_compile_error().into(),
+                }
+            }
+        }
+    }
+
     let validation_arms = field_validations.into_iter().map(|fv| {
         let field_name_str = fv.field_name.to_string();
         let field_name_ident = fv.field_name;
@@ -297,10 +651,10 @@ pub fn validate_csv_derive(input: TokenStream) -> TokenStream {
                 // mantém comportamento: se None => erro
                 quote! {
                     if (&self.#field_name_ident).is_none() {
-                        errors.push(::csv_schema_validator::ValidationError {
-                            field: #field_name_str.to_string(),
-                            message: "mandatory field".to_string(),
-                        });
+                        errors.push(::csv_schema_validator::ValidationError::new(
+                            #field_name_str,
+                            ::csv_schema_validator::ErrorCode::Required,
+                        ));
                     }
                 }
             }
@@ -309,10 +663,10 @@ pub fn validate_csv_derive(input: TokenStream) -> TokenStream {
                     quote! {
                         if let Some(value) = &self.#field_name_ident {
                             if value.trim().is_empty() {
-                                errors.push(::csv_schema_validator::ValidationError {
-                                    field: #field_name_str.to_string(),
-                                    message: "must not be blank or contain only whitespace".to_string(),
-                                });
+                                errors.push(::csv_schema_validator::ValidationError::new(
+                                    #field_name_str,
+                                    ::csv_schema_validator::ErrorCode::Blank { value: value.to_string() },
+                                ));
                             }
                         }
                     }
@@ -320,34 +674,37 @@ pub fn validate_csv_derive(input: TokenStream) -> TokenStream {
                     quote! {
                         let value = &self.#field_name_ident;
                         if value.trim().is_empty() {
-                            errors.push(::csv_schema_validator::ValidationError {
-                                field: #field_name_str.to_string(),
-                                message: "must not be blank or contain only whitespace".to_string(),
-                            });
+                            errors.push(::csv_schema_validator::ValidationError::new(
+                                #field_name_str,
+                                ::csv_schema_validator::ErrorCode::Blank { value: value.to_string() },
+                            ));
                         }
                     }
                 }
             }
             Validation::Range { min, max } => {
+                let (min, max) = (f64_tokens(min), f64_tokens(max));
                 if fv_is_option {
                     quote! {
                         if let Some(value) = &self.#field_name_ident {
-                            if !(#min <= *value && *value <= #max) {
-                                errors.push(::csv_schema_validator::ValidationError {
-                                    field: #field_name_str.to_string(),
-                                    message: format!("value out of expected range: {} to {}", #min, #max),
-                                });
+                            // aceita qualquer tipo numérico primitivo
+                            let value = *value as f64;
+                            if !(#min <= value && value <= #max) {
+                                errors.push(::csv_schema_validator::ValidationError::new(
+                                    #field_name_str,
+                                    ::csv_schema_validator::ErrorCode::range(value, #min, #max),
+                                ));
                             }
                         }
                     }
                 } else {
                     quote! {
-                        let value = &self.#field_name_ident;
-                        if !(#min <= *value && *value <= #max) {
-                            errors.push(::csv_schema_validator::ValidationError {
-                                field: #field_name_str.to_string(),
-                                message: format!("value out of expected range: {} to {}", #min, #max),
-                            });
+                        let value = self.#field_name_ident as f64;
+                        if !(#min <= value && value <= #max) {
+                            errors.push(::csv_schema_validator::ValidationError::new(
+                                #field_name_str,
+                                ::csv_schema_validator::ErrorCode::range(value, #min, #max),
+                            ));
                         }
                     }
                 }
@@ -359,10 +716,10 @@ pub fn validate_csv_derive(input: TokenStream) -> TokenStream {
                         if let Some(value) = &self.#field_name_ident {
                             let len = value.len();
                             if len < #min || len > #max {
-                                errors.push(::csv_schema_validator::ValidationError {
-                                    field: #field_name_str.to_string(),
-                                    message: format!("length out of expected range: {} to {}", #min, #max),
-                                });
+                                errors.push(::csv_schema_validator::ValidationError::new(
+                                    #field_name_str,
+                                    ::csv_schema_validator::ErrorCode::length(value, #min, #max),
+                                ));
                             }
                         }
                     }
@@ -371,10 +728,10 @@ pub fn validate_csv_derive(input: TokenStream) -> TokenStream {
                         let value = &self.#field_name_ident;
                         let len = value.len();
                         if len < #min || len > #max {
-                            errors.push(::csv_schema_validator::ValidationError {
-                                field: #field_name_str.to_string(),
-                                message: format!("length out of expected range: {} to {}", #min, #max),
-                            });
+                            errors.push(::csv_schema_validator::ValidationError::new(
+                                #field_name_str,
+                                ::csv_schema_validator::ErrorCode::length(value, #min, #max),
+                            ));
                         }
                     }
                 }
@@ -389,17 +746,23 @@ pub fn validate_csv_derive(input: TokenStream) -> TokenStream {
                     match RE.as_ref() {
                         Ok(compiled_regex) => {
                             if !compiled_regex.is_match(value) {
-                                errors.push(::csv_schema_validator::ValidationError {
-                                    field: #field_name_str.to_string(),
-                                    message: "does not match the expected pattern".to_string(),
-                                });
+                                errors.push(::csv_schema_validator::ValidationError::new(
+                                    #field_name_str,
+                                    ::csv_schema_validator::ErrorCode::RegexMismatch {
+                                        value: value.to_string(),
+                                        pattern: #regex.to_string(),
+                                    },
+                                ));
                             }
                         }
                         Err(e) => {
-                            errors.push(::csv_schema_validator::ValidationError {
-                                field: #field_name_str.to_string(),
-                                message: format!("invalid regex '{}': {}", #regex, e),
-                            });
+                            errors.push(::csv_schema_validator::ValidationError::new(
+                                #field_name_str,
+                                ::csv_schema_validator::ErrorCode::InvalidRegex {
+                                    pattern: #regex.to_string(),
+                                    error: e.to_string(),
+                                },
+                            ));
                         }
                     }
                 };
@@ -424,10 +787,13 @@ pub fn validate_csv_derive(input: TokenStream) -> TokenStream {
                         if let Some(value) = &self.#field_name_ident {
                             const __ALLOWED: &[&str] = &[#(#arr),*];
                             if !__ALLOWED.contains(&value.as_str()) {
-                                errors.push(::csv_schema_validator::ValidationError {
-                                    field: #field_name_str.to_string(),
-                                    message: format!("invalid value"),
-                                });
+                                errors.push(::csv_schema_validator::ValidationError::new(
+                                    #field_name_str,
+                                    ::csv_schema_validator::ErrorCode::NotInSet {
+                                        value: value.to_string(),
+                                        allowed: __ALLOWED.iter().map(|s| s.to_string()).collect(),
+                                    },
+                                ));
                             }
                         }
                     }
@@ -436,10 +802,13 @@ pub fn validate_csv_derive(input: TokenStream) -> TokenStream {
                         let value = &self.#field_name_ident;
                         const __ALLOWED: &[&str] = &[#(#arr),*];
                         if !__ALLOWED.contains(&value.as_str()) {
-                            errors.push(::csv_schema_validator::ValidationError {
-                                field: #field_name_str.to_string(),
-                                message: format!("invalid value"),
-                            });
+                            errors.push(::csv_schema_validator::ValidationError::new(
+                                #field_name_str,
+                                ::csv_schema_validator::ErrorCode::NotInSet {
+                                    value: value.to_string(),
+                                    allowed: __ALLOWED.iter().map(|s| s.to_string()).collect(),
+                                },
+                            ));
                         }
                     }
                 }
@@ -452,10 +821,13 @@ pub fn validate_csv_derive(input: TokenStream) -> TokenStream {
                         if let Some(value) = &self.#field_name_ident {
                             const __FORBIDDEN: &[&str] = &[#(#arr),*];
                             if __FORBIDDEN.contains(&value.as_str()) {
-                                errors.push(::csv_schema_validator::ValidationError {
-                                    field: #field_name_str.to_string(),
-                                    message: format!("value not allowed"),
-                                });
+                                errors.push(::csv_schema_validator::ValidationError::new(
+                                    #field_name_str,
+                                    ::csv_schema_validator::ErrorCode::ForbiddenValue {
+                                        value: value.to_string(),
+                                        forbidden: __FORBIDDEN.iter().map(|s| s.to_string()).collect(),
+                                    },
+                                ));
                             }
                         }
                     }
@@ -464,24 +836,102 @@ pub fn validate_csv_derive(input: TokenStream) -> TokenStream {
                         let value = &self.#field_name_ident;
                         const __FORBIDDEN: &[&str] = &[#(#arr),*];
                         if __FORBIDDEN.contains(&value.as_str()) {
-                            errors.push(::csv_schema_validator::ValidationError {
-                                field: #field_name_str.to_string(),
-                                message: format!("value not allowed"),
-                            });
+                            errors.push(::csv_schema_validator::ValidationError::new(
+                                #field_name_str,
+                                ::csv_schema_validator::ErrorCode::ForbiddenValue {
+                                    value: value.to_string(),
+                                    forbidden: __FORBIDDEN.iter().map(|s| s.to_string()).collect(),
+                                },
+                            ));
                         }
                     }
                 }
             }            
+            Validation::Date { format, min, max } => {
+                let bound = |b: &Option<String>| match b {
+                    Some(b) => quote!(::csv_schema_validator::validators::parse_date(#b, #format)),
+                    None => quote!(None),
+                };
+                let label = |b: &Option<String>| match b {
+                    Some(b) => quote!(Some(#b.to_string())),
+                    None => quote!(None),
+                };
+                let (min_date, max_date) = (bound(&min), bound(&max));
+                let (min_label, max_label) = (label(&min), label(&max));
+                with_value(&field_name_ident, fv_is_option, quote! {
+                    use ::csv_schema_validator::__private::chrono::NaiveDate;
+                    use ::csv_schema_validator::__private::once_cell::sync::Lazy;
+                    static BOUNDS: Lazy<(Option<NaiveDate>, Option<NaiveDate>)> =
+                        Lazy::new(|| (#min_date, #max_date));
+
+                    match ::csv_schema_validator::validators::parse_date(value, #format) {
+                        None => {
+                            errors.push(::csv_schema_validator::ValidationError::new(
+                                #field_name_str,
+                                ::csv_schema_validator::ErrorCode::InvalidDate {
+                                    value: value.to_string(),
+                                    format: #format.to_string(),
+                                },
+                            ));
+                        }
+                        Some(date) => {
+                            if matches!(BOUNDS.0, Some(min) if date < min)
+                                || matches!(BOUNDS.1, Some(max) if date > max)
+                            {
+                                errors.push(::csv_schema_validator::ValidationError::new(
+                                    #field_name_str,
+                                    ::csv_schema_validator::ErrorCode::DateOutOfRange {
+                                        value: value.to_string(),
+                                        min: #min_label,
+                                        max: #max_label,
+                                    },
+                                ));
+                            }
+                        }
+                    }
+                })
+            }
+            Validation::Email => builtin_pattern(
+                &field_name_ident, &field_name_str, fv_is_option,
+                quote!(::csv_schema_validator::validators::EMAIL_PATTERN), quote!(InvalidEmail),
+            ),
+            Validation::Url => builtin_pattern(
+                &field_name_ident, &field_name_str, fv_is_option,
+                quote!(::csv_schema_validator::validators::URL_PATTERN), quote!(InvalidUrl),
+            ),
+            Validation::Uuid => builtin_pattern(
+                &field_name_ident, &field_name_str, fv_is_option,
+                quote!(::csv_schema_validator::validators::UUID_PATTERN), quote!(InvalidUuid),
+            ),
+            Validation::Cpf => builtin_check(
+                &field_name_ident, &field_name_str, fv_is_option,
+                quote!(is_cpf(value)), quote!(InvalidCpf { value: value.to_string() }),
+            ),
+            Validation::Cnpj => builtin_check(
+                &field_name_ident, &field_name_str, fv_is_option,
+                quote!(is_cnpj(value)), quote!(InvalidCnpj { value: value.to_string() }),
+            ),
+            Validation::Ip => builtin_check(
+                &field_name_ident, &field_name_str, fv_is_option,
+                quote!(is_ip(value)), quote!(InvalidIp { value: value.to_string() }),
+            ),
+            Validation::Decimal { precision, scale } => builtin_check(
+                &field_name_ident, &field_name_str, fv_is_option,
+                quote!(is_decimal(value, #precision, #scale)),
+                quote!(InvalidDecimal { value: value.to_string(), precision: #precision, scale: #scale }),
+            ),
+            // não geram código aqui: veja DATASET_CONSTRAINTS abaixo
+            Validation::Unique | Validation::References { .. } => quote! {},
             Validation::Custom { path } => {
                 if fv_is_option {
                     quote! {
                         if let Some(value) = &self.#field_name_ident {
                             match #path(value) {
                                 Err(err) => {
-                                    errors.push(::csv_schema_validator::ValidationError {
-                                        field: #field_name_str.to_string(),
-                                        message: format!("{}", err),
-                                    });
+                                    errors.push(::csv_schema_validator::ValidationError::new(
+                                        #field_name_str,
+                                        ::csv_schema_validator::ErrorCode::Custom { message: format!("{}", err) },
+                                    ));
                                 }
                                 Ok(()) => {}
                             }
@@ -491,10 +941,10 @@ pub fn validate_csv_derive(input: TokenStream) -> TokenStream {
                     quote! {
                         match #path(&self.#field_name_ident) {
                             Err(err) => {
-                                errors.push(::csv_schema_validator::ValidationError {
-                                    field: #field_name_str.to_string(),
-                                    message: format!("{}", err),
-                                });
+                                errors.push(::csv_schema_validator::ValidationError::new(
+                                    #field_name_str,
+                                    ::csv_schema_validator::ErrorCode::Custom { message: format!("{}", err) },
+                                ));
                             }
                             Ok(()) => {}
                         }
@@ -503,16 +953,24 @@ pub fn validate_csv_derive(input: TokenStream) -> TokenStream {
             }
         });
 
+        // cada verificação em seu próprio bloco: `value` e os `static` não colidem
         quote! {
-            #(#checks)*
+            #({ #checks })*
         }
     });
 
+    let dataset_items = record::dataset_items(&dataset_keys, &record_fields);
+    let column_fields = csv_columns.iter().map(|(field, _)| field);
+    let column_names = csv_columns.iter().map(|(_, column)| column);
+
     let expanded = quote! {
         impl #name {
+            // as comparações de `range` e `length` ficam na forma explícita
+            #[allow(clippy::manual_range_contains)]
             pub fn validate_csv(&self) -> ::core::result::Result<(), ::std::vec::Vec<::csv_schema_validator::ValidationError>> {
                 let mut errors = ::std::vec::Vec::new();
                 #(#validation_arms)*
+                #(#record_checks)*
                 if errors.is_empty() {
                     Ok(())
                 } else {
@@ -520,6 +978,16 @@ pub fn validate_csv_derive(input: TokenStream) -> TokenStream {
                 }
             }
         }
+
+        impl ::csv_schema_validator::ValidateCsv for #name {
+            const CSV_COLUMNS: &'static [(&'static str, &'static str)] = &[#((#column_fields, #column_names)),*];
+
+            fn validate_csv(&self) -> ::core::result::Result<(), ::std::vec::Vec<::csv_schema_validator::ValidationError>> {
+                #name::validate_csv(self)
+            }
+
+            #dataset_items
+        }
     };
 
     TokenStream::from(expanded)
@@ -0,0 +1,433 @@
+// Validações de registro: #[validate(...)] aplicado na struct, envolvendo mais de um campo
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{Expr, ExprLit, Ident, Lit, LitStr, Meta};
+
+// Operadores aceitos em compare(op = "...")
+pub(crate) enum CompareOp {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+impl CompareOp {
+    fn parse(lit: &LitStr) -> syn::Result<Self> {
+        match lit.value().as_str() {
+            "<" => Ok(CompareOp::Lt),
+            "<=" => Ok(CompareOp::Le),
+            ">" => Ok(CompareOp::Gt),
+            ">=" => Ok(CompareOp::Ge),
+            "==" => Ok(CompareOp::Eq),
+            "!=" => Ok(CompareOp::Ne),
+            _ => Err(syn::Error::new_spanned(
+                lit,
+                "`op` must be one of \"<\", \"<=\", \">\", \">=\", \"==\", \"!=\"",
+            )),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+            CompareOp::Eq => "==",
+            CompareOp::Ne => "!=",
+        }
+    }
+
+    fn tokens(&self) -> TokenStream2 {
+        match self {
+            CompareOp::Lt => quote!(<),
+            CompareOp::Le => quote!(<=),
+            CompareOp::Gt => quote!(>),
+            CompareOp::Ge => quote!(>=),
+            CompareOp::Eq => quote!(==),
+            CompareOp::Ne => quote!(!=),
+        }
+    }
+}
+
+// Quantos campos de um grupo podem estar preenchidos (exatamente / no máximo / no mínimo um)
+pub(crate) enum GroupRule {
+    Exactly,
+    AtMost,
+    AtLeast,
+}
+
+// Tipos de validações de registro suportadas
+pub(crate) enum RecordValidation {
+    Compare { left: LitStr, op: CompareOp, right: LitStr },
+    RequiredIf { field: LitStr, when: LitStr, equals: Option<LitStr> },
+    Group { rule: GroupRule, fields: Vec<LitStr> },
+    Custom { path: syn::Path },
+    // não gera verificação por registro; vira um DatasetKey
+    PrimaryKey { fields: Vec<LitStr> },
+}
+
+// Restrições de conjunto de dados (unique, primary_key, references)
+pub(crate) enum DatasetKey {
+    Unique { fields: Vec<Ident> },
+    ForeignKey { field: Ident, references: String },
+}
+
+// Campo da struct visto pelas validações de registro
+pub(crate) struct RecordField {
+    pub name: Ident,
+    pub is_option: bool,
+}
+
+fn str_value(kv: &syn::MetaNameValue, what: &str) -> syn::Result<LitStr> {
+    if let Expr::Lit(ExprLit { lit: Lit::Str(s), .. }) = &kv.value {
+        Ok(s.clone())
+    } else {
+        Err(syn::Error::new_spanned(
+            &kv.value,
+            format!("Expected string literal for `{}`", what),
+        ))
+    }
+}
+
+impl RecordValidation {
+    /// Faz o parse de #[validate(...)] da struct em uma lista de RecordValidation
+    pub(crate) fn parse_validations(input: syn::parse::ParseStream) -> syn::Result<Vec<Self>> {
+        let mut validations = Vec::new();
+        let meta_items =
+            syn::punctuated::Punctuated::<Meta, syn::Token![,]>::parse_terminated(input)?;
+
+        for meta in meta_items {
+            match meta {
+                Meta::NameValue(mnv) if mnv.path.is_ident("custom") => {
+                    let s = str_value(&mnv, "custom")?;
+                    let path: syn::Path =
+                        syn::parse_str(&s.value()).map_err(|e| syn::Error::new_spanned(&s, e))?;
+                    validations.push(RecordValidation::Custom { path });
+                }
+                Meta::List(meta_list) if meta_list.path.is_ident("compare") => {
+                    let items: syn::punctuated::Punctuated<syn::MetaNameValue, syn::Token![,]> =
+                        meta_list.parse_args_with(syn::punctuated::Punctuated::parse_terminated)?;
+                    let (mut left, mut op, mut right) = (None, None, None);
+                    for kv in items {
+                        if kv.path.is_ident("left") {
+                            left = Some(str_value(&kv, "left")?);
+                        } else if kv.path.is_ident("op") {
+                            op = Some(CompareOp::parse(&str_value(&kv, "op")?)?);
+                        } else if kv.path.is_ident("right") {
+                            right = Some(str_value(&kv, "right")?);
+                        } else {
+                            return Err(syn::Error::new_spanned(kv.path, "unknown `compare` argument"));
+                        }
+                    }
+                    match (left, op, right) {
+                        (Some(left), Some(op), Some(right)) => {
+                            validations.push(RecordValidation::Compare { left, op, right })
+                        }
+                        _ => {
+                            return Err(syn::Error::new_spanned(
+                                meta_list,
+                                "`compare` requires `left`, `op` and `right`",
+                            ))
+                        }
+                    }
+                }
+                Meta::List(meta_list) if meta_list.path.is_ident("required_if") => {
+                    let items: syn::punctuated::Punctuated<syn::MetaNameValue, syn::Token![,]> =
+                        meta_list.parse_args_with(syn::punctuated::Punctuated::parse_terminated)?;
+                    let (mut field, mut when, mut equals) = (None, None, None);
+                    for kv in items {
+                        if kv.path.is_ident("field") {
+                            field = Some(str_value(&kv, "field")?);
+                        } else if kv.path.is_ident("when") {
+                            when = Some(str_value(&kv, "when")?);
+                        } else if kv.path.is_ident("equals") {
+                            equals = Some(str_value(&kv, "equals")?);
+                        } else {
+                            return Err(syn::Error::new_spanned(
+                                kv.path,
+                                "unknown `required_if` argument",
+                            ));
+                        }
+                    }
+                    match (field, when) {
+                        (Some(field), Some(when)) => {
+                            validations.push(RecordValidation::RequiredIf { field, when, equals })
+                        }
+                        _ => {
+                            return Err(syn::Error::new_spanned(
+                                meta_list,
+                                "`required_if` requires `field` and `when`",
+                            ))
+                        }
+                    }
+                }
+                Meta::List(meta_list) if meta_list.path.is_ident("primary_key") => {
+                    let items: syn::punctuated::Punctuated<LitStr, syn::Token![,]> =
+                        meta_list.parse_args_with(syn::punctuated::Punctuated::parse_terminated)?;
+                    let fields: Vec<LitStr> = items.into_iter().collect();
+                    if fields.is_empty() {
+                        return Err(syn::Error::new_spanned(
+                            meta_list,
+                            "`primary_key` requires at least one field",
+                        ));
+                    }
+                    validations.push(RecordValidation::PrimaryKey { fields });
+                }
+                Meta::List(meta_list) => {
+                    let rule = if meta_list.path.is_ident("exactly_one_of") {
+                        GroupRule::Exactly
+                    } else if meta_list.path.is_ident("at_most_one_of") {
+                        GroupRule::AtMost
+                    } else if meta_list.path.is_ident("at_least_one_of") {
+                        GroupRule::AtLeast
+                    } else {
+                        return Err(syn::Error::new_spanned(
+                            meta_list.path,
+                            "unknown record-level validation",
+                        ));
+                    };
+                    let items: syn::punctuated::Punctuated<LitStr, syn::Token![,]> =
+                        meta_list.parse_args_with(syn::punctuated::Punctuated::parse_terminated)?;
+                    let fields: Vec<LitStr> = items.into_iter().collect();
+                    if fields.len() < 2 {
+                        return Err(syn::Error::new_spanned(
+                            meta_list,
+                            "field groups require at least two fields",
+                        ));
+                    }
+                    validations.push(RecordValidation::Group { rule, fields });
+                }
+                other => {
+                    return Err(syn::Error::new_spanned(other, "unknown record-level validation"));
+                }
+            }
+        }
+
+        Ok(validations)
+    }
+
+    /// Gera o código da validação; `errors` e `self` vêm do método gerado
+    pub(crate) fn expand(&self, struct_name: &Ident, fields: &[RecordField]) -> syn::Result<TokenStream2> {
+        let lookup = |lit: &LitStr| -> syn::Result<&RecordField> {
+            fields.iter().find(|f| f.name == lit.value()).ok_or_else(|| {
+                syn::Error::new_spanned(lit, format!("no field named `{}`", lit.value()))
+            })
+        };
+        // Option<&T> para qualquer campo, opcional ou não
+        let as_option = |f: &RecordField| {
+            let name = &f.name;
+            if f.is_option {
+                quote!((&self.#name).as_ref())
+            } else {
+                quote!(::core::option::Option::Some(&self.#name))
+            }
+        };
+
+        let tokens = match self {
+            RecordValidation::Compare { left, op, right } => {
+                let l = lookup(left)?;
+                let r = lookup(right)?;
+                let (l_expr, r_expr) = (as_option(l), as_option(r));
+                let field_str = l.name.to_string();
+                let op_tokens = op.tokens();
+                let op_str = op.as_str();
+                let other = r.name.to_string();
+                quote! {
+                    if let (Some(left), Some(right)) = (#l_expr, #r_expr) {
+                        let ok = left #op_tokens right;
+                        if !ok {
+                            errors.push(::csv_schema_validator::ValidationError::new(
+                                #field_str,
+                                ::csv_schema_validator::ErrorCode::CompareFailed {
+                                    op: #op_str.to_string(),
+                                    other: #other.to_string(),
+                                },
+                            ));
+                        }
+                    }
+                }
+            }
+            RecordValidation::RequiredIf { field, when, equals } => {
+                let target = lookup(field)?;
+                if !target.is_option {
+                    return Err(syn::Error::new_spanned(
+                        field,
+                        "`required_if` target must be an `Option<T>` field",
+                    ));
+                }
+                let cond = lookup(when)?;
+                let cond_expr = as_option(cond);
+                let (condition, equals) = match equals {
+                    Some(expected) => (
+                        quote!(matches!(#cond_expr, Some(value) if value == #expected)),
+                        quote!(::core::option::Option::Some(#expected.to_string())),
+                    ),
+                    None => (
+                        quote!((#cond_expr).is_some()),
+                        quote!(::core::option::Option::None),
+                    ),
+                };
+                let target_name = &target.name;
+                let field_str = target_name.to_string();
+                let when_str = cond.name.to_string();
+                quote! {
+                    if #condition && (&self.#target_name).is_none() {
+                        errors.push(::csv_schema_validator::ValidationError::new(
+                            #field_str,
+                            ::csv_schema_validator::ErrorCode::RequiredIf {
+                                when: #when_str.to_string(),
+                                equals: #equals,
+                            },
+                        ));
+                    }
+                }
+            }
+            RecordValidation::Group { rule, fields: group } => {
+                let mut names = Vec::new();
+                for lit in group {
+                    let f = lookup(lit)?;
+                    if !f.is_option {
+                        return Err(syn::Error::new_spanned(
+                            lit,
+                            "field groups only accept `Option<T>` fields",
+                        ));
+                    }
+                    names.push(f.name.clone());
+                }
+                let name_strs: Vec<String> = names.iter().map(Ident::to_string).collect();
+                let fields_code = quote!(vec![#(#name_strs.to_string()),*]);
+                let (condition, code) = match rule {
+                    GroupRule::Exactly => (
+                        quote!(present != 1),
+                        quote!(::csv_schema_validator::ErrorCode::ExactlyOneOf {
+                            fields: #fields_code,
+                            present,
+                        }),
+                    ),
+                    GroupRule::AtMost => (
+                        quote!(present > 1),
+                        quote!(::csv_schema_validator::ErrorCode::AtMostOneOf {
+                            fields: #fields_code,
+                            present,
+                        }),
+                    ),
+                    GroupRule::AtLeast => (
+                        quote!(present == 0),
+                        quote!(::csv_schema_validator::ErrorCode::AtLeastOneOf {
+                            fields: #fields_code,
+                        }),
+                    ),
+                };
+                let field_str = name_strs.join(",");
+                quote! {
+                    let present = [#((&self.#names).is_some()),*].iter().filter(|p| **p).count();
+                    if #condition {
+                        errors.push(::csv_schema_validator::ValidationError::new(#field_str, #code));
+                    }
+                }
+            }
+            RecordValidation::PrimaryKey { .. } => quote! {},
+            RecordValidation::Custom { path } => {
+                let struct_str = struct_name.to_string();
+                quote! {
+                    match #path(self) {
+                        Err(err) => {
+                            errors.push(::csv_schema_validator::ValidationError::new(
+                                #struct_str,
+                                ::csv_schema_validator::ErrorCode::Custom { message: format!("{}", err) },
+                            ));
+                        }
+                        Ok(()) => {}
+                    }
+                }
+            }
+        };
+
+        // cada regra em seu próprio bloco para não conflitar nomes locais
+        Ok(quote!({ #tokens }))
+    }
+}
+
+/// Resolve os campos de primary_key("a", "b", ...)
+pub(crate) fn primary_key(names: &[LitStr], fields: &[RecordField]) -> syn::Result<DatasetKey> {
+    let mut key = Vec::new();
+    for lit in names {
+        let f = fields
+            .iter()
+            .find(|f| f.name == lit.value())
+            .ok_or_else(|| {
+                syn::Error::new_spanned(lit, format!("no field named `{}`", lit.value()))
+            })?;
+        key.push(f.name.clone());
+    }
+    Ok(DatasetKey::Unique { fields: key })
+}
+
+/// Gera DATASET_CONSTRAINTS e constraint_key() da implementação do trait
+pub(crate) fn dataset_items(keys: &[DatasetKey], fields: &[RecordField]) -> TokenStream2 {
+    if keys.is_empty() {
+        return quote! {};
+    }
+
+    let mut constraints = Vec::new();
+    let mut arms = Vec::new();
+    for (index, key) in keys.iter().enumerate() {
+        let key_fields: Vec<&RecordField> = match key {
+            DatasetKey::Unique { fields: names } => {
+                let names_str = names.iter().map(Ident::to_string);
+                constraints.push(quote! {
+                    ::csv_schema_validator::DatasetConstraint::Unique { fields: &[#(#names_str),*] }
+                });
+                names
+                    .iter()
+                    .map(|n| fields.iter().find(|f| f.name == *n).unwrap())
+                    .collect()
+            }
+            DatasetKey::ForeignKey { field, references } => {
+                let field_str = field.to_string();
+                constraints.push(quote! {
+                    ::csv_schema_validator::DatasetConstraint::ForeignKey {
+                        field: #field_str,
+                        references: #references,
+                    }
+                });
+                vec![fields.iter().find(|f| f.name == *field).unwrap()]
+            }
+        };
+
+        let binds: Vec<Ident> = (0..key_fields.len())
+            .map(|i| format_ident!("k{}", i))
+            .collect();
+        let exprs = key_fields.iter().map(|f| {
+            let name = &f.name;
+            if f.is_option {
+                quote!((&self.#name).as_ref())
+            } else {
+                quote!(::core::option::Option::Some(&self.#name))
+            }
+        });
+        arms.push(quote! {
+            #index => {
+                if let (#(Some(#binds),)*) = (#(#exprs,)*) {
+                    Some(vec![#(::std::string::ToString::to_string(#binds)),*])
+                } else {
+                    None
+                }
+            }
+        });
+    }
+
+    quote! {
+        const DATASET_CONSTRAINTS: &'static [::csv_schema_validator::DatasetConstraint] = &[#(#constraints),*];
+
+        fn constraint_key(&self, index: usize) -> ::core::option::Option<::std::vec::Vec<::std::string::String>> {
+            match index {
+                #(#arms)*
+                _ => None,
+            }
+        }
+    }
+}
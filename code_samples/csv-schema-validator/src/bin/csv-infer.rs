@@ -0,0 +1,101 @@
+//! Generates an annotated Rust struct from a sample CSV file.
+//!
+//! ```shell
+//! csv-infer [-d <delimiter>] [-n <rows>] [--name <Struct>] [--max-enum <n>] <sample.csv> [out.rs]
+//! ```
+//!
+//! The struct is written to `out.rs`, or to stdout when no output file is
+//! given. Exit status: 0 on success and 2 when the sample cannot be read or
+//! the output cannot be written.
+
+use std::env;
+use std::fs;
+use std::process::ExitCode;
+
+use csv_schema_validator::csv::ReaderBuilder;
+use csv_schema_validator::infer::{infer_reader, InferOptions};
+
+const USAGE: &str = "usage: csv-infer [-d <delimiter>] [-n <rows>] [--name <Struct>] [--max-enum <n>] <sample.csv> [out.rs]";
+
+fn main() -> ExitCode {
+    let mut delimiter = b',';
+    let mut name = String::from("Record");
+    let mut options = InferOptions::default();
+    let mut paths = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-d" | "--delimiter" => match args.next().as_deref().map(str::as_bytes) {
+                Some([d]) => delimiter = *d,
+                _ => {
+                    eprintln!("the delimiter must be a single ASCII character\n{}", USAGE);
+                    return ExitCode::from(2);
+                }
+            },
+            "-n" | "--rows" => match args.next().and_then(|n| n.parse().ok()) {
+                Some(n) => options.max_rows = Some(n),
+                None => {
+                    eprintln!("the number of rows must be a positive integer\n{}", USAGE);
+                    return ExitCode::from(2);
+                }
+            },
+            "--max-enum" => match args.next().and_then(|n| n.parse().ok()) {
+                Some(n) => options.max_enum_values = n,
+                None => {
+                    eprintln!("--max-enum must be an integer\n{}", USAGE);
+                    return ExitCode::from(2);
+                }
+            },
+            "--name" => match args.next() {
+                Some(n) => name = n,
+                None => {
+                    eprintln!("{}", USAGE);
+                    return ExitCode::from(2);
+                }
+            },
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            _ => paths.push(arg),
+        }
+    }
+    let (sample_path, out_path) = match paths.as_slice() {
+        [sample] => (sample, None),
+        [sample, out] => (sample, Some(out)),
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let inference = match ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_path(sample_path)
+        .and_then(|mut reader| infer_reader(&mut reader, &options))
+    {
+        Ok(inference) => inference,
+        Err(e) => {
+            eprintln!("{}: {}", sample_path, e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let source = inference.to_rust(&name);
+    match out_path {
+        Some(path) => {
+            if let Err(e) = fs::write(path, source) {
+                eprintln!("{}: {}", path, e);
+                return ExitCode::from(2);
+            }
+        }
+        None => print!("{}", source),
+    }
+    eprintln!(
+        "{} columns inferred from {} rows",
+        inference.columns.len(),
+        inference.rows
+    );
+    ExitCode::SUCCESS
+}
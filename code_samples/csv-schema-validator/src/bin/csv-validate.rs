@@ -0,0 +1,129 @@
+//! Validates a CSV file against a TOML or JSON schema.
+//!
+//! ```shell
+//! csv-validate [-d <delimiter>] [-f text|jsonl|sarif] [--rejects <out.csv>] <schema> <data.csv>
+//! ```
+//!
+//! Errors go to stdout in the chosen format (plain text by default). With
+//! `--rejects`, the invalid rows are also copied to a CSV file with an extra
+//! `errors` column.
+//!
+//! Exit status: 0 when every row is valid, 1 when errors were found and 2 when
+//! the schema or the CSV file cannot be read.
+
+use std::env;
+use std::io;
+use std::process::ExitCode;
+
+use csv_schema_validator::csv::{ReaderBuilder, WriterBuilder};
+use csv_schema_validator::report::{write_json_lines, write_rejects, write_sarif};
+use csv_schema_validator::schema::Schema;
+
+const USAGE: &str = "usage: csv-validate [-d <delimiter>] [-f text|jsonl|sarif] [--rejects <out.csv>] <schema.toml|schema.json> <data.csv>";
+
+fn main() -> ExitCode {
+    let mut delimiter = b',';
+    let mut format = String::from("text");
+    let mut rejects = None;
+    let mut paths = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-d" | "--delimiter" => match args.next().as_deref().map(str::as_bytes) {
+                Some([d]) => delimiter = *d,
+                _ => {
+                    eprintln!("the delimiter must be a single ASCII character\n{}", USAGE);
+                    return ExitCode::from(2);
+                }
+            },
+            "-f" | "--format" => match args.next() {
+                Some(f) if ["text", "jsonl", "sarif"].contains(&f.as_str()) => format = f,
+                _ => {
+                    eprintln!("the format must be text, jsonl or sarif\n{}", USAGE);
+                    return ExitCode::from(2);
+                }
+            },
+            "--rejects" => match args.next() {
+                Some(path) => rejects = Some(path),
+                None => {
+                    eprintln!("{}", USAGE);
+                    return ExitCode::from(2);
+                }
+            },
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            _ => paths.push(arg),
+        }
+    }
+    let (schema_path, data_path) = match paths.as_slice() {
+        [schema, data] => (schema, data),
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let schema = match Schema::from_path(schema_path) {
+        Ok(schema) => schema,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::from(2);
+        }
+    };
+    let open = |flexible: bool| {
+        ReaderBuilder::new()
+            .delimiter(delimiter)
+            .flexible(flexible)
+            .from_path(data_path)
+    };
+    let mut reader = match open(false) {
+        Ok(reader) => reader,
+        Err(e) => {
+            eprintln!("{}: {}", data_path, e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let report = schema.validate_reader(&mut reader);
+    let written = match format.as_str() {
+        "jsonl" => write_json_lines(io::stdout().lock(), &report.errors),
+        "sarif" => write_sarif(io::stdout().lock(), data_path, &report.errors),
+        _ => {
+            for error in &report.errors {
+                println!("{}:{}", data_path, error);
+            }
+            Ok(())
+        }
+    };
+    if let Err(e) = written {
+        eprintln!("cannot write report: {}", e);
+        return ExitCode::from(2);
+    }
+
+    if let Some(path) = rejects {
+        let result = open(true).and_then(|mut source| {
+            let mut out = WriterBuilder::new()
+                .delimiter(delimiter)
+                .flexible(true)
+                .from_path(&path)?;
+            write_rejects(&mut source, &report.errors, &mut out)
+        });
+        if let Err(e) = result {
+            eprintln!("{}: {}", path, e);
+            return ExitCode::from(2);
+        }
+    }
+    eprintln!(
+        "{} rows, {} valid, {} errors",
+        report.rows,
+        report.valid_rows,
+        report.errors.len()
+    );
+    if report.is_valid() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(1)
+    }
+}
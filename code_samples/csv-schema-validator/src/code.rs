@@ -0,0 +1,226 @@
+//! Stable, machine-readable error codes.
+
+use std::fmt;
+
+use serde::Serialize;
+
+/// Why a validation failed, with the offending value and the rule parameters.
+///
+/// [`ErrorCode::as_str`] returns an identifier that does not change between
+/// releases, suitable for aggregation or for looking up localized messages.
+/// `Display` renders the default English message.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// `required`: the field is `None` (or the cell is empty).
+    Required,
+    /// `not_blank`: the value only has whitespace.
+    Blank { value: String },
+    /// `range`: the value is lower than `min`.
+    RangeBelowMin { value: f64, min: f64, max: f64 },
+    /// `range`: the value is greater than `max` (or NaN).
+    RangeAboveMax { value: f64, min: f64, max: f64 },
+    /// `range` in a runtime schema: the cell is not a number.
+    NotANumber { value: String },
+    /// `length`: the value is shorter than `min`.
+    LengthTooShort {
+        value: String,
+        length: usize,
+        min: usize,
+        max: usize,
+    },
+    /// `length`: the value is longer than `max`.
+    LengthTooLong {
+        value: String,
+        length: usize,
+        min: usize,
+        max: usize,
+    },
+    /// `regex`: the value does not match `pattern`.
+    RegexMismatch { value: String, pattern: String },
+    /// `regex`: the pattern itself does not compile.
+    InvalidRegex { pattern: String, error: String },
+    /// `one_of`: the value is not in `allowed`.
+    NotInSet { value: String, allowed: Vec<String> },
+    /// `not_in`: the value is in `forbidden`.
+    ForbiddenValue {
+        value: String,
+        forbidden: Vec<String>,
+    },
+    /// `custom`: the function returned an error.
+    Custom { message: String },
+    /// `compare`: `field op other` does not hold.
+    CompareFailed { op: String, other: String },
+    /// `required_if`: the field is missing while `when` is present or equals `equals`.
+    RequiredIf {
+        when: String,
+        equals: Option<String>,
+    },
+    /// `exactly_one_of`: `present` of `fields` are set.
+    ExactlyOneOf { fields: Vec<String>, present: usize },
+    /// `at_most_one_of`: `present` of `fields` are set.
+    AtMostOneOf { fields: Vec<String>, present: usize },
+    /// `at_least_one_of`: none of `fields` is set.
+    AtLeastOneOf { fields: Vec<String> },
+    /// `date`: the value does not parse with `format`.
+    InvalidDate { value: String, format: String },
+    /// `date`: the date is before `min` or after `max`.
+    DateOutOfRange {
+        value: String,
+        min: Option<String>,
+        max: Option<String>,
+    },
+    /// `email`: the value is not an e-mail address.
+    InvalidEmail { value: String },
+    /// `url`: the value is not an absolute URL.
+    InvalidUrl { value: String },
+    /// `uuid`: the value is not a hyphenated UUID.
+    InvalidUuid { value: String },
+    /// `cpf`: the value is not a valid CPF (format or check digits).
+    InvalidCpf { value: String },
+    /// `cnpj`: the value is not a valid CNPJ (format or check digits).
+    InvalidCnpj { value: String },
+    /// `decimal`: the value is not a number with at most `precision` digits,
+    /// `scale` of them after the point.
+    InvalidDecimal {
+        value: String,
+        precision: u32,
+        scale: u32,
+    },
+    /// `ip`: the value is not an IPv4 or IPv6 address.
+    InvalidIp { value: String },
+    /// A runtime schema column is not in the CSV header.
+    MissingColumn,
+}
+
+impl ErrorCode {
+    /// Stable identifier of the rule that failed, e.g. `"range_below_min"`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::Required => "required",
+            ErrorCode::Blank { .. } => "blank",
+            ErrorCode::RangeBelowMin { .. } => "range_below_min",
+            ErrorCode::RangeAboveMax { .. } => "range_above_max",
+            ErrorCode::NotANumber { .. } => "not_a_number",
+            ErrorCode::LengthTooShort { .. } => "length_too_short",
+            ErrorCode::LengthTooLong { .. } => "length_too_long",
+            ErrorCode::RegexMismatch { .. } => "regex_mismatch",
+            ErrorCode::InvalidRegex { .. } => "invalid_regex",
+            ErrorCode::NotInSet { .. } => "not_in_set",
+            ErrorCode::ForbiddenValue { .. } => "forbidden_value",
+            ErrorCode::Custom { .. } => "custom",
+            ErrorCode::CompareFailed { .. } => "compare_failed",
+            ErrorCode::RequiredIf { .. } => "required_if",
+            ErrorCode::ExactlyOneOf { .. } => "exactly_one_of",
+            ErrorCode::AtMostOneOf { .. } => "at_most_one_of",
+            ErrorCode::AtLeastOneOf { .. } => "at_least_one_of",
+            ErrorCode::InvalidDate { .. } => "invalid_date",
+            ErrorCode::DateOutOfRange { .. } => "date_out_of_range",
+            ErrorCode::InvalidEmail { .. } => "invalid_email",
+            ErrorCode::InvalidUrl { .. } => "invalid_url",
+            ErrorCode::InvalidUuid { .. } => "invalid_uuid",
+            ErrorCode::InvalidCpf { .. } => "invalid_cpf",
+            ErrorCode::InvalidCnpj { .. } => "invalid_cnpj",
+            ErrorCode::InvalidDecimal { .. } => "invalid_decimal",
+            ErrorCode::InvalidIp { .. } => "invalid_ip",
+            ErrorCode::MissingColumn => "missing_column",
+        }
+    }
+
+    /// Picks `RangeBelowMin` or `RangeAboveMax` for a value outside `min..=max`.
+    pub fn range(value: f64, min: f64, max: f64) -> Self {
+        if value < min {
+            ErrorCode::RangeBelowMin { value, min, max }
+        } else {
+            ErrorCode::RangeAboveMax { value, min, max }
+        }
+    }
+
+    /// Picks `LengthTooShort` or `LengthTooLong` for a value outside `min..=max`.
+    pub fn length(value: &str, min: usize, max: usize) -> Self {
+        let value = value.to_string();
+        let length = value.len();
+        if length < min {
+            ErrorCode::LengthTooShort {
+                value,
+                length,
+                min,
+                max,
+            }
+        } else {
+            ErrorCode::LengthTooLong {
+                value,
+                length,
+                min,
+                max,
+            }
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorCode::Required => write!(f, "mandatory field"),
+            ErrorCode::Blank { .. } => write!(f, "must not be blank or contain only whitespace"),
+            ErrorCode::RangeBelowMin { min, max, .. }
+            | ErrorCode::RangeAboveMax { min, max, .. } => {
+                write!(f, "value out of expected range: {} to {}", min, max)
+            }
+            ErrorCode::NotANumber { .. } => write!(f, "not a number"),
+            ErrorCode::LengthTooShort { min, max, .. }
+            | ErrorCode::LengthTooLong { min, max, .. } => {
+                write!(f, "length out of expected range: {} to {}", min, max)
+            }
+            ErrorCode::RegexMismatch { .. } => write!(f, "does not match the expected pattern"),
+            ErrorCode::InvalidRegex { pattern, error } => {
+                write!(f, "invalid regex '{}': {}", pattern, error)
+            }
+            ErrorCode::NotInSet { .. } => write!(f, "invalid value"),
+            ErrorCode::ForbiddenValue { .. } => write!(f, "value not allowed"),
+            ErrorCode::Custom { message } => write!(f, "{}", message),
+            ErrorCode::CompareFailed { op, other } => write!(f, "must be {} {}", op, other),
+            ErrorCode::RequiredIf {
+                when,
+                equals: Some(v),
+            } => {
+                write!(f, "mandatory field when {} is {}", when, v)
+            }
+            ErrorCode::RequiredIf { when, equals: None } => {
+                write!(f, "mandatory field when {} is present", when)
+            }
+            ErrorCode::ExactlyOneOf { fields, .. } => {
+                write!(f, "exactly one of {} must be present", fields.join(", "))
+            }
+            ErrorCode::AtMostOneOf { fields, .. } => {
+                write!(f, "at most one of {} may be present", fields.join(", "))
+            }
+            ErrorCode::AtLeastOneOf { fields } => {
+                write!(f, "at least one of {} must be present", fields.join(", "))
+            }
+            ErrorCode::InvalidDate { format, .. } => {
+                write!(f, "invalid date, expected format {}", format)
+            }
+            ErrorCode::DateOutOfRange { min, max, .. } => write!(
+                f,
+                "date out of expected range: {} to {}",
+                min.as_deref().unwrap_or("-"),
+                max.as_deref().unwrap_or("-")
+            ),
+            ErrorCode::InvalidEmail { .. } => write!(f, "invalid e-mail address"),
+            ErrorCode::InvalidUrl { .. } => write!(f, "invalid URL"),
+            ErrorCode::InvalidUuid { .. } => write!(f, "invalid UUID"),
+            ErrorCode::InvalidCpf { .. } => write!(f, "invalid CPF"),
+            ErrorCode::InvalidCnpj { .. } => write!(f, "invalid CNPJ"),
+            ErrorCode::InvalidDecimal {
+                precision, scale, ..
+            } => write!(
+                f,
+                "invalid decimal, expected at most {} digits with {} decimal places",
+                precision, scale
+            ),
+            ErrorCode::InvalidIp { .. } => write!(f, "invalid IP address"),
+            ErrorCode::MissingColumn => write!(f, "column not found in header"),
+        }
+    }
+}
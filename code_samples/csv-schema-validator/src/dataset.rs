@@ -0,0 +1,170 @@
+//! Dataset-level constraints that span several rows or files.
+//!
+//! `#[validate(unique)]`, `#[validate(references = "name")]` on fields and
+//! `#[validate(primary_key("a", "b"))]` on the struct do not add anything to
+//! `validate_csv`; they are checked by a [`DatasetValidator`], which remembers
+//! the keys seen so far while streaming a reader.
+
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::marker::PhantomData;
+
+use csv::{Position, Reader};
+use serde::de::DeserializeOwned;
+
+use crate::reader::{validate_records, RowError, RowErrorKind, ValidationReport};
+use crate::ValidateCsv;
+
+/// A constraint declared on a type deriving `ValidateCsv`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatasetConstraint {
+    /// The combination of `fields` must not repeat (`unique` or `primary_key`).
+    Unique { fields: &'static [&'static str] },
+    /// The value of `field` must exist in the reference set named `references`.
+    ForeignKey {
+        field: &'static str,
+        references: &'static str,
+    },
+}
+
+/// Stateful validator for the [`DatasetConstraint`]s of `T`.
+///
+/// Keys are compared by their `Display` representation, and rows where any
+/// key field is `None` are skipped. The state is kept between calls, so a
+/// dataset split across several files can be checked one reader at a time.
+pub struct DatasetValidator<T> {
+    // per Unique constraint: key -> (line, record) of its first occurrence
+    seen: Vec<HashMap<Vec<String>, (u64, u64)>>,
+    references: HashMap<String, HashSet<String>>,
+    _record_type: PhantomData<fn(&T)>,
+}
+
+impl<T: ValidateCsv> Default for DatasetValidator<T> {
+    fn default() -> Self {
+        DatasetValidator {
+            seen: T::DATASET_CONSTRAINTS
+                .iter()
+                .map(|_| HashMap::new())
+                .collect(),
+            references: HashMap::new(),
+            _record_type: PhantomData,
+        }
+    }
+}
+
+impl<T: ValidateCsv> DatasetValidator<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the keys that `references = "name"` fields may point to.
+    ///
+    /// A reference that is never registered behaves like an empty set.
+    pub fn with_reference<I, S>(mut self, name: &str, keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.references
+            .entry(name.to_string())
+            .or_default()
+            .extend(keys.into_iter().map(Into::into));
+        self
+    }
+
+    /// Registers every value of `column` in another CSV as the reference set `name`.
+    pub fn load_reference<R: io::Read>(
+        self,
+        name: &str,
+        reader: &mut Reader<R>,
+        column: &str,
+    ) -> csv::Result<Self> {
+        let index = reader
+            .headers()?
+            .iter()
+            .position(|h| h == column)
+            .ok_or_else(|| {
+                csv::Error::from(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("column `{}` not found in reference file", column),
+                ))
+            })?;
+        let mut keys = Vec::new();
+        for result in reader.records() {
+            if let Some(value) = result?.get(index) {
+                keys.push(value.to_string());
+            }
+        }
+        Ok(self.with_reference(name, keys))
+    }
+
+    /// Checks one record, remembering its keys, and returns the violations.
+    pub fn check(&mut self, record: &T, position: &Position) -> Vec<RowErrorKind> {
+        let mut errors = Vec::new();
+        for (index, constraint) in T::DATASET_CONSTRAINTS.iter().enumerate() {
+            let key = match record.constraint_key(index) {
+                Some(key) => key,
+                None => continue,
+            };
+            match constraint {
+                DatasetConstraint::Unique { fields } => {
+                    let seen = &mut self.seen[index];
+                    match seen.get(&key) {
+                        Some(&(first_line, first_record)) => errors.push(RowErrorKind::Duplicate {
+                            fields: fields.iter().map(|f| f.to_string()).collect(),
+                            key,
+                            first_line,
+                            first_record,
+                        }),
+                        None => {
+                            seen.insert(key, (position.line(), position.record()));
+                        }
+                    }
+                }
+                DatasetConstraint::ForeignKey { field, references } => {
+                    let found = self
+                        .references
+                        .get(*references)
+                        .is_some_and(|keys| key.iter().all(|k| keys.contains(k)));
+                    if !found {
+                        errors.push(RowErrorKind::Orphan {
+                            field: field.to_string(),
+                            references: references.to_string(),
+                        });
+                    }
+                }
+            }
+        }
+        errors
+    }
+
+    /// Streams `reader` like [`validate_reader`](crate::validate_reader) and
+    /// also checks the dataset constraints of every row that could be
+    /// deserialized.
+    pub fn validate_reader<R>(&mut self, reader: &mut Reader<R>) -> ValidationReport
+    where
+        R: io::Read,
+        T: DeserializeOwned,
+    {
+        let mut report = ValidationReport::default();
+        let mut rows = validate_records::<R, T>(reader);
+        while let Some((value, mut errors)) = rows.next_row() {
+            if let Some(value) = value {
+                let record = rows.current_record();
+                let position = record.position().cloned().unwrap_or_else(Position::new);
+                for kind in self.check(&value, &position) {
+                    let column = match &kind {
+                        RowErrorKind::Duplicate { fields, .. } if fields.len() == 1 => {
+                            rows.column_of(&fields[0])
+                        }
+                        RowErrorKind::Orphan { field, .. } => rows.column_of(field),
+                        _ => None,
+                    };
+                    errors.push(RowError::at(Some(&position), kind).with_cell(record, column));
+                }
+            }
+            report.add_row(errors);
+        }
+        report
+    }
+}
@@ -0,0 +1,291 @@
+//! Schema inference from a sample CSV file.
+//!
+//! [`infer_reader`] scans the sample once and records, for each column, the
+//! narrowest type that accepts every non-empty cell, whether empty cells were
+//! seen, the numeric range, the longest value and, when there are few distinct
+//! values, the set of values. [`Inference::to_rust`] turns that into a struct
+//! annotated with `#[derive(Deserialize, ValidateCsv)]` and `#[validate(...)]`
+//! rules, meant as a starting point to be reviewed by hand.
+
+use std::collections::{BTreeSet, HashSet};
+use std::fmt::Write as _;
+use std::io;
+
+use csv::{Reader, StringRecord};
+
+/// Tuning knobs for [`infer_reader`].
+#[derive(Debug, Clone)]
+pub struct InferOptions {
+    /// Stop after this many rows (`None` reads the whole sample).
+    pub max_rows: Option<u64>,
+    /// A text column with at most this many distinct values becomes `one_of`.
+    pub max_enum_values: usize,
+}
+
+impl Default for InferOptions {
+    fn default() -> Self {
+        InferOptions {
+            max_rows: None,
+            max_enum_values: 10,
+        }
+    }
+}
+
+/// Narrowest Rust type that accepted every non-empty cell of a column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum InferredType {
+    Bool,
+    Integer,
+    Float,
+    Text,
+}
+
+impl InferredType {
+    fn of(value: &str) -> Self {
+        let digits = value.strip_prefix('-').unwrap_or(value);
+        if value == "true" || value == "false" {
+            InferredType::Bool
+        } else if digits.len() > 1 && digits.starts_with('0') && !digits.starts_with("0.") {
+            // zeros à esquerda (CEP, CPF, códigos) se perderiam num número
+            InferredType::Text
+        } else if value.parse::<i64>().is_ok() {
+            InferredType::Integer
+        } else if value.parse::<f64>().is_ok_and(f64::is_finite) {
+            InferredType::Float
+        } else {
+            InferredType::Text
+        }
+    }
+
+    // bool só combina com bool; inteiros se alargam para float
+    fn widen(self, other: Self) -> Self {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (InferredType::Integer, InferredType::Float)
+            | (InferredType::Float, InferredType::Integer) => InferredType::Float,
+            _ => InferredType::Text,
+        }
+    }
+
+    /// Rust type used in the generated struct.
+    pub fn rust_type(self) -> &'static str {
+        match self {
+            InferredType::Bool => "bool",
+            InferredType::Integer => "i64",
+            InferredType::Float => "f64",
+            InferredType::Text => "String",
+        }
+    }
+}
+
+/// What was observed in one column of the sample.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InferredColumn {
+    /// Header name, as found in the file.
+    pub name: String,
+    /// `None` when the column only had empty cells.
+    pub ty: Option<InferredType>,
+    /// Some cell was empty.
+    pub nullable: bool,
+    /// Number of non-empty cells.
+    pub count: u64,
+    /// Smallest and largest numeric values (numeric columns only).
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    /// Length in bytes of the longest value.
+    pub max_length: usize,
+    /// Some value only had whitespace.
+    pub blank: bool,
+    /// Distinct values, when there were at most `max_enum_values` of them.
+    pub values: Option<BTreeSet<String>>,
+}
+
+impl InferredColumn {
+    fn new(name: &str) -> Self {
+        InferredColumn {
+            name: name.to_string(),
+            ty: None,
+            nullable: false,
+            count: 0,
+            min: None,
+            max: None,
+            max_length: 0,
+            blank: false,
+            values: Some(BTreeSet::new()),
+        }
+    }
+
+    fn observe(&mut self, value: &str, max_enum_values: usize) {
+        if value.is_empty() {
+            self.nullable = true;
+            return;
+        }
+        self.count += 1;
+        let ty = InferredType::of(value);
+        self.ty = Some(self.ty.map_or(ty, |t| t.widen(ty)));
+        if let Ok(n) = value.parse::<f64>() {
+            self.min = Some(self.min.map_or(n, |m| m.min(n)));
+            self.max = Some(self.max.map_or(n, |m| m.max(n)));
+        }
+        self.max_length = self.max_length.max(value.len());
+        self.blank |= value.trim().is_empty();
+        if let Some(values) = &mut self.values {
+            values.insert(value.to_string());
+            if values.len() > max_enum_values {
+                self.values = None;
+            }
+        }
+    }
+}
+
+/// Result of [`infer_reader`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Inference {
+    pub columns: Vec<InferredColumn>,
+    /// Number of rows read.
+    pub rows: u64,
+}
+
+/// Reads the sample and infers one [`InferredColumn`] per header column.
+///
+/// Extra cells in rows longer than the header are ignored; use a flexible
+/// reader if the sample has rows of unequal length.
+pub fn infer_reader<R: io::Read>(
+    reader: &mut Reader<R>,
+    options: &InferOptions,
+) -> csv::Result<Inference> {
+    let headers = reader.headers()?.clone();
+    let mut columns: Vec<InferredColumn> = headers.iter().map(InferredColumn::new).collect();
+    let mut rows = 0;
+    let mut record = StringRecord::new();
+    while options.max_rows.is_none_or(|max| rows < max) && reader.read_record(&mut record)? {
+        rows += 1;
+        for (i, column) in columns.iter_mut().enumerate() {
+            column.observe(record.get(i).unwrap_or(""), options.max_enum_values);
+        }
+    }
+    Ok(Inference { columns, rows })
+}
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "crate", "dyn", "else", "enum",
+    "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+    "mut", "pub", "ref", "return", "self", "static", "struct", "super", "trait", "true", "try",
+    "type", "unsafe", "use", "where", "while", "yield",
+];
+
+/// Snake-case identifier for a header name (`"Unit Price (R$)"` becomes
+/// `unit_price_r`).
+fn field_ident(name: &str, index: usize) -> String {
+    let mut ident = String::new();
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            if c.is_ascii_uppercase() && prev_lower {
+                ident.push('_');
+            }
+            prev_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+            ident.push(c.to_ascii_lowercase());
+        } else {
+            if !ident.is_empty() && !ident.ends_with('_') {
+                ident.push('_');
+            }
+            prev_lower = false;
+        }
+    }
+    let ident = ident.trim_end_matches('_');
+    if ident.is_empty() {
+        format!("column_{}", index + 1)
+    } else if ident.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("column_{}", ident)
+    } else if KEYWORDS.contains(&ident) {
+        format!("{}_", ident)
+    } else {
+        ident.to_string()
+    }
+}
+
+/// Float literal accepted by `range` (`3.0`, not `3`).
+fn float_literal(value: f64) -> String {
+    format!("{:?}", value)
+}
+
+impl Inference {
+    /// Rust source of a struct named `struct_name` matching the sample.
+    ///
+    /// Columns with empty cells become `Option<T>`, fields are renamed with
+    /// `#[serde(rename)]` when the header is not a valid identifier, numeric
+    /// columns get a `range` with the observed bounds and text columns get
+    /// either `one_of` (few distinct values, repeated in the sample) or
+    /// `length(max)` plus `not_blank`.
+    pub fn to_rust(&self, struct_name: &str) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "// Inferred from a sample of {} rows; review the rules before use.",
+            self.rows
+        );
+        out.push_str("use csv_schema_validator::ValidateCsv;\nuse serde::Deserialize;\n\n");
+        out.push_str("#[derive(Debug, Deserialize, ValidateCsv)]\n");
+        let _ = writeln!(out, "pub struct {} {{", struct_name);
+
+        let mut used = HashSet::new();
+        for (i, column) in self.columns.iter().enumerate() {
+            let mut field = field_ident(&column.name, i);
+            if !used.insert(field.clone()) {
+                let base = field;
+                let mut n = 2;
+                while !used.insert(format!("{}_{}", base, n)) {
+                    n += 1;
+                }
+                field = format!("{}_{}", base, n);
+            }
+
+            let ty = column.ty.unwrap_or(InferredType::Text);
+            let mut rules = Vec::new();
+            match ty {
+                InferredType::Integer | InferredType::Float => {
+                    if let (Some(min), Some(max)) = (column.min, column.max) {
+                        rules.push(format!(
+                            "range(min = {}, max = {})",
+                            float_literal(min),
+                            float_literal(max)
+                        ));
+                    }
+                }
+                InferredType::Text if column.ty.is_some() => {
+                    // só vira enumeração se os valores se repetem na amostra
+                    match &column.values {
+                        Some(values) if values.len() as u64 * 2 <= column.count => {
+                            let list: Vec<String> =
+                                values.iter().map(|v| format!("{:?}", v)).collect();
+                            rules.push(format!("one_of({})", list.join(", ")));
+                        }
+                        _ => {
+                            rules.push(format!("length(max = {})", column.max_length));
+                            if !column.blank {
+                                rules.push("not_blank".to_string());
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+
+            if field != column.name {
+                let _ = writeln!(out, "    #[serde(rename = {:?})]", column.name);
+            }
+            if !rules.is_empty() {
+                let _ = writeln!(out, "    #[validate({})]", rules.join(", "));
+            }
+            let rust_type = if column.nullable || column.ty.is_none() {
+                format!("Option<{}>", ty.rust_type())
+            } else {
+                ty.rust_type().to_string()
+            };
+            let _ = writeln!(out, "    pub {}: {},", field, rust_type);
+        }
+        out.push_str("}\n");
+        out
+    }
+}
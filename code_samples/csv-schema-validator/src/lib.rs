@@ -3,8 +3,25 @@ pub use csv_schema_validator_derive::ValidateCsv;
 pub use csv;
 pub use serde;
 
+pub mod code;
+pub mod dataset;
+pub mod infer;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+pub mod reader;
+#[cfg(feature = "report")]
+pub mod report;
+#[cfg(feature = "schema")]
+pub mod schema;
+pub mod validators;
+
+pub use code::ErrorCode;
+pub use dataset::{DatasetConstraint, DatasetValidator};
+pub use reader::{validate_reader, validate_records, RowError, RowErrorKind, ValidationReport};
+
 #[doc(hidden)]
 pub mod __private {
+    pub use chrono;
     pub use once_cell;
     pub use regex;
 }
@@ -13,6 +30,18 @@ pub mod __private {
 pub struct ValidationError {
     pub field: String,
     pub message: String,
+    pub code: ErrorCode,
+}
+
+impl ValidationError {
+    /// Builds an error whose `message` is the default text of `code`.
+    pub fn new(field: impl Into<String>, code: ErrorCode) -> Self {
+        ValidationError {
+            field: field.into(),
+            message: code.to_string(),
+            code,
+        }
+    }
 }
 
 impl std::fmt::Display for ValidationError {
@@ -22,3 +51,25 @@ impl std::fmt::Display for ValidationError {
 }
 
 impl std::error::Error for ValidationError {}
+
+/// Implemented by `#[derive(ValidateCsv)]`.
+///
+/// The derive also generates an inherent `validate_csv` method, so the trait
+/// only needs to be in scope for generic code such as [`reader`].
+pub trait ValidateCsv {
+    /// `(field, column)` pairs in declaration order, where `column` is the
+    /// name serde expects in the CSV header.
+    const CSV_COLUMNS: &'static [(&'static str, &'static str)];
+
+    /// Constraints checked by [`DatasetValidator`] rather than `validate_csv`.
+    const DATASET_CONSTRAINTS: &'static [DatasetConstraint] = &[];
+
+    fn validate_csv(&self) -> Result<(), Vec<ValidationError>>;
+
+    /// Key of this record for `DATASET_CONSTRAINTS[index]`, or `None` when
+    /// any of its fields is `None`.
+    fn constraint_key(&self, index: usize) -> Option<Vec<String>> {
+        let _ = index;
+        None
+    }
+}
@@ -0,0 +1,359 @@
+//! Parallel validation of large files.
+//!
+//! [`ParallelValidator`] reads the input in large windows and cuts each
+//! window into chunks that end on record boundaries. A quick byte scan tracks
+//! quoted fields, so newlines inside quotes never split a record. The chunks
+//! are parsed, deserialized and validated on a rayon thread pool. Their results
+//! are then merged in file order, with lines, byte offsets and record indexes
+//! translated back to the whole file. The resulting [`ValidationReport`] is
+//! the one [`validate_reader`](crate::validate_reader) would produce.
+//!
+//! Only row-level rules run here. Dataset constraints (`unique`,
+//! `primary_key`, `references`) depend on earlier rows, so they still need a
+//! sequential [`DatasetValidator`](crate::DatasetValidator).
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::ops::Range;
+use std::path::Path;
+
+use csv::{ByteRecord, ReaderBuilder, StringRecord};
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use serde::de::DeserializeOwned;
+
+use crate::reader::{RowError, RowErrorKind, ValidatedRecords, ValidationReport};
+use crate::ValidateCsv;
+
+/// Builder for parallel validation runs.
+///
+/// The CSV options mirror the ones of [`csv::ReaderBuilder`] that affect
+/// record boundaries and validation. Other options (comments, escapes, custom
+/// terminators) are not supported.
+#[derive(Debug, Clone)]
+pub struct ParallelValidator {
+    delimiter: u8,
+    quote: u8,
+    has_headers: bool,
+    flexible: bool,
+    chunk_size: usize,
+    threads: Option<usize>,
+}
+
+impl Default for ParallelValidator {
+    fn default() -> Self {
+        ParallelValidator {
+            delimiter: b',',
+            quote: b'"',
+            has_headers: true,
+            flexible: false,
+            chunk_size: 1 << 20,
+            threads: None,
+        }
+    }
+}
+
+/// Position of the first byte of a chunk in the whole file.
+#[derive(Debug, Clone, Copy)]
+struct Offset {
+    byte: u64,
+    line: u64,
+    record: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Scan {
+    FieldStart,
+    Unquoted,
+    Quoted,
+    // aspas dentro de um campo entre aspas: escape ("") ou fim do campo
+    QuoteInQuoted,
+}
+
+impl ParallelValidator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Field delimiter (`,` by default).
+    pub fn delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Quote character (`"` by default); quotes are escaped by doubling them.
+    pub fn quote(mut self, quote: u8) -> Self {
+        self.quote = quote;
+        self
+    }
+
+    /// Whether the first record is a header (`true` by default).
+    pub fn has_headers(mut self, yes: bool) -> Self {
+        self.has_headers = yes;
+        self
+    }
+
+    /// Accept rows with a different number of fields (`false` by default).
+    pub fn flexible(mut self, yes: bool) -> Self {
+        self.flexible = yes;
+        self
+    }
+
+    /// Approximate size in bytes of the chunk given to each task (1 MiB by
+    /// default). Chunks always end on a record boundary.
+    pub fn chunk_size(mut self, bytes: usize) -> Self {
+        self.chunk_size = bytes.max(1);
+        self
+    }
+
+    /// Number of worker threads. Without it, rayon's global pool is used.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+
+    /// Opens `path` and validates it with [`validate_reader`](Self::validate_reader).
+    pub fn validate_path<P, T>(&self, path: P) -> io::Result<ValidationReport>
+    where
+        P: AsRef<Path>,
+        T: DeserializeOwned + ValidateCsv,
+    {
+        Ok(self.validate_reader::<_, T>(File::open(path)?))
+    }
+
+    /// Validates every row of `input` as `T`, using several threads.
+    ///
+    /// Memory use is bounded by a window of `chunk_size` bytes per thread,
+    /// plus the collected errors.
+    pub fn validate_reader<R, T>(&self, input: R) -> ValidationReport
+    where
+        R: Read,
+        T: DeserializeOwned + ValidateCsv,
+    {
+        let pool = self
+            .threads
+            .and_then(|n| ThreadPoolBuilder::new().num_threads(n).build().ok());
+        self.run::<R, T>(input, pool.as_ref())
+    }
+
+    fn run<R, T>(&self, mut input: R, pool: Option<&ThreadPool>) -> ValidationReport
+    where
+        R: Read,
+        T: DeserializeOwned + ValidateCsv,
+    {
+        let threads = pool.map_or_else(rayon::current_num_threads, ThreadPool::current_num_threads);
+        let window = self.chunk_size.saturating_mul(threads.max(1));
+
+        let mut report = ValidationReport::default();
+        let mut offset = Offset {
+            byte: 0,
+            line: 1,
+            record: 0,
+        };
+        let mut headers = None;
+        let mut expected_len = None;
+        let mut first_record = true;
+        let mut buf = Vec::new();
+
+        loop {
+            let (eof, io_error) = match input.by_ref().take(window as u64).read_to_end(&mut buf) {
+                Ok(n) => (n < window, None),
+                Err(e) => (true, Some(e)),
+            };
+
+            if first_record {
+                // o primeiro registro (cabeçalho ou não) é lido antes de dividir
+                if !eof && self.boundaries(&buf).next().is_none() {
+                    continue;
+                }
+                first_record = false;
+                let consumed = self.first_record(&buf, &mut offset, &mut report, &mut headers);
+                expected_len = match (&headers, self.flexible) {
+                    (_, true) => None,
+                    (Some(h), false) => Some(h.len()),
+                    (None, false) => self.peek_len(&buf[consumed..]),
+                };
+                buf.drain(..consumed);
+            }
+
+            let (chunks, consumed) = self.split(&buf, eof);
+            let check = |range: &Range<usize>| {
+                self.validate_chunk::<T>(&buf[range.clone()], headers.as_ref(), expected_len)
+            };
+            let results: Vec<ValidationReport> = match pool {
+                Some(pool) => pool.install(|| chunks.par_iter().map(check).collect()),
+                None => chunks.par_iter().map(check).collect(),
+            };
+
+            for (range, chunk) in chunks.iter().zip(results) {
+                for mut error in chunk.errors {
+                    error.line += offset.line - 1;
+                    error.byte += offset.byte;
+                    error.record += offset.record;
+                    report.errors.push(error);
+                }
+                report.rows += chunk.rows;
+                report.valid_rows += chunk.valid_rows;
+                offset.byte += range.len() as u64;
+                offset.line += newlines(&buf[range.clone()]);
+                offset.record += chunk.rows;
+            }
+            buf.drain(..consumed);
+
+            if let Some(e) = io_error {
+                let error = RowError {
+                    line: offset.line,
+                    byte: offset.byte,
+                    record: offset.record,
+                    column: None,
+                    value: None,
+                    kind: RowErrorKind::Io(e.to_string()),
+                };
+                report.add_row(vec![error]);
+                break;
+            }
+            if eof {
+                break;
+            }
+        }
+        report
+    }
+
+    fn builder(&self) -> ReaderBuilder {
+        let mut builder = ReaderBuilder::new();
+        builder
+            .delimiter(self.delimiter)
+            .quote(self.quote)
+            .has_headers(false)
+            .flexible(true);
+        builder
+    }
+
+    /// Reads the header, when there is one, and returns how many bytes of
+    /// `buf` it used.
+    fn first_record(
+        &self,
+        buf: &[u8],
+        offset: &mut Offset,
+        report: &mut ValidationReport,
+        headers: &mut Option<StringRecord>,
+    ) -> usize {
+        if !self.has_headers {
+            return 0;
+        }
+        let mut reader = self.builder().from_reader(buf);
+        let mut record = ByteRecord::new();
+        if !matches!(reader.read_byte_record(&mut record), Ok(true)) {
+            return 0;
+        }
+        let header_pos = record.position().cloned();
+        match StringRecord::from_byte_record(record) {
+            Ok(h) => *headers = Some(h),
+            Err(e) => {
+                // como no modo sequencial: erro na linha do cabeçalho e
+                // colunas casadas pela ordem de declaração
+                let mut error = RowError::at(
+                    header_pos.as_ref(),
+                    RowErrorKind::Utf8(e.utf8_error().to_string()),
+                );
+                error.column = Some(e.utf8_error().field());
+                report.add_row(vec![error]);
+            }
+        }
+        let pos = reader.position();
+        offset.byte = pos.byte();
+        offset.line = pos.line();
+        offset.record = 1;
+        pos.byte() as usize
+    }
+
+    /// Number of fields of the first record, the expected length of a
+    /// headerless file.
+    fn peek_len(&self, data: &[u8]) -> Option<usize> {
+        let mut record = ByteRecord::new();
+        match self
+            .builder()
+            .from_reader(data)
+            .read_byte_record(&mut record)
+        {
+            Ok(true) => Some(record.len()),
+            _ => None,
+        }
+    }
+
+    fn validate_chunk<T>(
+        &self,
+        chunk: &[u8],
+        headers: Option<&StringRecord>,
+        expected_len: Option<usize>,
+    ) -> ValidationReport
+    where
+        T: DeserializeOwned + ValidateCsv,
+    {
+        let mut reader = self.builder().from_reader(chunk);
+        let mut rows =
+            ValidatedRecords::<_, T>::with_headers(&mut reader, headers.cloned(), expected_len);
+        let mut report = ValidationReport::default();
+        while let Some((_, errors)) = rows.next_row() {
+            report.add_row(errors);
+        }
+        report
+    }
+
+    /// Cuts `buf` into chunks of about `chunk_size` bytes ending on record
+    /// boundaries. Returns the chunks and the number of bytes they cover; the
+    /// rest is an incomplete record, kept for the next window unless `eof`.
+    fn split(&self, buf: &[u8], eof: bool) -> (Vec<Range<usize>>, usize) {
+        let mut chunks = Vec::new();
+        let mut start = 0;
+        let mut last = 0;
+        for end in self.boundaries(buf) {
+            last = end;
+            if end - start >= self.chunk_size {
+                chunks.push(start..end);
+                start = end;
+            }
+        }
+        if eof {
+            last = buf.len();
+        }
+        if last > start {
+            chunks.push(start..last);
+        }
+        (chunks, last)
+    }
+
+    /// Offsets just past the first terminator byte (`\r` or `\n` outside
+    /// quotes) of each non-empty record.
+    ///
+    /// Cutting there rather than after the whole line break leaves any `\n`
+    /// of a `\r\n` and the blank lines that follow at the start of the next
+    /// chunk, where the `csv` reader skips them exactly as it does when
+    /// reading the whole file, so record positions come out the same.
+    fn boundaries<'a>(&self, data: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+        let (delimiter, quote) = (self.delimiter, self.quote);
+        let mut state = Scan::FieldStart;
+        let mut empty = true;
+        data.iter().enumerate().filter_map(move |(i, &b)| {
+            let mut end = false;
+            state = match state {
+                Scan::Quoted if b == quote => Scan::QuoteInQuoted,
+                Scan::Quoted => Scan::Quoted,
+                _ if b == b'\n' || b == b'\r' => {
+                    end = !empty;
+                    Scan::FieldStart
+                }
+                Scan::FieldStart if b == quote => Scan::Quoted,
+                Scan::QuoteInQuoted if b == quote => Scan::Quoted,
+                _ if b == delimiter => Scan::FieldStart,
+                _ => Scan::Unquoted,
+            };
+            empty = end || (empty && (b == b'\n' || b == b'\r'));
+            end.then_some(i + 1)
+        })
+    }
+}
+
+fn newlines(data: &[u8]) -> u64 {
+    data.iter().filter(|b| **b == b'\n').count() as u64
+}
@@ -0,0 +1,365 @@
+//! Streaming validation of whole CSV files.
+//!
+//! The functions in this module read a [`csv::Reader`] one row at a time,
+//! deserialize each row into a type deriving [`ValidateCsv`](crate::ValidateCsv)
+//! and report every failure together with its position in the file. Rows are
+//! never buffered, so arbitrarily large files can be checked in constant memory
+//! (apart from the collected errors).
+
+use std::fmt;
+use std::io;
+use std::marker::PhantomData;
+
+use csv::{Position, Reader, StringRecord};
+use serde::de::DeserializeOwned;
+
+use crate::{ValidateCsv, ValidationError};
+
+/// What went wrong with a row.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RowErrorKind {
+    /// A rule declared with `#[validate(...)]` failed.
+    Validation(ValidationError),
+    /// The row could not be deserialized into the target type.
+    Deserialize(String),
+    /// The row does not have the same number of fields as the previous ones.
+    UnequalLengths { expected: u64, found: u64 },
+    /// A cell is not valid UTF-8.
+    Utf8(String),
+    /// The underlying reader failed. No more rows are read after this error.
+    Io(String),
+    /// A `unique` or `primary_key` constraint was violated. `first_line` and
+    /// `first_record` locate the earlier row holding the same key.
+    Duplicate {
+        fields: Vec<String>,
+        key: Vec<String>,
+        first_line: u64,
+        first_record: u64,
+    },
+    /// A `references` constraint points to a key missing from the reference set.
+    Orphan { field: String, references: String },
+}
+
+/// A failure located in the CSV input.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowError {
+    /// Line where the row starts (1-based, header included).
+    pub line: u64,
+    /// Byte offset where the row starts.
+    pub byte: u64,
+    /// Record index as counted by the `csv` crate (the header is record 0).
+    pub record: u64,
+    /// Zero-based column index of the offending cell, when known.
+    pub column: Option<usize>,
+    /// Raw content of the offending cell, when known.
+    pub value: Option<String>,
+    pub kind: RowErrorKind,
+}
+
+impl RowError {
+    pub(crate) fn at(pos: Option<&Position>, kind: RowErrorKind) -> Self {
+        RowError {
+            line: pos.map_or(0, Position::line),
+            byte: pos.map_or(0, Position::byte),
+            record: pos.map_or(0, Position::record),
+            column: None,
+            value: None,
+            kind,
+        }
+    }
+
+    pub(crate) fn with_cell(mut self, record: &StringRecord, column: Option<usize>) -> Self {
+        self.column = column;
+        self.value = column.and_then(|c| record.get(c)).map(str::to_string);
+        self
+    }
+}
+
+impl RowErrorKind {
+    /// Stable identifier of the failure, e.g. `"range_below_min"` or `"duplicate_key"`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            RowErrorKind::Validation(err) => err.code.as_str(),
+            RowErrorKind::Deserialize(_) => "deserialize",
+            RowErrorKind::UnequalLengths { .. } => "unequal_lengths",
+            RowErrorKind::Utf8(_) => "invalid_utf8",
+            RowErrorKind::Io(_) => "io",
+            RowErrorKind::Duplicate { .. } => "duplicate_key",
+            RowErrorKind::Orphan { .. } => "orphan_key",
+        }
+    }
+}
+
+impl fmt::Display for RowErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RowErrorKind::Validation(err) => write!(f, "{}", err),
+            RowErrorKind::Deserialize(msg) => write!(f, "deserialization failed: {}", msg),
+            RowErrorKind::UnequalLengths { expected, found } => {
+                write!(f, "expected {} fields, found {}", expected, found)
+            }
+            RowErrorKind::Utf8(msg) => write!(f, "invalid UTF-8: {}", msg),
+            RowErrorKind::Io(msg) => write!(f, "I/O error: {}", msg),
+            RowErrorKind::Duplicate {
+                fields,
+                key,
+                first_line,
+                ..
+            } => write!(
+                f,
+                "{}: duplicate key ({}), first seen at line {}",
+                fields.join(","),
+                key.join(", "),
+                first_line
+            ),
+            RowErrorKind::Orphan { field, references } => {
+                write!(f, "{}: value not found in `{}`", field, references)
+            }
+        }
+    }
+}
+
+impl fmt::Display for RowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}", self.line)?;
+        if let Some(column) = self.column {
+            write!(f, ", column {}", column)?;
+        }
+        if let Some(value) = &self.value {
+            write!(f, " ({:?})", value)?;
+        }
+        write!(f, ": {}", self.kind)
+    }
+}
+
+impl std::error::Error for RowError {}
+
+/// Iterator returned by [`validate_records`].
+///
+/// Yields one item per data row: the deserialized record when it is valid, or
+/// every error found in that row otherwise.
+pub struct ValidatedRecords<'r, R, T> {
+    reader: &'r mut Reader<R>,
+    headers: Option<StringRecord>,
+    // checked by hand when the reader is flexible but the data must not be
+    expected_len: Option<usize>,
+    pending: Option<csv::Error>,
+    record: StringRecord,
+    done: bool,
+    _record_type: PhantomData<fn() -> T>,
+}
+
+/// Streams `reader`, deserializing and validating each row as `T`.
+///
+/// When the reader was built with headers, columns are matched by name
+/// (honouring `#[serde(rename = "...")]`); otherwise by field declaration order.
+pub fn validate_records<R, T>(reader: &mut Reader<R>) -> ValidatedRecords<'_, R, T>
+where
+    R: io::Read,
+    T: DeserializeOwned + ValidateCsv,
+{
+    let mut headers = None;
+    let mut pending = None;
+    if reader.has_headers() {
+        match reader.headers() {
+            Ok(h) => headers = Some(h.clone()),
+            Err(e) => pending = Some(e),
+        }
+    }
+    ValidatedRecords {
+        reader,
+        headers,
+        expected_len: None,
+        pending,
+        record: StringRecord::new(),
+        done: false,
+        _record_type: PhantomData,
+    }
+}
+
+impl<'r, R, T> ValidatedRecords<'r, R, T>
+where
+    R: io::Read,
+    T: DeserializeOwned + ValidateCsv,
+{
+    /// Validates a headerless `reader` over part of a file, using the
+    /// `headers` read from the start of that file. Rows whose length differs
+    /// from `expected_len` are reported as [`RowErrorKind::UnequalLengths`].
+    #[cfg(feature = "parallel")]
+    pub(crate) fn with_headers(
+        reader: &'r mut Reader<R>,
+        headers: Option<StringRecord>,
+        expected_len: Option<usize>,
+    ) -> Self {
+        ValidatedRecords {
+            reader,
+            headers,
+            expected_len,
+            pending: None,
+            record: StringRecord::new(),
+            done: false,
+            _record_type: PhantomData,
+        }
+    }
+
+    pub(crate) fn current_record(&self) -> &StringRecord {
+        &self.record
+    }
+
+    pub(crate) fn column_of(&self, field: &str) -> Option<usize> {
+        let declared = T::CSV_COLUMNS.iter().position(|(f, _)| *f == field)?;
+        match &self.headers {
+            Some(headers) => {
+                let name = T::CSV_COLUMNS[declared].1;
+                headers.iter().position(|h| h == name)
+            }
+            None => Some(declared),
+        }
+    }
+
+    /// Reads the next row. The record is returned whenever it could be
+    /// deserialized, even if it failed validation.
+    pub(crate) fn next_row(&mut self) -> Option<(Option<T>, Vec<RowError>)> {
+        if self.done {
+            return None;
+        }
+        let result = match self.pending.take() {
+            Some(err) => Err(err),
+            None => self.reader.read_record(&mut self.record),
+        };
+        match result {
+            Ok(true) => match self.expected_len {
+                Some(expected) if self.record.len() != expected => {
+                    let kind = RowErrorKind::UnequalLengths {
+                        expected: expected as u64,
+                        found: self.record.len() as u64,
+                    };
+                    Some((None, vec![RowError::at(self.record.position(), kind)]))
+                }
+                _ => Some(self.check_record()),
+            },
+            Ok(false) => {
+                self.done = true;
+                None
+            }
+            Err(err) => {
+                let error = self.csv_error(err);
+                if matches!(error.kind, RowErrorKind::Io(_)) {
+                    self.done = true;
+                }
+                Some((None, vec![error]))
+            }
+        }
+    }
+
+    fn check_record(&self) -> (Option<T>, Vec<RowError>) {
+        let pos = self.record.position();
+        let value: T = match self.record.deserialize(self.headers.as_ref()) {
+            Ok(value) => value,
+            Err(err) => return (None, vec![self.csv_error(err)]),
+        };
+        let errors = match value.validate_csv() {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors
+                .into_iter()
+                .map(|e| {
+                    let column = self.column_of(&e.field);
+                    RowError::at(pos, RowErrorKind::Validation(e)).with_cell(&self.record, column)
+                })
+                .collect(),
+        };
+        (Some(value), errors)
+    }
+
+    fn csv_error(&self, err: csv::Error) -> RowError {
+        csv_row_error(err, &self.record, self.reader.position())
+    }
+}
+
+impl<R, T> Iterator for ValidatedRecords<'_, R, T>
+where
+    R: io::Read,
+    T: DeserializeOwned + ValidateCsv,
+{
+    type Item = Result<T, Vec<RowError>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_row()? {
+            (Some(value), errors) if errors.is_empty() => Some(Ok(value)),
+            (_, errors) => Some(Err(errors)),
+        }
+    }
+}
+
+/// Converts an error returned while reading `record` into a [`RowError`].
+pub(crate) fn csv_row_error(
+    err: csv::Error,
+    record: &StringRecord,
+    current: &Position,
+) -> RowError {
+    let fallback = record.position().cloned();
+    match err.into_kind() {
+        csv::ErrorKind::Deserialize { pos, err } => {
+            let column = err.field().map(|c| c as usize);
+            RowError::at(pos.as_ref().or(fallback.as_ref()), RowErrorKind::Deserialize(err.to_string()))
+                .with_cell(record, column)
+        }
+        csv::ErrorKind::UnequalLengths { pos, expected_len, len } => RowError::at(
+            pos.as_ref(),
+            RowErrorKind::UnequalLengths { expected: expected_len, found: len },
+        ),
+        csv::ErrorKind::Utf8 { pos, err } => {
+            let mut row = RowError::at(pos.as_ref(), RowErrorKind::Utf8(err.to_string()));
+            row.column = Some(err.field());
+            row
+        }
+        csv::ErrorKind::Io(err) => RowError::at(Some(current), RowErrorKind::Io(err.to_string())),
+        other => RowError::at(Some(current), RowErrorKind::Io(format!("{:?}", other))),
+    }
+}
+
+/// Summary of a whole-file validation.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValidationReport {
+    /// Number of data rows read.
+    pub rows: u64,
+    /// Number of rows that passed every check.
+    pub valid_rows: u64,
+    /// Every error found, in file order.
+    pub errors: Vec<RowError>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    pub(crate) fn add_row(&mut self, mut errors: Vec<RowError>) {
+        if errors.is_empty() {
+            self.rows += 1;
+            self.valid_rows += 1;
+        } else {
+            // an I/O failure does not correspond to a row
+            if !errors.iter().any(|e| matches!(e.kind, RowErrorKind::Io(_))) {
+                self.rows += 1;
+            }
+            self.errors.append(&mut errors);
+        }
+    }
+}
+
+/// Validates every row of `reader` as `T` and collects the errors.
+///
+/// Valid records are discarded; use [`validate_records`] to keep them.
+pub fn validate_reader<R, T>(reader: &mut Reader<R>) -> ValidationReport
+where
+    R: io::Read,
+    T: DeserializeOwned + ValidateCsv,
+{
+    let mut report = ValidationReport::default();
+    let mut rows = validate_records::<R, T>(reader);
+    while let Some((_, errors)) = rows.next_row() {
+        report.add_row(errors);
+    }
+    report
+}
@@ -0,0 +1,182 @@
+//! Machine-readable output for [`RowError`]s.
+//!
+//! Three formats are available: JSON Lines (one object per error), a
+//! SARIF-like JSON document for code-scanning dashboards, and a CSV "rejects"
+//! file holding the original rows that failed plus an `errors` column.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Write};
+
+use csv::{ByteRecord, Reader, Writer};
+use serde_json::{json, Map, Value};
+
+use crate::reader::{RowError, RowErrorKind};
+
+/// Rule parameters of an error, without the `code` tag.
+fn params(kind: &RowErrorKind) -> Value {
+    let value = match kind {
+        RowErrorKind::Validation(err) => serde_json::to_value(&err.code).unwrap_or(Value::Null),
+        RowErrorKind::UnequalLengths { expected, found } => {
+            json!({ "expected": expected, "found": found })
+        }
+        RowErrorKind::Duplicate {
+            fields,
+            key,
+            first_line,
+            first_record,
+        } => json!({
+            "fields": fields,
+            "key": key,
+            "first_line": first_line,
+            "first_record": first_record,
+        }),
+        RowErrorKind::Orphan { field, references } => {
+            json!({ "field": field, "references": references })
+        }
+        RowErrorKind::Deserialize(_) | RowErrorKind::Utf8(_) | RowErrorKind::Io(_) => Value::Null,
+    };
+    match value {
+        Value::Object(mut map) => {
+            map.remove("code");
+            Value::Object(map)
+        }
+        _ => Value::Object(Map::new()),
+    }
+}
+
+fn field(kind: &RowErrorKind) -> Option<&str> {
+    match kind {
+        RowErrorKind::Validation(err) => Some(&err.field),
+        RowErrorKind::Orphan { field, .. } => Some(field),
+        _ => None,
+    }
+}
+
+fn message(kind: &RowErrorKind) -> String {
+    match kind {
+        RowErrorKind::Validation(err) => err.message.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// JSON object describing one error.
+pub fn error_to_json(error: &RowError) -> Value {
+    json!({
+        "line": error.line,
+        "byte": error.byte,
+        "record": error.record,
+        "column": error.column,
+        "value": error.value,
+        "field": field(&error.kind),
+        "code": error.kind.code(),
+        "message": message(&error.kind),
+        "params": params(&error.kind),
+    })
+}
+
+/// Writes one JSON object per line.
+pub fn write_json_lines<W: Write>(mut out: W, errors: &[RowError]) -> io::Result<()> {
+    for error in errors {
+        serde_json::to_writer(&mut out, &error_to_json(error))?;
+        out.write_all(b"\n")?;
+    }
+    out.flush()
+}
+
+/// Writes a SARIF 2.1.0-style document; `artifact` is the URI of the CSV file.
+///
+/// Each error becomes a `result` whose `ruleId` is the error code. The CSV
+/// column, raw value and rule parameters go into the result `properties`.
+pub fn write_sarif<W: Write>(out: W, artifact: &str, errors: &[RowError]) -> io::Result<()> {
+    let rules: BTreeSet<&str> = errors.iter().map(|e| e.kind.code()).collect();
+    let results: Vec<Value> = errors
+        .iter()
+        .map(|e| {
+            let text = match field(&e.kind) {
+                Some(field) => format!("{}: {}", field, message(&e.kind)),
+                None => message(&e.kind),
+            };
+            json!({
+                "ruleId": e.kind.code(),
+                "level": "error",
+                "message": { "text": text },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": artifact },
+                        "region": { "startLine": e.line, "byteOffset": e.byte },
+                    }
+                }],
+                "properties": {
+                    "record": e.record,
+                    "column": e.column,
+                    "value": e.value,
+                    "field": field(&e.kind),
+                    "params": params(&e.kind),
+                },
+            })
+        })
+        .collect();
+    let document = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules.iter().map(|id| json!({ "id": id })).collect::<Vec<_>>(),
+                }
+            },
+            "results": results,
+        }]
+    });
+    serde_json::to_writer_pretty(out, &document)?;
+    Ok(())
+}
+
+/// Copies the rows of `source` that have errors to `out`, appending an
+/// `errors` column with the messages joined by `"; "`.
+///
+/// `source` must be a fresh reader over the same data that produced
+/// `errors`, since rows are matched by record index. Build both `source` and
+/// `out` with `flexible(true)` if the data may have rows of unequal length.
+/// Returns the number of rejected rows written.
+pub fn write_rejects<R: io::Read, W: Write>(
+    source: &mut Reader<R>,
+    errors: &[RowError],
+    out: &mut Writer<W>,
+) -> csv::Result<u64> {
+    let mut by_record: BTreeMap<u64, Vec<String>> = BTreeMap::new();
+    for error in errors {
+        by_record
+            .entry(error.record)
+            .or_default()
+            .push(error.kind.to_string());
+    }
+
+    if source.has_headers() {
+        let mut headers = source.byte_headers()?.clone();
+        headers.push_field(b"errors");
+        out.write_byte_record(&headers)?;
+    }
+
+    let mut written = 0;
+    let mut record = ByteRecord::new();
+    loop {
+        match source.read_byte_record(&mut record) {
+            Ok(true) => {}
+            Ok(false) => break,
+            // malformed rows are already in `errors` and cannot be copied
+            Err(e) if !matches!(e.kind(), csv::ErrorKind::Io(_)) => continue,
+            Err(e) => return Err(e),
+        }
+        let index = record.position().map_or(0, |p| p.record());
+        if let Some(messages) = by_record.get(&index) {
+            record.push_field(messages.join("; ").as_bytes());
+            out.write_byte_record(&record)?;
+            written += 1;
+        }
+    }
+    out.flush()?;
+    Ok(written)
+}
@@ -0,0 +1,370 @@
+//! Runtime schemas loaded from TOML or JSON files.
+//!
+//! A schema lists columns by header name, using the same rule vocabulary as
+//! `#[validate(...)]`:
+//!
+//! ```toml
+//! [[columns]]
+//! name = "grade"
+//! range = { min = 0.0, max = 100.0 }
+//!
+//! [[columns]]
+//! name = "code"
+//! required = true
+//! regex = '^[A-Z]{3}\d{4}$'
+//! ```
+//!
+//! Empty cells behave like `None` for an `Option<T>` field: they only fail
+//! `required`, and every other rule is skipped.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use csv::{Reader, StringRecord};
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::reader::{csv_row_error, RowError, RowErrorKind, ValidationReport};
+use crate::{ErrorCode, ValidationError};
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SchemaFile {
+    columns: Vec<ColumnFile>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ColumnFile {
+    name: String,
+    #[serde(default)]
+    required: bool,
+    #[serde(default)]
+    not_blank: bool,
+    range: Option<Bounds<f64>>,
+    length: Option<Bounds<usize>>,
+    regex: Option<String>,
+    one_of: Option<Vec<String>>,
+    not_in: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Bounds<T> {
+    min: Option<T>,
+    max: Option<T>,
+}
+
+/// A rule of a [`Schema`] column.
+#[derive(Debug, Clone)]
+pub enum Rule {
+    Required,
+    NotBlank,
+    Range { min: f64, max: f64 },
+    Length { min: usize, max: usize },
+    Regex(Regex),
+    OneOf(Vec<String>),
+    NotIn(Vec<String>),
+}
+
+/// Rules that apply to one column, identified by its header name.
+#[derive(Debug, Clone)]
+pub struct Column {
+    pub name: String,
+    pub rules: Vec<Rule>,
+}
+
+/// A validated, ready-to-use schema.
+#[derive(Debug, Clone)]
+pub struct Schema {
+    pub columns: Vec<Column>,
+}
+
+/// Errors raised while loading a schema.
+#[derive(Debug)]
+pub enum SchemaError {
+    Io(io::Error),
+    Toml(toml::de::Error),
+    Json(serde_json::Error),
+    /// The file parsed but a rule is inconsistent (e.g. `min > max`).
+    Invalid {
+        column: String,
+        message: String,
+    },
+    /// The file extension is neither `.toml` nor `.json`.
+    UnknownFormat(String),
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaError::Io(e) => write!(f, "cannot read schema: {}", e),
+            SchemaError::Toml(e) => write!(f, "invalid TOML schema: {}", e),
+            SchemaError::Json(e) => write!(f, "invalid JSON schema: {}", e),
+            SchemaError::Invalid { column, message } => write!(f, "{}: {}", column, message),
+            SchemaError::UnknownFormat(path) => {
+                write!(f, "{}: schema files must end in .toml or .json", path)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+fn invalid(column: &str, message: &str) -> SchemaError {
+    SchemaError::Invalid {
+        column: column.to_string(),
+        message: message.to_string(),
+    }
+}
+
+impl Column {
+    fn from_file(c: ColumnFile) -> Result<Self, SchemaError> {
+        let mut rules = Vec::new();
+        if c.required {
+            rules.push(Rule::Required);
+        }
+        if c.not_blank {
+            rules.push(Rule::NotBlank);
+        }
+        if let Some(Bounds { min, max }) = c.range {
+            if min.is_none() && max.is_none() {
+                return Err(invalid(
+                    &c.name,
+                    "`range` requires at least one of `min` or `max`",
+                ));
+            }
+            rules.push(Rule::Range {
+                min: min.unwrap_or(f64::NEG_INFINITY),
+                max: max.unwrap_or(f64::INFINITY),
+            });
+        }
+        if let Some(Bounds { min, max }) = c.length {
+            if min.is_none() && max.is_none() {
+                return Err(invalid(
+                    &c.name,
+                    "`length` requires at least one of `min` or `max`",
+                ));
+            }
+            if max == Some(0) {
+                return Err(invalid(&c.name, "`max` for `length` cannot be zero"));
+            }
+            if let (Some(a), Some(b)) = (min, max) {
+                if a > b {
+                    return Err(invalid(&c.name, "`min` must be <= `max` for `length`"));
+                }
+            }
+            rules.push(Rule::Length {
+                min: min.unwrap_or(0),
+                max: max.unwrap_or(usize::MAX),
+            });
+        }
+        if let Some(pattern) = c.regex {
+            let re = Regex::new(&pattern)
+                .map_err(|e| invalid(&c.name, &format!("invalid regex '{}': {}", pattern, e)))?;
+            rules.push(Rule::Regex(re));
+        }
+        if let Some(values) = c.one_of {
+            if values.is_empty() {
+                return Err(invalid(&c.name, "`one_of` requires at least one value"));
+            }
+            rules.push(Rule::OneOf(values));
+        }
+        if let Some(values) = c.not_in {
+            if values.is_empty() {
+                return Err(invalid(&c.name, "`not_in` requires at least one value"));
+            }
+            rules.push(Rule::NotIn(values));
+        }
+        Ok(Column {
+            name: c.name,
+            rules,
+        })
+    }
+
+    /// Checks one cell; `None` means the column is absent from the record.
+    fn check(&self, cell: Option<&str>) -> Vec<ValidationError> {
+        let mut codes = Vec::new();
+        let value = match cell {
+            Some(v) if !v.is_empty() => v,
+            _ => {
+                if self.rules.iter().any(|r| matches!(r, Rule::Required)) {
+                    codes.push(ErrorCode::Required);
+                }
+                return self.errors(codes);
+            }
+        };
+        for rule in &self.rules {
+            match rule {
+                Rule::Required => {}
+                Rule::NotBlank => {
+                    if value.trim().is_empty() {
+                        codes.push(ErrorCode::Blank {
+                            value: value.to_string(),
+                        });
+                    }
+                }
+                Rule::Range { min, max } => match value.trim().parse::<f64>() {
+                    Ok(n) if (*min..=*max).contains(&n) => {}
+                    Ok(n) => codes.push(ErrorCode::range(n, *min, *max)),
+                    Err(_) => codes.push(ErrorCode::NotANumber {
+                        value: value.to_string(),
+                    }),
+                },
+                Rule::Length { min, max } => {
+                    if !(*min..=*max).contains(&value.len()) {
+                        codes.push(ErrorCode::length(value, *min, *max));
+                    }
+                }
+                Rule::Regex(re) => {
+                    if !re.is_match(value) {
+                        codes.push(ErrorCode::RegexMismatch {
+                            value: value.to_string(),
+                            pattern: re.as_str().to_string(),
+                        });
+                    }
+                }
+                Rule::OneOf(values) => {
+                    if !values.iter().any(|v| v == value) {
+                        codes.push(ErrorCode::NotInSet {
+                            value: value.to_string(),
+                            allowed: values.clone(),
+                        });
+                    }
+                }
+                Rule::NotIn(values) => {
+                    if values.iter().any(|v| v == value) {
+                        codes.push(ErrorCode::ForbiddenValue {
+                            value: value.to_string(),
+                            forbidden: values.clone(),
+                        });
+                    }
+                }
+            }
+        }
+        self.errors(codes)
+    }
+
+    fn errors(&self, codes: Vec<ErrorCode>) -> Vec<ValidationError> {
+        codes
+            .into_iter()
+            .map(|code| ValidationError::new(self.name.clone(), code))
+            .collect()
+    }
+}
+
+impl Schema {
+    fn from_file(file: SchemaFile) -> Result<Self, SchemaError> {
+        let columns = file
+            .columns
+            .into_iter()
+            .map(Column::from_file)
+            .collect::<Result<_, _>>()?;
+        Ok(Schema { columns })
+    }
+
+    pub fn from_toml_str(s: &str) -> Result<Self, SchemaError> {
+        Self::from_file(toml::from_str(s).map_err(SchemaError::Toml)?)
+    }
+
+    pub fn from_json_str(s: &str) -> Result<Self, SchemaError> {
+        Self::from_file(serde_json::from_str(s).map_err(SchemaError::Json)?)
+    }
+
+    /// Loads a `.toml` or `.json` schema file.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, SchemaError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(SchemaError::Io)?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Self::from_toml_str(&text),
+            Some("json") => Self::from_json_str(&text),
+            _ => Err(SchemaError::UnknownFormat(path.display().to_string())),
+        }
+    }
+
+    fn column_indexes(&self, headers: &StringRecord) -> Vec<Option<usize>> {
+        self.columns
+            .iter()
+            .map(|c| headers.iter().position(|h| h == c.name))
+            .collect()
+    }
+
+    /// Validates one record, looking its cells up by header name.
+    pub fn validate_record(
+        &self,
+        headers: &StringRecord,
+        record: &StringRecord,
+    ) -> Result<(), Vec<ValidationError>> {
+        let errors: Vec<_> = self
+            .columns
+            .iter()
+            .zip(self.column_indexes(headers))
+            .flat_map(|(column, index)| column.check(index.and_then(|i| record.get(i))))
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Streams `reader`, which must have headers, and validates every row.
+    ///
+    /// Schema columns missing from the header are reported once, on the
+    /// header line, and their rules are not evaluated.
+    pub fn validate_reader<R: io::Read>(&self, reader: &mut Reader<R>) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        let mut record = StringRecord::new();
+
+        let headers = match reader.headers() {
+            Ok(h) => h.clone(),
+            Err(err) => {
+                let error = csv_row_error(err, &record, reader.position());
+                report.errors.push(error);
+                return report;
+            }
+        };
+        let indexes = self.column_indexes(&headers);
+        for (column, index) in self.columns.iter().zip(&indexes) {
+            if index.is_none() {
+                let error = ValidationError::new(column.name.clone(), ErrorCode::MissingColumn);
+                report.errors.push(RowError::at(
+                    headers.position(),
+                    RowErrorKind::Validation(error),
+                ));
+            }
+        }
+
+        loop {
+            match reader.read_record(&mut record) {
+                Ok(true) => {
+                    let pos = record.position();
+                    let mut errors = Vec::new();
+                    for (column, index) in self.columns.iter().zip(&indexes) {
+                        if let Some(index) = *index {
+                            for e in column.check(record.get(index)) {
+                                errors.push(
+                                    RowError::at(pos, RowErrorKind::Validation(e))
+                                        .with_cell(&record, Some(index)),
+                                );
+                            }
+                        }
+                    }
+                    report.add_row(errors);
+                }
+                Ok(false) => break,
+                Err(err) => {
+                    let error = csv_row_error(err, &record, reader.position());
+                    let fatal = matches!(error.kind, RowErrorKind::Io(_));
+                    report.add_row(vec![error]);
+                    if fatal {
+                        break;
+                    }
+                }
+            }
+        }
+        report
+    }
+}
@@ -0,0 +1,94 @@
+//! Helpers behind the typed built-in validators (`email`, `cpf`, `decimal`, ...).
+//!
+//! The derive calls these functions from the generated code, but they are
+//! public so `custom` validators can reuse them.
+
+use std::net::IpAddr;
+
+use chrono::NaiveDate;
+
+/// Pattern used by `#[validate(email)]`: one `@`, no spaces, and a dotted domain.
+pub const EMAIL_PATTERN: &str = r"^[A-Za-z0-9.!#$%&'*+/=?^_`{|}~-]+@[A-Za-z0-9](?:[A-Za-z0-9-]{0,61}[A-Za-z0-9])?(?:\.[A-Za-z0-9](?:[A-Za-z0-9-]{0,61}[A-Za-z0-9])?)+$";
+
+/// Pattern used by `#[validate(url)]`: a scheme, `://`, a host and no spaces.
+pub const URL_PATTERN: &str = r"^[A-Za-z][A-Za-z0-9+.-]*://[^\s/?#@]+(?:@[^\s/?#]+)?(?:[/?#]\S*)?$";
+
+/// Pattern used by `#[validate(uuid)]`: the hyphenated 8-4-4-4-12 hex form.
+pub const UUID_PATTERN: &str =
+    r"^[0-9A-Fa-f]{8}-[0-9A-Fa-f]{4}-[0-9A-Fa-f]{4}-[0-9A-Fa-f]{4}-[0-9A-Fa-f]{12}$";
+
+/// Parses `value` as a date in the `chrono` `format` (e.g. `"%Y-%m-%d"`).
+pub fn parse_date(value: &str, format: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value, format).ok()
+}
+
+/// Keeps only the digits of a document number, accepting the usual
+/// punctuation (`.`, `-`, `/`).
+fn document_digits(value: &str, len: usize) -> Option<Vec<u32>> {
+    let mut digits = Vec::with_capacity(len);
+    for c in value.chars() {
+        match c {
+            '0'..='9' => digits.push(c as u32 - '0' as u32),
+            '.' | '-' | '/' => {}
+            _ => return None,
+        }
+    }
+    // números com todos os dígitos iguais passam no cálculo, mas são inválidos
+    if digits.len() != len || digits.iter().all(|d| *d == digits[0]) {
+        return None;
+    }
+    Some(digits)
+}
+
+fn check_digit(digits: &[u32], weights: impl Iterator<Item = u32>) -> u32 {
+    let sum: u32 = digits.iter().zip(weights).map(|(d, w)| d * w).sum();
+    match sum % 11 {
+        0 | 1 => 0,
+        r => 11 - r,
+    }
+}
+
+/// Validates a Brazilian CPF, with or without punctuation (`123.456.789-09`).
+pub fn is_cpf(value: &str) -> bool {
+    let d = match document_digits(value, 11) {
+        Some(d) => d,
+        None => return false,
+    };
+    check_digit(&d[..9], (2..=10).rev()) == d[9] && check_digit(&d[..10], (2..=11).rev()) == d[10]
+}
+
+/// Validates a Brazilian CNPJ, with or without punctuation (`11.222.333/0001-81`).
+pub fn is_cnpj(value: &str) -> bool {
+    let d = match document_digits(value, 14) {
+        Some(d) => d,
+        None => return false,
+    };
+    const WEIGHTS: [u32; 13] = [6, 5, 4, 3, 2, 9, 8, 7, 6, 5, 4, 3, 2];
+    check_digit(&d[..12], WEIGHTS[1..].iter().copied()) == d[12]
+        && check_digit(&d[..13], WEIGHTS.iter().copied()) == d[13]
+}
+
+/// Checks that `value` is a plain decimal number (optional sign, digits,
+/// optional `.` and fraction) with at most `precision` significant digits,
+/// of which at most `scale` after the point.
+pub fn is_decimal(value: &str, precision: u32, scale: u32) -> bool {
+    let unsigned = value.strip_prefix(['-', '+']).unwrap_or(value);
+    let (int, frac) = match unsigned.split_once('.') {
+        Some((i, f)) => (i, f),
+        None => (unsigned, ""),
+    };
+    if (int.is_empty() && frac.is_empty())
+        || !int.bytes().all(|b| b.is_ascii_digit())
+        || !frac.bytes().all(|b| b.is_ascii_digit())
+    {
+        return false;
+    }
+    let int_digits = int.trim_start_matches('0').len();
+    let frac_digits = frac.len();
+    frac_digits <= scale as usize && int_digits + frac_digits <= precision as usize
+}
+
+/// Validates an IPv4 or IPv6 address.
+pub fn is_ip(value: &str) -> bool {
+    value.parse::<IpAddr>().is_ok()
+}
@@ -0,0 +1,96 @@
+use csv::ReaderBuilder;
+use csv_schema_validator::{DatasetValidator, RowErrorKind, ValidateCsv};
+use serde::Deserialize;
+
+#[derive(Deserialize, ValidateCsv, Debug)]
+#[validate(primary_key("order_id", "item"))]
+struct OrderItem {
+    order_id: u32,
+    item: u32,
+
+    #[validate(references = "customers")]
+    customer: String,
+
+    #[validate(unique)]
+    invoice: Option<String>,
+}
+
+#[test]
+fn test_duplicates_and_orphans() {
+    let customers = "id,name\nC1,Alice\nC2,Bob\n";
+    let orders = "order_id,item,customer,invoice\n\
+                  1,1,C1,INV-1\n\
+                  1,2,C2,INV-2\n\
+                  1,1,C2,\n\
+                  2,1,C9,INV-1\n";
+
+    let mut reference = ReaderBuilder::new().from_reader(customers.as_bytes());
+    let mut validator = DatasetValidator::<OrderItem>::new()
+        .load_reference("customers", &mut reference, "id")
+        .unwrap();
+
+    let mut reader = ReaderBuilder::new().from_reader(orders.as_bytes());
+    let report = validator.validate_reader(&mut reader);
+
+    assert_eq!(report.rows, 4);
+    assert_eq!(report.valid_rows, 2);
+    assert_eq!(report.errors.len(), 3);
+
+    let pk = &report.errors[0];
+    assert_eq!(pk.line, 4);
+    assert_eq!(pk.column, None);
+    assert_eq!(
+        pk.kind,
+        RowErrorKind::Duplicate {
+            fields: vec!["order_id".into(), "item".into()],
+            key: vec!["1".into(), "1".into()],
+            first_line: 2,
+            first_record: 1,
+        }
+    );
+
+    let orphan = &report.errors[1];
+    assert_eq!(orphan.line, 5);
+    assert_eq!(orphan.column, Some(2));
+    assert_eq!(orphan.value.as_deref(), Some("C9"));
+    assert_eq!(
+        orphan.kind,
+        RowErrorKind::Orphan {
+            field: "customer".into(),
+            references: "customers".into()
+        }
+    );
+
+    let unique = &report.errors[2];
+    assert_eq!(unique.column, Some(3));
+    assert_eq!(unique.value.as_deref(), Some("INV-1"));
+    assert!(matches!(
+        unique.kind,
+        RowErrorKind::Duplicate { first_line: 2, .. }
+    ));
+}
+
+#[test]
+fn test_state_is_kept_across_readers() {
+    let mut validator = DatasetValidator::<OrderItem>::new().with_reference("customers", ["C1"]);
+
+    let mut part1 =
+        ReaderBuilder::new().from_reader("order_id,item,customer,invoice\n1,1,C1,\n".as_bytes());
+    assert!(validator.validate_reader(&mut part1).is_valid());
+
+    let mut part2 =
+        ReaderBuilder::new().from_reader("order_id,item,customer,invoice\n1,1,C1,\n".as_bytes());
+    let report = validator.validate_reader(&mut part2);
+    assert!(matches!(
+        report.errors[0].kind,
+        RowErrorKind::Duplicate { .. }
+    ));
+}
+
+#[test]
+fn test_missing_reference_column() {
+    let mut reference = ReaderBuilder::new().from_reader("code\nC1\n".as_bytes());
+    let result =
+        DatasetValidator::<OrderItem>::new().load_reference("customers", &mut reference, "id");
+    assert!(result.is_err());
+}
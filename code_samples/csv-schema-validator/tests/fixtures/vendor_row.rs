@@ -0,0 +1,31 @@
+// Inferred from a sample of 4 rows; review the rules before use.
+use csv_schema_validator::ValidateCsv;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, ValidateCsv)]
+pub struct VendorRow {
+    #[serde(rename = "Order ID")]
+    #[validate(range(min = 1001.0, max = 1004.0))]
+    pub order_id: i64,
+    #[serde(rename = "Customer Name")]
+    #[validate(length(max = 10), not_blank)]
+    pub customer_name: String,
+    #[validate(one_of("closed", "open"))]
+    pub status: String,
+    #[serde(rename = "Unit Price (R$)")]
+    #[validate(range(min = -3.25, max = 100.0))]
+    pub unit_price_r: f64,
+    #[validate(range(min = 1.0, max = 12.0))]
+    pub qty: i64,
+    #[validate(length(max = 8), not_blank)]
+    pub zip: String,
+    #[serde(rename = "type")]
+    #[validate(one_of("A", "B"))]
+    pub type_: String,
+    pub active: bool,
+    #[validate(length(max = 7), not_blank)]
+    pub notes: Option<String>,
+    #[serde(rename = "")]
+    #[validate(one_of("x"))]
+    pub column_10: String,
+}
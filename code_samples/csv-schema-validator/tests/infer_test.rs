@@ -0,0 +1,70 @@
+use csv::ReaderBuilder;
+use csv_schema_validator::infer::{infer_reader, InferOptions, InferredType};
+use csv_schema_validator::validate_reader;
+
+// gerado por `csv-infer --name VendorRow tests/fixtures/vendor_sample.csv`
+#[allow(dead_code)]
+mod generated {
+    include!("fixtures/vendor_row.rs");
+}
+
+const SAMPLE: &str = include_str!("fixtures/vendor_sample.csv");
+
+fn reader() -> csv::Reader<&'static [u8]> {
+    ReaderBuilder::new().from_reader(SAMPLE.as_bytes())
+}
+
+#[test]
+fn test_generated_source() {
+    let inference = infer_reader(&mut reader(), &InferOptions::default()).unwrap();
+    assert_eq!(
+        inference.to_rust("VendorRow"),
+        include_str!("fixtures/vendor_row.rs")
+    );
+}
+
+#[test]
+fn test_generated_struct_accepts_sample() {
+    let report = validate_reader::<_, generated::VendorRow>(&mut reader());
+    assert_eq!(report.rows, 4);
+    assert!(report.is_valid(), "{:?}", report.errors);
+}
+
+#[test]
+fn test_inferred_columns() {
+    let inference = infer_reader(&mut reader(), &InferOptions::default()).unwrap();
+    let column = |name: &str| {
+        inference
+            .columns
+            .iter()
+            .find(|c| c.name == name)
+            .unwrap()
+            .clone()
+    };
+
+    assert_eq!(inference.rows, 4);
+    assert_eq!(column("qty").ty, Some(InferredType::Integer));
+    assert_eq!(column("Unit Price (R$)").ty, Some(InferredType::Float));
+    assert_eq!(column("Unit Price (R$)").min, Some(-3.25));
+    // zeros à esquerda mantêm a coluna como texto
+    assert_eq!(column("zip").ty, Some(InferredType::Text));
+    assert_eq!(column("active").ty, Some(InferredType::Bool));
+    assert!(column("notes").nullable);
+    assert_eq!(column("notes").count, 1);
+    assert_eq!(column("status").values.unwrap().len(), 2);
+}
+
+#[test]
+fn test_options() {
+    let options = InferOptions {
+        max_rows: Some(2),
+        max_enum_values: 1,
+    };
+    let inference = infer_reader(&mut reader(), &options).unwrap();
+    assert_eq!(inference.rows, 2);
+    let status = &inference.columns[2];
+    assert_eq!(status.values, None);
+    assert!(inference
+        .to_rust("Row")
+        .contains("    #[validate(length(max = 6), not_blank)]\n    pub status: String,\n"));
+}
@@ -0,0 +1,113 @@
+#![cfg(feature = "parallel")]
+
+use std::io::{self, Read};
+
+use csv::ReaderBuilder;
+use csv_schema_validator::parallel::ParallelValidator;
+use csv_schema_validator::{validate_reader, RowErrorKind, ValidateCsv, ValidationReport};
+use serde::Deserialize;
+
+#[allow(dead_code)]
+#[derive(Deserialize, ValidateCsv, Debug)]
+struct Note {
+    #[validate(range(min = 1.0))]
+    id: i64,
+    #[validate(length(max = 12))]
+    text: String,
+    #[validate(one_of("a", "b"))]
+    kind: String,
+}
+
+// aspas com quebras de linha, aspas escapadas, linhas vazias, CRLF,
+// linhas com tamanho errado e valores inválidos; sem \n no final
+const DATA: &[u8] = b"id,text,kind\n\
+1,plain,a\n\
+2,\"two\nlines\",b\n\
+3,\"say \"\"hi\"\"\",c\n\
+\n\
+x,bad id,a\r\n\
+5,\"a,b\",a,extra\n\
+6,\"quoted \"\"\nnewline\"\"\",b\n\
+0,this text is too long,a\n\
+8,end,b";
+
+fn sequential(data: &[u8], has_headers: bool) -> ValidationReport {
+    let mut reader = ReaderBuilder::new()
+        .has_headers(has_headers)
+        .from_reader(data);
+    validate_reader::<_, Note>(&mut reader)
+}
+
+#[test]
+fn test_same_report_as_sequential() {
+    let expected = sequential(DATA, true);
+    assert_eq!(expected.rows, 8);
+    assert_eq!(expected.errors.len(), 6);
+
+    for chunk_size in [1, 5, 16, 40, 1 << 20] {
+        let report = ParallelValidator::new()
+            .chunk_size(chunk_size)
+            .threads(3)
+            .validate_reader::<_, Note>(DATA);
+        assert_eq!(report, expected, "chunk_size = {}", chunk_size);
+    }
+}
+
+#[test]
+fn test_without_headers() {
+    let data = &DATA[b"id,text,kind\n".len()..];
+    let expected = sequential(data, false);
+    let report = ParallelValidator::new()
+        .has_headers(false)
+        .chunk_size(8)
+        .validate_reader::<_, Note>(data);
+    assert_eq!(report, expected);
+    assert!(report.errors.iter().any(|e| e.kind
+        == RowErrorKind::UnequalLengths {
+            expected: 3,
+            found: 4
+        }));
+}
+
+#[test]
+fn test_flexible_and_delimiter() {
+    let data = b"id;text;kind\n1;ok;a\n2;ok;b;extra\n3;ok\n";
+    let mut reader = ReaderBuilder::new()
+        .delimiter(b';')
+        .flexible(true)
+        .from_reader(&data[..]);
+    let expected = validate_reader::<_, Note>(&mut reader);
+    let report = ParallelValidator::new()
+        .delimiter(b';')
+        .flexible(true)
+        .chunk_size(4)
+        .validate_reader::<_, Note>(&data[..]);
+    assert_eq!(report, expected);
+    assert_eq!(report.valid_rows, 2);
+}
+
+/// Fails after returning `data`.
+struct Failing<'a> {
+    data: &'a [u8],
+}
+
+impl Read for Failing<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.data.is_empty() {
+            return Err(io::Error::other("disk on fire"));
+        }
+        self.data.read(buf)
+    }
+}
+
+#[test]
+fn test_io_error_stops() {
+    let data = b"id,text,kind\n1,ok,a\n2,ok,b\n";
+    let report = ParallelValidator::new()
+        .chunk_size(4)
+        .validate_reader::<_, Note>(Failing { data: &data[..] });
+    assert_eq!(report.rows, 2);
+    let error = report.errors.last().unwrap();
+    assert_eq!(error.kind, RowErrorKind::Io("disk on fire".into()));
+    assert_eq!(error.line, 4);
+}
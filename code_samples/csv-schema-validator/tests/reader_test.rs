@@ -0,0 +1,83 @@
+use csv::ReaderBuilder;
+use csv_schema_validator::{validate_reader, validate_records, RowErrorKind, ValidateCsv};
+use serde::Deserialize;
+
+#[derive(Deserialize, ValidateCsv, Debug)]
+struct Person {
+    #[validate(length(min = 2, max = 10))]
+    name: String,
+
+    #[serde(rename = "score")]
+    #[validate(range(min = 0.0, max = 10.0))]
+    grade: f64,
+
+    #[validate(one_of("A", "B"))]
+    group: String,
+}
+
+#[test]
+fn test_report_locates_validation_errors() {
+    let data = "name,score,group\nAlice,9.5,A\nBob,11.0,C\n";
+    let mut reader = ReaderBuilder::new().from_reader(data.as_bytes());
+    let report = validate_reader::<_, Person>(&mut reader);
+
+    assert_eq!(report.rows, 2);
+    assert_eq!(report.valid_rows, 1);
+    assert_eq!(report.errors.len(), 2);
+
+    let grade = &report.errors[0];
+    assert_eq!(grade.line, 3);
+    assert_eq!(grade.byte, 29);
+    assert_eq!(grade.column, Some(1));
+    assert_eq!(grade.value.as_deref(), Some("11.0"));
+    match &grade.kind {
+        RowErrorKind::Validation(e) => assert_eq!(e.field, "grade"),
+        other => panic!("unexpected error kind: {:?}", other),
+    }
+
+    let group = &report.errors[1];
+    assert_eq!(group.column, Some(2));
+    assert_eq!(group.value.as_deref(), Some("C"));
+}
+
+#[test]
+fn test_columns_are_matched_by_header_name() {
+    let data = "group,score,name\nC,5.0,Alice\n";
+    let mut reader = ReaderBuilder::new().from_reader(data.as_bytes());
+    let report = validate_reader::<_, Person>(&mut reader);
+
+    assert_eq!(report.errors.len(), 1);
+    assert_eq!(report.errors[0].column, Some(0));
+    assert_eq!(report.errors[0].value.as_deref(), Some("C"));
+}
+
+#[test]
+fn test_deserialize_errors_are_reported() {
+    let data = "Alice,abc,A\nBob,1.0,B\n";
+    let mut reader = ReaderBuilder::new()
+        .has_headers(false)
+        .from_reader(data.as_bytes());
+    let results: Vec<_> = validate_records::<_, Person>(&mut reader).collect();
+
+    assert_eq!(results.len(), 2);
+    let errors = results[0].as_ref().unwrap_err();
+    assert_eq!(errors[0].line, 1);
+    assert_eq!(errors[0].column, Some(1));
+    assert_eq!(errors[0].value.as_deref(), Some("abc"));
+    assert!(matches!(errors[0].kind, RowErrorKind::Deserialize(_)));
+    assert_eq!(results[1].as_ref().unwrap().name, "Bob");
+}
+
+#[test]
+fn test_unequal_lengths_do_not_stop_the_stream() {
+    let data = "name,score,group\nAlice,1.0\nBob,2.0,B\n";
+    let mut reader = ReaderBuilder::new().from_reader(data.as_bytes());
+    let report = validate_reader::<_, Person>(&mut reader);
+
+    assert_eq!(report.rows, 2);
+    assert_eq!(report.valid_rows, 1);
+    assert_eq!(
+        report.errors[0].kind,
+        RowErrorKind::UnequalLengths { expected: 3, found: 2 }
+    );
+}
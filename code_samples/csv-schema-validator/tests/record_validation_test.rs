@@ -0,0 +1,136 @@
+use assert_matches::assert_matches;
+use csv_schema_validator::{ErrorCode, ValidateCsv, ValidationError};
+use serde::Deserialize;
+
+#[derive(Deserialize, ValidateCsv, Debug)]
+#[validate(compare(left = "end_date", op = ">=", right = "start_date"))]
+#[validate(exactly_one_of("cpf", "cnpj"))]
+#[validate(required_if(field = "closed_at", when = "status", equals = "closed"))]
+#[validate(custom = "check_contract")]
+struct Contract {
+    #[validate(one_of("open", "closed"))]
+    status: String,
+    start_date: String,
+    end_date: Option<String>,
+    cpf: Option<String>,
+    cnpj: Option<String>,
+    closed_at: Option<String>,
+    amount: f64,
+}
+
+fn check_contract(c: &Contract) -> Result<(), String> {
+    if c.status == "closed" && c.amount > 0.0 {
+        Err("closed contracts must have no pending amount".into())
+    } else {
+        Ok(())
+    }
+}
+
+fn contract() -> Contract {
+    Contract {
+        status: "open".into(),
+        start_date: "2024-01-01".into(),
+        end_date: Some("2024-12-31".into()),
+        cpf: Some("12345678900".into()),
+        cnpj: None,
+        closed_at: None,
+        amount: 10.0,
+    }
+}
+
+#[test]
+fn test_valid_contract() {
+    assert_matches!(contract().validate_csv(), Ok(()));
+}
+
+#[test]
+fn test_compare_fields() {
+    let record = Contract {
+        end_date: Some("2023-12-31".into()),
+        ..contract()
+    };
+    let errors = record.validate_csv().unwrap_err();
+    assert_eq!(
+        errors,
+        vec![ValidationError {
+            field: "end_date".to_string(),
+            message: "must be >= start_date".to_string(),
+            code: ErrorCode::CompareFailed {
+                op: ">=".into(),
+                other: "start_date".into()
+            },
+        }]
+    );
+
+    // comparação ignorada quando o campo opcional está ausente
+    let record = Contract {
+        end_date: None,
+        ..contract()
+    };
+    assert_matches!(record.validate_csv(), Ok(()));
+}
+
+#[test]
+fn test_exactly_one_of() {
+    let both = Contract {
+        cnpj: Some("12345678000199".into()),
+        ..contract()
+    };
+    let errors = both.validate_csv().unwrap_err();
+    assert_eq!(errors[0].field, "cpf,cnpj");
+    assert_eq!(errors[0].message, "exactly one of cpf, cnpj must be present");
+    assert_eq!(
+        errors[0].code,
+        ErrorCode::ExactlyOneOf {
+            fields: vec!["cpf".into(), "cnpj".into()],
+            present: 2
+        }
+    );
+
+    let none = Contract {
+        cpf: None,
+        ..contract()
+    };
+    assert_eq!(none.validate_csv().unwrap_err()[0].field, "cpf,cnpj");
+}
+
+#[test]
+fn test_required_if_and_record_custom() {
+    let record = Contract {
+        status: "closed".into(),
+        ..contract()
+    };
+    let errors = record.validate_csv().unwrap_err();
+    assert_eq!(
+        errors,
+        vec![
+            ValidationError {
+                field: "closed_at".to_string(),
+                message: "mandatory field when status is closed".to_string(),
+                code: ErrorCode::RequiredIf {
+                    when: "status".into(),
+                    equals: Some("closed".into())
+                },
+            },
+            ValidationError {
+                field: "Contract".to_string(),
+                message: "closed contracts must have no pending amount".to_string(),
+                code: ErrorCode::Custom {
+                    message: "closed contracts must have no pending amount".into()
+                },
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_field_errors_come_first() {
+    let record = Contract {
+        status: "archived".into(),
+        cpf: None,
+        ..contract()
+    };
+    let errors = record.validate_csv().unwrap_err();
+    assert_eq!(errors[0].field, "status");
+    assert_eq!(errors[1].field, "cpf,cnpj");
+}
@@ -0,0 +1,100 @@
+#![cfg(feature = "report")]
+
+use csv::{ReaderBuilder, WriterBuilder};
+use csv_schema_validator::report::{write_json_lines, write_rejects, write_sarif};
+use csv_schema_validator::{validate_reader, ErrorCode, ValidateCsv};
+use serde::Deserialize;
+use serde_json::Value;
+
+#[derive(Deserialize, ValidateCsv, Debug)]
+struct Item {
+    #[validate(length(max = 5))]
+    sku: String,
+
+    #[validate(range(min = 1.0))]
+    price: f64,
+}
+
+const DATA: &str = "sku,price\nAB1,10.0\nTOOLONG,0.5\nX,abc\n";
+
+fn errors() -> Vec<csv_schema_validator::RowError> {
+    let mut reader = ReaderBuilder::new().from_reader(DATA.as_bytes());
+    validate_reader::<_, Item>(&mut reader).errors
+}
+
+#[test]
+fn test_error_codes() {
+    let errors = errors();
+    let codes: Vec<_> = errors.iter().map(|e| e.kind.code()).collect();
+    assert_eq!(codes, ["length_too_long", "range_below_min", "deserialize"]);
+    assert_eq!(
+        ErrorCode::range(0.5, 1.0, f64::INFINITY),
+        ErrorCode::RangeBelowMin {
+            value: 0.5,
+            min: 1.0,
+            max: f64::INFINITY
+        }
+    );
+}
+
+#[test]
+fn test_json_lines() {
+    let mut out = Vec::new();
+    write_json_lines(&mut out, &errors()).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    let lines: Vec<Value> = text
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0]["code"], "length_too_long");
+    assert_eq!(lines[0]["field"], "sku");
+    assert_eq!(lines[0]["line"], 3);
+    assert_eq!(lines[0]["column"], 0);
+    assert_eq!(lines[0]["value"], "TOOLONG");
+    assert_eq!(lines[0]["params"]["length"], 7);
+    assert_eq!(lines[0]["params"]["max"], 5);
+    assert!(lines[0]["params"].get("code").is_none());
+    assert_eq!(lines[2]["code"], "deserialize");
+    assert_eq!(lines[2]["field"], Value::Null);
+}
+
+#[test]
+fn test_sarif() {
+    let mut out = Vec::new();
+    write_sarif(&mut out, "items.csv", &errors()).unwrap();
+    let doc: Value = serde_json::from_slice(&out).unwrap();
+
+    assert_eq!(doc["version"], "2.1.0");
+    let run = &doc["runs"][0];
+    assert_eq!(run["tool"]["driver"]["rules"].as_array().unwrap().len(), 3);
+    let result = &run["results"][1];
+    assert_eq!(result["ruleId"], "range_below_min");
+    assert_eq!(
+        result["message"]["text"],
+        "price: value out of expected range: 1 to inf"
+    );
+    let location = &result["locations"][0]["physicalLocation"];
+    assert_eq!(location["artifactLocation"]["uri"], "items.csv");
+    assert_eq!(location["region"]["startLine"], 3);
+    assert_eq!(result["properties"]["column"], 1);
+}
+
+#[test]
+fn test_rejects_file() {
+    let errors = errors();
+    let mut source = ReaderBuilder::new().from_reader(DATA.as_bytes());
+    let mut out = WriterBuilder::new().from_writer(Vec::new());
+    let written = write_rejects(&mut source, &errors, &mut out).unwrap();
+    let text = String::from_utf8(out.into_inner().unwrap()).unwrap();
+
+    assert_eq!(written, 2);
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[0], "sku,price,errors");
+    assert_eq!(
+        lines[1],
+        "TOOLONG,0.5,sku: length out of expected range: 0 to 5; price: value out of expected range: 1 to inf"
+    );
+    assert!(lines[2].starts_with("X,abc,deserialization failed"));
+}
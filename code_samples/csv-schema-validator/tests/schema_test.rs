@@ -0,0 +1,155 @@
+#![cfg(feature = "schema")]
+
+use std::fs;
+use std::process::Command;
+
+use csv::{ReaderBuilder, StringRecord};
+use csv_schema_validator::schema::{Schema, SchemaError};
+use csv_schema_validator::{ErrorCode, RowErrorKind, ValidationError};
+
+const SCHEMA_TOML: &str = r#"
+[[columns]]
+name = "grade"
+range = { min = 0.0, max = 100.0 }
+
+[[columns]]
+name = "code"
+regex = '^[A-Z]{3}\d{4}$'
+
+[[columns]]
+name = "name"
+required = true
+not_blank = true
+length = { min = 10, max = 50 }
+
+[[columns]]
+name = "size"
+one_of = ["short", "medium", "long"]
+"#;
+
+const SCHEMA_JSON: &str = r#"{
+  "columns": [
+    { "name": "grade", "range": { "min": 0.0, "max": 100.0 } },
+    { "name": "tag", "required": true, "not_in": ["forbidden", "banned"] }
+  ]
+}"#;
+
+#[test]
+fn test_validate_record_by_header_name() {
+    let schema = Schema::from_toml_str(SCHEMA_TOML).unwrap();
+    let headers = StringRecord::from(vec!["size", "name", "code", "grade"]);
+
+    let ok = StringRecord::from(vec!["short", "John Smith Jr", "ABC1234", "75"]);
+    assert!(schema.validate_record(&headers, &ok).is_ok());
+
+    let bad = StringRecord::from(vec!["huge", "", "abc1234", "150"]);
+    let errors = schema.validate_record(&headers, &bad).unwrap_err();
+    let found: Vec<_> = errors
+        .iter()
+        .map(|e| (e.field.as_str(), e.message.as_str(), e.code.as_str()))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (
+                "grade",
+                "value out of expected range: 0 to 100",
+                "range_above_max"
+            ),
+            (
+                "code",
+                "does not match the expected pattern",
+                "regex_mismatch"
+            ),
+            ("name", "mandatory field", "required"),
+            ("size", "invalid value", "not_in_set"),
+        ]
+    );
+    assert_eq!(
+        errors[3],
+        ValidationError::new(
+            "size",
+            ErrorCode::NotInSet {
+                value: "huge".into(),
+                allowed: vec!["short".into(), "medium".into(), "long".into()]
+            }
+        )
+    );
+}
+
+#[test]
+fn test_json_schema_and_reader() {
+    let schema = Schema::from_json_str(SCHEMA_JSON).unwrap();
+    let data = "grade,tag\n50,ok\nabc,banned\n";
+    let mut reader = ReaderBuilder::new().from_reader(data.as_bytes());
+    let report = schema.validate_reader(&mut reader);
+
+    assert_eq!(report.rows, 2);
+    assert_eq!(report.valid_rows, 1);
+    assert_eq!(report.errors.len(), 2);
+    assert_eq!(report.errors[0].line, 3);
+    assert_eq!(report.errors[0].column, Some(0));
+    assert_eq!(report.errors[0].value.as_deref(), Some("abc"));
+    assert_eq!(report.errors[1].column, Some(1));
+}
+
+#[test]
+fn test_missing_column_is_reported_on_header() {
+    let schema = Schema::from_json_str(SCHEMA_JSON).unwrap();
+    let mut reader = ReaderBuilder::new().from_reader("grade\n1\n".as_bytes());
+    let report = schema.validate_reader(&mut reader);
+
+    assert_eq!(report.errors.len(), 1);
+    assert_eq!(report.errors[0].line, 1);
+    match &report.errors[0].kind {
+        RowErrorKind::Validation(e) => assert_eq!(e.field, "tag"),
+        other => panic!("unexpected error kind: {:?}", other),
+    }
+}
+
+#[test]
+fn test_invalid_schemas() {
+    let bad_length = "[[columns]]\nname = \"a\"\nlength = { min = 5, max = 2 }\n";
+    assert!(matches!(
+        Schema::from_toml_str(bad_length),
+        Err(SchemaError::Invalid { .. })
+    ));
+    let bad_regex = r#"{ "columns": [ { "name": "a", "regex": "(" } ] }"#;
+    assert!(matches!(
+        Schema::from_json_str(bad_regex),
+        Err(SchemaError::Invalid { .. })
+    ));
+    let unknown_rule = "[[columns]]\nname = \"a\"\nmaximum = 3\n";
+    assert!(matches!(
+        Schema::from_toml_str(unknown_rule),
+        Err(SchemaError::Toml(_))
+    ));
+}
+
+#[test]
+fn test_cli_exit_status() {
+    let dir = std::env::temp_dir().join(format!("csv-validate-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let schema = dir.join("schema.json");
+    let valid = dir.join("valid.csv");
+    let invalid = dir.join("invalid.csv");
+    fs::write(&schema, SCHEMA_JSON).unwrap();
+    fs::write(&valid, "grade,tag\n10,a\n").unwrap();
+    fs::write(&invalid, "grade,tag\n10,banned\n").unwrap();
+
+    let run = |data: &std::path::Path| {
+        Command::new(env!("CARGO_BIN_EXE_csv-validate"))
+            .arg(&schema)
+            .arg(data)
+            .output()
+            .unwrap()
+    };
+
+    assert_eq!(run(&valid).status.code(), Some(0));
+    let output = run(&invalid);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("tag: value not allowed"));
+    assert_eq!(run(&dir.join("missing.csv")).status.code(), Some(2));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
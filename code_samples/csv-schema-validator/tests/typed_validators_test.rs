@@ -0,0 +1,116 @@
+use assert_matches::assert_matches;
+use csv_schema_validator::validators::{is_cnpj, is_cpf, is_decimal, is_ip};
+use csv_schema_validator::{ErrorCode, ValidateCsv};
+use serde::Deserialize;
+
+#[derive(Deserialize, ValidateCsv, Debug)]
+struct Customer {
+    #[validate(uuid)]
+    id: String,
+    #[validate(email)]
+    email: String,
+    #[validate(url)]
+    site: Option<String>,
+    #[validate(cpf)]
+    cpf: Option<String>,
+    #[validate(cnpj)]
+    cnpj: Option<String>,
+    #[validate(date(format = "%d/%m/%Y", min = "01/01/1900", max = "31/12/2099"))]
+    birth: String,
+    #[validate(date)]
+    since: Option<String>,
+    #[validate(decimal(precision = 8, scale = 2))]
+    balance: String,
+    #[validate(ip)]
+    last_ip: Option<String>,
+}
+
+fn customer() -> Customer {
+    Customer {
+        id: "67e55044-10b1-426f-9247-bb680e5fe0c8".into(),
+        email: "ana.souza+news@example.com.br".into(),
+        site: Some("https://example.com/path?q=1".into()),
+        cpf: Some("529.982.247-25".into()),
+        cnpj: Some("11.222.333/0001-81".into()),
+        birth: "29/02/2000".into(),
+        since: Some("2024-06-30".into()),
+        balance: "-123456.78".into(),
+        last_ip: Some("2001:db8::1".into()),
+    }
+}
+
+#[test]
+fn test_valid_customer() {
+    assert_matches!(customer().validate_csv(), Ok(()));
+}
+
+#[test]
+fn test_invalid_values() {
+    let record = Customer {
+        id: "67e55044-10b1-426f-9247".into(),
+        email: "ana@".into(),
+        site: Some("example.com".into()),
+        cpf: Some("529.982.247-26".into()),
+        cnpj: Some("11.111.111/1111-11".into()),
+        birth: "31/02/2000".into(),
+        since: Some("2024-13-01".into()),
+        balance: "12345678.9".into(),
+        last_ip: Some("256.1.1.1".into()),
+    };
+    let errors = record.validate_csv().unwrap_err();
+    let codes: Vec<_> = errors.iter().map(|e| e.code.as_str()).collect();
+    assert_eq!(
+        codes,
+        [
+            "invalid_uuid",
+            "invalid_email",
+            "invalid_url",
+            "invalid_cpf",
+            "invalid_cnpj",
+            "invalid_date",
+            "invalid_date",
+            "invalid_decimal",
+            "invalid_ip",
+        ]
+    );
+    assert_eq!(errors[5].message, "invalid date, expected format %d/%m/%Y");
+    assert_eq!(
+        errors[7].code,
+        ErrorCode::InvalidDecimal {
+            value: "12345678.9".into(),
+            precision: 8,
+            scale: 2
+        }
+    );
+}
+
+#[test]
+fn test_date_bounds() {
+    let record = Customer {
+        birth: "31/12/1899".into(),
+        ..customer()
+    };
+    let errors = record.validate_csv().unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].field, "birth");
+    assert_eq!(
+        errors[0].message,
+        "date out of expected range: 01/01/1900 to 31/12/2099"
+    );
+}
+
+#[test]
+fn test_validator_helpers() {
+    assert!(is_cpf("52998224725"));
+    assert!(!is_cpf("111.111.111-11"));
+    assert!(!is_cpf("529.982.247"));
+    assert!(is_cnpj("11222333000181"));
+    assert!(!is_cnpj("11.222.333/0001-82"));
+    assert!(is_decimal("0.5", 1, 1));
+    assert!(is_decimal("007.50", 3, 2));
+    assert!(!is_decimal("1.234", 8, 2));
+    assert!(!is_decimal("1e3", 8, 2));
+    assert!(!is_decimal("-", 8, 2));
+    assert!(is_ip("192.168.0.1"));
+    assert!(!is_ip("192.168.0"));
+}
@@ -1,5 +1,5 @@
 use assert_matches::assert_matches;
-use csv_schema_validator::{ValidateCsv, ValidationError};
+use csv_schema_validator::{ErrorCode, ValidateCsv, ValidationError};
 use serde::Deserialize;
 
 #[derive(Deserialize, ValidateCsv, Debug)]
@@ -59,7 +59,12 @@ fn test_invalid_grade() {
         errors[0],
         ValidationError {
             field: "grade".to_string(),
-            message: "value out of expected range: 0 to 100".to_string()
+            message: "value out of expected range: 0 to 100".to_string(),
+            code: ErrorCode::RangeAboveMax {
+                value: 150.0,
+                min: 0.0,
+                max: 100.0
+            },
         }
     );
 }
@@ -108,7 +113,13 @@ fn test_blank_name() {
         errors[0],
         ValidationError {
             field: "name".to_string(),
-            message: "length out of expected range: 10 to 50".to_string()
+            message: "length out of expected range: 10 to 50".to_string(),
+            code: ErrorCode::LengthTooShort {
+                value: "  \n \t".into(),
+                length: 5,
+                min: 10,
+                max: 50
+            },
         }
     );
 }
@@ -128,7 +139,13 @@ fn test_invalid_name_length() {
         errors[0],
         ValidationError {
             field: "name".to_string(),
-            message: "length out of expected range: 10 to 50".to_string()
+            message: "length out of expected range: 10 to 50".to_string(),
+            code: ErrorCode::LengthTooShort {
+                value: "John".into(),
+                length: 4,
+                min: 10,
+                max: 50
+            },
         }
     );
 }
@@ -207,3 +224,36 @@ fn test_tag_invalid_value() {
     assert_eq!(errors[0].field, "tag");
     assert_eq!(errors[0].message, "value not allowed");
 }
+
+#[derive(Deserialize, ValidateCsv, Debug)]
+struct Reading {
+    #[validate(range(min = -40.0, max = 85.0))]
+    celsius: i32,
+
+    #[validate(range(max = 100.0))]
+    humidity: Option<u8>,
+}
+
+#[test]
+fn test_integer_and_negative_range() {
+    let ok = Reading {
+        celsius: -40,
+        humidity: Some(55),
+    };
+    assert_matches!(ok.validate_csv(), Ok(()));
+
+    let record = Reading {
+        celsius: -41,
+        humidity: Some(101),
+    };
+    let errors = record.validate_csv().unwrap_err();
+    assert_eq!(
+        errors[0].code,
+        ErrorCode::RangeBelowMin {
+            value: -41.0,
+            min: -40.0,
+            max: 85.0
+        }
+    );
+    assert_eq!(errors[1].message, "value out of expected range: -inf to 100");
+}
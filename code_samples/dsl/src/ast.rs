@@ -0,0 +1,48 @@
+use crate::lexer::{Span, Token};
+
+// Definição da AST. Cada nó guarda o trecho do código fonte de onde veio,
+// usado nas mensagens de erro
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr {
+    pub tipo: TipoExpr,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TipoExpr {
+    Numero(i64),
+    Real(f64),
+    Texto(String),
+    Booleano(bool),
+    Variavel(String),
+    // Operador unário: - ou !
+    Unaria {
+        op: Token,
+        operando: Box<Expr>,
+    },
+    Operacao {
+        op: Token,
+        esquerda: Box<Expr>,
+        direita: Box<Expr>,
+    },
+    // if/else é uma expressão: o valor é o do bloco escolhido
+    Se {
+        condicao: Box<Expr>,
+        entao: Box<Expr>,
+        senao: Box<Expr>,
+    },
+    // { instrução; ...; expressão }, com escopo próprio para os let
+    Bloco(Vec<Instrucao>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instrucao {
+    Let { nome: String, valor: Expr },
+    Expressao(Expr),
+}
+
+// Um programa é uma sequência de instruções separadas por ';'
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Programa {
+    pub instrucoes: Vec<Instrucao>,
+}
@@ -0,0 +1,456 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use crate::ast::{Expr, Instrucao, Programa, TipoExpr};
+use crate::diagnostico::Diagnostico;
+use crate::lexer::{Span, Token};
+
+// Valores da linguagem
+#[derive(Debug, Clone, PartialEq)]
+pub enum Valor {
+    Inteiro(i64),
+    Real(f64),
+    Booleano(bool),
+    Texto(String),
+}
+
+impl Valor {
+    pub fn tipo(&self) -> &'static str {
+        match self {
+            Valor::Inteiro(_) => "inteiro",
+            Valor::Real(_) => "real",
+            Valor::Booleano(_) => "booleano",
+            Valor::Texto(_) => "texto",
+        }
+    }
+
+    fn como_real(&self) -> Option<f64> {
+        match self {
+            Valor::Inteiro(n) => Some(*n as f64),
+            Valor::Real(n) => Some(*n),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Valor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Valor::Inteiro(n) => write!(f, "{}", n),
+            // {:?} mantém o ".0" e deixa claro que o valor é real
+            Valor::Real(n) => write!(f, "{:?}", n),
+            Valor::Booleano(b) => write!(f, "{}", b),
+            Valor::Texto(t) => write!(f, "{}", t),
+        }
+    }
+}
+
+// Tipos de erro de execução
+#[derive(Debug, Clone, PartialEq)]
+pub enum TipoErroExecucao {
+    VariavelIndefinida(String),
+    DivisaoPorZero,
+    // Resultado inteiro fora da faixa de i64
+    Estouro,
+    TiposIncompativeis {
+        op: Token,
+        esquerda: &'static str,
+        direita: &'static str,
+    },
+    TipoInesperado {
+        esperado: &'static str,
+        encontrado: &'static str,
+    },
+    OperadorInvalido(Token),
+    // Só acontece em ASTs montadas à mão; o parser exige uma instrução
+    BlocoVazio,
+}
+
+impl fmt::Display for TipoErroExecucao {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TipoErroExecucao::VariavelIndefinida(nome) => {
+                write!(f, "variável '{}' não definida", nome)
+            }
+            TipoErroExecucao::DivisaoPorZero => write!(f, "divisão por zero"),
+            TipoErroExecucao::Estouro => write!(f, "estouro aritmético"),
+            TipoErroExecucao::TiposIncompativeis {
+                op,
+                esquerda,
+                direita,
+            } => write!(f, "'{}' não se aplica a {} e {}", op, esquerda, direita),
+            TipoErroExecucao::TipoInesperado {
+                esperado,
+                encontrado,
+            } => write!(f, "esperado {}, encontrado {}", esperado, encontrado),
+            TipoErroExecucao::OperadorInvalido(op) => write!(f, "operador inválido '{}'", op),
+            TipoErroExecucao::BlocoVazio => write!(f, "bloco sem instruções"),
+        }
+    }
+}
+
+// Erro de execução com o trecho do código que o causou: a variável, a
+// operação inteira ou o operando do tipo errado
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErroExecucao {
+    pub tipo: TipoErroExecucao,
+    pub span: Span,
+}
+
+impl ErroExecucao {
+    fn new(tipo: TipoErroExecucao, span: &Span) -> Self {
+        ErroExecucao {
+            tipo,
+            span: span.clone(),
+        }
+    }
+
+    pub fn diagnostico(&self) -> Diagnostico {
+        Diagnostico::new(self.tipo.to_string(), self.span.clone())
+    }
+}
+
+impl fmt::Display for ErroExecucao {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.tipo)
+    }
+}
+
+impl std::error::Error for ErroExecucao {}
+
+// Variáveis definidas com let; cada bloco abre um escopo novo
+#[derive(Debug, Clone)]
+pub struct Ambiente {
+    escopos: Vec<HashMap<String, Valor>>,
+}
+
+impl Default for Ambiente {
+    fn default() -> Self {
+        Ambiente {
+            escopos: vec![HashMap::new()],
+        }
+    }
+}
+
+impl Ambiente {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Define (ou redefine) a variável no escopo atual
+    pub fn definir(&mut self, nome: &str, valor: Valor) {
+        self.escopos
+            .last_mut()
+            .unwrap()
+            .insert(nome.to_string(), valor);
+    }
+
+    // Procura a variável do escopo mais interno para o mais externo
+    pub fn obter(&self, nome: &str) -> Option<&Valor> {
+        self.escopos
+            .iter()
+            .rev()
+            .find_map(|escopo| escopo.get(nome))
+    }
+
+    // Variáveis visíveis, em ordem alfabética
+    pub fn variaveis(&self) -> Vec<(&str, &Valor)> {
+        let mut visiveis = BTreeMap::new();
+        for escopo in &self.escopos {
+            for (nome, valor) in escopo {
+                visiveis.insert(nome.as_str(), valor);
+            }
+        }
+        visiveis.into_iter().collect()
+    }
+}
+
+// Executa as instruções em ordem; o resultado é o valor da última
+// (None para um programa vazio)
+pub fn executar(
+    programa: &Programa,
+    ambiente: &mut Ambiente,
+) -> Result<Option<Valor>, ErroExecucao> {
+    let mut ultimo = None;
+    for instrucao in &programa.instrucoes {
+        ultimo = Some(executar_instrucao(instrucao, ambiente)?);
+    }
+    Ok(ultimo)
+}
+
+// Um let resulta no valor atribuído
+fn executar_instrucao(
+    instrucao: &Instrucao,
+    ambiente: &mut Ambiente,
+) -> Result<Valor, ErroExecucao> {
+    match instrucao {
+        Instrucao::Let { nome, valor } => {
+            let valor = avaliar(valor, ambiente)?;
+            ambiente.definir(nome, valor.clone());
+            Ok(valor)
+        }
+        Instrucao::Expressao(expr) => avaliar(expr, ambiente),
+    }
+}
+
+// Avaliador da AST
+pub fn avaliar(expr: &Expr, ambiente: &mut Ambiente) -> Result<Valor, ErroExecucao> {
+    let erro = |tipo| ErroExecucao::new(tipo, &expr.span);
+    match &expr.tipo {
+        TipoExpr::Numero(valor) => Ok(Valor::Inteiro(*valor)),
+        TipoExpr::Real(valor) => Ok(Valor::Real(*valor)),
+        TipoExpr::Texto(texto) => Ok(Valor::Texto(texto.clone())),
+        TipoExpr::Booleano(b) => Ok(Valor::Booleano(*b)),
+        TipoExpr::Variavel(nome) => ambiente
+            .obter(nome)
+            .cloned()
+            .ok_or_else(|| erro(TipoErroExecucao::VariavelIndefinida(nome.clone()))),
+        TipoExpr::Unaria { op, operando } => match (op, avaliar(operando, ambiente)?) {
+            (Token::Menos, Valor::Inteiro(n)) => n
+                .checked_neg()
+                .map(Valor::Inteiro)
+                .ok_or_else(|| erro(TipoErroExecucao::Estouro)),
+            (Token::Menos, Valor::Real(n)) => Ok(Valor::Real(-n)),
+            (Token::Menos, outro) => Err(ErroExecucao::new(
+                TipoErroExecucao::TipoInesperado {
+                    esperado: "número",
+                    encontrado: outro.tipo(),
+                },
+                &operando.span,
+            )),
+            (Token::Nao, valor) => Ok(Valor::Booleano(!booleano(&valor, operando)?)),
+            (op, _) => Err(erro(TipoErroExecucao::OperadorInvalido(op.clone()))),
+        },
+        // && e || só avaliam a direita quando necessário
+        TipoExpr::Operacao {
+            op: op @ (Token::E | Token::Ou),
+            esquerda,
+            direita,
+        } => {
+            let esq = booleano(&avaliar(esquerda, ambiente)?, esquerda)?;
+            if esq == (*op == Token::Ou) {
+                return Ok(Valor::Booleano(esq));
+            }
+            let dir = booleano(&avaliar(direita, ambiente)?, direita)?;
+            Ok(Valor::Booleano(dir))
+        }
+        TipoExpr::Operacao {
+            op,
+            esquerda,
+            direita,
+        } => {
+            let esq = avaliar(esquerda, ambiente)?;
+            let dir = avaliar(direita, ambiente)?;
+            operar(op, esq, dir).map_err(erro)
+        }
+        TipoExpr::Se {
+            condicao,
+            entao,
+            senao,
+        } => {
+            if booleano(&avaliar(condicao, ambiente)?, condicao)? {
+                avaliar(entao, ambiente)
+            } else {
+                avaliar(senao, ambiente)
+            }
+        }
+        TipoExpr::Bloco(instrucoes) => {
+            ambiente.escopos.push(HashMap::new());
+            let mut resultado = Err(erro(TipoErroExecucao::BlocoVazio));
+            for instrucao in instrucoes {
+                resultado = executar_instrucao(instrucao, ambiente);
+                if resultado.is_err() {
+                    break;
+                }
+            }
+            ambiente.escopos.pop();
+            resultado
+        }
+    }
+}
+
+// O erro aponta para a expressão que deveria ser booleana
+fn booleano(valor: &Valor, expr: &Expr) -> Result<bool, ErroExecucao> {
+    match valor {
+        Valor::Booleano(b) => Ok(*b),
+        outro => Err(ErroExecucao::new(
+            TipoErroExecucao::TipoInesperado {
+                esperado: "booleano",
+                encontrado: outro.tipo(),
+            },
+            &expr.span,
+        )),
+    }
+}
+
+// Operadores binários. Inteiros continuam inteiros (a divisão é inteira);
+// com um real na operação, o resultado é real
+fn operar(op: &Token, esq: Valor, dir: Valor) -> Result<Valor, TipoErroExecucao> {
+    let incompativeis = || TipoErroExecucao::TiposIncompativeis {
+        op: op.clone(),
+        esquerda: esq.tipo(),
+        direita: dir.tipo(),
+    };
+    match op {
+        Token::Mais | Token::Menos | Token::Multiplicacao | Token::Divisao => match (&esq, &dir) {
+            (Valor::Texto(a), Valor::Texto(b)) if *op == Token::Mais => {
+                Ok(Valor::Texto(format!("{}{}", a, b)))
+            }
+            (Valor::Inteiro(a), Valor::Inteiro(b)) => {
+                let resultado = match op {
+                    Token::Mais => a.checked_add(*b),
+                    Token::Menos => a.checked_sub(*b),
+                    Token::Multiplicacao => a.checked_mul(*b),
+                    _ if *b == 0 => return Err(TipoErroExecucao::DivisaoPorZero),
+                    _ => a.checked_div(*b),
+                };
+                resultado
+                    .map(Valor::Inteiro)
+                    .ok_or(TipoErroExecucao::Estouro)
+            }
+            _ => {
+                let (a, b) = esq
+                    .como_real()
+                    .zip(dir.como_real())
+                    .ok_or_else(incompativeis)?;
+                Ok(Valor::Real(match op {
+                    Token::Mais => a + b,
+                    Token::Menos => a - b,
+                    Token::Multiplicacao => a * b,
+                    _ if b == 0.0 => return Err(TipoErroExecucao::DivisaoPorZero),
+                    _ => a / b,
+                }))
+            }
+        },
+        Token::Igual
+        | Token::Diferente
+        | Token::Menor
+        | Token::MenorOuIgual
+        | Token::Maior
+        | Token::MaiorOuIgual => {
+            let ordem = match (&esq, &dir) {
+                (Valor::Inteiro(a), Valor::Inteiro(b)) => Some(a.cmp(b)),
+                (Valor::Texto(a), Valor::Texto(b)) => Some(a.cmp(b)),
+                (Valor::Booleano(a), Valor::Booleano(b))
+                    if matches!(op, Token::Igual | Token::Diferente) =>
+                {
+                    Some(a.cmp(b))
+                }
+                _ => {
+                    let (a, b) = esq
+                        .como_real()
+                        .zip(dir.como_real())
+                        .ok_or_else(incompativeis)?;
+                    // NaN não é igual nem menor que nada
+                    a.partial_cmp(&b)
+                }
+            };
+            Ok(Valor::Booleano(match op {
+                Token::Igual => ordem == Some(Ordering::Equal),
+                Token::Diferente => ordem != Some(Ordering::Equal),
+                Token::Menor => ordem == Some(Ordering::Less),
+                Token::MenorOuIgual => matches!(ordem, Some(Ordering::Less | Ordering::Equal)),
+                Token::Maior => ordem == Some(Ordering::Greater),
+                _ => matches!(ordem, Some(Ordering::Greater | Ordering::Equal)),
+            }))
+        }
+        _ => Err(TipoErroExecucao::OperadorInvalido(op.clone())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::analisar_fonte;
+
+    fn executar_fonte(fonte: &str) -> Result<Option<Valor>, ErroExecucao> {
+        executar(&analisar_fonte(fonte).unwrap(), &mut Ambiente::new())
+    }
+
+    fn rodar(fonte: &str) -> Result<Option<Valor>, TipoErroExecucao> {
+        executar_fonte(fonte).map_err(|e| e.tipo)
+    }
+
+    fn valor(fonte: &str) -> Valor {
+        rodar(fonte).unwrap().unwrap()
+    }
+
+    #[test]
+    fn aritmetica_e_tipos() {
+        assert_eq!(valor("3 + 5 * (2 - 8)"), Valor::Inteiro(-27));
+        assert_eq!(valor("7 / 2"), Valor::Inteiro(3));
+        assert_eq!(valor("7 / 2.0"), Valor::Real(3.5));
+        assert_eq!(
+            valor("\"total: \" + \"10\""),
+            Valor::Texto("total: 10".into())
+        );
+        assert_eq!(
+            valor("1 < 1.5 && !(\"a\" >= \"b\") && true != false"),
+            Valor::Booleano(true)
+        );
+        assert_eq!(valor("-(2 - 5)"), Valor::Inteiro(3));
+    }
+
+    #[test]
+    fn let_blocos_e_condicionais() {
+        let programa = "
+            let limite = 1000;
+            let valor = 1500.0;
+            let taxa = if valor > limite { let excedente = valor - limite; excedente * 0.1 } else { 0.0 };
+            taxa";
+        assert_eq!(valor(programa), Valor::Real(50.0));
+        // variáveis de um bloco não vazam para fora dele
+        assert_eq!(
+            rodar("let a = 1; { let b = 2; a + b }; b"),
+            Err(TipoErroExecucao::VariavelIndefinida("b".into()))
+        );
+        // um let dentro do bloco esconde o de fora só até o fim do bloco
+        assert_eq!(
+            valor("let a = 1; let b = { let a = 10; a + 1 }; a + b"),
+            Valor::Inteiro(12)
+        );
+        assert_eq!(rodar(""), Ok(None));
+    }
+
+    #[test]
+    fn erros_de_execucao() {
+        assert_eq!(rodar("1 / 0"), Err(TipoErroExecucao::DivisaoPorZero));
+        assert_eq!(rodar("1.5 / 0"), Err(TipoErroExecucao::DivisaoPorZero));
+        assert_eq!(
+            rodar("9223372036854775807 + 1"),
+            Err(TipoErroExecucao::Estouro)
+        );
+        assert_eq!(
+            rodar("\"a\" * 2"),
+            Err(TipoErroExecucao::TiposIncompativeis {
+                op: Token::Multiplicacao,
+                esquerda: "texto",
+                direita: "inteiro"
+            })
+        );
+        assert_eq!(
+            rodar("if 1 { 2 } else { 3 }"),
+            Err(TipoErroExecucao::TipoInesperado {
+                esperado: "booleano",
+                encontrado: "inteiro"
+            })
+        );
+        // o lado direito de && não é avaliado quando o esquerdo é falso
+        assert_eq!(
+            rodar("false && 1 / 0 == 0"),
+            Ok(Some(Valor::Booleano(false)))
+        );
+    }
+
+    #[test]
+    fn erros_apontam_o_trecho() {
+        let trecho = |fonte| executar_fonte(fonte).unwrap_err().span;
+        assert_eq!(trecho("let a = 1;\nlet b = a + c;"), 23..24);
+        // a operação inteira
+        assert_eq!(trecho("1 + 10 / (2 - 2) * 3"), 4..16);
+        // só o operando com o tipo errado
+        assert_eq!(trecho("true && 1 > 0 && \"sim\""), 17..22);
+        assert_eq!(trecho("if 1 + 1 { 2 } else { 3 }"), 3..8);
+    }
+}
@@ -0,0 +1,96 @@
+use std::fmt::Write;
+
+use crate::lexer::Span;
+
+// Mensagem de erro apontando um trecho do código fonte
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostico {
+    pub mensagem: String,
+    pub span: Span,
+}
+
+impl Diagnostico {
+    pub fn new(mensagem: impl Into<String>, span: Span) -> Self {
+        Diagnostico {
+            mensagem: mensagem.into(),
+            span,
+        }
+    }
+
+    // Linha e coluna (a partir de 1, em caracteres) do início do trecho
+    pub fn linha_coluna(&self, fonte: &str) -> (usize, usize) {
+        let inicio = self.span.start.min(fonte.len());
+        let antes = &fonte[..inicio];
+        let linha = antes.matches('\n').count() + 1;
+        let coluna = antes[antes.rfind('\n').map_or(0, |i| i + 1)..]
+            .chars()
+            .count()
+            + 1;
+        (linha, coluna)
+    }
+
+    // Formata o erro com a linha do código e um '^' sob cada caractere do
+    // trecho:
+    //
+    // erro: esperado expressão, encontrado ';'
+    //  --> 1:9
+    //   |
+    // 1 | let x = ;
+    //   |         ^
+    pub fn renderizar(&self, fonte: &str) -> String {
+        let (linha, coluna) = self.linha_coluna(fonte);
+        let inicio_linha = fonte[..self.span.start.min(fonte.len())]
+            .rfind('\n')
+            .map_or(0, |i| i + 1);
+        let texto_linha = fonte[inicio_linha..].lines().next().unwrap_or("");
+        let fim_linha = inicio_linha + texto_linha.len();
+
+        // tabulações são mantidas para o '^' ficar alinhado
+        let recuo: String = texto_linha
+            .chars()
+            .take(coluna - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let marcados = fonte
+            .get(self.span.start..self.span.end.min(fim_linha))
+            .map_or(0, |trecho| trecho.chars().count())
+            .max(1);
+
+        let margem = " ".repeat(linha.to_string().len());
+        let mut saida = String::new();
+        writeln!(saida, "erro: {}", self.mensagem).unwrap();
+        writeln!(saida, "{} --> {}:{}", margem, linha, coluna).unwrap();
+        writeln!(saida, "{} |", margem).unwrap();
+        writeln!(saida, "{} | {}", linha, texto_linha).unwrap();
+        write!(saida, "{} | {}{}", margem, recuo, "^".repeat(marcados)).unwrap();
+        saida
+    }
+}
+
+// Formata vários diagnósticos, separados por uma linha em branco
+pub fn renderizar_todos(diagnosticos: &[Diagnostico], fonte: &str) -> String {
+    diagnosticos
+        .iter()
+        .map(|d| d.renderizar(fonte))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn circunflexo_sob_o_trecho() {
+        let fonte = "let a = 1;\n\tlet b = a +* 2;";
+        let diagnostico = Diagnostico::new("esperado expressão, encontrado '*'", 23..24);
+        assert_eq!(diagnostico.linha_coluna(fonte), (2, 13));
+        assert_eq!(
+            diagnostico.renderizar(fonte),
+            "erro: esperado expressão, encontrado '*'\n  --> 2:13\n  |\n2 | \tlet b = a +* 2;\n  | \t           ^"
+        );
+        // no fim da entrada o '^' fica logo após o último caractere
+        let fim = Diagnostico::new("esperado expressão, encontrado fim da entrada", 6..6);
+        assert!(fim.renderizar("1 + é").ends_with("1 | 1 + é\n  |      ^"));
+    }
+}
@@ -0,0 +1,205 @@
+use std::fmt;
+use std::ops::Range;
+
+use logos::Logos;
+
+// Intervalo de bytes no código fonte
+pub type Span = Range<usize>;
+
+// Definição dos tokens
+#[derive(Logos, Debug, PartialEq, Clone)]
+pub enum Token {
+    #[regex(r"[0-9]+\.[0-9]+([eE][+-]?[0-9]+)?", |lex| lex.slice().parse())]
+    Real(f64),
+    #[regex(r"[0-9]+", |lex| lex.slice().parse())]
+    Numero(i64),
+    // Texto entre aspas, com os escapes \" \\ \n e \t
+    #[regex(r#""([^"\\]|\\.)*""#, |lex| desescapar(lex.slice()))]
+    Texto(String),
+
+    #[token("true")]
+    Verdadeiro,
+    #[token("false")]
+    Falso,
+    #[token("let")]
+    Let,
+    #[token("if")]
+    If,
+    #[token("else")]
+    Else,
+    #[regex(r"[A-Za-z_][A-Za-z0-9_]*", |lex| lex.slice().to_string())]
+    Identificador(String),
+
+    #[token("+")]
+    Mais,
+    #[token("-")]
+    Menos,
+    #[token("*")]
+    Multiplicacao,
+    #[token("/")]
+    Divisao,
+
+    #[token("==")]
+    Igual,
+    #[token("!=")]
+    Diferente,
+    #[token("<")]
+    Menor,
+    #[token("<=")]
+    MenorOuIgual,
+    #[token(">")]
+    Maior,
+    #[token(">=")]
+    MaiorOuIgual,
+
+    #[token("&&")]
+    E,
+    #[token("||")]
+    Ou,
+    #[token("!")]
+    Nao,
+
+    #[token("=")]
+    Atribuicao,
+    #[token(";")]
+    PontoEVirgula,
+
+    #[token("(")]
+    AbreParenteses,
+    #[token(")")]
+    FechaParenteses,
+    #[token("{")]
+    AbreChaves,
+    #[token("}")]
+    FechaChaves,
+
+    #[regex(r"[ \t\r\n\f]+", logos::skip)] // Ignorar espaços
+    Whitespace,
+    #[regex(r"#[^\n]*", logos::skip)] // Comentários vão até o fim da linha
+    Comentario,
+
+    #[error]
+    Error,
+}
+
+// Token com a sua posição no código fonte
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lexema {
+    pub token: Token,
+    pub span: Span,
+}
+
+// Divide o código fonte em tokens; caracteres e literais inválidos viram
+// Token::Error, que o parser reporta como diagnóstico
+pub fn tokenizar(fonte: &str) -> Vec<Lexema> {
+    Token::lexer(fonte)
+        .spanned()
+        .map(|(token, span)| Lexema { token, span })
+        .collect()
+}
+
+// Remove as aspas e troca as sequências de escape pelos caracteres
+fn desescapar(literal: &str) -> Option<String> {
+    let mut texto = String::new();
+    let mut chars = literal[1..literal.len() - 1].chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            texto.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => texto.push('\n'),
+            't' => texto.push('\t'),
+            c @ ('"' | '\\') => texto.push(c),
+            _ => return None,
+        }
+    }
+    Some(texto)
+}
+
+// O token como aparece no código fonte
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Real(valor) => write!(f, "{:?}", valor),
+            Token::Numero(valor) => write!(f, "{}", valor),
+            Token::Texto(texto) => write!(f, "{:?}", texto),
+            Token::Identificador(nome) => write!(f, "{}", nome),
+            outro => {
+                let simbolo = match outro {
+                    Token::Verdadeiro => "true",
+                    Token::Falso => "false",
+                    Token::Let => "let",
+                    Token::If => "if",
+                    Token::Else => "else",
+                    Token::Mais => "+",
+                    Token::Menos => "-",
+                    Token::Multiplicacao => "*",
+                    Token::Divisao => "/",
+                    Token::Igual => "==",
+                    Token::Diferente => "!=",
+                    Token::Menor => "<",
+                    Token::MenorOuIgual => "<=",
+                    Token::Maior => ">",
+                    Token::MaiorOuIgual => ">=",
+                    Token::E => "&&",
+                    Token::Ou => "||",
+                    Token::Nao => "!",
+                    Token::Atribuicao => "=",
+                    Token::PontoEVirgula => ";",
+                    Token::AbreParenteses => "(",
+                    Token::FechaParenteses => ")",
+                    Token::AbreChaves => "{",
+                    Token::FechaChaves => "}",
+                    _ => "caractere inválido",
+                };
+                write!(f, "{}", simbolo)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens() {
+        let tokens: Vec<_> =
+            Token::lexer("let taxa = 2.5e1; # comentário\nif taxa >= 10 { \"a\\\"b\" }").collect();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Let,
+                Token::Identificador("taxa".into()),
+                Token::Atribuicao,
+                Token::Real(25.0),
+                Token::PontoEVirgula,
+                Token::If,
+                Token::Identificador("taxa".into()),
+                Token::MaiorOuIgual,
+                Token::Numero(10),
+                Token::AbreChaves,
+                Token::Texto("a\"b".into()),
+                Token::FechaChaves,
+            ]
+        );
+        assert_eq!(
+            tokenizar("lets $ \"\\q\""),
+            vec![
+                Lexema {
+                    token: Token::Identificador("lets".into()),
+                    span: 0..4
+                },
+                Lexema {
+                    token: Token::Error,
+                    span: 5..6
+                },
+                Lexema {
+                    token: Token::Error,
+                    span: 7..11
+                },
+            ]
+        );
+    }
+}
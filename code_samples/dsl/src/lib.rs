@@ -0,0 +1,17 @@
+// Linguagem de regras: lexer com logos, parser com nom e um avaliador que
+// devolve erros em vez de entrar em pânico. Os erros apontam o trecho do
+// código fonte onde aconteceram
+
+pub mod ast;
+pub mod avaliador;
+pub mod diagnostico;
+pub mod lexer;
+pub mod parser;
+pub mod repl;
+
+pub use ast::{Expr, Instrucao, Programa, TipoExpr};
+pub use avaliador::{avaliar, executar, Ambiente, ErroExecucao, TipoErroExecucao, Valor};
+pub use diagnostico::{renderizar_todos, Diagnostico};
+pub use lexer::{tokenizar, Lexema, Span, Token};
+pub use parser::{analisar, analisar_fonte, parse_expr, ErroParser};
+pub use repl::{Repl, Resposta};
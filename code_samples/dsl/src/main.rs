@@ -1,172 +1,103 @@
 use std::env;
-use logos::Logos;
-use nom::{
-    branch::alt,
-    multi::fold_many0,
-    sequence::{delimited, pair},
-    IResult,
-};
+use std::path::PathBuf;
 
-// Definição dos tokens
-#[derive(Logos, Debug, PartialEq, Clone)]
-enum Token {
-    #[regex(r"[0-9]+", |lex| lex.slice().parse())]
-    Numero(i32),
+use dsl_project::{analisar_fonte, executar, renderizar_todos, Ambiente, Repl, Resposta};
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
 
-    #[token("+")]
-    Mais,
-    #[token("-")]
-    Menos,
-    #[token("*")]
-    Multiplicacao,
-    #[token("/")]
-    Divisao,
-
-    #[token("(")]
-    AbreParenteses,
-    #[token(")")]
-    FechaParenteses,
-
-    #[regex(r"[ \t\n\f]+", logos::skip)] // Ignorar espaços
-    Whitespace,
-
-    #[error]
-    Error,
-}
-
-// Definição da AST
-#[derive(Debug, Clone)]
-enum Expr {
-    Numero(i32),
-    Operacao {
-        op: Token,
-        esquerda: Box<Expr>,
-        direita: Box<Expr>,
-    },
-}
-
-// Parser para números
-fn parse_numero(tokens: &[Token]) -> IResult<&[Token], Expr> {
-    if let Some((Token::Numero(valor), rest)) = tokens.split_first() {
-        Ok((rest, Expr::Numero(*valor)))
-    } else {
-        Err(nom::Err::Error(nom::error::Error::new(tokens, nom::error::ErrorKind::Tag)))
-    }
-}
-
-// Parser para fatores (números ou expressões entre parênteses)
-fn parse_fator(tokens: &[Token]) -> IResult<&[Token], Expr> {
-    alt((
-        parse_numero,
-        delimited(
-            parse_abre_parenteses,
-            parse_expr,
-            parse_fecha_parenteses,
-        ),
-    ))(tokens)
-}
+// cargo run                        => modo interativo (REPL)
+// cargo run "3 + 5 * (2 - 8)"    => -27
+// cargo run "(3 + 12) * (4 - 3)" => 15
+// cargo run "let preco = 120.0; if preco > 100 { preco * 0.9 } else { preco }" => 108.0
+//
+// Erros mostram a linha e um '^' sob o trecho:
+// cargo run "let x = 1 +; x / 0"
+// erro: esperado expressão, encontrado ';'
+//  --> 1:12
+//   |
+// 1 | let x = 1 +; x / 0
+//   |            ^
 
-fn parse_abre_parenteses(tokens: &[Token]) -> IResult<&[Token], ()> {
-    if let Some((Token::AbreParenteses, rest)) = tokens.split_first() {
-        Ok((rest, ()))
-    } else {
-        Err(nom::Err::Error(nom::error::Error::new(tokens, nom::error::ErrorKind::Tag)))
-    }
-}
+fn main() {
+    let args: Vec<String> = env::args().collect();
 
-fn parse_fecha_parenteses(tokens: &[Token]) -> IResult<&[Token], ()> {
-    if let Some((Token::FechaParenteses, rest)) = tokens.split_first() {
-        Ok((rest, ()))
-    } else {
-        Err(nom::Err::Error(nom::error::Error::new(tokens, nom::error::ErrorKind::Tag)))
+    if args.len() < 2 {
+        interativo();
+        return;
     }
-}
 
-// Parser para termos (multiplicação e divisão)
-fn parse_termo(tokens: &[Token]) -> IResult<&[Token], Expr> {
-    let (tokens, inicial) = parse_fator(tokens)?;
-    fold_many0(
-        pair(parse_multiplicacao_ou_divisao, parse_fator),
-        move || inicial.clone(),
-        |esquerda, (op, direita)| Expr::Operacao {
-            op,
-            esquerda: Box::new(esquerda),
-            direita: Box::new(direita),
-        },
-    )(tokens)
-}
+    let programa = &args[1];
 
-fn parse_multiplicacao_ou_divisao(tokens: &[Token]) -> IResult<&[Token], Token> {
-    if let Some((token @ Token::Multiplicacao, rest)) = tokens.split_first() {
-        Ok((rest, token.clone()))
-    } else if let Some((token @ Token::Divisao, rest)) = tokens.split_first() {
-        Ok((rest, token.clone()))
-    } else {
-        Err(nom::Err::Error(nom::error::Error::new(tokens, nom::error::ErrorKind::Tag)))
+    match analisar_fonte(programa) {
+        Ok(ast) => {
+            println!("AST: {:?}", ast);
+            match executar(&ast, &mut Ambiente::new()) {
+                Ok(Some(valor)) => println!("Resultado: {}", valor),
+                Ok(None) => println!("Programa vazio"),
+                Err(erro) => {
+                    eprintln!("{}", erro.diagnostico().renderizar(programa));
+                    std::process::exit(1);
+                }
+            }
+        }
+        Err(diagnosticos) => {
+            eprintln!("{}", renderizar_todos(&diagnosticos, programa));
+            std::process::exit(1);
+        }
     }
 }
 
-// Parser para expressões completas (adição e subtração)
-fn parse_expr(tokens: &[Token]) -> IResult<&[Token], Expr> {
-    let (tokens, inicial) = parse_termo(tokens)?;
-    fold_many0(
-        pair(parse_mais_ou_menos, parse_termo),
-        move || inicial.clone(),
-        |esquerda, (op, direita)| Expr::Operacao {
-            op,
-            esquerda: Box::new(esquerda),
-            direita: Box::new(direita),
-        },
-    )(tokens)
-}
-
-fn parse_mais_ou_menos(tokens: &[Token]) -> IResult<&[Token], Token> {
-    if let Some((token @ Token::Mais, rest)) = tokens.split_first() {
-        Ok((rest, token.clone()))
-    } else if let Some((token @ Token::Menos, rest)) = tokens.split_first() {
-        Ok((rest, token.clone()))
-    } else {
-        Err(nom::Err::Error(nom::error::Error::new(tokens, nom::error::ErrorKind::Tag)))
-    }
+// O histórico fica em ~/.dsl_historico (ou no diretório atual, sem HOME)
+fn arquivo_historico() -> PathBuf {
+    env::var_os("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_default()
+        .join(".dsl_historico")
 }
 
-// Avaliador da AST
-fn avaliar(expr: &Expr) -> i32 {
-    match expr {
-        Expr::Numero(valor) => *valor,
-        Expr::Operacao { op, esquerda, direita } => {
-            let esq = avaliar(esquerda);
-            let dir = avaliar(direita);
-            match op {
-                Token::Mais => esq + dir,
-                Token::Menos => esq - dir,
-                Token::Multiplicacao => esq * dir,
-                Token::Divisao => esq / dir,
-                _ => panic!("Operador inválido"),
+fn interativo() {
+    let mut editor = match DefaultEditor::new() {
+        Ok(editor) => editor,
+        Err(erro) => {
+            eprintln!("Erro ao abrir o terminal: {}", erro);
+            std::process::exit(1);
+        }
+    };
+    let historico = arquivo_historico();
+    // na primeira vez o arquivo ainda não existe
+    let _ = editor.load_history(&historico);
+
+    println!("Linguagem de regras. Digite :ajuda para ver os comandos.");
+    let mut repl = Repl::new();
+    loop {
+        let prompt = if repl.pendente() { "... " } else { ">>> " };
+        let linha = match editor.readline(prompt) {
+            Ok(linha) => linha,
+            // Ctrl-C descarta a entrada pela metade; Ctrl-D encerra
+            Err(ReadlineError::Interrupted) => {
+                repl.descartar();
+                continue;
+            }
+            Err(ReadlineError::Eof) => break,
+            Err(erro) => {
+                eprintln!("Erro de leitura: {}", erro);
+                break;
             }
+        };
+        if !linha.trim().is_empty() {
+            let _ = editor.add_history_entry(linha.as_str());
         }
-    }
-}
 
-// cargo run "3 + 5 * (2 - 8)"    => -27
-// cargo run "(3 + 12) * (4 - 3)" => 15
-
-fn main() {
-    let args: Vec<String> = env::args().collect();
-
-    if args.len() < 2 {
-        eprintln!("Uso: {} <expressão>", args[0]);
-        std::process::exit(1);
+        match repl.processar(&linha) {
+            Resposta::Continuar => {}
+            Resposta::Saida(texto) if texto.is_empty() => {}
+            Resposta::Saida(texto) => println!("{}", texto),
+            Resposta::Erro(texto) => eprintln!("{}", texto),
+            Resposta::Sair => break,
+        }
     }
 
-    let expressao = &args[1];
-    let tokens: Vec<_> = Token::lexer(expressao).collect();
-
-    match parse_expr(&tokens) {
-        Ok((_, ast)) => {
-            println!("AST: {:?}", ast);
-            println!("Resultado: {}", avaliar(&ast));
-        }
-        Err(err) => println!("Erro ao parsear: {:?}", err),
+    if let Err(erro) = editor.save_history(&historico) {
+        eprintln!("Erro ao salvar o histórico: {}", erro);
     }
 }
@@ -0,0 +1,517 @@
+use nom::{
+    branch::alt,
+    combinator::{cut, map, opt},
+    error::{ErrorKind, ParseError},
+    multi::{fold_many0, separated_list1},
+    sequence::pair,
+    IResult,
+};
+
+use crate::ast::{Expr, Instrucao, Programa, TipoExpr};
+use crate::diagnostico::Diagnostico;
+use crate::lexer::{tokenizar, Lexema, Span, Token};
+
+const OU: &[Token] = &[Token::Ou];
+const E: &[Token] = &[Token::E];
+const COMPARACAO: &[Token] = &[
+    Token::Igual,
+    Token::Diferente,
+    Token::Menor,
+    Token::MenorOuIgual,
+    Token::Maior,
+    Token::MaiorOuIgual,
+];
+const MAIS_OU_MENOS: &[Token] = &[Token::Mais, Token::Menos];
+const MULTIPLICACAO_OU_DIVISAO: &[Token] = &[Token::Multiplicacao, Token::Divisao];
+const UNARIOS: &[Token] = &[Token::Menos, Token::Nao];
+
+// Erro do parser: onde parou (os tokens restantes) e o que era esperado ali
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErroParser<'a> {
+    pub resto: &'a [Lexema],
+    pub esperado: Vec<String>,
+    // Mensagem própria, no lugar de "esperado ..., encontrado ..."
+    pub mensagem: Option<&'static str>,
+}
+
+impl<'a> ErroParser<'a> {
+    fn new(resto: &'a [Lexema], esperado: impl Into<String>) -> Self {
+        ErroParser {
+            resto,
+            esperado: vec![esperado.into()],
+            mensagem: None,
+        }
+    }
+
+    fn diagnostico(&self, fim: usize) -> Diagnostico {
+        let (encontrado, span) = match self.resto.first() {
+            Some(lexema) => (format!("'{}'", lexema.token), lexema.span.clone()),
+            None => ("fim da entrada".to_string(), fim..fim),
+        };
+        match self.mensagem {
+            Some(mensagem) => Diagnostico::new(mensagem, span),
+            None => Diagnostico::new(
+                format!(
+                    "esperado {}, encontrado {}",
+                    self.esperado.join(" ou "),
+                    encontrado
+                ),
+                span,
+            ),
+        }
+    }
+}
+
+impl<'a> ParseError<&'a [Lexema]> for ErroParser<'a> {
+    fn from_error_kind(resto: &'a [Lexema], _kind: ErrorKind) -> Self {
+        ErroParser {
+            resto,
+            esperado: Vec::new(),
+            mensagem: None,
+        }
+    }
+
+    fn append(_resto: &'a [Lexema], _kind: ErrorKind, outro: Self) -> Self {
+        outro
+    }
+
+    // Entre alternativas fica o erro que avançou mais; no mesmo ponto, as
+    // expectativas são somadas ("esperado 'if' ou '{'")
+    fn or(mut self, outro: Self) -> Self {
+        if outro.resto.len() < self.resto.len() {
+            return outro;
+        }
+        if outro.resto.len() == self.resto.len() {
+            for esperado in outro.esperado {
+                if !self.esperado.contains(&esperado) {
+                    self.esperado.push(esperado);
+                }
+            }
+        }
+        self
+    }
+}
+
+type Resultado<'a, T> = IResult<&'a [Lexema], T, ErroParser<'a>>;
+
+fn erro<'a, T>(lexemas: &'a [Lexema], esperado: impl Into<String>) -> Resultado<'a, T> {
+    Err(nom::Err::Error(ErroParser::new(lexemas, esperado)))
+}
+
+// Parser para um token específico
+fn simbolo(esperado: Token) -> impl Fn(&[Lexema]) -> Resultado<'_, Lexema> {
+    move |lexemas| match lexemas.split_first() {
+        Some((lexema, rest)) if lexema.token == esperado => Ok((rest, lexema.clone())),
+        _ => erro(lexemas, format!("'{}'", esperado)),
+    }
+}
+
+// Parser para qualquer um dos operadores da lista. Operadores são sempre
+// opcionais, então a falha não entra no "esperado ..." da mensagem
+fn operador(opcoes: &'static [Token]) -> impl Fn(&[Lexema]) -> Resultado<'_, Lexema> {
+    move |lexemas| match lexemas.split_first() {
+        Some((lexema, rest)) if opcoes.contains(&lexema.token) => Ok((rest, lexema.clone())),
+        _ => Err(nom::Err::Error(ErroParser::from_error_kind(
+            lexemas,
+            ErrorKind::Tag,
+        ))),
+    }
+}
+
+fn operacao(op: Token, esquerda: Expr, direita: Expr) -> Expr {
+    Expr {
+        span: esquerda.span.start..direita.span.end,
+        tipo: TipoExpr::Operacao {
+            op,
+            esquerda: Box::new(esquerda),
+            direita: Box::new(direita),
+        },
+    }
+}
+
+// Um nível de precedência associativo à esquerda: operando (op operando)*.
+// Depois do operador, o operando é obrigatório (cut)
+fn parse_binaria<'a>(
+    lexemas: &'a [Lexema],
+    opcoes: &'static [Token],
+    operando: fn(&[Lexema]) -> Resultado<'_, Expr>,
+) -> Resultado<'a, Expr> {
+    let (lexemas, inicial) = operando(lexemas)?;
+    fold_many0(
+        pair(operador(opcoes), cut(operando)),
+        move || inicial.clone(),
+        |esquerda, (op, direita)| operacao(op.token, esquerda, direita),
+    )(lexemas)
+}
+
+// Parser para literais: números, textos e booleanos
+fn parse_numero(lexemas: &[Lexema]) -> Resultado<'_, Expr> {
+    let Some((lexema, rest)) = lexemas.split_first() else {
+        return erro(lexemas, "literal");
+    };
+    let tipo = match &lexema.token {
+        Token::Numero(valor) => TipoExpr::Numero(*valor),
+        Token::Real(valor) => TipoExpr::Real(*valor),
+        Token::Texto(texto) => TipoExpr::Texto(texto.clone()),
+        Token::Verdadeiro => TipoExpr::Booleano(true),
+        Token::Falso => TipoExpr::Booleano(false),
+        _ => return erro(lexemas, "literal"),
+    };
+    Ok((
+        rest,
+        Expr {
+            tipo,
+            span: lexema.span.clone(),
+        },
+    ))
+}
+
+fn parse_identificador(lexemas: &[Lexema]) -> Resultado<'_, (String, Span)> {
+    match lexemas.split_first() {
+        Some((
+            Lexema {
+                token: Token::Identificador(nome),
+                span,
+            },
+            rest,
+        )) => Ok((rest, (nome.clone(), span.clone()))),
+        _ => erro(lexemas, "identificador"),
+    }
+}
+
+// ( expressão ); o trecho inclui os parênteses
+fn parse_parenteses(lexemas: &[Lexema]) -> Resultado<'_, Expr> {
+    let (rest, abre) = simbolo(Token::AbreParenteses)(lexemas)?;
+    let (rest, mut expr) = cut(parse_expr)(rest)?;
+    let (rest, fecha) = cut(simbolo(Token::FechaParenteses))(rest)?;
+    expr.span = abre.span.start..fecha.span.end;
+    Ok((rest, expr))
+}
+
+// Parser para fatores: literais, variáveis, expressões entre parênteses,
+// if/else e blocos
+fn parse_fator(lexemas: &[Lexema]) -> Resultado<'_, Expr> {
+    alt((
+        parse_numero,
+        map(parse_identificador, |(nome, span)| Expr {
+            tipo: TipoExpr::Variavel(nome),
+            span,
+        }),
+        parse_parenteses,
+        parse_se,
+        parse_bloco,
+    ))(lexemas)
+    .map_err(|e| match e {
+        nom::Err::Error(_) => nom::Err::Error(ErroParser::new(lexemas, "expressão")),
+        outro => outro,
+    })
+}
+
+// if <condição> { ... } else { ... }, com "else if" encadeado
+fn parse_se(lexemas: &[Lexema]) -> Resultado<'_, Expr> {
+    let (rest, se) = simbolo(Token::If)(lexemas)?;
+    let (rest, condicao) = cut(parse_expr)(rest)?;
+    let (rest, entao) = cut(parse_bloco)(rest)?;
+    let (rest, _) = cut(simbolo(Token::Else))(rest)?;
+    let (rest, senao) = cut(alt((parse_se, parse_bloco)))(rest)?;
+    let expr = Expr {
+        span: se.span.start..senao.span.end,
+        tipo: TipoExpr::Se {
+            condicao: Box::new(condicao),
+            entao: Box::new(entao),
+            senao: Box::new(senao),
+        },
+    };
+    Ok((rest, expr))
+}
+
+fn parse_bloco(lexemas: &[Lexema]) -> Resultado<'_, Expr> {
+    let (rest, abre) = simbolo(Token::AbreChaves)(lexemas)?;
+    let (rest, instrucoes) = cut(separated_list1(
+        simbolo(Token::PontoEVirgula),
+        parse_instrucao,
+    ))(rest)?;
+    let (rest, ponto_e_virgula) = opt(simbolo(Token::PontoEVirgula))(rest)?;
+    let Ok((rest, fecha)) = simbolo(Token::FechaChaves)(rest) else {
+        let antes = if ponto_e_virgula.is_some() {
+            "expressão"
+        } else {
+            "';'"
+        };
+        let mut erro = ErroParser::new(rest, antes);
+        erro.esperado.push("'}'".to_string());
+        return Err(nom::Err::Failure(erro));
+    };
+    let expr = Expr {
+        tipo: TipoExpr::Bloco(instrucoes),
+        span: abre.span.start..fecha.span.end,
+    };
+    Ok((rest, expr))
+}
+
+// Parser para operadores unários (- e !)
+fn parse_unario(lexemas: &[Lexema]) -> Resultado<'_, Expr> {
+    alt((
+        map(
+            pair(operador(UNARIOS), cut(parse_unario)),
+            |(op, operando)| Expr {
+                span: op.span.start..operando.span.end,
+                tipo: TipoExpr::Unaria {
+                    op: op.token,
+                    operando: Box::new(operando),
+                },
+            },
+        ),
+        parse_fator,
+    ))(lexemas)
+}
+
+// Parser para termos (multiplicação e divisão)
+fn parse_termo(lexemas: &[Lexema]) -> Resultado<'_, Expr> {
+    parse_binaria(lexemas, MULTIPLICACAO_OU_DIVISAO, parse_unario)
+}
+
+// Parser para somas (adição e subtração)
+fn parse_soma(lexemas: &[Lexema]) -> Resultado<'_, Expr> {
+    parse_binaria(lexemas, MAIS_OU_MENOS, parse_termo)
+}
+
+// Comparações não são associativas: "a < b < c" não é aceito
+fn parse_comparacao(lexemas: &[Lexema]) -> Resultado<'_, Expr> {
+    let (rest, esquerda) = parse_soma(lexemas)?;
+    let (rest, op) = match operador(COMPARACAO)(rest) {
+        Ok(ok) => ok,
+        Err(_) => return Ok((rest, esquerda)),
+    };
+    let (rest, direita) = cut(parse_soma)(rest)?;
+    if operador(COMPARACAO)(rest).is_ok() {
+        let mut erro = ErroParser::from_error_kind(rest, ErrorKind::Verify);
+        erro.mensagem = Some("comparações não podem ser encadeadas; use && entre elas");
+        return Err(nom::Err::Failure(erro));
+    }
+    Ok((rest, operacao(op.token, esquerda, direita)))
+}
+
+fn parse_e(lexemas: &[Lexema]) -> Resultado<'_, Expr> {
+    parse_binaria(lexemas, E, parse_comparacao)
+}
+
+// Parser para expressões completas; || tem a menor precedência
+pub fn parse_expr(lexemas: &[Lexema]) -> Resultado<'_, Expr> {
+    parse_binaria(lexemas, OU, parse_e)
+}
+
+// let <nome> = <expressão> ou uma expressão
+fn parse_instrucao(lexemas: &[Lexema]) -> Resultado<'_, Instrucao> {
+    if let Ok((rest, _)) = simbolo(Token::Let)(lexemas) {
+        let (rest, (nome, _)) = cut(parse_identificador)(rest)?;
+        let (rest, _) = cut(simbolo(Token::Atribuicao))(rest)?;
+        let (rest, valor) = cut(parse_expr)(rest)?;
+        return Ok((rest, Instrucao::Let { nome, valor }));
+    }
+    map(parse_expr, Instrucao::Expressao)(lexemas).map_err(|e| match e {
+        nom::Err::Error(_) => nom::Err::Error(ErroParser::new(lexemas, "instrução")),
+        outro => outro,
+    })
+}
+
+// Pula até depois do próximo ';' fora de parênteses e chaves, onde começa a
+// próxima instrução
+fn sincronizar(lexemas: &[Lexema]) -> &[Lexema] {
+    let mut profundidade = 0i32;
+    for (i, lexema) in lexemas.iter().enumerate() {
+        match lexema.token {
+            Token::AbreParenteses | Token::AbreChaves => profundidade += 1,
+            Token::FechaParenteses | Token::FechaChaves => profundidade -= 1,
+            Token::PontoEVirgula if profundidade <= 0 => return &lexemas[i + 1..],
+            _ => {}
+        }
+    }
+    &[]
+}
+
+// Analisa o programa inteiro. Em caso de erro, continua a partir da próxima
+// instrução para reportar todos os erros de uma vez; tokens inválidos e
+// tokens que sobram depois de uma instrução também são erros
+pub fn analisar(lexemas: &[Lexema]) -> Result<Programa, Vec<Diagnostico>> {
+    let fim = lexemas.last().map_or(0, |l| l.span.end);
+    let mut diagnosticos: Vec<Diagnostico> = lexemas
+        .iter()
+        .filter(|l| l.token == Token::Error)
+        .map(|l| Diagnostico::new("caractere ou literal inválido", l.span.clone()))
+        .collect();
+
+    let mut instrucoes = Vec::new();
+    let mut resto = lexemas;
+    while !resto.is_empty() {
+        let (falha, proximo) = match parse_instrucao(resto) {
+            Ok((rest, instrucao)) => {
+                instrucoes.push(instrucao);
+                match rest.split_first() {
+                    None => break,
+                    Some((
+                        Lexema {
+                            token: Token::PontoEVirgula,
+                            ..
+                        },
+                        rest,
+                    )) => {
+                        resto = rest;
+                        continue;
+                    }
+                    // faltou o ';': a próxima instrução começa onde esta terminou
+                    Some(_) => (ErroParser::new(rest, "';'"), rest),
+                }
+            }
+            Err(nom::Err::Error(e) | nom::Err::Failure(e)) => (e, sincronizar(resto)),
+            Err(nom::Err::Incomplete(_)) => {
+                unreachable!("o parser trabalha sobre a entrada completa")
+            }
+        };
+        // o token inválido já foi reportado
+        if falha.resto.first().map(|l| &l.token) != Some(&Token::Error) {
+            diagnosticos.push(falha.diagnostico(fim));
+        }
+        resto = proximo;
+    }
+
+    if diagnosticos.is_empty() {
+        Ok(Programa { instrucoes })
+    } else {
+        diagnosticos.sort_by_key(|d| d.span.start);
+        Err(diagnosticos)
+    }
+}
+
+// Lê e analisa o código fonte
+pub fn analisar_fonte(fonte: &str) -> Result<Programa, Vec<Diagnostico>> {
+    analisar(&tokenizar(fonte))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mensagens(fonte: &str) -> Vec<(String, Span)> {
+        analisar_fonte(fonte)
+            .unwrap_err()
+            .into_iter()
+            .map(|d| (d.mensagem, d.span))
+            .collect()
+    }
+
+    #[test]
+    fn precedencia_e_spans() {
+        let programa = analisar_fonte("a || b && -x + 1 < 2 * (y)").unwrap();
+        let Instrucao::Expressao(expr) = &programa.instrucoes[0] else {
+            panic!("esperada uma expressão");
+        };
+        assert_eq!(expr.span, 0..26);
+        let TipoExpr::Operacao {
+            op: Token::Ou,
+            direita,
+            ..
+        } = &expr.tipo
+        else {
+            panic!("esperado ||");
+        };
+        let TipoExpr::Operacao {
+            op: Token::E,
+            direita: comparacao,
+            ..
+        } = &direita.tipo
+        else {
+            panic!("esperado &&");
+        };
+        assert_eq!(comparacao.span, 10..26);
+        let TipoExpr::Operacao {
+            op: Token::Menor,
+            esquerda,
+            direita,
+        } = &comparacao.tipo
+        else {
+            panic!("esperado <");
+        };
+        assert_eq!(esquerda.span, 10..16);
+        assert!(matches!(
+            esquerda.tipo,
+            TipoExpr::Operacao {
+                op: Token::Mais,
+                ..
+            }
+        ));
+        // os parênteses fazem parte do trecho
+        assert_eq!(direita.span, 19..26);
+    }
+
+    #[test]
+    fn instrucoes_e_condicionais() {
+        let p = analisar_fonte(
+            "let a = 1; let b = if a > 0 { \"sim\" } else if a < 0 { \"não\" } else { \"zero\" }; b;",
+        )
+        .unwrap();
+        assert_eq!(p.instrucoes.len(), 3);
+        assert!(matches!(
+            &p.instrucoes[1],
+            Instrucao::Let { nome, valor: Expr { tipo: TipoExpr::Se { senao, .. }, .. } }
+                if nome == "b" && matches!(senao.tipo, TipoExpr::Se { .. })
+        ));
+        assert_eq!(analisar_fonte("").unwrap(), Programa::default());
+    }
+
+    #[test]
+    fn esperado_e_encontrado() {
+        assert_eq!(
+            mensagens("let x = ;"),
+            vec![("esperado expressão, encontrado ';'".into(), 8..9)]
+        );
+        assert_eq!(
+            mensagens("1 + 2 3"),
+            vec![("esperado ';', encontrado '3'".into(), 6..7)]
+        );
+        assert_eq!(
+            mensagens("(1 + 2"),
+            vec![("esperado ')', encontrado fim da entrada".into(), 6..6)]
+        );
+        assert_eq!(
+            mensagens("if true { 1 }"),
+            vec![("esperado 'else', encontrado fim da entrada".into(), 13..13)]
+        );
+        assert_eq!(
+            mensagens("if x { 1 } else 2"),
+            vec![("esperado 'if' ou '{', encontrado '2'".into(), 16..17)]
+        );
+        assert_eq!(
+            mensagens("{ 1 2 }"),
+            vec![("esperado ';' ou '}', encontrado '2'".into(), 4..5)]
+        );
+        assert_eq!(
+            mensagens("1 < 2 < 3"),
+            vec![(
+                "comparações não podem ser encadeadas; use && entre elas".into(),
+                6..7
+            )]
+        );
+    }
+
+    #[test]
+    fn recupera_nas_instrucoes_seguintes() {
+        assert_eq!(
+            mensagens("let a = 1 +; let b = { 2 * }; let c = $; let = 4; ok"),
+            vec![
+                ("esperado expressão, encontrado ';'".into(), 11..12),
+                ("esperado expressão, encontrado '}'".into(), 27..28),
+                ("caractere ou literal inválido".into(), 38..39),
+                ("esperado identificador, encontrado '='".into(), 45..46),
+            ]
+        );
+        // a instrução depois de um ';' faltando ainda é analisada
+        assert_eq!(
+            mensagens("let a = 1 let b = ; 2"),
+            vec![
+                ("esperado ';', encontrado 'let'".into(), 10..13),
+                ("esperado expressão, encontrado ';'".into(), 18..19),
+            ]
+        );
+    }
+}
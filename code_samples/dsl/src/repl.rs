@@ -0,0 +1,243 @@
+use std::fmt::Write;
+use std::fs;
+use std::mem;
+
+use crate::avaliador::{executar, Ambiente};
+use crate::diagnostico::renderizar_todos;
+use crate::lexer::tokenizar;
+use crate::parser::analisar_fonte;
+
+pub const AJUDA: &str = "\
+Digite instruções separadas por ';'. As variáveis definidas com let
+continuam valendo nas linhas seguintes. Uma entrada incompleta (um bloco
+aberto, um operador no fim da linha) continua na próxima linha; uma linha
+em branco encerra a entrada.
+
+Comandos:
+  :tokens <código>     mostra os tokens e os seus trechos
+  :ast <código>        mostra a AST, sem executar
+  :carregar <arquivo>  executa um arquivo com o ambiente atual
+  :vars                lista as variáveis definidas
+  :limpar              apaga todas as variáveis
+  :ajuda               mostra esta ajuda
+  :sair                encerra (Ctrl-D também)";
+
+// O que fazer com uma linha digitada
+#[derive(Debug, Clone, PartialEq)]
+pub enum Resposta {
+    // A entrada ainda não terminou; a próxima linha continua a mesma entrada
+    Continuar,
+    Saida(String),
+    Erro(String),
+    Sair,
+}
+
+// Estado da sessão interativa: o ambiente compartilhado entre as linhas e a
+// entrada de várias linhas que ainda não terminou. Não lê nem escreve no
+// terminal, para poder ser testado
+#[derive(Debug, Default)]
+pub struct Repl {
+    ambiente: Ambiente,
+    pendente: String,
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn ambiente(&self) -> &Ambiente {
+        &self.ambiente
+    }
+
+    // Há uma entrada de várias linhas em andamento (o prompt muda)
+    pub fn pendente(&self) -> bool {
+        !self.pendente.is_empty()
+    }
+
+    // Descarta a entrada em andamento (Ctrl-C)
+    pub fn descartar(&mut self) {
+        self.pendente.clear();
+    }
+
+    pub fn processar(&mut self, linha: &str) -> Resposta {
+        if self.pendente() {
+            let mut entrada = mem::take(&mut self.pendente);
+            // a linha em branco força a análise, mostrando o que falta
+            let completa = linha.trim().is_empty();
+            if !completa {
+                entrada.push('\n');
+                entrada.push_str(linha);
+            }
+            return self.avaliar(&entrada, completa);
+        }
+
+        let linha = linha.trim();
+        match linha.strip_prefix(':') {
+            Some(comando) => self.comando(comando),
+            None if linha.is_empty() => Resposta::Saida(String::new()),
+            None => self.avaliar(linha, false),
+        }
+    }
+
+    fn comando(&mut self, comando: &str) -> Resposta {
+        let (nome, argumento) = comando
+            .split_once(char::is_whitespace)
+            .map_or((comando, ""), |(nome, resto)| (nome, resto.trim()));
+        match nome {
+            "tokens" => {
+                let mut saida = String::new();
+                for lexema in tokenizar(argumento) {
+                    writeln!(saida, "{:?}\t{:?}", lexema.span, lexema.token).unwrap();
+                }
+                Resposta::Saida(saida.trim_end().to_string())
+            }
+            "ast" => match analisar_fonte(argumento) {
+                Ok(programa) => Resposta::Saida(format!("{:#?}", programa)),
+                Err(diagnosticos) => Resposta::Erro(renderizar_todos(&diagnosticos, argumento)),
+            },
+            "carregar" if argumento.is_empty() => Resposta::Erro("uso: :carregar <arquivo>".into()),
+            "carregar" => match fs::read_to_string(argumento) {
+                Ok(fonte) => self.avaliar(&fonte, true),
+                Err(erro) => {
+                    Resposta::Erro(format!("não foi possível ler '{}': {}", argumento, erro))
+                }
+            },
+            "vars" => {
+                let mut saida = String::new();
+                for (nome, valor) in self.ambiente.variaveis() {
+                    writeln!(saida, "{} = {} ({})", nome, valor, valor.tipo()).unwrap();
+                }
+                Resposta::Saida(saida.trim_end().to_string())
+            }
+            "limpar" => {
+                self.ambiente = Ambiente::new();
+                Resposta::Saida(String::new())
+            }
+            "ajuda" => Resposta::Saida(AJUDA.to_string()),
+            "sair" => Resposta::Sair,
+            _ => Resposta::Erro(format!("comando desconhecido ':{}'; veja :ajuda", nome)),
+        }
+    }
+
+    // Analisa e executa a entrada. Se ela só falhou por ter acabado cedo (todo
+    // erro está no fim da entrada), espera a próxima linha. Uma entrada com
+    // erro de execução não altera o ambiente
+    fn avaliar(&mut self, entrada: &str, completa: bool) -> Resposta {
+        let programa = match analisar_fonte(entrada) {
+            Ok(programa) => programa,
+            Err(diagnosticos) => {
+                if !completa && diagnosticos.iter().all(|d| d.span.is_empty()) {
+                    self.pendente = entrada.to_string();
+                    return Resposta::Continuar;
+                }
+                return Resposta::Erro(renderizar_todos(&diagnosticos, entrada));
+            }
+        };
+
+        let mut ambiente = self.ambiente.clone();
+        match executar(&programa, &mut ambiente) {
+            Ok(valor) => {
+                self.ambiente = ambiente;
+                Resposta::Saida(valor.map(|v| v.to_string()).unwrap_or_default())
+            }
+            Err(erro) => Resposta::Erro(erro.diagnostico().renderizar(entrada)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::avaliador::Valor;
+
+    fn saida(texto: &str) -> Resposta {
+        Resposta::Saida(texto.to_string())
+    }
+
+    #[test]
+    fn variaveis_continuam_entre_linhas() {
+        let mut repl = Repl::new();
+        assert_eq!(repl.processar("let limite = 1000"), saida("1000"));
+        assert_eq!(repl.processar("let taxa = 0.1;"), saida("0.1"));
+        assert_eq!(repl.processar("limite * taxa"), saida("100.0"));
+        assert_eq!(
+            repl.processar(":vars"),
+            saida("limite = 1000 (inteiro)\ntaxa = 0.1 (real)")
+        );
+        // um erro de execução desfaz a entrada inteira
+        assert!(matches!(
+            repl.processar("let limite = 5; limite / 0"),
+            Resposta::Erro(erro) if erro.starts_with("erro: divisão por zero")
+        ));
+        assert_eq!(repl.ambiente().obter("limite"), Some(&Valor::Inteiro(1000)));
+        assert_eq!(repl.processar(":limpar"), saida(""));
+        assert!(repl.ambiente().variaveis().is_empty());
+    }
+
+    #[test]
+    fn entrada_de_varias_linhas() {
+        let mut repl = Repl::new();
+        assert_eq!(
+            repl.processar("let taxa = if 1500 > 1000 {"),
+            Resposta::Continuar
+        );
+        assert!(repl.pendente());
+        assert_eq!(repl.processar("  0.1"), Resposta::Continuar);
+        assert_eq!(repl.processar("} else { 0.0 }"), saida("0.1"));
+        assert!(!repl.pendente());
+
+        // a linha em branco encerra a entrada e mostra o que falta
+        assert_eq!(repl.processar("1 +"), Resposta::Continuar);
+        assert_eq!(
+            repl.processar(""),
+            Resposta::Erro(
+                "erro: esperado expressão, encontrado fim da entrada\n  --> 1:4\n  |\n1 | 1 +\n  |    ^"
+                    .into()
+            )
+        );
+        // um erro no meio da linha não espera por mais linhas
+        assert!(matches!(repl.processar("1 + ) {"), Resposta::Erro(_)));
+        assert!(!repl.pendente());
+    }
+
+    #[test]
+    fn comandos() {
+        let mut repl = Repl::new();
+        assert_eq!(
+            repl.processar(":tokens let x = 1"),
+            saida("0..3\tLet\n4..5\tIdentificador(\"x\")\n6..7\tAtribuicao\n8..9\tNumero(1)")
+        );
+        assert!(matches!(
+            repl.processar(":ast 1 + 2"),
+            Resposta::Saida(ast) if ast.contains("op: Mais")
+        ));
+        // :ast não executa
+        assert!(matches!(
+            repl.processar(":ast let y = 1"),
+            Resposta::Saida(_)
+        ));
+        assert_eq!(repl.ambiente().obter("y"), None);
+        assert!(matches!(
+            repl.processar(":carregar /nao/existe.regra"),
+            Resposta::Erro(_)
+        ));
+        assert!(matches!(repl.processar(":xyz"), Resposta::Erro(_)));
+        assert_eq!(repl.processar(":sair"), Resposta::Sair);
+    }
+
+    #[test]
+    fn carrega_arquivo() {
+        let caminho = std::env::temp_dir().join("dsl_repl_carrega_arquivo.regra");
+        fs::write(
+            &caminho,
+            "# regras de teste\nlet base = 10;\nlet dobro = base * 2;\n",
+        )
+        .unwrap();
+        let mut repl = Repl::new();
+        let resposta = repl.processar(&format!(":carregar {}", caminho.display()));
+        fs::remove_file(&caminho).unwrap();
+        assert_eq!(resposta, saida("20"));
+        assert_eq!(repl.processar("dobro + base"), saida("30"));
+    }
+}
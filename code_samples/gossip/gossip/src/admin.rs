@@ -0,0 +1,513 @@
+//! API HTTP/JSON de administração, para usar o nó como sidecar: outro
+//! processo na mesma máquina lê e escreve o estado replicado sem passar
+//! pelo terminal.
+//!
+//! * `GET /keys` — as chaves (menos as apagadas) e os seus valores;
+//! * `GET /keys/{chave}` — uma chave;
+//! * `PUT /keys/{chave}` — um texto JSON escreve no registrador LWW; uma
+//!   operação (`{"op": "increment", "by": 2}`, `{"op": "add", "element":
+//!   "x"}`...) altera a chave conforme o tipo;
+//! * `DELETE /keys/{chave}` — apaga a chave;
+//! * `GET /watch?prefix=...` — Server-Sent Events: um `snapshot` com as
+//!   chaves atuais e depois um `change` a cada alteração, daqui ou da fofoca;
+//! * `GET /members` — a visão do cluster;
+//! * `GET /stats` — o tráfego da última rodada, o total e o de cada peer.
+
+use crate::antientropy::Traffic;
+use crate::crdt::{Crdt, CrdtError, Op};
+use crate::membership::MemberState;
+use crate::protocol::Protocol;
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::sse::{self, KeepAlive, Sse},
+    routing::get,
+};
+use futures::{Stream, StreamExt, stream};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::Mutex;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// Quantas alterações ficam na fila de quem acompanha `/watch`; um cliente
+/// mais atrasado que isso recebe um `snapshot` novo.
+pub const WATCH_BUFFER: usize = 1024;
+
+#[derive(Clone)]
+pub struct AdminState {
+    pub protocol: Arc<Mutex<Protocol>>,
+    pub changes: broadcast::Sender<KeyView>,
+}
+
+/// Uma chave como a API a mostra.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct KeyView {
+    pub key: String,
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub value: Value,
+    pub deleted: bool,
+}
+
+impl KeyView {
+    fn new(key: &str, crdt: &Crdt) -> Self {
+        KeyView {
+            key: key.to_string(),
+            kind: crdt.type_name(),
+            value: crdt.value(),
+            deleted: crdt.is_deleted(),
+        }
+    }
+}
+
+/// Avisa quem acompanha `/watch` das chaves que mudaram no protocolo.
+pub fn publish_changes(protocol: &mut Protocol, changes: &broadcast::Sender<KeyView>) {
+    let mut keys = protocol.take_changes();
+    keys.sort();
+    keys.dedup();
+    for key in keys {
+        if let Some(crdt) = protocol.state().get(&key) {
+            // sem ninguém acompanhando, o envio falha, e tudo bem
+            let _ = changes.send(KeyView::new(&key, crdt));
+        }
+    }
+}
+
+pub fn router(state: AdminState) -> Router {
+    Router::new()
+        .route("/keys", get(list_keys))
+        .route("/keys/:key", get(get_key).put(put_key).delete(delete_key))
+        .route("/watch", get(watch))
+        .route("/members", get(members))
+        .route("/stats", get(stats))
+        .with_state(state)
+}
+
+type ApiError = (StatusCode, Json<Value>);
+
+fn error(status: StatusCode, message: impl ToString) -> ApiError {
+    (
+        status,
+        Json(serde_json::json!({ "error": message.to_string() })),
+    )
+}
+
+fn crdt_error(e: CrdtError) -> ApiError {
+    let status = match e {
+        CrdtError::NotFound(_) => StatusCode::NOT_FOUND,
+        CrdtError::TypeMismatch { .. } => StatusCode::CONFLICT,
+    };
+    error(status, e)
+}
+
+/// As chaves que existem (não apagadas), em ordem, das que começam com `prefix`.
+fn current(protocol: &Protocol, prefix: &str) -> Vec<KeyView> {
+    let mut keys: Vec<KeyView> = protocol
+        .state()
+        .iter()
+        .filter(|(key, crdt)| key.starts_with(prefix) && !crdt.is_deleted())
+        .map(|(key, crdt)| KeyView::new(key, crdt))
+        .collect();
+    keys.sort_by(|a, b| a.key.cmp(&b.key));
+    keys
+}
+
+async fn list_keys(State(state): State<AdminState>) -> Json<Vec<KeyView>> {
+    Json(current(&*state.protocol.lock().await, ""))
+}
+
+async fn get_key(
+    State(state): State<AdminState>,
+    Path(key): Path<String>,
+) -> Result<Json<KeyView>, ApiError> {
+    let protocol = state.protocol.lock().await;
+    match protocol.state().get(&key) {
+        Some(crdt) if !crdt.is_deleted() => Ok(Json(KeyView::new(&key, crdt))),
+        _ => Err(crdt_error(CrdtError::NotFound(key))),
+    }
+}
+
+/// O corpo do `PUT`: um texto, para o caso comum, ou uma operação.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PutBody {
+    Value(String),
+    Op(Op),
+}
+
+async fn put_key(
+    State(state): State<AdminState>,
+    Path(key): Path<String>,
+    Json(body): Json<PutBody>,
+) -> Result<Json<KeyView>, ApiError> {
+    let op = match body {
+        PutBody::Value(value) => Op::Set { value },
+        PutBody::Op(op) => op,
+    };
+    change(&state, &key, op).await.map(Json)
+}
+
+async fn delete_key(
+    State(state): State<AdminState>,
+    Path(key): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    change(&state, &key, Op::Delete).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn change(state: &AdminState, key: &str, op: Op) -> Result<KeyView, ApiError> {
+    let mut protocol = state.protocol.lock().await;
+    let crdt = protocol
+        .apply(key, op, Instant::now())
+        .map_err(crdt_error)?;
+    publish_changes(&mut protocol, &state.changes);
+    Ok(KeyView::new(key, &crdt))
+}
+
+#[derive(Deserialize)]
+struct WatchQuery {
+    #[serde(default)]
+    prefix: String,
+}
+
+/// Um aviso do `/watch`.
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Notification {
+    Snapshot(Vec<KeyView>),
+    Change(KeyView),
+}
+
+/// Server-Sent Events: `snapshot` com as chaves atuais e depois `change`
+/// a cada alteração de uma chave com o prefixo. Um cliente lento demais,
+/// que perde alterações, recebe um `snapshot` novo.
+async fn watch(
+    State(state): State<AdminState>,
+    Query(query): Query<WatchQuery>,
+) -> Sse<impl Stream<Item = Result<sse::Event, Infallible>>> {
+    let (first, receiver) = {
+        // protocolo travado: nenhuma alteração entre o snapshot e a inscrição
+        let protocol = state.protocol.lock().await;
+        let receiver = state.changes.subscribe();
+        (
+            Notification::Snapshot(current(&protocol, &query.prefix)),
+            receiver,
+        )
+    };
+    let live = stream::unfold(
+        (receiver, state, query.prefix),
+        |(mut receiver, state, prefix)| async move {
+            loop {
+                let notification = match receiver.recv().await {
+                    Ok(view) if view.key.starts_with(&prefix) => Notification::Change(view),
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(_)) => {
+                        Notification::Snapshot(current(&*state.protocol.lock().await, &prefix))
+                    }
+                    Err(RecvError::Closed) => return None,
+                };
+                return Some((notification, (receiver, state, prefix)));
+            }
+        },
+    );
+    let events = stream::iter([first]).chain(live).map(|notification| {
+        let name = match notification {
+            Notification::Snapshot(_) => "snapshot",
+            Notification::Change(_) => "change",
+        };
+        Ok(sse::Event::default()
+            .event(name)
+            .json_data(&notification)
+            .unwrap())
+    });
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+#[derive(Serialize)]
+struct MemberView {
+    addr: SocketAddr,
+    state: MemberState,
+    incarnation: u64,
+}
+
+#[derive(Serialize)]
+struct MembersView {
+    me: MemberView,
+    members: Vec<MemberView>,
+}
+
+async fn members(State(state): State<AdminState>) -> Json<MembersView> {
+    let protocol = state.protocol.lock().await;
+    let membership = protocol.membership();
+    Json(MembersView {
+        me: MemberView {
+            addr: membership.me(),
+            state: MemberState::Alive,
+            incarnation: membership.incarnation(),
+        },
+        members: membership
+            .members()
+            .into_iter()
+            .map(|(addr, member)| MemberView {
+                addr,
+                state: member.state,
+                incarnation: member.incarnation,
+            })
+            .collect(),
+    })
+}
+
+#[derive(Serialize)]
+struct PeerStats {
+    /// None se o peer já foi esquecido (ou nunca entrou na visão do cluster).
+    state: Option<MemberState>,
+    #[serde(flatten)]
+    traffic: Traffic,
+}
+
+#[derive(Serialize)]
+struct StatsView {
+    round: u64,
+    last_round: Traffic,
+    total: Traffic,
+    peers: BTreeMap<SocketAddr, PeerStats>,
+}
+
+async fn stats(State(state): State<AdminState>) -> Json<StatsView> {
+    let protocol = state.protocol.lock().await;
+    let metrics = protocol.metrics();
+    let peers = metrics
+        .peers
+        .iter()
+        .map(|(addr, traffic)| {
+            let stats = PeerStats {
+                state: protocol.membership().state_of(*addr),
+                traffic: *traffic,
+            };
+            (*addr, stats)
+        })
+        .collect();
+    Json(StatsView {
+        round: metrics.round,
+        last_round: metrics.last_round,
+        total: metrics.total,
+        peers,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::membership::SwimConfig;
+    use crate::protocol::Config;
+    use axum::body::BodyDataStream;
+    use axum::body::{Body, to_bytes};
+    use axum::http::Request;
+    use std::time::Duration;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    fn app() -> Router {
+        app_with_buffer(WATCH_BUFFER)
+    }
+
+    fn app_with_buffer(buffer: usize) -> Router {
+        let config = Config {
+            id: Uuid::from_u128(1),
+            addr: "127.0.0.1:7000".parse().unwrap(),
+            max_datagram: 1400,
+            swim: SwimConfig::default(),
+            guard: None,
+            verbose: false,
+        };
+        let protocol = Protocol::new(config, Instant::now(), 0);
+        let (changes, _) = broadcast::channel(buffer);
+        router(AdminState {
+            protocol: Arc::new(Mutex::new(protocol)),
+            changes,
+        })
+    }
+
+    async fn call(app: &Router, method: &str, uri: &str, body: &str) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (
+            status,
+            serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+        )
+    }
+
+    /// Abre o `/watch` e devolve o corpo, que chega aos poucos.
+    async fn watch(app: &Router, uri: &str) -> BodyDataStream {
+        let request = Request::get(uri).body(Body::empty()).unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        response.into_body().into_data_stream()
+    }
+
+    /// O próximo evento do `/watch`: o nome e os dados.
+    async fn next_event(events: &mut BodyDataStream, pending: &mut String) -> (String, Value) {
+        loop {
+            if let Some(end) = pending.find("\n\n") {
+                let event: String = pending.drain(..end + 2).collect();
+                let field = |name: &str| {
+                    event
+                        .lines()
+                        .find_map(|line| line.strip_prefix(name))
+                        .map(str::to_string)
+                };
+                if let (Some(name), Some(data)) = (field("event: "), field("data: ")) {
+                    return (name, serde_json::from_str(&data).unwrap());
+                }
+                continue;
+            }
+            let chunk = tokio::time::timeout(Duration::from_secs(5), events.next())
+                .await
+                .expect("o /watch não mandou nada")
+                .unwrap()
+                .unwrap();
+            pending.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+    }
+
+    #[tokio::test]
+    async fn keys_can_be_written_read_and_deleted() {
+        let app = app();
+        let (status, body) = call(&app, "PUT", "/keys/cor", r#""azul""#).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["value"], "azul");
+
+        call(
+            &app,
+            "PUT",
+            "/keys/visitas",
+            r#"{"op": "increment", "by": 3}"#,
+        )
+        .await;
+        let (_, body) = call(
+            &app,
+            "PUT",
+            "/keys/tags",
+            r#"{"op": "add", "element": "a"}"#,
+        )
+        .await;
+        assert_eq!(body["type"], "set");
+        assert_eq!(body["value"], serde_json::json!(["a"]));
+
+        let (status, body) = call(&app, "GET", "/keys/visitas", "").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["value"], 3);
+
+        // o tipo da chave não muda
+        let (status, _) = call(&app, "PUT", "/keys/visitas", r#""x""#).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        let (status, _) = call(&app, "DELETE", "/keys/cor", "").await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = call(&app, "GET", "/keys/cor", "").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = call(&app, "DELETE", "/keys/nada", "").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (_, body) = call(&app, "GET", "/keys", "").await;
+        let keys: Vec<&str> = body
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|view| view["key"].as_str().unwrap())
+            .collect();
+        assert_eq!(keys, vec!["tags", "visitas"]);
+    }
+
+    #[tokio::test]
+    async fn members_and_stats_describe_the_node() {
+        let app = app();
+        let (status, body) = call(&app, "GET", "/members", "").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["me"]["addr"], "127.0.0.1:7000");
+        assert_eq!(body["members"], serde_json::json!([]));
+
+        let (status, body) = call(&app, "GET", "/stats", "").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["total"]["bytes_sent"], 0);
+        assert_eq!(body["peers"], serde_json::json!({}));
+    }
+
+    #[tokio::test]
+    async fn counters_at_zero_are_not_deleted() {
+        let app = app();
+        call(&app, "PUT", "/keys/c", r#"{"op": "increment", "by": 1}"#).await;
+        call(&app, "PUT", "/keys/c", r#"{"op": "increment", "by": -1}"#).await;
+        let (status, body) = call(&app, "GET", "/keys/c", "").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["value"], 0);
+
+        let (status, body) = call(&app, "PUT", "/keys/z", r#"{"op": "increment", "by": 0}"#).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["deleted"], false);
+        let (_, body) = call(&app, "GET", "/keys", "").await;
+        assert_eq!(body.as_array().unwrap().len(), 2);
+
+        // apagado de fato só com DELETE, até a próxima escrita
+        call(&app, "DELETE", "/keys/c", "").await;
+        let (status, _) = call(&app, "GET", "/keys/c", "").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        call(&app, "PUT", "/keys/c", r#"{"op": "increment", "by": 0}"#).await;
+        let (status, _) = call(&app, "GET", "/keys/c", "").await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn watch_sends_a_snapshot_then_the_changes_under_the_prefix() {
+        let app = app();
+        call(&app, "PUT", "/keys/app.cor", r#""azul""#).await;
+        call(&app, "PUT", "/keys/outra", r#""x""#).await;
+        let (mut events, mut pending) = (watch(&app, "/watch?prefix=app.").await, String::new());
+
+        let (name, data) = next_event(&mut events, &mut pending).await;
+        assert_eq!(name, "snapshot");
+        assert_eq!(data[0]["key"], "app.cor");
+        assert_eq!(data.as_array().unwrap().len(), 1);
+
+        // fora do prefixo: nenhum evento
+        call(&app, "PUT", "/keys/outra", r#""y""#).await;
+        call(&app, "PUT", "/keys/app.cor", r#""verde""#).await;
+        let (name, data) = next_event(&mut events, &mut pending).await;
+        assert_eq!(name, "change");
+        assert_eq!(data["key"], "app.cor");
+        assert_eq!(data["value"], "verde");
+
+        call(&app, "DELETE", "/keys/app.cor", "").await;
+        let (name, data) = next_event(&mut events, &mut pending).await;
+        assert_eq!(name, "change");
+        assert_eq!(data["deleted"], true);
+    }
+
+    #[tokio::test]
+    async fn a_lagging_watcher_gets_a_new_snapshot() {
+        let app = app_with_buffer(1);
+        let (mut events, mut pending) = (watch(&app, "/watch").await, String::new());
+        let (name, _) = next_event(&mut events, &mut pending).await;
+        assert_eq!(name, "snapshot");
+
+        for key in ["a", "b", "c"] {
+            call(&app, "PUT", &format!("/keys/{}", key), r#""v""#).await;
+        }
+        let (name, data) = next_event(&mut events, &mut pending).await;
+        assert_eq!(name, "snapshot");
+        assert_eq!(data.as_array().unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,270 @@
+//! Anti-entropia push-pull: em vez do estado inteiro, os nós trocam primeiro
+//! um digest com um resumo (hash) do conteúdo de cada chave e depois só as
+//! entradas que faltam ou que estão diferentes de um dos lados.
+//!
+//! 1. A envia a B o seu digest (`Syn`), em trechos se ele não couber em um
+//!    datagrama;
+//! 2. B responde com as entradas que ele tem diferentes ou que A não tem
+//!    (`Delta`) e pede as que A tem diferentes ou que ele não tem (`Request`);
+//! 3. A responde ao pedido com as entradas (`Delta`).
+//!
+//! Os valores são CRDTs, sem uma ordem entre versões: quando os resumos de
+//! uma chave diferem, os dois lados mandam a sua e juntam a que recebem.
+//! Como a junção é idempotente, receber de volta o que já se tem não muda
+//! nada.
+
+use crate::NodeState;
+use crate::crdt::Crdt;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::SocketAddr;
+
+/// Uma entrada do estado, como viaja em um `Delta`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub key: String,
+    pub value: Crdt,
+}
+
+/// Um trecho do digest. Os trechos dividem as chaves, em ordem, em
+/// intervalos: cada um cobre as chaves depois de `after` até a sua última
+/// chave (ou até o fim, no último trecho). Assim quem recebe sabe também
+/// quais das suas chaves o remetente não tem.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DigestChunk {
+    /// Última chave do trecho anterior; None no primeiro trecho.
+    pub after: Option<String>,
+    /// Chave e resumo do conteúdo, em ordem de chave.
+    pub versions: Vec<(String, u64)>,
+    /// Verdadeiro no último trecho.
+    pub last: bool,
+}
+
+impl DigestChunk {
+    /// Verdadeiro se a chave está no intervalo coberto pelo trecho.
+    pub fn covers(&self, key: &str) -> bool {
+        let after_start = self.after.as_deref().is_none_or(|after| key > after);
+        let before_end = self.last
+            || self
+                .versions
+                .last()
+                .is_some_and(|(last_key, _)| key <= last_key.as_str());
+        after_start && before_end
+    }
+}
+
+/// O resumo de cada chave, em ordem de chave.
+pub fn digest(state: &NodeState) -> Vec<(String, u64)> {
+    let mut versions: Vec<(String, u64)> = state
+        .iter()
+        .map(|(key, value)| (key.clone(), value.digest()))
+        .collect();
+    versions.sort();
+    versions
+}
+
+/// As entradas das chaves pedidas que existem no estado.
+pub fn entries(state: &NodeState, keys: &[String]) -> Vec<Entry> {
+    keys.iter()
+        .filter_map(|key| {
+            state.get(key).map(|value| Entry {
+                key: key.clone(),
+                value: value.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Compara um trecho do digest remoto com o estado local. Devolve as
+/// entradas a mandar ao remetente (as diferentes e as que ele não tem) e as
+/// chaves a pedir a ele (as diferentes e as que o local não tem).
+pub fn reconcile(state: &NodeState, chunk: &DigestChunk) -> (Vec<Entry>, Vec<String>) {
+    let mut differing = Vec::new();
+    let mut wanted = Vec::new();
+    for (key, remote_digest) in &chunk.versions {
+        match state.get(key) {
+            Some(value) if value.digest() == *remote_digest => {}
+            Some(_) => {
+                differing.push(key.clone());
+                wanted.push(key.clone());
+            }
+            None => wanted.push(key.clone()),
+        }
+    }
+    let mentioned: std::collections::HashSet<&str> =
+        chunk.versions.iter().map(|(key, _)| key.as_str()).collect();
+    for key in state.keys() {
+        if chunk.covers(key) && !mentioned.contains(key.as_str()) {
+            differing.push(key.clone());
+        }
+    }
+    differing.sort();
+    (entries(state, &differing), wanted)
+}
+
+/// Junta as entradas recebidas ao estado e devolve as chaves que mudaram.
+pub fn apply(state: &mut NodeState, entries: Vec<Entry>) -> Vec<String> {
+    let mut changed = Vec::new();
+    for entry in entries {
+        match state.get_mut(&entry.key) {
+            Some(local) => {
+                let before = local.digest();
+                local.merge(&entry.value);
+                if local.digest() != before {
+                    changed.push(entry.key);
+                }
+            }
+            None => {
+                changed.push(entry.key.clone());
+                state.insert(entry.key, entry.value);
+            }
+        }
+    }
+    changed
+}
+
+// --- Métricas ---
+
+/// Mensagens, bytes e entradas enviados e recebidos.
+#[derive(Serialize, Debug, Default, Clone, Copy, PartialEq)]
+pub struct Traffic {
+    pub messages_sent: u64,
+    pub bytes_sent: u64,
+    pub entries_sent: u64,
+    pub messages_received: u64,
+    pub bytes_received: u64,
+    pub entries_received: u64,
+}
+
+impl Traffic {
+    fn count_sent(&mut self, bytes: usize, entries: usize) {
+        self.messages_sent += 1;
+        self.bytes_sent += bytes as u64;
+        self.entries_sent += entries as u64;
+    }
+
+    fn count_received(&mut self, bytes: usize, entries: usize) {
+        self.messages_received += 1;
+        self.bytes_received += bytes as u64;
+        self.entries_received += entries as u64;
+    }
+
+    fn add(&mut self, other: &Traffic) {
+        self.messages_sent += other.messages_sent;
+        self.bytes_sent += other.bytes_sent;
+        self.entries_sent += other.entries_sent;
+        self.messages_received += other.messages_received;
+        self.bytes_received += other.bytes_received;
+        self.entries_received += other.entries_received;
+    }
+}
+
+impl fmt::Display for Traffic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "enviados {} bytes em {} mensagens ({} entradas), recebidos {} bytes em {} mensagens ({} entradas)",
+            self.bytes_sent,
+            self.messages_sent,
+            self.entries_sent,
+            self.bytes_received,
+            self.messages_received,
+            self.entries_received
+        )
+    }
+}
+
+/// Tráfego por rodada. Uma rodada vai de um envio de digest do nó até o
+/// seguinte, e inclui as respostas às rodadas dos outros nós.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    pub round: u64,
+    pub current: Traffic,
+    pub last_round: Traffic,
+    pub total: Traffic,
+    /// Tráfego com cada peer, desde o início.
+    pub peers: BTreeMap<SocketAddr, Traffic>,
+}
+
+impl Metrics {
+    pub fn sent(&mut self, to: SocketAddr, bytes: usize, entries: usize) {
+        self.current.count_sent(bytes, entries);
+        self.peers.entry(to).or_default().count_sent(bytes, entries);
+    }
+
+    pub fn received(&mut self, from: SocketAddr, bytes: usize, entries: usize) {
+        self.current.count_received(bytes, entries);
+        self.peers
+            .entry(from)
+            .or_default()
+            .count_received(bytes, entries);
+    }
+
+    /// Fecha a rodada atual e devolve o tráfego dela.
+    pub fn finish_round(&mut self) -> Traffic {
+        let finished = std::mem::take(&mut self.current);
+        self.total.add(&finished);
+        self.last_round = finished;
+        self.round += 1;
+        finished
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crdt::{Op, Replica};
+    use uuid::Uuid;
+
+    fn state(replica: u128, entries: &[(&str, &str)]) -> NodeState {
+        let mut replica = Replica::new(Uuid::from_u128(replica));
+        let mut state = NodeState::new();
+        for (i, (key, value)) in entries.iter().enumerate() {
+            let op = Op::Set {
+                value: value.to_string(),
+            };
+            replica.apply(&mut state, key, op, i as u64 + 1).unwrap();
+        }
+        state
+    }
+
+    #[test]
+    fn reconcile_exchanges_differing_and_missing_keys() {
+        let local = state(1, &[("a", "1"), ("b", "x"), ("c", "c"), ("z", "z")]);
+        let remote = state(2, &[("a", "2"), ("d", "d")]);
+        let mut versions = digest(&remote);
+        versions.insert(1, ("b".into(), local["b"].digest()));
+        let chunk = DigestChunk {
+            after: None,
+            versions,
+            last: false,
+        };
+        let (differing, wanted) = reconcile(&local, &chunk);
+        let differing: Vec<&str> = differing.iter().map(|e| e.key.as_str()).collect();
+        // "c" está no intervalo do trecho e o remetente não tem; "z" fica para outro trecho
+        assert_eq!(differing, vec!["a", "c"]);
+        assert_eq!(wanted, vec!["a".to_string(), "d".to_string()]);
+
+        let last = DigestChunk {
+            after: Some("d".into()),
+            versions: vec![],
+            last: true,
+        };
+        let (differing, wanted) = reconcile(&local, &last);
+        assert_eq!(differing.len(), 1);
+        assert_eq!(differing[0].key, "z");
+        assert!(wanted.is_empty());
+    }
+
+    #[test]
+    fn apply_merges_and_counts_changes() {
+        let mut local = state(1, &[("a", "velho")]);
+        let newer = state(2, &[("x", "x"), ("a", "novo")]);
+        let entries = entries(&newer, &["a".to_string(), "x".to_string()]);
+        assert_eq!(apply(&mut local, entries.clone()), vec!["a", "x"]);
+        assert_eq!(local, newer);
+        // receber de novo não muda nada
+        assert!(apply(&mut local, entries).is_empty());
+    }
+}
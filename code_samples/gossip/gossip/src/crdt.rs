@@ -0,0 +1,748 @@
+//! Tipos de dados replicados sem conflito (CRDTs) para o estado do nó.
+//!
+//! Cada chave guarda um CRDT, e juntar (`merge`) duas réplicas dá sempre o
+//! mesmo resultado, em qualquer ordem e quantas vezes for: a junção é
+//! comutativa, associativa e idempotente. Assim os nós convergem mesmo que
+//! as mensagens cheguem fora de ordem, repetidas ou por caminhos diferentes,
+//! e escritas concorrentes na mesma chave não se perdem em silêncio.
+//!
+//! * `Lww`: registrador em que a última escrita vence, pelo carimbo de um
+//!   relógio lógico híbrido (HLC);
+//! * `Set`: conjunto em que só se remove o que já se viu adicionado (OR-set):
+//!   uma adição concorrente com a remoção sobrevive;
+//! * `Counter`: contador que soma e subtrai (PN-counter);
+//! * `Mv`: registrador multivalor: escritas concorrentes ficam todas, como
+//!   irmãs, até uma escrita que já viu todas substituí-las.
+
+use crate::NodeState;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use uuid::Uuid;
+
+// --- Relógio lógico híbrido ---
+
+/// Carimbo de um relógio lógico híbrido: o tempo físico em milissegundos, um
+/// contador para eventos no mesmo milissegundo (ou com o relógio físico
+/// atrasado) e a réplica, que desempata. A ordem é a dos campos.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Hlc {
+    pub wall: u64,
+    pub logical: u32,
+    pub replica: Uuid,
+}
+
+impl Hlc {
+    /// Anterior a qualquer carimbo de verdade.
+    pub const ZERO: Hlc = Hlc {
+        wall: 0,
+        logical: 0,
+        replica: Uuid::nil(),
+    };
+}
+
+/// O relógio de uma réplica. Nunca anda para trás, mesmo que o relógio
+/// físico ande, e fica sempre depois dos carimbos que já viu.
+#[derive(Debug, Clone)]
+pub struct Clock {
+    wall: u64,
+    logical: u32,
+    replica: Uuid,
+}
+
+impl Clock {
+    pub fn new(replica: Uuid) -> Self {
+        Clock {
+            wall: 0,
+            logical: 0,
+            replica,
+        }
+    }
+
+    /// Carimbo para um evento local.
+    pub fn tick(&mut self, physical_ms: u64) -> Hlc {
+        if physical_ms > self.wall {
+            self.wall = physical_ms;
+            self.logical = 0;
+        } else {
+            self.logical += 1;
+        }
+        Hlc {
+            wall: self.wall,
+            logical: self.logical,
+            replica: self.replica,
+        }
+    }
+
+    /// Avança o relógio para depois de um carimbo visto em outra réplica.
+    pub fn observe(&mut self, seen: Hlc) {
+        if (seen.wall, seen.logical) > (self.wall, self.logical) {
+            self.wall = seen.wall;
+            self.logical = seen.logical;
+        }
+    }
+}
+
+// --- Os CRDTs ---
+
+/// Registrador em que vence a escrita de carimbo maior. `None` é a chave
+/// apagada.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LwwRegister {
+    pub value: Option<String>,
+    pub stamp: Hlc,
+}
+
+impl LwwRegister {
+    fn merge(&mut self, other: &LwwRegister) {
+        if other.stamp > self.stamp {
+            *self = other.clone();
+        }
+    }
+}
+
+/// Conjunto com remoção observada. Cada adição ganha uma etiqueta única (o
+/// carimbo HLC); remover um elemento descarta as etiquetas vistas dele, e
+/// uma adição concorrente, com uma etiqueta que a remoção não viu, sobrevive.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct OrSet {
+    adds: BTreeMap<String, BTreeSet<Hlc>>,
+    /// Etiquetas removidas, guardadas para que a mesma adição não volte por
+    /// outra réplica.
+    removed: BTreeSet<Hlc>,
+    /// Carimbo do último `del`, que esvazia o conjunto e o marca como apagado.
+    cleared: Option<Hlc>,
+}
+
+impl OrSet {
+    fn add(&mut self, element: String, tag: Hlc) {
+        self.adds.entry(element).or_default().insert(tag);
+    }
+
+    fn remove(&mut self, element: &str) {
+        if let Some(tags) = self.adds.remove(element) {
+            self.removed.extend(tags);
+        }
+    }
+
+    fn clear(&mut self, stamp: Hlc) {
+        let elements: Vec<String> = self.adds.keys().cloned().collect();
+        for element in elements {
+            self.remove(&element);
+        }
+        self.cleared = self.cleared.max(Some(stamp));
+    }
+
+    pub fn elements(&self) -> impl Iterator<Item = &str> {
+        self.adds.keys().map(String::as_str)
+    }
+
+    /// Apagado se está vazio e nada foi adicionado depois do último `del`.
+    fn is_deleted(&self) -> bool {
+        self.adds.is_empty()
+            && self
+                .cleared
+                .is_some_and(|cleared| self.removed.iter().all(|tag| *tag < cleared))
+    }
+
+    fn merge(&mut self, other: &OrSet) {
+        for (element, tags) in &other.adds {
+            self.adds
+                .entry(element.clone())
+                .or_default()
+                .extend(tags.iter().copied());
+        }
+        self.removed.extend(other.removed.iter().copied());
+        self.cleared = self.cleared.max(other.cleared);
+        let removed = &self.removed;
+        self.adds.retain(|_, tags| {
+            tags.retain(|tag| !removed.contains(tag));
+            !tags.is_empty()
+        });
+    }
+}
+
+/// Contador que soma e subtrai: cada réplica conta só as próprias somas e
+/// subtrações, e a junção fica com o maior de cada contagem.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct PnCounter {
+    increments: BTreeMap<Uuid, u64>,
+    decrements: BTreeMap<Uuid, u64>,
+    /// Carimbos da última soma e do último `del`, que zera o contador e o
+    /// marca como apagado.
+    written: Option<Hlc>,
+    cleared: Option<Hlc>,
+}
+
+impl PnCounter {
+    /// O valor, limitado à faixa do `i64`.
+    pub fn value(&self) -> i64 {
+        let (up, down) = self.totals();
+        (up as i128 - down as i128).clamp(i64::MIN as i128, i64::MAX as i128) as i64
+    }
+
+    /// As somas e as subtrações de todas as réplicas, sem transbordar.
+    fn totals(&self) -> (u128, u128) {
+        let total = |counts: &BTreeMap<Uuid, u64>| counts.values().map(|&n| n as u128).sum();
+        (total(&self.increments), total(&self.decrements))
+    }
+
+    fn add(&mut self, replica: Uuid, by: i64, stamp: Hlc) {
+        self.count(replica, by >= 0, by.unsigned_abs());
+        self.written = self.written.max(Some(stamp));
+    }
+
+    /// Volta a zero, somando ou subtraindo o que falta.
+    fn clear(&mut self, replica: Uuid, stamp: Hlc) {
+        let (up, down) = self.totals();
+        let missing = u64::try_from(up.abs_diff(down)).unwrap_or(u64::MAX);
+        self.count(replica, down > up, missing);
+        self.cleared = self.cleared.max(Some(stamp));
+    }
+
+    /// Apagado se ninguém somou depois do último `del` e nenhuma soma
+    /// concorrente com ele sobrou.
+    fn is_deleted(&self) -> bool {
+        self.cleared > self.written && self.value() == 0
+    }
+
+    /// Cada contagem para no maior `u64` em vez de transbordar.
+    fn count(&mut self, replica: Uuid, increment: bool, n: u64) {
+        let counts = if increment {
+            &mut self.increments
+        } else {
+            &mut self.decrements
+        };
+        let count = counts.entry(replica).or_insert(0);
+        *count = count.saturating_add(n);
+    }
+
+    fn merge(&mut self, other: &PnCounter) {
+        for (mine, theirs) in [
+            (&mut self.increments, &other.increments),
+            (&mut self.decrements, &other.decrements),
+        ] {
+            for (replica, count) in theirs {
+                let entry = mine.entry(*replica).or_insert(0);
+                *entry = (*entry).max(*count);
+            }
+        }
+        self.written = self.written.max(other.written);
+        self.cleared = self.cleared.max(other.cleared);
+    }
+}
+
+/// Quantas escritas de cada réplica uma versão já viu.
+type VersionVector = BTreeMap<Uuid, u64>;
+
+/// Verdadeiro se `a` veio antes de `b`: `b` viu tudo o que `a` viu, e mais.
+fn happened_before(a: &VersionVector, b: &VersionVector) -> bool {
+    a != b
+        && a.iter()
+            .all(|(replica, n)| b.get(replica).is_some_and(|m| m >= n))
+}
+
+/// Registrador multivalor: guarda os valores de escritas concorrentes (as
+/// irmãs) em vez de escolher um. `None` é uma remoção.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct MvRegister {
+    /// Em ordem, para que réplicas iguais serializem igual.
+    versions: Vec<(VersionVector, Option<String>)>,
+}
+
+impl MvRegister {
+    /// Uma escrita substitui todas as versões que a réplica já viu.
+    fn write(&mut self, replica: Uuid, value: Option<String>) {
+        let mut seen = VersionVector::new();
+        for (version, _) in &self.versions {
+            for (r, n) in version {
+                let entry = seen.entry(*r).or_insert(0);
+                *entry = (*entry).max(*n);
+            }
+        }
+        *seen.entry(replica).or_insert(0) += 1;
+        self.versions = vec![(seen, value)];
+    }
+
+    /// Os valores atuais; mais de um quando houve escritas concorrentes.
+    pub fn values(&self) -> impl Iterator<Item = &str> {
+        self.versions
+            .iter()
+            .filter_map(|(_, value)| value.as_deref())
+    }
+
+    fn merge(&mut self, other: &MvRegister) {
+        let mut all = self.versions.clone();
+        all.extend(other.versions.iter().cloned());
+        all.sort();
+        all.dedup();
+        let kept: Vec<(VersionVector, Option<String>)> = all
+            .iter()
+            .filter(|(version, _)| !all.iter().any(|(o, _)| happened_before(version, o)))
+            .cloned()
+            .collect();
+        self.versions = kept;
+    }
+}
+
+/// O valor de uma chave. O tipo é escolhido na primeira escrita.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Crdt {
+    Lww(LwwRegister),
+    Set(OrSet),
+    Counter(PnCounter),
+    Mv(MvRegister),
+}
+
+impl Crdt {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Crdt::Lww(_) => "lww",
+            Crdt::Set(_) => "set",
+            Crdt::Counter(_) => "counter",
+            Crdt::Mv(_) => "mv",
+        }
+    }
+
+    /// Posição na ordem dos tipos, que resolve uma chave escrita com tipos
+    /// diferentes em réplicas diferentes.
+    fn rank(&self) -> u8 {
+        match self {
+            Crdt::Lww(_) => 0,
+            Crdt::Set(_) => 1,
+            Crdt::Counter(_) => 2,
+            Crdt::Mv(_) => 3,
+        }
+    }
+
+    /// Junta a outra réplica a esta. Se a chave tem tipos diferentes nas
+    /// duas, fica o tipo que vem depois na ordem `lww`, `set`, `counter`,
+    /// `mv`, o que também converge.
+    pub fn merge(&mut self, other: &Crdt) {
+        match (&mut *self, other) {
+            (Crdt::Lww(a), Crdt::Lww(b)) => a.merge(b),
+            (Crdt::Set(a), Crdt::Set(b)) => a.merge(b),
+            (Crdt::Counter(a), Crdt::Counter(b)) => a.merge(b),
+            (Crdt::Mv(a), Crdt::Mv(b)) => a.merge(b),
+            _ => {
+                if other.rank() > self.rank() {
+                    *self = other.clone();
+                }
+            }
+        }
+    }
+
+    /// O maior carimbo HLC guardado, se o tipo usa carimbos.
+    pub fn latest_stamp(&self) -> Option<Hlc> {
+        match self {
+            Crdt::Lww(register) => Some(register.stamp),
+            Crdt::Set(set) => set
+                .adds
+                .values()
+                .flatten()
+                .chain(set.removed.iter())
+                .max()
+                .copied()
+                .max(set.cleared),
+            Crdt::Counter(counter) => counter.written.max(counter.cleared),
+            Crdt::Mv(_) => None,
+        }
+    }
+
+    /// O valor como a aplicação o vê, em JSON: texto (ou `null`, se
+    /// apagado) no LWW, a lista de elementos no conjunto, o número no
+    /// contador e a lista das irmãs no multivalor.
+    pub fn value(&self) -> serde_json::Value {
+        match self {
+            Crdt::Lww(register) => register.value.clone().into(),
+            Crdt::Set(set) => set.elements().collect::<Vec<_>>().into(),
+            Crdt::Counter(counter) => counter.value().into(),
+            Crdt::Mv(register) => register.values().collect::<Vec<_>>().into(),
+        }
+    }
+
+    /// Verdadeiro se a chave foi apagada com `del` e não foi escrita depois.
+    /// Ela continua no estado, para a remoção chegar às outras réplicas. Um
+    /// conjunto vazio ou um contador em zero não estão apagados.
+    pub fn is_deleted(&self) -> bool {
+        match self {
+            Crdt::Lww(register) => register.value.is_none(),
+            Crdt::Set(set) => set.is_deleted(),
+            Crdt::Counter(counter) => counter.is_deleted(),
+            Crdt::Mv(register) => register.values().next().is_none(),
+        }
+    }
+
+    /// Resumo do conteúdo, para o digest do gossip: FNV-1a do JSON, que é o
+    /// mesmo em todas as réplicas (mapas e conjuntos ficam em ordem).
+    pub fn digest(&self) -> u64 {
+        let bytes = serde_json::to_vec(self).expect("CRDTs sempre serializam");
+        bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+        })
+    }
+
+    fn empty(op: &Op) -> Crdt {
+        match op {
+            Op::Set { .. } | Op::Delete => Crdt::Lww(LwwRegister {
+                value: None,
+                stamp: Hlc::ZERO,
+            }),
+            Op::Add { .. } | Op::Remove { .. } => Crdt::Set(OrSet::default()),
+            Op::Increment { .. } => Crdt::Counter(PnCounter::default()),
+            Op::MvSet { .. } => Crdt::Mv(MvRegister::default()),
+        }
+    }
+}
+
+impl fmt::Display for Crdt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_deleted() {
+            return write!(f, "(apagado)");
+        }
+        match self {
+            Crdt::Lww(register) => write!(f, "'{}'", register.value.as_deref().unwrap_or("")),
+            Crdt::Set(set) => {
+                let elements: Vec<String> = set.elements().map(|e| format!("'{}'", e)).collect();
+                write!(f, "{{{}}}", elements.join(", "))
+            }
+            Crdt::Counter(counter) => write!(f, "{}", counter.value()),
+            Crdt::Mv(register) => {
+                let values: Vec<String> = register.values().map(|v| format!("'{}'", v)).collect();
+                write!(f, "{}", values.join(" | "))
+            }
+        }
+    }
+}
+
+// --- Operações locais ---
+
+/// Uma alteração feita no próprio nó. O tipo da chave vem da primeira
+/// operação sobre ela.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Op {
+    /// Escreve no registrador LWW.
+    Set { value: String },
+    /// Adiciona ao conjunto.
+    Add { element: String },
+    /// Remove do conjunto.
+    Remove { element: String },
+    /// Soma ao contador (ou subtrai, se negativo).
+    Increment { by: i64 },
+    /// Escreve no registrador multivalor, substituindo as irmãs que já viu.
+    MvSet { value: String },
+    /// Apaga a chave, qualquer que seja o tipo. No contador, subtrai o valor
+    /// atual: somas concorrentes continuam valendo.
+    Delete,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CrdtError {
+    /// A operação é de um tipo e a chave é de outro.
+    TypeMismatch {
+        key: String,
+        found: &'static str,
+    },
+    NotFound(String),
+}
+
+impl fmt::Display for CrdtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CrdtError::TypeMismatch { key, found } => {
+                write!(f, "a chave '{}' é do tipo {}", key, found)
+            }
+            CrdtError::NotFound(key) => write!(f, "chave '{}' não encontrada", key),
+        }
+    }
+}
+
+impl std::error::Error for CrdtError {}
+
+/// A identidade de um nó como réplica e o seu relógio.
+#[derive(Debug, Clone)]
+pub struct Replica {
+    id: Uuid,
+    clock: Clock,
+}
+
+impl Replica {
+    pub fn new(id: Uuid) -> Self {
+        Replica {
+            id,
+            clock: Clock::new(id),
+        }
+    }
+
+    /// Aplica uma operação local à chave e devolve o valor novo. O carimbo
+    /// fica depois de todos os que a chave já tem, então uma escrita vence
+    /// as que ela já viu, mesmo com o relógio físico atrasado.
+    pub fn apply(
+        &mut self,
+        state: &mut NodeState,
+        key: &str,
+        op: Op,
+        physical_ms: u64,
+    ) -> Result<Crdt, CrdtError> {
+        if op == Op::Delete && !state.contains_key(key) {
+            return Err(CrdtError::NotFound(key.to_string()));
+        }
+        if let Some(stamp) = state.get(key).and_then(Crdt::latest_stamp) {
+            self.clock.observe(stamp);
+        }
+        let stamp = self.clock.tick(physical_ms);
+        let crdt = state
+            .entry(key.to_string())
+            .or_insert_with(|| Crdt::empty(&op));
+        match (&mut *crdt, op) {
+            (Crdt::Lww(register), Op::Set { value }) => {
+                *register = LwwRegister {
+                    value: Some(value),
+                    stamp,
+                }
+            }
+            (Crdt::Set(set), Op::Add { element }) => set.add(element, stamp),
+            (Crdt::Set(set), Op::Remove { element }) => set.remove(&element),
+            (Crdt::Counter(counter), Op::Increment { by }) => counter.add(self.id, by, stamp),
+            (Crdt::Mv(register), Op::MvSet { value }) => register.write(self.id, Some(value)),
+            (crdt, Op::Delete) => match crdt {
+                Crdt::Lww(register) => *register = LwwRegister { value: None, stamp },
+                Crdt::Set(set) => set.clear(stamp),
+                Crdt::Counter(counter) => counter.clear(self.id, stamp),
+                Crdt::Mv(register) => register.write(self.id, None),
+            },
+            (crdt, _) => {
+                return Err(CrdtError::TypeMismatch {
+                    key: key.to_string(),
+                    found: crdt.type_name(),
+                });
+            }
+        }
+        Ok(crdt.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn replica(n: u128) -> Replica {
+        Replica::new(Uuid::from_u128(n))
+    }
+
+    fn merged(a: &NodeState, b: &NodeState) -> NodeState {
+        let mut result = a.clone();
+        merge_states(&mut result, b);
+        result
+    }
+
+    fn merge_states(into: &mut NodeState, from: &NodeState) {
+        for (key, value) in from {
+            match into.get_mut(key) {
+                Some(local) => local.merge(value),
+                None => {
+                    into.insert(key.clone(), value.clone());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn concurrent_writes_are_not_lost() {
+        let (mut a, mut b) = (replica(1), replica(2));
+        let (mut state_a, mut state_b) = (NodeState::new(), NodeState::new());
+
+        // multivalor: as duas escritas concorrentes ficam, como irmãs
+        a.apply(&mut state_a, "mv", Op::MvSet { value: "x".into() }, 10)
+            .unwrap();
+        b.apply(&mut state_b, "mv", Op::MvSet { value: "y".into() }, 10)
+            .unwrap();
+        // conjunto: a adição concorrente com a remoção sobrevive
+        a.apply(
+            &mut state_a,
+            "s",
+            Op::Add {
+                element: "e".into(),
+            },
+            11,
+        )
+        .unwrap();
+        let mut state_b2 = merged(&state_b, &state_a);
+        b.apply(
+            &mut state_b2,
+            "s",
+            Op::Remove {
+                element: "e".into(),
+            },
+            12,
+        )
+        .unwrap();
+        a.apply(
+            &mut state_a,
+            "s",
+            Op::Add {
+                element: "e".into(),
+            },
+            13,
+        )
+        .unwrap();
+        // contador: as somas das duas réplicas contam
+        a.apply(&mut state_a, "c", Op::Increment { by: 5 }, 14)
+            .unwrap();
+        b.apply(&mut state_b2, "c", Op::Increment { by: -2 }, 14)
+            .unwrap();
+
+        let both = merged(&state_a, &state_b2);
+        assert_eq!(both, merged(&state_b2, &state_a));
+        assert_eq!(both["mv"].to_string(), "'x' | 'y'");
+        assert_eq!(both["s"].to_string(), "{'e'}");
+        assert_eq!(both["c"].to_string(), "3");
+
+        // uma escrita que viu as irmãs substitui as duas
+        let mut state = both.clone();
+        b.apply(&mut state, "mv", Op::MvSet { value: "z".into() }, 15)
+            .unwrap();
+        assert_eq!(merged(&both, &state)["mv"].to_string(), "'z'");
+    }
+
+    #[test]
+    fn later_writes_win_even_with_a_slow_clock() {
+        let (mut a, mut b) = (replica(1), replica(2));
+        let mut state = NodeState::new();
+        a.apply(&mut state, "k", Op::Set { value: "a".into() }, 1_000)
+            .unwrap();
+        // o relógio de b está atrasado, mas b já viu a escrita de a
+        b.apply(&mut state, "k", Op::Set { value: "b".into() }, 10)
+            .unwrap();
+        assert_eq!(state["k"].to_string(), "'b'");
+
+        b.apply(&mut state, "k", Op::Delete, 11).unwrap();
+        assert!(state["k"].is_deleted());
+        assert_eq!(
+            a.apply(&mut state, "k", Op::Increment { by: 1 }, 12),
+            Err(CrdtError::TypeMismatch {
+                key: "k".into(),
+                found: "lww"
+            })
+        );
+        assert_eq!(
+            a.apply(&mut state, "x", Op::Delete, 13),
+            Err(CrdtError::NotFound("x".into()))
+        );
+    }
+
+    #[test]
+    fn counters_saturate_instead_of_overflowing() {
+        let mut a = replica(1);
+        let mut state = NodeState::new();
+        for (key, by) in [("up", i64::MAX), ("down", i64::MIN)] {
+            for clock in 0..3 {
+                a.apply(&mut state, key, Op::Increment { by }, clock)
+                    .unwrap();
+            }
+            assert_eq!(state[key].to_string(), by.to_string());
+            a.apply(&mut state, key, Op::Delete, 3).unwrap();
+            assert_eq!(state[key].value(), 0);
+        }
+    }
+
+    // --- Convergência, com operações e ordens de entrega aleatórias ---
+
+    #[derive(Debug, Clone)]
+    enum Step {
+        /// Operação local em uma réplica
+        Local(usize, &'static str, Op),
+        /// Uma réplica recebe o estado de outra
+        Sync(usize, usize),
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        let text = prop::sample::select(vec!["a", "b", "c"]).prop_map(String::from);
+        prop_oneof![
+            text.clone().prop_map(|value| Op::Set { value }),
+            text.clone().prop_map(|element| Op::Add { element }),
+            text.clone().prop_map(|element| Op::Remove { element }),
+            (-5i64..5).prop_map(|by| Op::Increment { by }),
+            text.prop_map(|value| Op::MvSet { value }),
+            Just(Op::Delete),
+        ]
+    }
+
+    fn step() -> impl Strategy<Value = Step> {
+        let key = prop::sample::select(vec!["k1", "k2", "k3"]);
+        prop_oneof![
+            3 => (0..3usize, key, op()).prop_map(|(r, k, op)| Step::Local(r, k, op)),
+            1 => (0..3usize, 0..3usize).prop_map(|(from, to)| Step::Sync(from, to)),
+        ]
+    }
+
+    /// Roda os passos e devolve cada estado produzido (as "mensagens").
+    fn run(steps: &[Step], clocks: &[u64]) -> Vec<NodeState> {
+        let mut replicas: Vec<Replica> = (0..3).map(|i| replica(i as u128 + 1)).collect();
+        let mut states = vec![NodeState::new(); 3];
+        // o estado vazio também é uma mensagem possível
+        let mut messages = vec![NodeState::new()];
+        for (i, step) in steps.iter().enumerate() {
+            match step {
+                Step::Local(r, key, op) => {
+                    // relógios físicos desencontrados entre as réplicas
+                    let physical = i as u64 * 10 + clocks[*r];
+                    let _ = replicas[*r].apply(&mut states[*r], key, op.clone(), physical);
+                    messages.push(states[*r].clone());
+                }
+                Step::Sync(from, to) => {
+                    let from = states[*from].clone();
+                    merge_states(&mut states[*to], &from);
+                }
+            }
+        }
+        messages.extend(states);
+        messages
+    }
+
+    proptest! {
+        #[test]
+        fn delivery_order_does_not_matter(
+            steps in prop::collection::vec(step(), 1..40),
+            clocks in prop::collection::vec(0u64..100, 3),
+            order in Just((0..80usize).collect::<Vec<_>>()).prop_shuffle(),
+            duplicates in prop::collection::vec(0..80usize, 0..10),
+        ) {
+            let messages = run(&steps, &clocks);
+            let mut in_order = NodeState::new();
+            for message in &messages {
+                merge_states(&mut in_order, message);
+            }
+            // outra ordem, com mensagens repetidas
+            let mut shuffled = NodeState::new();
+            for i in order.iter().chain(duplicates.iter()) {
+                if let Some(message) = messages.get(*i) {
+                    merge_states(&mut shuffled, message);
+                }
+            }
+            prop_assert_eq!(&in_order, &shuffled);
+            let digests = |state: &NodeState| -> Vec<(String, u64)> {
+                let mut d: Vec<_> = state.iter().map(|(k, v)| (k.clone(), v.digest())).collect();
+                d.sort();
+                d
+            };
+            prop_assert_eq!(digests(&in_order), digests(&shuffled));
+        }
+
+        #[test]
+        fn merge_is_commutative_associative_and_idempotent(
+            steps in prop::collection::vec(step(), 1..40),
+            clocks in prop::collection::vec(0u64..100, 3),
+            picks in prop::collection::vec(any::<prop::sample::Index>(), 3),
+        ) {
+            let messages = run(&steps, &clocks);
+            let [a, b, c] = [0, 1, 2].map(|i| &messages[picks[i].index(messages.len())]);
+            prop_assert_eq!(merged(a, b), merged(b, a));
+            prop_assert_eq!(merged(&merged(a, b), c), merged(a, &merged(b, c)));
+            prop_assert_eq!(merged(a, a), a.clone());
+        }
+    }
+}
@@ -1,104 +1,124 @@
+mod admin;
+mod antientropy;
+mod crdt;
+mod membership;
+mod message;
+mod protocol;
+mod security;
+#[cfg(test)]
+mod sim;
+mod transport;
+
+use admin::{AdminState, KeyView};
 use clap::Parser;
-use rand::seq::SliceRandom;
-use serde::{Deserialize, Serialize};
+use crdt::{Crdt, Op};
+use membership::SwimConfig;
+use protocol::{Config, Protocol, state_name};
+use security::Guard;
 use std::collections::HashMap;
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::Duration;
-use tokio::net::UdpSocket;
-use tokio::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tokio::io::AsyncBufReadExt;
+use tokio::net::UdpSocket;
+use tokio::sync::{Mutex, broadcast};
 use uuid::Uuid;
 
 // --- Estruturas de Dados ---
 
 /// Representa o estado de um nó.
-/// Usamos um HashMap para armazenar dados (chave-valor) e suas versões.
-/// A versão é um simples contador que incrementa a cada atualização.
-type NodeState = HashMap<String, (String, u64)>;
+/// Usamos um HashMap para armazenar os dados: cada chave guarda um CRDT,
+/// que se junta ao das outras réplicas sem perder escritas concorrentes.
+type NodeState = HashMap<String, Crdt>;
 
-/// Estrutura do nosso nó de gossip.
-#[derive(Debug)]
+/// Estrutura do nosso nó de gossip: a lógica do protocolo, ligada a um
+/// socket UDP, ao relógio do sistema e ao terminal.
 struct Node {
     /// Endereço de escuta do nó (IP:PORTA).
     listen_addr: SocketAddr,
-    /// ID único para este nó, para evitar se conectar a si mesmo.
-    id: Uuid,
-    /// A lista de outros nós conhecidos na rede.
-    peers: Arc<Mutex<Vec<SocketAddr>>>,
-    /// O estado (dados) deste nó.
-    state: Arc<Mutex<NodeState>>,
-}
-
-/// A mensagem que será trocada entre os nós.
-/// Ela contém o estado completo do nó remetente e sua lista de peers.
-#[derive(Serialize, Deserialize, Debug)]
-struct GossipMessage {
-    node_id: Uuid,
-    state: NodeState,
-    peers: Vec<SocketAddr>,
+    protocol: Arc<Mutex<Protocol>>,
+    /// Avisa a API de administração das chaves que mudaram.
+    changes: broadcast::Sender<KeyView>,
 }
 
 // --- Lógica Principal ---
 
 impl Node {
     /// Cria uma nova instância do nó.
-    fn new(listen_addr: SocketAddr) -> Self {
+    fn new(
+        listen_addr: SocketAddr,
+        max_datagram: usize,
+        swim: SwimConfig,
+        guard: Option<Guard>,
+    ) -> Self {
+        let config = Config {
+            id: Uuid::new_v4(),
+            addr: listen_addr,
+            max_datagram,
+            swim,
+            guard,
+            verbose: true,
+        };
+        let protocol = Protocol::new(config, Instant::now(), physical_ms());
+        let (changes, _) = broadcast::channel(admin::WATCH_BUFFER);
         Node {
             listen_addr,
-            id: Uuid::new_v4(),
-            peers: Arc::new(Mutex::new(Vec::new())),
-            state: Arc::new(Mutex::new(NodeState::new())),
+            protocol: Arc::new(Mutex::new(protocol)),
+            changes,
         }
     }
 
     /// Inicia os processos principais do nó: escuta, fofoca e entrada do usuário.
-    async fn start(&self, initial_peers: Vec<SocketAddr>) -> Result<(), Box<dyn std::error::Error>> {
-        println!("[INFO] Nó ID: {}", self.id);
+    async fn start(
+        &self,
+        initial_peers: Vec<SocketAddr>,
+        admin_addr: Option<SocketAddr>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        println!("[INFO] Nó ID: {}", self.protocol.lock().await.id());
         println!("[INFO] Escutando em: {}", self.listen_addr);
+        if self.protocol.lock().await.encrypted() {
+            println!("[INFO] Mensagens cifradas e autenticadas com chave compartilhada");
+        }
 
         // Adiciona os peers iniciais informados via linha de comando.
-        {
-            let mut peers = self.peers.lock().await;
-            for peer_addr in initial_peers {
-                if !peers.contains(&peer_addr) {
-                    peers.push(peer_addr);
-                }
-            }
-            println!("[INFO] Peers iniciais: {:?}", peers);
-        }
+        self.protocol
+            .lock()
+            .await
+            .join(&initial_peers, Instant::now());
+        println!("[INFO] Peers iniciais: {:?}", initial_peers);
 
         let socket = Arc::new(UdpSocket::bind(self.listen_addr).await?);
 
-        // Clona Arcs para mover para as tasks assíncronas
-        let listen_socket = socket.clone();
-        let listen_peers = self.peers.clone();
-        let listen_state = self.state.clone();
-        let listen_node_id = self.id;
+        // --- API HTTP de administração (opcional) ---
+        if let Some(admin_addr) = admin_addr {
+            let listener = tokio::net::TcpListener::bind(admin_addr).await?;
+            println!("[INFO] API de administração em: http://{}", admin_addr);
+            let app = admin::router(AdminState {
+                protocol: self.protocol.clone(),
+                changes: self.changes.clone(),
+            });
+            tokio::spawn(async move {
+                if let Err(e) = axum::serve(listener, app).await {
+                    eprintln!("[ERRO] API de administração parou: {}", e);
+                }
+            });
+        }
 
         // --- Task 1: Escutar por mensagens de gossip ---
+        let listen_socket = socket.clone();
+        let listen_protocol = self.protocol.clone();
+        let listen_changes = self.changes.clone();
         tokio::spawn(async move {
-            let mut buf = [0; 4096]; // Buffer para receber dados
+            // Buffer do tamanho máximo de um datagrama UDP, para aceitar
+            // nós configurados com um limite maior que o nosso
+            let mut buf = vec![0; 65536];
             loop {
                 match listen_socket.recv_from(&mut buf).await {
                     Ok((len, src_addr)) => {
-                        // Deserializa a mensagem recebida
-                        if let Ok(message) = serde_json::from_slice::<GossipMessage>(&buf[..len]) {
-                            // Ignora mensagens de si mesmo
-                            if message.node_id == listen_node_id {
-                                continue;
-                            }
-
-                            println!("[GOSSIP] Mensagem recebida de {}", src_addr);
-                            
-                            // Processa a mensagem fundindo os estados e listas de peers
-                            Self::merge_state_and_peers(
-                                listen_state.clone(),
-                                listen_peers.clone(),
-                                message,
-                                src_addr,
-                            ).await;
-                        }
+                        let mut protocol = listen_protocol.lock().await;
+                        protocol.receive(src_addr, &buf[..len], Instant::now(), &*listen_socket);
+                        admin::publish_changes(&mut protocol, &listen_changes);
                     }
                     Err(e) => eprintln!("[ERRO] Falha ao receber dados: {}", e),
                 }
@@ -107,86 +127,114 @@ impl Node {
 
         // --- Task 2: Enviar "fofocas" periodicamente ---
         let gossip_socket = socket.clone();
-        let gossip_peers = self.peers.clone();
-        let gossip_state = self.state.clone();
-        let gossip_node_id = self.id;
-
+        let gossip_protocol = self.protocol.clone();
         tokio::spawn(async move {
             loop {
                 // Intervalo da "fofoca"
-                tokio::time::sleep(Duration::from_secs(2)).await;
-
-                // Seleciona um peer aleatório para enviar a mensagem
-                let target_peer = {
-                    let peers = gossip_peers.lock().await;
-                    peers.choose(&mut rand::thread_rng()).cloned()
-                };
-
-                if let Some(target) = target_peer {
-                    let peers = gossip_peers.lock().await;
-                    let state = gossip_state.lock().await;
-
-                    // Cria a mensagem de gossip com o estado atual e a lista de peers
-                    let message = GossipMessage {
-                        node_id: gossip_node_id,
-                        state: state.clone(),
-                        peers: peers.clone(),
-                    };
-
-                    // Serializa e envia a mensagem via UDP
-                    if let Ok(encoded) = serde_json::to_vec(&message) {
-                        if let Err(e) = gossip_socket.send_to(&encoded, target).await {
-                            eprintln!("[ERRO] Falha ao enviar gossip para {}: {}", target, e);
-                        } else {
-                            println!("[GOSSIP] Fofoca enviada para {}", target);
-                        }
-                    }
-                }
+                tokio::time::sleep(protocol::GOSSIP_INTERVAL).await;
+                gossip_protocol
+                    .lock()
+                    .await
+                    .gossip(Instant::now(), &*gossip_socket);
             }
         });
 
-        // --- Task 3: Lidar com a entrada do usuário para alterar o estado ---
-        let state_handle = self.state.clone();
+        // --- Task 3: Detectar falhas (SWIM): sondagens, suspeitas e lápides ---
+        let swim_socket = socket.clone();
+        let swim_protocol = self.protocol.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(protocol::TICK_INTERVAL);
+            loop {
+                ticker.tick().await;
+                swim_protocol
+                    .lock()
+                    .await
+                    .tick(Instant::now(), &*swim_socket);
+            }
+        });
+
+        // --- Task 4: Lidar com a entrada do usuário para alterar o estado ---
+        let input_protocol = self.protocol.clone();
+        let input_changes = self.changes.clone();
         tokio::spawn(async move {
             let mut stdin = tokio::io::BufReader::new(tokio::io::stdin());
             let mut line = String::new();
             loop {
                 line.clear();
                 // Aguarda a entrada do usuário de forma assíncrona
-                if let Err(e) = stdin.read_line(&mut line).await {
-                    eprintln!("[ERRO] Falha ao ler a entrada do terminal: {}", e);
-                    break;
+                match stdin.read_line(&mut line).await {
+                    // Fim da entrada (por exemplo, rodando em segundo plano):
+                    // o nó continua funcionando, só sem comandos
+                    Ok(0) => break,
+                    Ok(_) => {}
+                    Err(e) => {
+                        eprintln!("[ERRO] Falha ao ler a entrada do terminal: {}", e);
+                        break;
+                    }
                 }
 
                 let parts: Vec<&str> = line.trim().splitn(3, ' ').collect();
-
-                if parts.get(0) == Some(&"set") && parts.len() == 3 {
-                    let key = parts[1].to_string();
-                    let value = parts[2].to_string();
-
-                    let mut state = state_handle.lock().await;
-
-                    // Atualiza a versão do dado
-                    let version = state.get(&key).map_or(1, |(_, v)| v + 1);
-                    state.insert(key.clone(), (value.clone(), version));
-
-                    println!("[ESTADO] Atualizado: {} = '{}' (versão {})", key, value, version);
-                    Self::print_state(&state).await;
-
+                let mut protocol = input_protocol.lock().await;
+
+                if let Some((key, op)) = parse_op(&parts) {
+                    match protocol.apply(key, op, Instant::now()) {
+                        Ok(value) => {
+                            admin::publish_changes(&mut protocol, &input_changes);
+                            println!(
+                                "[ESTADO] Atualizado: {} = {} ({})",
+                                key,
+                                value,
+                                value.type_name()
+                            );
+                            protocol.print_state();
+                        }
+                        Err(e) => println!("[ERRO] {}", e),
+                    }
+                } else if let ["get", key] = parts.as_slice() {
+                    match protocol.state().get(*key) {
+                        Some(value) => println!("  - '{}': {} ({})", key, value, value.type_name()),
+                        None => println!("[ERRO] chave '{}' não encontrada", key),
+                    }
+                } else if parts.first() == Some(&"metrics") {
+                    let metrics = protocol.metrics();
+                    println!(
+                        "[MÉTRICAS] Última rodada ({}): {}",
+                        metrics.round, metrics.last_round
+                    );
+                    println!("[MÉTRICAS] Total: {}", metrics.total);
+                } else if parts.first() == Some(&"members") {
+                    let membership = protocol.membership();
+                    println!(
+                        "  - {} (este nó, encarnação {})",
+                        membership.me(),
+                        membership.incarnation()
+                    );
+                    for (addr, member) in membership.members() {
+                        println!(
+                            "  - {} {} (encarnação {})",
+                            addr,
+                            state_name(member.state),
+                            member.incarnation
+                        );
+                    }
                 } else {
-                    println!("[CMD] Comando inválido. Use: set <chave> <valor>");
+                    println!(
+                        "[CMD] Comando inválido. Use: set <chave> <valor> | mvset <chave> <valor> | \
+                         add <chave> <elemento> | remove <chave> <elemento> | incr <chave> [n] | \
+                         decr <chave> [n] | del <chave> | get <chave> | metrics | members"
+                    );
                 }
             }
-        });        
-        
-        // --- Task 4: Imprimir o estado atual periodicamente ---
-        let print_state_handle = self.state.clone();
+        });
+
+        // --- Task 5: Imprimir o estado atual periodicamente ---
+        let print_protocol = self.protocol.clone();
         tokio::spawn(async move {
             loop {
                 tokio::time::sleep(Duration::from_secs(10)).await;
-                let state = print_state_handle.lock().await;
+                let protocol = print_protocol.lock().await;
                 println!("\n--- Estado Atual do Nó ---");
-                Self::print_state(&state).await;
+                protocol.print_state();
                 println!("------------------------\n");
             }
         });
@@ -196,63 +244,61 @@ impl Node {
 
         Ok(())
     }
-    
-    /// Imprime o estado atual do nó de forma legível.
-    async fn print_state(state: &NodeState) {
-        if state.is_empty() {
-            println!("O estado está vazio.");
-        } else {
-            for (key, (value, version)) in state.iter() {
-                println!("  - '{}': '{}' (v{})", key, value, version);
-            }
-        }
-    }
-
-    /// A lógica central do gossip: funde o estado recebido com o estado local.
-    async fn merge_state_and_peers(
-        local_state_arc: Arc<Mutex<NodeState>>,
-        local_peers_arc: Arc<Mutex<Vec<SocketAddr>>>,
-        remote_message: GossipMessage,
-        remote_addr: SocketAddr,
-    ) {
-        let mut local_state = local_state_arc.lock().await;
-        let mut state_changed = false;
-
-        // 1. Fundir o estado (dados chave-valor)
-        for (key, (remote_value, remote_version)) in remote_message.state {
-            let local_entry = local_state.entry(key.clone()).or_insert((String::new(), 0));
-
-            // A informação remota é mais nova, então atualizamos nosso estado local.
-            if remote_version > local_entry.1 {
-                *local_entry = (remote_value, remote_version);
-                state_changed = true;
-            }
-        }
-        
-        if state_changed {
-            println!("[MERGE] Estado local foi atualizado pela fofoca.");
-            Self::print_state(&local_state).await;
-        }
-
-        // 2. Fundir a lista de peers
-        let mut local_peers = local_peers_arc.lock().await;
-        
-        // Adiciona o remetente à nossa lista de peers, se ainda não o conhecemos.
-        if !local_peers.contains(&remote_addr) {
-            local_peers.push(remote_addr);
-            println!("[PEERS] Novo peer descoberto: {}", remote_addr);
-        }
+}
 
-        // Adiciona os peers do remetente à nossa lista, se ainda não os conhecemos.
-        for peer in remote_message.peers {
-            if peer != local_peers[0] && !local_peers.contains(&peer) {
-                local_peers.push(peer);
-                println!("[PEERS] Peer descoberto via fofoca: {}", peer);
-            }
-        }
+/// Interpreta os comandos que alteram o estado: a chave e a operação.
+fn parse_op<'a>(parts: &[&'a str]) -> Option<(&'a str, Op)> {
+    let count = |n: Option<&&str>| n.map_or(Some(1), |n| n.parse::<i64>().ok());
+    match parts {
+        ["set", key, value] => Some((
+            key,
+            Op::Set {
+                value: value.to_string(),
+            },
+        )),
+        ["mvset", key, value] => Some((
+            key,
+            Op::MvSet {
+                value: value.to_string(),
+            },
+        )),
+        ["add", key, element] => Some((
+            key,
+            Op::Add {
+                element: element.to_string(),
+            },
+        )),
+        ["remove", key, element] => Some((
+            key,
+            Op::Remove {
+                element: element.to_string(),
+            },
+        )),
+        ["incr", key, rest @ ..] => Some((
+            key,
+            Op::Increment {
+                by: count(rest.first())?,
+            },
+        )),
+        ["decr", key, rest @ ..] => Some((
+            key,
+            Op::Increment {
+                by: count(rest.first())?.checked_neg()?,
+            },
+        )),
+        ["del", key] => Some((key, Op::Delete)),
+        _ => None,
     }
 }
 
+/// O relógio do sistema, em milissegundos; o relógio de parede do nó (para
+/// o HLC e os carimbos das mensagens cifradas) começa nele.
+fn physical_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
 /// Define os argumentos da linha de comando.
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -264,6 +310,47 @@ struct Args {
     /// O endereço de um peer existente para se conectar inicialmente.
     #[arg(long)]
     peer: Option<SocketAddr>,
+
+    /// Tamanho máximo, em bytes, de cada datagrama enviado. Digests e
+    /// deltas maiores são divididos em várias mensagens.
+    #[arg(long, default_value_t = message::DEFAULT_MAX_DATAGRAM)]
+    max_datagram: usize,
+
+    /// Intervalo entre as sondagens de falha (SWIM), em milissegundos.
+    #[arg(long, default_value_t = 1000)]
+    probe_interval_ms: u64,
+
+    /// Espera pela resposta de uma sondagem antes da sondagem indireta, em milissegundos.
+    #[arg(long, default_value_t = 300)]
+    probe_timeout_ms: u64,
+
+    /// Quantos membros fazem a sondagem indireta.
+    #[arg(long, default_value_t = 3)]
+    indirect_probes: usize,
+
+    /// Prazo para um membro suspeito se defender antes de ser declarado morto, em milissegundos.
+    #[arg(long, default_value_t = 5000)]
+    suspicion_timeout_ms: u64,
+
+    /// Por quanto tempo um membro morto é lembrado, em milissegundos.
+    #[arg(long, default_value_t = 60000)]
+    dead_retention_ms: u64,
+
+    /// Arquivo de chaves compartilhadas (`<id> <64 dígitos hexadecimais>`
+    /// por linha; a primeira é a de envio). Com ele, todas as mensagens são
+    /// cifradas e autenticadas, e o arquivo é relido quando muda.
+    #[arg(long)]
+    key_file: Option<PathBuf>,
+
+    /// Diferença máxima aceita entre o carimbo de uma mensagem cifrada e o
+    /// relógio local, em milissegundos; cópias repetidas dentro dela são descartadas.
+    #[arg(long, default_value_t = security::DEFAULT_WINDOW_MS)]
+    replay_window_ms: u64,
+
+    /// Porta da API HTTP/JSON de administração (chaves, `/watch`, membros e
+    /// estatísticas), em 127.0.0.1. Sem ela, a API não é iniciada.
+    #[arg(long)]
+    admin_port: Option<u16>,
 }
 
 #[tokio::main]
@@ -271,9 +358,42 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
 
     let listen_addr = format!("127.0.0.1:{}", args.port).parse()?;
-    let node = Node::new(listen_addr);
+    let swim = SwimConfig {
+        probe_interval: Duration::from_millis(args.probe_interval_ms),
+        probe_timeout: Duration::from_millis(args.probe_timeout_ms),
+        indirect_probes: args.indirect_probes,
+        suspicion_timeout: Duration::from_millis(args.suspicion_timeout_ms),
+        dead_retention: Duration::from_millis(args.dead_retention_ms),
+        ..SwimConfig::default()
+    };
+    let guard = match args.key_file {
+        Some(path) => Some(Guard::from_file(path, args.replay_window_ms)?),
+        None => None,
+    };
+    let node = Node::new(listen_addr, args.max_datagram, swim, guard);
 
     let initial_peers = args.peer.map_or(vec![], |p| vec![p]);
 
-    node.start(initial_peers).await
+    let admin_addr = args
+        .admin_port
+        .map(|port| SocketAddr::from(([127, 0, 0, 1], port)));
+
+    node.start(initial_peers, admin_addr).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counter_commands_parse_their_amount() {
+        fn parse(line: &'static str) -> Option<(&'static str, Op)> {
+            parse_op(&line.splitn(3, ' ').collect::<Vec<_>>())
+        }
+        assert_eq!(parse("incr k"), Some(("k", Op::Increment { by: 1 })));
+        assert_eq!(parse("decr k 5"), Some(("k", Op::Increment { by: -5 })));
+        // sem o oposto no i64: comando inválido, e não pânico
+        assert_eq!(parse("decr k -9223372036854775808"), None);
+        assert_eq!(parse("incr k x"), None);
+    }
 }
@@ -0,0 +1,710 @@
+//! Pertencimento ao cluster e detecção de falhas no estilo SWIM.
+//!
+//! A cada período (`probe_interval`), o nó sonda um membro com um `Ping`.
+//! Sem `Ack` dentro de `probe_timeout`, pede a `indirect_probes` outros
+//! membros que o sondem por ele (`PingReq`); sem resposta até o fim do
+//! período, o membro passa a suspeito. Um suspeito que não se defende em
+//! `suspicion_timeout` é declarado morto.
+//!
+//! Cada membro tem um número de encarnação, que só ele incrementa: ao saber
+//! que suspeitam dele (ou que o declararam morto), ele se anuncia vivo com
+//! uma encarnação maior, e essa notícia vence a anterior. As notícias vão de
+//! carona nas mensagens do próprio SWIM e, numa amostra, nos digests do
+//! gossip. Mortos ficam guardados como lápides por `dead_retention`, para
+//! que notícias antigas não os ressuscitem.
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+use crate::message::Payload;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum MemberState {
+    Alive,
+    Suspect,
+    Dead,
+}
+
+/// Uma notícia sobre um membro, como viaja de carona nas mensagens.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemberUpdate {
+    pub addr: SocketAddr,
+    pub state: MemberState,
+    pub incarnation: u64,
+}
+
+/// O que se sabe de um membro.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Member {
+    pub state: MemberState,
+    pub incarnation: u64,
+    /// Desde quando ele está no estado atual.
+    pub since: Instant,
+}
+
+/// Tempos e limites do protocolo.
+#[derive(Debug, Clone)]
+pub struct SwimConfig {
+    /// Intervalo entre duas sondagens; é também o prazo da sondagem indireta.
+    pub probe_interval: Duration,
+    /// Quanto esperar pelo `Ack` antes de pedir a sondagem indireta.
+    pub probe_timeout: Duration,
+    /// Quantos membros sondam indiretamente.
+    pub indirect_probes: usize,
+    /// Quanto tempo um suspeito tem para se defender.
+    pub suspicion_timeout: Duration,
+    /// Quanto tempo a lápide de um morto é guardada.
+    pub dead_retention: Duration,
+    /// Quantas notícias vão de carona em cada mensagem.
+    pub max_piggyback: usize,
+    /// Cada notícia é repassada `retransmit_mult * log2(membros)` vezes.
+    pub retransmit_mult: usize,
+    /// Semente das escolhas aleatórias (quem sondar, a quem fofocar). Sem
+    /// ela, vem da entropia do sistema; o simulador fixa uma para que cada
+    /// execução se repita igual.
+    pub seed: Option<u64>,
+}
+
+impl Default for SwimConfig {
+    fn default() -> Self {
+        SwimConfig {
+            probe_interval: Duration::from_secs(1),
+            probe_timeout: Duration::from_millis(300),
+            indirect_probes: 3,
+            suspicion_timeout: Duration::from_secs(5),
+            dead_retention: Duration::from_secs(60),
+            max_piggyback: 6,
+            retransmit_mult: 4,
+            seed: None,
+        }
+    }
+}
+
+/// Uma sondagem em andamento.
+#[derive(Debug, Clone)]
+struct Probe {
+    seq: u64,
+    target: SocketAddr,
+    started: Instant,
+    /// Os membros a quem pedimos a sondagem indireta; o `Ack` deles vale.
+    helpers: Vec<SocketAddr>,
+    indirect_sent: bool,
+    acked: bool,
+}
+
+/// Uma sondagem que fazemos a pedido de outro membro (`PingReq`).
+#[derive(Debug, Clone, Copy)]
+struct Relay {
+    target: SocketAddr,
+    requester: SocketAddr,
+    requester_seq: u64,
+    expires: Instant,
+}
+
+/// Uma notícia a repassar, com quantas vezes já foi repassada.
+#[derive(Debug, Clone, Copy)]
+struct Broadcast {
+    update: MemberUpdate,
+    transmits: usize,
+}
+
+/// A visão que um nó tem do cluster.
+pub struct Membership {
+    me: SocketAddr,
+    incarnation: u64,
+    members: BTreeMap<SocketAddr, Member>,
+    config: SwimConfig,
+    rng: StdRng,
+    next_seq: u64,
+    probe: Option<Probe>,
+    /// Ordem das próximas sondagens: todos os membros, embaralhados, a cada volta.
+    probe_queue: Vec<SocketAddr>,
+    relays: BTreeMap<u64, Relay>,
+    broadcasts: Vec<Broadcast>,
+    /// Mudanças aplicadas desde a última chamada a `take_events`.
+    events: Vec<MemberUpdate>,
+}
+
+impl Membership {
+    /// Começa conhecendo só as sementes, supostas vivas.
+    pub fn new(me: SocketAddr, seeds: &[SocketAddr], config: SwimConfig, now: Instant) -> Self {
+        let rng = match config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let mut membership = Membership {
+            me,
+            incarnation: 0,
+            members: BTreeMap::new(),
+            config,
+            rng,
+            next_seq: 0,
+            probe: None,
+            probe_queue: Vec::new(),
+            relays: BTreeMap::new(),
+            broadcasts: Vec::new(),
+            events: Vec::new(),
+        };
+        for seed in seeds {
+            membership.heard_from(*seed, now);
+        }
+        // anuncia a própria entrada
+        membership.broadcast(membership.me_alive());
+        membership
+    }
+
+    pub fn me(&self) -> SocketAddr {
+        self.me
+    }
+
+    pub fn incarnation(&self) -> u64 {
+        self.incarnation
+    }
+
+    fn me_alive(&self) -> MemberUpdate {
+        MemberUpdate {
+            addr: self.me,
+            state: MemberState::Alive,
+            incarnation: self.incarnation,
+        }
+    }
+
+    /// Os membros conhecidos, inclusive as lápides, em ordem de endereço.
+    pub fn members(&self) -> Vec<(SocketAddr, Member)> {
+        self.members.iter().map(|(addr, m)| (*addr, *m)).collect()
+    }
+
+    pub fn state_of(&self, addr: SocketAddr) -> Option<MemberState> {
+        self.members.get(&addr).map(|m| m.state)
+    }
+
+    /// Um membro vivo qualquer, para a rodada de gossip.
+    pub fn random_alive(&mut self) -> Option<SocketAddr> {
+        let alive: Vec<SocketAddr> = self
+            .members
+            .iter()
+            .filter(|(_, m)| m.state == MemberState::Alive)
+            .map(|(addr, _)| *addr)
+            .collect();
+        alive.choose(&mut self.rng).copied()
+    }
+
+    /// Até `n` membros ao acaso, mais o próprio nó: vão no digest do gossip,
+    /// para quem o recebe conhecer membros que ainda não conhece.
+    pub fn sample(&mut self, n: usize) -> Vec<MemberUpdate> {
+        let all: Vec<MemberUpdate> = self
+            .members
+            .iter()
+            .map(|(addr, m)| MemberUpdate {
+                addr: *addr,
+                state: m.state,
+                incarnation: m.incarnation,
+            })
+            .collect();
+        let mut sample: Vec<MemberUpdate> =
+            all.choose_multiple(&mut self.rng, n).copied().collect();
+        sample.push(self.me_alive());
+        sample
+    }
+
+    /// As mudanças aplicadas desde a última chamada.
+    pub fn take_events(&mut self) -> Vec<MemberUpdate> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Recebemos uma mensagem de `from`. Um remetente desconhecido entra
+    /// como vivo; um que consideramos morto recebe de novo a notícia da
+    /// própria morte, para poder se defender.
+    pub fn heard_from(&mut self, from: SocketAddr, now: Instant) {
+        match self.members.get(&from) {
+            None => {
+                self.apply(
+                    MemberUpdate {
+                        addr: from,
+                        state: MemberState::Alive,
+                        incarnation: 0,
+                    },
+                    now,
+                );
+            }
+            Some(member) if member.state == MemberState::Dead => {
+                let update = MemberUpdate {
+                    addr: from,
+                    state: MemberState::Dead,
+                    incarnation: member.incarnation,
+                };
+                self.broadcast(update);
+            }
+            Some(_) => {}
+        }
+    }
+
+    /// Aplica uma notícia, se ela for mais nova que o que já sabemos, e a
+    /// repassa adiante. Devolve verdadeiro se ela mudou algo.
+    pub fn apply(&mut self, update: MemberUpdate, now: Instant) -> bool {
+        if update.addr == self.me {
+            // suspeitam de nós (ou nos deram por mortos): nos defendemos
+            if update.state != MemberState::Alive && update.incarnation >= self.incarnation {
+                self.incarnation = update.incarnation + 1;
+                self.broadcast(self.me_alive());
+            }
+            return false;
+        }
+        if let Some(current) = self.members.get(&update.addr)
+            && !overrides(&update, current)
+        {
+            return false;
+        }
+        self.members.insert(
+            update.addr,
+            Member {
+                state: update.state,
+                incarnation: update.incarnation,
+                since: now,
+            },
+        );
+        self.broadcast(update);
+        self.events.push(update);
+        true
+    }
+
+    pub fn apply_all(&mut self, updates: Vec<MemberUpdate>, now: Instant) {
+        for update in updates {
+            self.apply(update, now);
+        }
+    }
+
+    /// Põe a notícia na fila de repasse, no lugar de uma mais antiga sobre o
+    /// mesmo membro.
+    fn broadcast(&mut self, update: MemberUpdate) {
+        self.broadcasts.retain(|b| b.update.addr != update.addr);
+        self.broadcasts.push(Broadcast {
+            update,
+            transmits: 0,
+        });
+    }
+
+    /// As notícias que vão de carona na próxima mensagem: as menos
+    /// repassadas até agora.
+    fn piggyback(&mut self) -> Vec<MemberUpdate> {
+        let members = self.members.len() + 1;
+        // log2 do número de membros, arredondado para cima
+        let log2 = (usize::BITS - members.leading_zeros()) as usize;
+        let limit = self.config.retransmit_mult * log2;
+        self.broadcasts.sort_by_key(|b| b.transmits);
+        let mut updates = Vec::new();
+        for broadcast in self.broadcasts.iter_mut().take(self.config.max_piggyback) {
+            broadcast.transmits += 1;
+            updates.push(broadcast.update);
+        }
+        self.broadcasts.retain(|b| b.transmits < limit);
+        updates
+    }
+
+    fn ping(&mut self, seq: u64) -> Payload {
+        let updates = self.piggyback();
+        Payload::Ping { seq, updates }
+    }
+
+    fn ack(&mut self, seq: u64) -> Payload {
+        let updates = self.piggyback();
+        Payload::Ack { seq, updates }
+    }
+
+    /// O próximo membro a sondar. Todos são sondados uma vez por volta, em
+    /// ordem aleatória.
+    fn next_target(&mut self) -> Option<SocketAddr> {
+        loop {
+            if self.probe_queue.is_empty() {
+                self.probe_queue = self
+                    .members
+                    .iter()
+                    .filter(|(_, m)| m.state != MemberState::Dead)
+                    .map(|(addr, _)| *addr)
+                    .collect();
+                if self.probe_queue.is_empty() {
+                    return None;
+                }
+                self.probe_queue.shuffle(&mut self.rng);
+            }
+            let target = self.probe_queue.pop()?;
+            if self
+                .state_of(target)
+                .is_some_and(|s| s != MemberState::Dead)
+            {
+                return Some(target);
+            }
+        }
+    }
+
+    /// Avança os relógios do protocolo: sondagens, prazos de suspeita e
+    /// lápides. Devolve as mensagens a enviar.
+    pub fn tick(&mut self, now: Instant) -> Vec<(SocketAddr, Payload)> {
+        let mut out = Vec::new();
+
+        if let Some(probe) = self.probe.clone() {
+            if now >= probe.started + self.config.probe_interval {
+                self.probe = None;
+                if !probe.acked {
+                    self.suspect(probe.target, now);
+                }
+            } else if !probe.acked
+                && !probe.indirect_sent
+                && now >= probe.started + self.config.probe_timeout
+            {
+                let mut helpers: Vec<SocketAddr> = self
+                    .members
+                    .iter()
+                    .filter(|(addr, m)| **addr != probe.target && m.state == MemberState::Alive)
+                    .map(|(addr, _)| *addr)
+                    .collect();
+                helpers.shuffle(&mut self.rng);
+                helpers.truncate(self.config.indirect_probes);
+                for &helper in &helpers {
+                    let updates = self.piggyback();
+                    let request = Payload::PingReq {
+                        seq: probe.seq,
+                        target: probe.target,
+                        updates,
+                    };
+                    out.push((helper, request));
+                }
+                if let Some(probe) = &mut self.probe {
+                    probe.indirect_sent = true;
+                    probe.helpers = helpers;
+                }
+            }
+        }
+
+        if self.probe.is_none()
+            && let Some(target) = self.next_target()
+        {
+            self.next_seq += 1;
+            self.probe = Some(Probe {
+                seq: self.next_seq,
+                target,
+                started: now,
+                helpers: Vec::new(),
+                indirect_sent: false,
+                acked: false,
+            });
+            let ping = self.ping(self.next_seq);
+            out.push((target, ping));
+        }
+
+        // suspeitos que não se defenderam a tempo e lápides vencidas
+        let mut expired = Vec::new();
+        let mut forgotten = Vec::new();
+        for (addr, member) in &self.members {
+            match member.state {
+                MemberState::Suspect if now >= member.since + self.config.suspicion_timeout => {
+                    expired.push(MemberUpdate {
+                        addr: *addr,
+                        state: MemberState::Dead,
+                        incarnation: member.incarnation,
+                    })
+                }
+                MemberState::Dead if now >= member.since + self.config.dead_retention => {
+                    forgotten.push(*addr)
+                }
+                _ => {}
+            }
+        }
+        self.apply_all(expired, now);
+        for addr in forgotten {
+            self.members.remove(&addr);
+            self.broadcasts.retain(|b| b.update.addr != addr);
+        }
+        self.relays.retain(|_, relay| relay.expires > now);
+
+        out
+    }
+
+    fn suspect(&mut self, addr: SocketAddr, now: Instant) {
+        if let Some(member) = self.members.get(&addr)
+            && member.state == MemberState::Alive
+        {
+            let update = MemberUpdate {
+                addr,
+                state: MemberState::Suspect,
+                incarnation: member.incarnation,
+            };
+            self.apply(update, now);
+        }
+    }
+
+    /// Trata uma mensagem do SWIM (`Ping`, `Ack` ou `PingReq`) e devolve as
+    /// respostas. Outras mensagens são ignoradas.
+    pub fn handle(
+        &mut self,
+        from: SocketAddr,
+        payload: Payload,
+        now: Instant,
+    ) -> Vec<(SocketAddr, Payload)> {
+        self.heard_from(from, now);
+        match payload {
+            Payload::Ping { seq, updates } => {
+                self.apply_all(updates, now);
+                vec![(from, self.ack(seq))]
+            }
+            Payload::PingReq {
+                seq,
+                target,
+                updates,
+            } => {
+                self.apply_all(updates, now);
+                self.next_seq += 1;
+                self.relays.insert(
+                    self.next_seq,
+                    Relay {
+                        target,
+                        requester: from,
+                        requester_seq: seq,
+                        expires: now + self.config.probe_interval,
+                    },
+                );
+                vec![(target, self.ping(self.next_seq))]
+            }
+            Payload::Ack { seq, updates } => {
+                self.apply_all(updates, now);
+                // o Ack só vale se vier de quem sondamos (ou de quem sondou por nós)
+                if let Some(probe) = &mut self.probe
+                    && probe.seq == seq
+                    && (from == probe.target || probe.helpers.contains(&from))
+                {
+                    probe.acked = true;
+                    return Vec::new();
+                }
+                // resposta a uma sondagem que fizemos por outro membro
+                match self.relays.get(&seq) {
+                    Some(relay) if relay.target == from => {
+                        let (requester, requester_seq) = (relay.requester, relay.requester_seq);
+                        self.relays.remove(&seq);
+                        vec![(requester, self.ack(requester_seq))]
+                    }
+                    _ => Vec::new(),
+                }
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Verdadeiro se a notícia vence o que sabemos do membro: encarnação maior
+/// sempre vence; na mesma encarnação, suspeito vence vivo e morto vence os
+/// dois.
+fn overrides(update: &MemberUpdate, current: &Member) -> bool {
+    use MemberState::*;
+    match (update.state, current.state) {
+        (Alive, _) => update.incarnation > current.incarnation,
+        (Suspect, Alive) => update.incarnation >= current.incarnation,
+        (Suspect, Suspect) => update.incarnation > current.incarnation,
+        (Dead, Alive | Suspect) => update.incarnation >= current.incarnation,
+        (Suspect | Dead, Dead) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::GossipMessage;
+    use std::sync::Arc;
+    use tokio::net::UdpSocket;
+    use tokio::sync::Mutex;
+    use uuid::Uuid;
+
+    fn addr(port: u16) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], port))
+    }
+
+    fn update(port: u16, state: MemberState, incarnation: u64) -> MemberUpdate {
+        MemberUpdate {
+            addr: addr(port),
+            state,
+            incarnation,
+        }
+    }
+
+    #[test]
+    fn newer_news_wins() {
+        let now = Instant::now();
+        let mut m = Membership::new(addr(1), &[addr(2)], SwimConfig::default(), now);
+        assert!(m.apply(update(2, MemberState::Suspect, 0), now));
+        // notícia velha não desfaz a suspeita; a defesa (encarnação maior) desfaz
+        assert!(!m.apply(update(2, MemberState::Alive, 0), now));
+        assert!(m.apply(update(2, MemberState::Alive, 1), now));
+        assert!(m.apply(update(2, MemberState::Dead, 1), now));
+        assert!(!m.apply(update(2, MemberState::Alive, 1), now));
+        assert_eq!(m.state_of(addr(2)), Some(MemberState::Dead));
+
+        // suspeitam de nós: a encarnação sobe e a defesa vai de carona
+        m.apply(update(1, MemberState::Suspect, 0), now);
+        assert_eq!(m.incarnation(), 1);
+        let Payload::Ping { updates, .. } = m.ping(1) else {
+            unreachable!()
+        };
+        assert!(updates.contains(&update(1, MemberState::Alive, 1)));
+    }
+
+    #[test]
+    fn unanswered_probes_lead_to_suspicion_then_death() {
+        let config = SwimConfig::default();
+        let start = Instant::now();
+        let mut m = Membership::new(addr(1), &[addr(2), addr(3)], config.clone(), start);
+
+        let out = m.tick(start);
+        let (target, _) = out[0];
+        let other = if target == addr(2) { addr(3) } else { addr(2) };
+
+        // sem Ack: sondagem indireta pelo outro membro
+        let out = m.tick(start + config.probe_timeout);
+        assert!(
+            matches!(out[..], [(helper, Payload::PingReq { target: t, .. })] if helper == other && t == target)
+        );
+
+        let out = m.tick(start + config.probe_interval);
+        assert_eq!(m.state_of(target), Some(MemberState::Suspect));
+        // o outro membro responde e continua vivo
+        let Some((_, Payload::Ping { seq, .. })) = out.into_iter().find(|(a, _)| *a == other)
+        else {
+            panic!("esperava um ping para {}", other)
+        };
+        let ack = Payload::Ack {
+            seq,
+            updates: vec![],
+        };
+        m.handle(other, ack, start + config.probe_interval);
+
+        let later = start + config.probe_interval + config.suspicion_timeout;
+        m.tick(later);
+        assert_eq!(m.state_of(target), Some(MemberState::Dead));
+        assert_eq!(m.state_of(other), Some(MemberState::Alive));
+
+        m.tick(later + config.dead_retention);
+        assert_eq!(m.state_of(target), None);
+    }
+
+    #[test]
+    fn acks_count_only_from_the_probed_member() {
+        let config = SwimConfig::default();
+        let start = Instant::now();
+        let mut m = Membership::new(addr(1), &[addr(2), addr(3)], config.clone(), start);
+        let ack = |seq| Payload::Ack {
+            seq,
+            updates: vec![],
+        };
+
+        let out = m.tick(start);
+        let (target, Payload::Ping { seq, .. }) = out[0].clone() else {
+            panic!("esperava um ping")
+        };
+        let other = if target == addr(2) { addr(3) } else { addr(2) };
+        // um Ack com o mesmo número, mas de outro membro, não vale
+        m.handle(other, ack(seq), start);
+        m.tick(start + config.probe_interval);
+        assert_eq!(m.state_of(target), Some(MemberState::Suspect));
+
+        // sondagem por outro membro: só o Ack do alvo é repassado
+        let out = m.handle(
+            other,
+            Payload::PingReq {
+                seq: 7,
+                target: addr(4),
+                updates: vec![],
+            },
+            start,
+        );
+        let [(_, Payload::Ping { seq, .. })] = out[..] else {
+            panic!("esperava um ping para o alvo")
+        };
+        assert!(m.handle(addr(5), ack(seq), start).is_empty());
+        let out = m.handle(addr(4), ack(seq), start);
+        assert!(matches!(out[..], [(to, Payload::Ack { seq: 7, .. })] if to == other));
+    }
+
+    /// Um nó de verdade, com socket UDP, rodando só o SWIM.
+    async fn spawn_node(
+        socket: UdpSocket,
+        seeds: Vec<SocketAddr>,
+        config: SwimConfig,
+    ) -> (Arc<Mutex<Membership>>, tokio::task::JoinHandle<()>) {
+        let me = socket.local_addr().unwrap();
+        let membership = Arc::new(Mutex::new(Membership::new(
+            me,
+            &seeds,
+            config,
+            Instant::now(),
+        )));
+        let handle = membership.clone();
+        let task = tokio::spawn(async move {
+            let id = Uuid::new_v4();
+            let mut buf = vec![0; 65536];
+            let mut ticker = tokio::time::interval(Duration::from_millis(10));
+            loop {
+                let out = tokio::select! {
+                    _ = ticker.tick() => handle.lock().await.tick(Instant::now()),
+                    received = socket.recv_from(&mut buf) => {
+                        let (len, from) = received.unwrap();
+                        let message: GossipMessage = serde_json::from_slice(&buf[..len]).unwrap();
+                        handle.lock().await.handle(from, message.payload, Instant::now())
+                    }
+                };
+                for (to, payload) in out {
+                    let bytes = serde_json::to_vec(&GossipMessage {
+                        node_id: id,
+                        payload,
+                    })
+                    .unwrap();
+                    let _ = socket.send_to(&bytes, to).await;
+                }
+            }
+        });
+        (membership, task)
+    }
+
+    #[tokio::test]
+    async fn nodes_on_localhost_detect_a_failure() {
+        let config = SwimConfig {
+            probe_interval: Duration::from_millis(100),
+            probe_timeout: Duration::from_millis(40),
+            suspicion_timeout: Duration::from_millis(400),
+            ..SwimConfig::default()
+        };
+        let mut sockets = Vec::new();
+        for _ in 0..4 {
+            sockets.push(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+        }
+        let addrs: Vec<SocketAddr> = sockets.iter().map(|s| s.local_addr().unwrap()).collect();
+
+        // cada nó só conhece o anterior; o resto vem pelo gossip
+        let mut nodes = Vec::new();
+        for (i, socket) in sockets.into_iter().enumerate() {
+            let seeds = if i == 0 { vec![] } else { vec![addrs[i - 1]] };
+            nodes.push(spawn_node(socket, seeds, config.clone()).await);
+        }
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        for (membership, _) in &nodes {
+            let membership = membership.lock().await;
+            let alive = membership
+                .members()
+                .iter()
+                .filter(|(_, m)| m.state == MemberState::Alive)
+                .count();
+            assert_eq!(alive, 3, "visão de {}", membership.me());
+        }
+
+        // o último nó para de responder
+        nodes[3].1.abort();
+        tokio::time::sleep(Duration::from_millis(2000)).await;
+        for (membership, _) in &nodes[..3] {
+            let membership = membership.lock().await;
+            assert_eq!(membership.state_of(addrs[3]), Some(MemberState::Dead));
+        }
+    }
+}
@@ -0,0 +1,248 @@
+//! As mensagens trocadas entre os nós e a divisão delas em datagramas que
+//! cabem no limite de tamanho escolhido para o UDP.
+
+use crate::antientropy::{DigestChunk, Entry};
+use crate::membership::MemberUpdate;
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use uuid::Uuid;
+
+/// Tamanho máximo padrão de um datagrama: cabe no MTU de uma rede Ethernet,
+/// sem fragmentação de IP.
+pub const DEFAULT_MAX_DATAGRAM: usize = 1400;
+
+/// Quantos membros conhecidos vão, no máximo, em cada `Syn`.
+pub const MAX_MEMBERS_PER_SYN: usize = 8;
+
+/// Uma mensagem de gossip; `payload.type` diz qual é.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GossipMessage {
+    pub node_id: Uuid,
+    pub payload: Payload,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type")]
+pub enum Payload {
+    /// Início de uma rodada: as versões de um trecho das chaves e, no
+    /// primeiro trecho, uma amostra dos membros conhecidos.
+    Syn {
+        digest: DigestChunk,
+        members: Vec<MemberUpdate>,
+    },
+    /// Chaves que o remetente quer, por serem mais novas do outro lado.
+    Request { keys: Vec<String> },
+    /// Entradas mais novas que as do destinatário.
+    Delta { entries: Vec<Entry> },
+    /// Sondagem do SWIM; as `updates` são notícias sobre membros, de carona.
+    Ping {
+        seq: u64,
+        updates: Vec<MemberUpdate>,
+    },
+    /// Resposta a um `Ping` (direto ou feito a pedido de outro membro).
+    Ack {
+        seq: u64,
+        updates: Vec<MemberUpdate>,
+    },
+    /// Pedido para sondar `target` e repassar o `Ack` ao remetente.
+    PingReq {
+        seq: u64,
+        target: SocketAddr,
+        updates: Vec<MemberUpdate>,
+    },
+}
+
+impl GossipMessage {
+    /// A mensagem serializada, para as que sempre cabem em um datagrama.
+    pub fn encode(&self) -> Datagram {
+        encode(self, 0)
+    }
+}
+
+/// Uma mensagem serializada, pronta para o envio.
+#[derive(Debug)]
+pub struct Datagram {
+    pub bytes: Vec<u8>,
+    /// Quantas entradas do estado ela leva (para as métricas).
+    pub entries: usize,
+}
+
+fn json_len<T: Serialize>(value: &T) -> usize {
+    serde_json::to_vec(value).map_or(0, |bytes| bytes.len())
+}
+
+fn encode(message: &GossipMessage, entries: usize) -> Datagram {
+    Datagram {
+        bytes: serde_json::to_vec(message).expect("mensagens sempre serializam"),
+        entries,
+    }
+}
+
+/// Quantos dos primeiros itens cabem juntos em uma mensagem cujo tamanho,
+/// com a lista vazia, é `fixed`. Pelo menos um: um item maior que o limite
+/// vai sozinho, e o envio dele pode falhar.
+fn fitting(sizes: &[usize], fixed: usize, limit: usize) -> usize {
+    let mut size = fixed;
+    for (i, item) in sizes.iter().enumerate() {
+        // vírgula entre os itens
+        size += item + usize::from(i > 0);
+        if size > limit && i > 0 {
+            return i;
+        }
+    }
+    sizes.len()
+}
+
+/// Divide os itens em quantas mensagens forem necessárias.
+fn split<T: Serialize>(
+    node_id: Uuid,
+    items: Vec<T>,
+    limit: usize,
+    counts_entries: bool,
+    build: impl Fn(Vec<T>) -> Payload,
+) -> Vec<Datagram> {
+    let fixed = json_len(&GossipMessage {
+        node_id,
+        payload: build(Vec::new()),
+    });
+    let sizes: Vec<usize> = items.iter().map(json_len).collect();
+    let mut items = items.into_iter();
+    let mut datagrams = Vec::new();
+    let mut start = 0;
+    while start < sizes.len() {
+        let count = fitting(&sizes[start..], fixed, limit);
+        let chunk: Vec<T> = items.by_ref().take(count).collect();
+        let message = GossipMessage {
+            node_id,
+            payload: build(chunk),
+        };
+        datagrams.push(encode(&message, if counts_entries { count } else { 0 }));
+        start += count;
+    }
+    datagrams
+}
+
+/// As entradas em mensagens `Delta`.
+pub fn deltas(node_id: Uuid, entries: Vec<Entry>, limit: usize) -> Vec<Datagram> {
+    split(node_id, entries, limit, true, |entries| Payload::Delta {
+        entries,
+    })
+}
+
+/// As chaves pedidas em mensagens `Request`.
+pub fn requests(node_id: Uuid, keys: Vec<String>, limit: usize) -> Vec<Datagram> {
+    split(node_id, keys, limit, false, |keys| Payload::Request {
+        keys,
+    })
+}
+
+/// O digest (em ordem de chave) em mensagens `Syn`. Sempre há pelo menos
+/// uma, mesmo com o estado vazio: é ela que leva os membros.
+pub fn syns(
+    node_id: Uuid,
+    digest: &[(String, u64)],
+    members: Vec<MemberUpdate>,
+    limit: usize,
+) -> Vec<Datagram> {
+    let syn = |after: Option<String>, versions: Vec<(String, u64)>, last, members| GossipMessage {
+        node_id,
+        payload: Payload::Syn {
+            digest: DigestChunk {
+                after,
+                versions,
+                last,
+            },
+            members,
+        },
+    };
+    let sizes: Vec<usize> = digest.iter().map(json_len).collect();
+    let mut datagrams = Vec::new();
+    let mut members = Some(members);
+    let mut after = None;
+    let mut start = 0;
+    loop {
+        let chunk_members = members.take().unwrap_or_default();
+        // `last: false` é o maior dos dois
+        let fixed = json_len(&syn(
+            after.clone(),
+            Vec::new(),
+            false,
+            chunk_members.clone(),
+        ));
+        let count = fitting(&sizes[start..], fixed, limit);
+        let versions = digest[start..start + count].to_vec();
+        start += count;
+        let last = start == digest.len();
+        let next_after = versions.last().map(|(key, _)| key.clone());
+        datagrams.push(encode(&syn(after, versions, last, chunk_members), 0));
+        if last {
+            return datagrams;
+        }
+        after = next_after;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crdt::{Crdt, Hlc, LwwRegister};
+
+    fn decode(datagram: &Datagram) -> Payload {
+        serde_json::from_slice::<GossipMessage>(&datagram.bytes)
+            .unwrap()
+            .payload
+    }
+
+    #[test]
+    fn large_digests_are_split_into_covering_chunks() {
+        let digest: Vec<(String, u64)> = (0..500).map(|i| (format!("chave-{:04}", i), i)).collect();
+        let members = vec![MemberUpdate {
+            addr: "127.0.0.1:8000".parse().unwrap(),
+            state: crate::membership::MemberState::Alive,
+            incarnation: 0,
+        }];
+        let datagrams = syns(Uuid::nil(), &digest, members, 600);
+        assert!(datagrams.len() > 1);
+
+        let mut seen = Vec::new();
+        for (i, datagram) in datagrams.iter().enumerate() {
+            assert!(datagram.bytes.len() <= 600);
+            let Payload::Syn {
+                digest: chunk,
+                members,
+            } = decode(datagram)
+            else {
+                panic!("esperava Syn");
+            };
+            assert_eq!(members.len(), usize::from(i == 0));
+            assert_eq!(chunk.last, i == datagrams.len() - 1);
+            // cada chave está coberta por exatamente um trecho
+            for (key, _) in &digest {
+                if chunk.covers(key) {
+                    seen.push(key.clone());
+                }
+            }
+        }
+        let expected: Vec<String> = digest.into_iter().map(|(key, _)| key).collect();
+        assert_eq!(seen, expected);
+
+        let empty = syns(Uuid::nil(), &[], Vec::new(), 600);
+        assert_eq!(empty.len(), 1);
+    }
+
+    #[test]
+    fn deltas_fit_the_limit() {
+        let entries: Vec<Entry> = (0..100)
+            .map(|i| Entry {
+                key: format!("k{}", i),
+                value: Crdt::Lww(LwwRegister {
+                    value: Some("v".repeat(40)),
+                    stamp: Hlc::ZERO,
+                }),
+            })
+            .collect();
+        let datagrams = deltas(Uuid::nil(), entries, 1000);
+        assert!(datagrams.iter().all(|d| d.bytes.len() <= 1000));
+        assert_eq!(datagrams.iter().map(|d| d.entries).sum::<usize>(), 100);
+    }
+}
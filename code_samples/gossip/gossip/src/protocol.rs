@@ -0,0 +1,380 @@
+//! A lógica do nó, sem rede e sem relógio próprios: quem a usa entrega os
+//! datagramas recebidos e o instante atual, e ela devolve o que enviar por
+//! um `Transport`. Assim o mesmo código roda sobre o socket UDP (em
+//! `main.rs`) e sobre a rede simulada dos testes.
+
+use crate::NodeState;
+use crate::antientropy::{self, Metrics};
+use crate::crdt::{Crdt, CrdtError, Op, Replica};
+use crate::membership::{MemberState, MemberUpdate, Membership, SwimConfig};
+use crate::message::{self, Datagram, GossipMessage, Payload};
+use crate::security::Guard;
+use crate::transport::Transport;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Imprime só se o nó foi criado com `verbose`.
+macro_rules! log {
+    ($protocol:expr, $($arg:tt)*) => {
+        if $protocol.verbose {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// Como o nó se apresenta e se comporta.
+pub struct Config {
+    /// ID único para este nó, para ignorar as próprias mensagens.
+    pub id: Uuid,
+    /// Endereço de escuta do nó (IP:PORTA).
+    pub addr: SocketAddr,
+    /// Tamanho máximo de cada datagrama enviado.
+    pub max_datagram: usize,
+    pub swim: SwimConfig,
+    /// Cifra e autentica os datagramas, se o nó tem chaves.
+    pub guard: Option<Guard>,
+    /// Imprime o que acontece (os nós simulados ficam calados).
+    pub verbose: bool,
+}
+
+pub struct Protocol {
+    id: Uuid,
+    /// Os outros nós conhecidos na rede e se estão vivos (SWIM).
+    membership: Membership,
+    /// O estado (dados) deste nó.
+    state: NodeState,
+    /// Este nó como réplica dos CRDTs, com o seu relógio lógico híbrido.
+    replica: Replica,
+    /// Bytes e entradas trocados, por rodada.
+    metrics: Metrics,
+    max_datagram: usize,
+    guard: Option<Guard>,
+    verbose: bool,
+    /// Chaves alteradas desde a última chamada a `take_changes`.
+    changes: Vec<String>,
+    /// O relógio de parede (para o HLC e os carimbos dos datagramas) é
+    /// `epoch_ms` mais o tempo decorrido desde `started`.
+    started: Instant,
+    epoch_ms: u64,
+}
+
+impl Protocol {
+    /// Cria o nó no instante `now`, que no relógio de parede é `epoch_ms`.
+    pub fn new(config: Config, now: Instant, epoch_ms: u64) -> Self {
+        Protocol {
+            id: config.id,
+            membership: Membership::new(config.addr, &[], config.swim, now),
+            state: NodeState::new(),
+            replica: Replica::new(config.id),
+            metrics: Metrics::default(),
+            // a cifragem aumenta cada datagrama; as mensagens ficam menores
+            max_datagram: match config.guard {
+                Some(_) => config
+                    .max_datagram
+                    .saturating_sub(crate::security::OVERHEAD),
+                None => config.max_datagram,
+            },
+            guard: config.guard,
+            verbose: config.verbose,
+            changes: Vec::new(),
+            started: now,
+            epoch_ms,
+        }
+    }
+
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    pub fn state(&self) -> &NodeState {
+        &self.state
+    }
+
+    pub fn membership(&self) -> &Membership {
+        &self.membership
+    }
+
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    /// Verdadeiro se as mensagens vão cifradas e autenticadas.
+    pub fn encrypted(&self) -> bool {
+        self.guard.is_some()
+    }
+
+    fn wall_ms(&self, now: Instant) -> u64 {
+        self.epoch_ms + now.saturating_duration_since(self.started).as_millis() as u64
+    }
+
+    /// Passa a conhecer os peers iniciais.
+    pub fn join(&mut self, peers: &[SocketAddr], now: Instant) {
+        for peer in peers {
+            self.membership.heard_from(*peer, now);
+        }
+        self.membership.take_events();
+    }
+
+    /// Aplica uma alteração feita neste nó.
+    pub fn apply(&mut self, key: &str, op: Op, now: Instant) -> Result<Crdt, CrdtError> {
+        let physical_ms = self.wall_ms(now);
+        let value = self.replica.apply(&mut self.state, key, op, physical_ms)?;
+        self.changes.push(key.to_string());
+        Ok(value)
+    }
+
+    /// As chaves alteradas (aqui ou pela fofoca) desde a última chamada.
+    pub fn take_changes(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.changes)
+    }
+
+    /// Imprime o estado atual do nó de forma legível.
+    pub fn print_state(&self) {
+        if self.state.is_empty() {
+            println!("O estado está vazio.");
+        } else {
+            for (key, value) in self.state.iter() {
+                println!("  - '{}': {} ({})", key, value, value.type_name());
+            }
+        }
+    }
+
+    /// Uma rodada de gossip: envia o digest a um membro vivo qualquer.
+    pub fn gossip(&mut self, now: Instant, transport: &impl Transport) {
+        // Relê o arquivo de chaves, se mudou: troca sem reiniciar
+        if let Some(guard) = &mut self.guard {
+            match guard.reload_if_changed() {
+                Ok(Some(primary)) => {
+                    log!(
+                        self,
+                        "[SEGURANÇA] Chaves relidas; enviando com a chave {}",
+                        primary
+                    )
+                }
+                Ok(None) => {}
+                Err(e) => eprintln!("[ERRO] Chaves mantidas: {}", e),
+            }
+        }
+
+        // Cada envio de digest começa uma rodada nova
+        let traffic = self.metrics.finish_round();
+        if traffic != Default::default() {
+            log!(
+                self,
+                "[MÉTRICAS] Rodada {}: {}",
+                self.metrics.round,
+                traffic
+            );
+        }
+
+        // Seleciona um peer vivo aleatório para enviar a mensagem, e
+        // alguns membros conhecidos para ele descobrir
+        let sample = self.membership.sample(message::MAX_MEMBERS_PER_SYN);
+        if let Some(target) = self.membership.random_alive() {
+            // Só o digest (resumo de cada chave); os dados vão depois,
+            // apenas os que estiverem faltando ou diferentes
+            let digest = antientropy::digest(&self.state);
+            let datagrams = message::syns(self.id, &digest, sample, self.max_datagram);
+            let count = datagrams.len();
+            let datagrams = datagrams.into_iter().map(|d| (target, d)).collect();
+            self.send_all(datagrams, now, transport);
+            log!(
+                self,
+                "[GOSSIP] Digest enviado para {} ({} mensagem(ns))",
+                target,
+                count
+            );
+        }
+    }
+
+    /// Avança a detecção de falhas (SWIM): sondagens, suspeitas e lápides.
+    pub fn tick(&mut self, now: Instant, transport: &impl Transport) {
+        let outgoing = self.membership.tick(now);
+        self.print_member_events();
+        let datagrams = outgoing
+            .into_iter()
+            .map(|(to, payload)| (to, self.encode(payload)))
+            .collect();
+        self.send_all(datagrams, now, transport);
+    }
+
+    /// Trata um datagrama recebido e envia as respostas.
+    pub fn receive(
+        &mut self,
+        from: SocketAddr,
+        bytes: &[u8],
+        now: Instant,
+        transport: &impl Transport,
+    ) {
+        // Confere e decifra, se as mensagens são cifradas
+        let wall_ms = self.wall_ms(now);
+        let me = self.membership.me();
+        let plaintext = match &mut self.guard {
+            Some(guard) => match guard.open(bytes, me, wall_ms) {
+                Ok(plaintext) => plaintext,
+                Err(reason) => {
+                    log!(
+                        self,
+                        "[SEGURANÇA] Datagrama de {} descartado: {}",
+                        from,
+                        reason
+                    );
+                    return;
+                }
+            },
+            None => bytes.to_vec(),
+        };
+        // Deserializa a mensagem recebida
+        let Ok(message) = serde_json::from_slice::<GossipMessage>(&plaintext) else {
+            return;
+        };
+        // Ignora mensagens de si mesmo
+        if message.node_id == self.id {
+            return;
+        }
+
+        let entries = match &message.payload {
+            Payload::Delta { entries } => entries.len(),
+            _ => 0,
+        };
+        self.metrics.received(from, bytes.len(), entries);
+
+        // Processa a mensagem e responde ao remetente, se for o caso
+        let replies = self.handle_message(message, from, now);
+        self.send_all(replies, now, transport);
+    }
+
+    fn encode(&self, payload: Payload) -> Datagram {
+        GossipMessage {
+            node_id: self.id,
+            payload,
+        }
+        .encode()
+    }
+
+    /// Envia os datagramas (cifrados, se houver chaves), registrando o tráfego.
+    fn send_all(
+        &mut self,
+        datagrams: Vec<(SocketAddr, Datagram)>,
+        now: Instant,
+        transport: &impl Transport,
+    ) {
+        let wall_ms = self.wall_ms(now);
+        for (target, datagram) in datagrams {
+            let bytes = match &self.guard {
+                Some(guard) => guard.seal(&datagram.bytes, target, wall_ms),
+                None => datagram.bytes,
+            };
+            match transport.send(target, &bytes) {
+                Ok(()) => self.metrics.sent(target, bytes.len(), datagram.entries),
+                Err(e) => {
+                    if self.verbose {
+                        eprintln!("[ERRO] Falha ao enviar gossip para {}: {}", target, e);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Imprime as mudanças na visão do cluster.
+    fn print_member_events(&mut self) {
+        for MemberUpdate {
+            addr,
+            state,
+            incarnation,
+        } in self.membership.take_events()
+        {
+            log!(
+                self,
+                "[MEMBROS] {} agora está {} (encarnação {})",
+                addr,
+                state_name(state),
+                incarnation
+            );
+        }
+    }
+
+    /// A lógica central do gossip: compara o digest recebido com o estado
+    /// local, aplica as entradas recebidas, responde aos pedidos e às
+    /// sondagens do SWIM. Devolve os datagramas de resposta.
+    fn handle_message(
+        &mut self,
+        remote_message: GossipMessage,
+        remote_addr: SocketAddr,
+        now: Instant,
+    ) -> Vec<(SocketAddr, Datagram)> {
+        // 1. Atualizar a visão do cluster: o remetente e os membros que
+        // vieram na mensagem
+        self.membership.heard_from(remote_addr, now);
+        let payload = match remote_message.payload {
+            Payload::Syn { digest, members } => {
+                self.membership.apply_all(members, now);
+                Payload::Syn {
+                    digest,
+                    members: Vec::new(),
+                }
+            }
+            swim @ (Payload::Ping { .. } | Payload::Ack { .. } | Payload::PingReq { .. }) => {
+                let replies = self.membership.handle(remote_addr, swim, now);
+                self.print_member_events();
+                return replies
+                    .into_iter()
+                    .map(|(to, payload)| (to, self.encode(payload)))
+                    .collect();
+            }
+            other => other,
+        };
+        self.print_member_events();
+
+        let reply = |datagrams: Vec<Datagram>| -> Vec<(SocketAddr, Datagram)> {
+            datagrams.into_iter().map(|d| (remote_addr, d)).collect()
+        };
+
+        // 2. Sincronizar o estado (dados chave-valor)
+        match payload {
+            // Um trecho do digest do remetente: enviamos o que temos de
+            // diferente e pedimos o que ele tem de diferente
+            Payload::Syn { digest, .. } => {
+                let (newer_here, wanted) = antientropy::reconcile(&self.state, &digest);
+                let mut replies = message::deltas(self.id, newer_here, self.max_datagram);
+                replies.extend(message::requests(self.id, wanted, self.max_datagram));
+                reply(replies)
+            }
+            // O remetente quer as nossas versões destas chaves
+            Payload::Request { keys } => {
+                let entries = antientropy::entries(&self.state, &keys);
+                reply(message::deltas(self.id, entries, self.max_datagram))
+            }
+            Payload::Delta { entries } => {
+                let changed = antientropy::apply(&mut self.state, entries);
+                if !changed.is_empty() && self.verbose {
+                    println!(
+                        "[MERGE] Estado local foi atualizado pela fofoca ({} entradas).",
+                        changed.len()
+                    );
+                    self.print_state();
+                }
+                self.changes.extend(changed);
+                Vec::new()
+            }
+            // tratadas pelo SWIM, acima
+            Payload::Ping { .. } | Payload::Ack { .. } | Payload::PingReq { .. } => Vec::new(),
+        }
+    }
+}
+
+/// Nome do estado de um membro, para os logs.
+pub fn state_name(state: MemberState) -> &'static str {
+    match state {
+        MemberState::Alive => "vivo",
+        MemberState::Suspect => "suspeito",
+        MemberState::Dead => "morto",
+    }
+}
+
+/// Intervalo entre as rodadas de gossip.
+pub const GOSSIP_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Intervalo entre os passos da detecção de falhas.
+pub const TICK_INTERVAL: Duration = Duration::from_millis(50);
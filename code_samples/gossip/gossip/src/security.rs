@@ -0,0 +1,342 @@
+//! Autenticação e cifragem dos datagramas com uma chave compartilhada.
+//!
+//! Sem isso, qualquer processo que alcance a porta UDP injeta estado no nó:
+//! a mensagem é JSON puro e o `node_id` é declarado pelo próprio remetente.
+//! Com um arquivo de chaves, cada datagrama vai cifrado e autenticado com
+//! ChaCha20-Poly1305 e leva um cabeçalho em claro, também autenticado:
+//!
+//! | versão (1) | id da chave (4) | carimbo em ms (8) | nonce (12) | cifrado + tag (16) |
+//!
+//! O endereço do destinatário também é autenticado, sem ir no datagrama:
+//! uma cópia do que foi mandado a um nó não abre em nenhum outro.
+//!
+//! Quem recebe descarta o datagrama que não abre com nenhuma das chaves
+//! conhecidas, o que está fora da janela de tempo e o que repete
+//! um nonce já visto dentro da janela: uma cópia gravada e reenviada depois
+//! não é aceita.
+//!
+//! O arquivo tem uma chave por linha, `<id> <64 dígitos hexadecimais>`. A
+//! primeira é a usada para enviar; todas são aceitas ao receber. O nó relê o
+//! arquivo quando ele muda, então a troca de chaves não exige reiniciar:
+//! acrescenta-se a nova em todos os nós, depois ela passa a ser a primeira
+//! e, por fim, a antiga é removida.
+
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use std::collections::HashMap;
+use std::fmt;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+const VERSION: u8 = 1;
+const NONCE_LEN: usize = 12;
+const HEADER_LEN: usize = 1 + 4 + 8 + NONCE_LEN;
+const TAG_LEN: usize = 16;
+
+/// Quantos bytes a cifragem acrescenta a cada datagrama.
+pub const OVERHEAD: usize = HEADER_LEN + TAG_LEN;
+
+/// Diferença máxima padrão, em milissegundos, entre o carimbo de um
+/// datagrama e o relógio de quem o recebe.
+pub const DEFAULT_WINDOW_MS: u64 = 30_000;
+
+/// As chaves conhecidas; a primeira é a de envio.
+pub struct Keyring {
+    keys: Vec<(u32, ChaCha20Poly1305)>,
+}
+
+impl Keyring {
+    /// Lê o conteúdo de um arquivo de chaves. Linhas vazias e as começadas
+    /// por `#` são ignoradas.
+    pub fn parse(text: &str) -> Result<Keyring, String> {
+        let mut keys: Vec<(u32, ChaCha20Poly1305)> = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |reason: &str| format!("linha {}: {}", number + 1, reason);
+            let (id, hex) = line
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| invalid("esperava <id> <chave>"))?;
+            let id: u32 = id.parse().map_err(|_| invalid("id inválido"))?;
+            let key = decode_hex(hex.trim())
+                .filter(|bytes| bytes.len() == 32)
+                .ok_or_else(|| invalid("a chave deve ter 64 dígitos hexadecimais"))?;
+            if keys.iter().any(|(known, _)| *known == id) {
+                return Err(invalid("id repetido"));
+            }
+            keys.push((id, ChaCha20Poly1305::new(Key::from_slice(&key))));
+        }
+        if keys.is_empty() {
+            return Err("nenhuma chave no arquivo".to_string());
+        }
+        Ok(Keyring { keys })
+    }
+
+    /// O id da chave de envio.
+    pub fn primary(&self) -> u32 {
+        self.keys[0].0
+    }
+
+    fn get(&self, id: u32) -> Option<&ChaCha20Poly1305> {
+        self.keys
+            .iter()
+            .find(|(known, _)| *known == id)
+            .map(|(_, cipher)| cipher)
+    }
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Por que um datagrama foi descartado.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rejected {
+    /// Curto demais, ou de outra versão (por exemplo, JSON sem cifragem).
+    Malformed,
+    UnknownKey(u32),
+    /// Não abre com a chave: foi alterado ou não veio de quem a tem.
+    Forged,
+    /// Fora da janela de tempo.
+    Stale,
+    /// Nonce já visto dentro da janela.
+    Replayed,
+}
+
+impl fmt::Display for Rejected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rejected::Malformed => write!(f, "formato inválido"),
+            Rejected::UnknownKey(id) => write!(f, "chave {} desconhecida", id),
+            Rejected::Forged => write!(f, "autenticação falhou"),
+            Rejected::Stale => write!(f, "fora da janela de tempo"),
+            Rejected::Replayed => write!(f, "repetido"),
+        }
+    }
+}
+
+/// Cifra os datagramas que saem e confere os que chegam.
+pub struct Guard {
+    keyring: Keyring,
+    /// O arquivo de onde as chaves vieram, e quando ele foi alterado.
+    source: Option<(PathBuf, Option<SystemTime>)>,
+    window_ms: u64,
+    /// Nonces aceitos e os seus carimbos, enquanto estão dentro da janela.
+    seen: HashMap<[u8; NONCE_LEN], u64>,
+    last_prune_ms: u64,
+}
+
+impl Guard {
+    pub fn new(keyring: Keyring, window_ms: u64) -> Self {
+        Guard {
+            keyring,
+            source: None,
+            window_ms,
+            seen: HashMap::new(),
+            last_prune_ms: 0,
+        }
+    }
+
+    /// Carrega as chaves de um arquivo, que será relido quando mudar.
+    pub fn from_file(path: PathBuf, window_ms: u64) -> Result<Self, String> {
+        let modified = modified(&path);
+        let keyring = load(&path)?;
+        let mut guard = Guard::new(keyring, window_ms);
+        guard.source = Some((path, modified));
+        Ok(guard)
+    }
+
+    /// Relê o arquivo de chaves se ele mudou. Devolve o id da nova chave de
+    /// envio; se o arquivo novo for inválido, as chaves atuais continuam.
+    pub fn reload_if_changed(&mut self) -> Result<Option<u32>, String> {
+        let Some((path, last_modified)) = &mut self.source else {
+            return Ok(None);
+        };
+        let current = modified(path);
+        if current == *last_modified {
+            return Ok(None);
+        }
+        *last_modified = current;
+        self.keyring = load(path)?;
+        Ok(Some(self.keyring.primary()))
+    }
+
+    /// Cifra um datagrama com a chave de envio.
+    pub fn seal(&self, plaintext: &[u8], to: SocketAddr, now_ms: u64) -> Vec<u8> {
+        let (id, cipher) = &self.keyring.keys[0];
+        let nonce: [u8; NONCE_LEN] = rand::random();
+        let mut datagram = Vec::with_capacity(plaintext.len() + OVERHEAD);
+        datagram.push(VERSION);
+        datagram.extend_from_slice(&id.to_be_bytes());
+        datagram.extend_from_slice(&now_ms.to_be_bytes());
+        datagram.extend_from_slice(&nonce);
+        let sealed = cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: plaintext,
+                    aad: &associated_data(&datagram, to),
+                },
+            )
+            .expect("a cifragem só falha com mensagens de gigabytes");
+        datagram.extend_from_slice(&sealed);
+        datagram
+    }
+
+    /// Confere e decifra um datagrama recebido no endereço `me`.
+    pub fn open(
+        &mut self,
+        datagram: &[u8],
+        me: SocketAddr,
+        now_ms: u64,
+    ) -> Result<Vec<u8>, Rejected> {
+        if datagram.len() < OVERHEAD || datagram[0] != VERSION {
+            return Err(Rejected::Malformed);
+        }
+        let (header, sealed) = datagram.split_at(HEADER_LEN);
+        let id = u32::from_be_bytes(header[1..5].try_into().unwrap());
+        let stamp = u64::from_be_bytes(header[5..13].try_into().unwrap());
+        let nonce: [u8; NONCE_LEN] = header[13..].try_into().unwrap();
+
+        let cipher = self.keyring.get(id).ok_or(Rejected::UnknownKey(id))?;
+        let plaintext = cipher
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: sealed,
+                    aad: &associated_data(header, me),
+                },
+            )
+            .map_err(|_| Rejected::Forged)?;
+        if stamp.abs_diff(now_ms) > self.window_ms {
+            return Err(Rejected::Stale);
+        }
+        self.prune(now_ms);
+        if self.seen.insert(nonce, stamp).is_some() {
+            return Err(Rejected::Replayed);
+        }
+        Ok(plaintext)
+    }
+
+    /// Esquece os nonces que já saíram da janela: um datagrama com eles
+    /// seria recusado pelo carimbo.
+    fn prune(&mut self, now_ms: u64) {
+        if now_ms.saturating_sub(self.last_prune_ms) < 1000 {
+            return;
+        }
+        self.last_prune_ms = now_ms;
+        let oldest = now_ms.saturating_sub(self.window_ms);
+        self.seen.retain(|_, stamp| *stamp >= oldest);
+    }
+}
+
+/// O que a tag autentica além do cifrado: o cabeçalho e o destinatário.
+fn associated_data(header: &[u8], to: SocketAddr) -> Vec<u8> {
+    let mut aad = header.to_vec();
+    aad.extend_from_slice(to.to_string().as_bytes());
+    aad
+}
+
+fn modified(path: &PathBuf) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn load(path: &PathBuf) -> Result<Keyring, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("não foi possível ler {}: {}", path.display(), e))?;
+    Keyring::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{Ipv4Addr, SocketAddrV4};
+
+    const OLD: &str = "1 000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+    const NEW: &str = "2 ffeeddccbbaa99887766554433221100ffeeddccbbaa99887766554433221100";
+
+    const B: SocketAddr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 7002));
+    const C: SocketAddr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 7003));
+
+    fn guard(keys: &str) -> Guard {
+        Guard::new(Keyring::parse(keys).unwrap(), 1000)
+    }
+
+    #[test]
+    fn sealed_datagrams_open_once_and_only_unaltered() {
+        let sender = guard(OLD);
+        let mut receiver = guard(OLD);
+        let datagram = sender.seal(b"{\"oi\":1}", B, 5000);
+        assert_eq!(datagram.len(), 8 + OVERHEAD);
+        assert!(!datagram.windows(4).any(|w| w == b"\"oi\""));
+
+        assert_eq!(receiver.open(&datagram, B, 5200).unwrap(), b"{\"oi\":1}");
+        assert_eq!(receiver.open(&datagram, B, 5300), Err(Rejected::Replayed));
+
+        let mut altered = sender.seal(b"{\"oi\":1}", B, 5000);
+        let last = altered.len() - 1;
+        altered[last] ^= 1;
+        assert_eq!(receiver.open(&altered, B, 5000), Err(Rejected::Forged));
+        // o carimbo é autenticado: não dá para "renovar" uma cópia antiga
+        let mut restamped = sender.seal(b"x", B, 1000);
+        restamped[5..13].copy_from_slice(&5000u64.to_be_bytes());
+        assert_eq!(receiver.open(&restamped, B, 5000), Err(Rejected::Forged));
+
+        let old = sender.seal(b"x", B, 1000);
+        assert_eq!(receiver.open(&old, B, 5000), Err(Rejected::Stale));
+        assert_eq!(
+            receiver.open(b"{\"node_id\":1}", B, 5000),
+            Err(Rejected::Malformed)
+        );
+        assert_eq!(
+            guard(NEW).open(&sender.seal(b"x", B, 5000), B, 5000),
+            Err(Rejected::UnknownKey(1))
+        );
+    }
+
+    #[test]
+    fn datagrams_open_only_at_their_recipient() {
+        let sender = guard(OLD);
+        let for_b = sender.seal(b"x", B, 5000);
+        // a cópia reenviada a C não abre lá, nem gasta o nonce em B
+        assert_eq!(guard(OLD).open(&for_b, C, 5000), Err(Rejected::Forged));
+        assert!(guard(OLD).open(&for_b, B, 5000).is_ok());
+    }
+
+    #[test]
+    fn keys_rotate_without_losing_messages() {
+        let old = guard(OLD);
+        // 1. a chave nova é acrescentada: aceita, mas ainda não usada
+        let mut adding = guard(&format!("{}\n{}", OLD, NEW));
+        assert_eq!(adding.keyring.primary(), 1);
+        assert!(adding.open(&old.seal(b"a", B, 0), B, 0).is_ok());
+        // 2. a nova passa a ser a de envio; quem ainda usa a antiga é aceito
+        let mut switched = guard(&format!("# nova primeiro\n{}\n\n{}", NEW, OLD));
+        assert!(switched.open(&adding.seal(b"b", B, 0), B, 0).is_ok());
+        assert!(adding.open(&switched.seal(b"c", B, 0), B, 0).is_ok());
+        // 3. a antiga é removida
+        let mut done = guard(NEW);
+        assert!(done.open(&switched.seal(b"d", B, 0), B, 0).is_ok());
+        assert_eq!(
+            done.open(&old.seal(b"e", B, 0), B, 0),
+            Err(Rejected::UnknownKey(1))
+        );
+    }
+
+    #[test]
+    fn invalid_key_files_are_refused() {
+        assert!(Keyring::parse("").is_err());
+        assert!(Keyring::parse("1 abcd").is_err());
+        assert!(Keyring::parse(&format!("{}\n{}", OLD, OLD)).is_err());
+        assert!(Keyring::parse("x 00").is_err());
+    }
+}
@@ -0,0 +1,560 @@
+//! Rede simulada e determinística, para testar o protocolo com muitos nós.
+//!
+//! Os nós rodam todos no mesmo processo, sobre um relógio virtual. O
+//! simulador entrega cada datagrama depois de um atraso sorteado (o que
+//! também os tira de ordem), perde e duplica alguns, separa grupos de nós em
+//! partições e derruba nós. Tudo sai de uma semente: a mesma semente repete
+//! a mesma execução, e um minuto de cluster roda em segundos.
+
+use crate::crdt::{Crdt, CrdtError, Op};
+use crate::membership::{MemberState, SwimConfig};
+use crate::message::DEFAULT_MAX_DATAGRAM;
+use crate::protocol::{Config, GOSSIP_INTERVAL, Protocol, TICK_INTERVAL};
+use crate::transport::Transport;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cell::RefCell;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+use std::io;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// O relógio de parede dos nós no instante zero da simulação.
+const EPOCH_MS: u64 = 1_700_000_000_000;
+
+/// Como a rede simulada se comporta.
+#[derive(Debug, Clone)]
+pub struct Network {
+    /// Atraso de entrega, sorteado entre o mínimo e o máximo para cada
+    /// datagrama; com atrasos diferentes, eles chegam fora de ordem.
+    pub min_latency: Duration,
+    pub max_latency: Duration,
+    /// Probabilidade de um datagrama se perder.
+    pub drop_rate: f64,
+    /// Probabilidade de um datagrama chegar duas vezes.
+    pub duplicate_rate: f64,
+}
+
+impl Default for Network {
+    fn default() -> Self {
+        Network {
+            min_latency: Duration::from_millis(1),
+            max_latency: Duration::from_millis(20),
+            drop_rate: 0.0,
+            duplicate_rate: 0.0,
+        }
+    }
+}
+
+/// Os datagramas que um nó envia enquanto trata um evento.
+#[derive(Default)]
+struct Outbox(RefCell<Vec<(SocketAddr, Vec<u8>)>>);
+
+impl Transport for Outbox {
+    fn send(&self, to: SocketAddr, bytes: &[u8]) -> io::Result<()> {
+        self.0.borrow_mut().push((to, bytes.to_vec()));
+        Ok(())
+    }
+}
+
+enum Event {
+    Deliver {
+        from: SocketAddr,
+        to: SocketAddr,
+        bytes: Vec<u8>,
+    },
+    Tick(SocketAddr),
+    Gossip(SocketAddr),
+}
+
+/// Um evento na fila; empates no tempo saem na ordem em que entraram.
+struct Scheduled {
+    at: Duration,
+    seq: u64,
+    event: Event,
+}
+
+impl PartialEq for Scheduled {
+    fn eq(&self, other: &Self) -> bool {
+        (self.at, self.seq) == (other.at, other.seq)
+    }
+}
+
+impl Eq for Scheduled {}
+
+impl PartialOrd for Scheduled {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Scheduled {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.at, self.seq).cmp(&(other.at, other.seq))
+    }
+}
+
+struct SimNode {
+    protocol: Protocol,
+    up: bool,
+}
+
+pub struct Simulation {
+    rng: StdRng,
+    network: Network,
+    /// O instante zero do relógio virtual.
+    start: Instant,
+    elapsed: Duration,
+    nodes: BTreeMap<SocketAddr, SimNode>,
+    queue: BinaryHeap<Reverse<Scheduled>>,
+    next_seq: u64,
+    /// O grupo de cada nó durante uma partição: só nós do mesmo grupo se
+    /// falam. Quem não está em nenhum fica no grupo 0.
+    groups: HashMap<SocketAddr, usize>,
+    pub delivered: u64,
+    pub dropped: u64,
+}
+
+impl Simulation {
+    pub fn new(seed: u64, network: Network) -> Self {
+        Simulation {
+            rng: StdRng::seed_from_u64(seed),
+            network,
+            start: Instant::now(),
+            elapsed: Duration::ZERO,
+            nodes: BTreeMap::new(),
+            queue: BinaryHeap::new(),
+            next_seq: 0,
+            groups: HashMap::new(),
+            delivered: 0,
+            dropped: 0,
+        }
+    }
+
+    fn instant(&self) -> Instant {
+        self.start + self.elapsed
+    }
+
+    /// Cria um nó que conhece as sementes e devolve o seu endereço.
+    pub fn add_node(&mut self, swim: &SwimConfig, seeds: &[SocketAddr]) -> SocketAddr {
+        let index = self.nodes.len();
+        let addr = SocketAddr::from(([10, 0, (index / 250) as u8, (index % 250 + 1) as u8], 7000));
+        let config = Config {
+            id: Uuid::from_u128(self.rng.r#gen()),
+            addr,
+            max_datagram: DEFAULT_MAX_DATAGRAM,
+            swim: SwimConfig {
+                seed: Some(self.rng.r#gen()),
+                ..swim.clone()
+            },
+            guard: None,
+            verbose: false,
+        };
+        let now = self.instant();
+        let mut protocol = Protocol::new(config, now, EPOCH_MS + self.elapsed.as_millis() as u64);
+        protocol.join(seeds, now);
+        self.nodes.insert(addr, SimNode { protocol, up: true });
+
+        // fases sorteadas, para os nós não agirem todos no mesmo instante
+        let tick = self.rng.gen_range(Duration::ZERO..TICK_INTERVAL);
+        let gossip = self.rng.gen_range(Duration::ZERO..GOSSIP_INTERVAL);
+        self.schedule(self.elapsed + tick, Event::Tick(addr));
+        self.schedule(self.elapsed + gossip, Event::Gossip(addr));
+        addr
+    }
+
+    /// Os nós que não foram derrubados.
+    pub fn live(&self) -> Vec<SocketAddr> {
+        self.nodes
+            .iter()
+            .filter(|(_, node)| node.up)
+            .map(|(addr, _)| *addr)
+            .collect()
+    }
+
+    pub fn node(&self, addr: SocketAddr) -> &Protocol {
+        &self.nodes[&addr].protocol
+    }
+
+    /// Uma alteração local em um nó.
+    pub fn apply(&mut self, addr: SocketAddr, key: &str, op: Op) -> Result<Crdt, CrdtError> {
+        let now = self.instant();
+        self.nodes
+            .get_mut(&addr)
+            .expect("nó desconhecido")
+            .protocol
+            .apply(key, op, now)
+    }
+
+    /// O nó para de vez: não recebe, não envia, não sonda.
+    pub fn crash(&mut self, addr: SocketAddr) {
+        if let Some(node) = self.nodes.get_mut(&addr) {
+            node.up = false;
+        }
+    }
+
+    /// Separa a rede: cada grupo só fala com ele mesmo, e os nós fora dos
+    /// grupos só falam entre si. Os datagramas a caminho entre grupos se perdem.
+    pub fn partition(&mut self, groups: &[Vec<SocketAddr>]) {
+        self.groups.clear();
+        for (i, group) in groups.iter().enumerate() {
+            for addr in group {
+                self.groups.insert(*addr, i + 1);
+            }
+        }
+    }
+
+    pub fn heal(&mut self) {
+        self.groups.clear();
+    }
+
+    /// Avança o relógio virtual, tratando os eventos do caminho.
+    pub fn run_for(&mut self, duration: Duration) {
+        let end = self.elapsed + duration;
+        while self
+            .queue
+            .peek()
+            .is_some_and(|Reverse(next)| next.at <= end)
+        {
+            let Reverse(next) = self.queue.pop().unwrap();
+            self.elapsed = next.at;
+            self.dispatch(next.event);
+        }
+        self.elapsed = end;
+    }
+
+    /// Roda até a condição valer, conferindo a cada 100 ms simulados.
+    /// Devolve quanto tempo levou, ou None se passou do limite.
+    pub fn run_until(
+        &mut self,
+        limit: Duration,
+        done: impl Fn(&Simulation) -> bool,
+    ) -> Option<Duration> {
+        let started = self.elapsed;
+        loop {
+            if done(self) {
+                return Some(self.elapsed - started);
+            }
+            if self.elapsed - started >= limit {
+                return None;
+            }
+            self.run_for(Duration::from_millis(100));
+        }
+    }
+
+    fn schedule(&mut self, at: Duration, event: Event) {
+        self.next_seq += 1;
+        self.queue.push(Reverse(Scheduled {
+            at,
+            seq: self.next_seq,
+            event,
+        }));
+    }
+
+    fn dispatch(&mut self, event: Event) {
+        let now = self.instant();
+        let outbox = Outbox::default();
+        let sender = match event {
+            Event::Deliver { from, to, bytes } => {
+                let reachable = self.groups.get(&from) == self.groups.get(&to);
+                match self.nodes.get_mut(&to) {
+                    Some(node) if node.up && reachable => {
+                        self.delivered += 1;
+                        node.protocol.receive(from, &bytes, now, &outbox);
+                    }
+                    _ => self.dropped += 1,
+                }
+                to
+            }
+            Event::Tick(addr) => {
+                self.schedule(self.elapsed + TICK_INTERVAL, Event::Tick(addr));
+                let node = self.nodes.get_mut(&addr).unwrap();
+                if node.up {
+                    node.protocol.tick(now, &outbox);
+                }
+                addr
+            }
+            Event::Gossip(addr) => {
+                self.schedule(self.elapsed + GOSSIP_INTERVAL, Event::Gossip(addr));
+                let node = self.nodes.get_mut(&addr).unwrap();
+                if node.up {
+                    node.protocol.gossip(now, &outbox);
+                }
+                addr
+            }
+        };
+        for (to, bytes) in outbox.0.into_inner() {
+            self.send(sender, to, bytes);
+        }
+    }
+
+    /// Põe o datagrama na rede: talvez se perca, talvez chegue duas vezes,
+    /// cada cópia com o seu atraso.
+    fn send(&mut self, from: SocketAddr, to: SocketAddr, bytes: Vec<u8>) {
+        if self.rng.gen_bool(self.network.drop_rate) {
+            self.dropped += 1;
+            return;
+        }
+        let copies = if self.rng.gen_bool(self.network.duplicate_rate) {
+            2
+        } else {
+            1
+        };
+        for _ in 0..copies {
+            let delay = self
+                .rng
+                .gen_range(self.network.min_latency..=self.network.max_latency);
+            let event = Event::Deliver {
+                from,
+                to,
+                bytes: bytes.clone(),
+            };
+            self.schedule(self.elapsed + delay, event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::antientropy;
+
+    fn lossy() -> Network {
+        Network {
+            min_latency: Duration::from_millis(1),
+            max_latency: Duration::from_millis(80),
+            drop_rate: 0.05,
+            duplicate_rate: 0.02,
+        }
+    }
+
+    /// Um cluster em que cada nó novo conhece só um nó anterior qualquer.
+    fn cluster(sim: &mut Simulation, size: usize, swim: &SwimConfig) -> Vec<SocketAddr> {
+        let mut addrs = Vec::new();
+        for i in 0..size {
+            let seeds = if i == 0 {
+                vec![]
+            } else {
+                vec![addrs[sim.rng.gen_range(0..i)]]
+            };
+            addrs.push(sim.add_node(swim, &seeds));
+        }
+        addrs
+    }
+
+    /// Cada nó de `nodes` conhece todos os outros e não dá nenhum deles
+    /// por morto, e dá por mortos todos os de `dead`. Com perdas na rede,
+    /// um nó vivo pode ficar suspeito por um momento, até se defender.
+    fn views_agree(sim: &Simulation, nodes: &[SocketAddr], dead: &[SocketAddr]) -> bool {
+        nodes.iter().all(|me| {
+            let membership = sim.node(*me).membership();
+            let alive = nodes.iter().filter(|other| *other != me).all(|other| {
+                matches!(
+                    membership.state_of(*other),
+                    Some(MemberState::Alive | MemberState::Suspect)
+                )
+            });
+            alive
+                && dead
+                    .iter()
+                    .all(|other| membership.state_of(*other) == Some(MemberState::Dead))
+        })
+    }
+
+    fn converged(sim: &Simulation, nodes: &[SocketAddr]) -> bool {
+        let first = antientropy::digest(sim.node(nodes[0]).state());
+        nodes
+            .iter()
+            .all(|addr| antientropy::digest(sim.node(*addr).state()) == first)
+    }
+
+    fn value(sim: &Simulation, addr: SocketAddr, key: &str) -> String {
+        sim.node(addr).state()[key].to_string()
+    }
+
+    #[test]
+    fn a_hundred_nodes_converge_despite_loss_and_reordering() {
+        let mut sim = Simulation::new(7, lossy());
+        let swim = SwimConfig::default();
+        let nodes = cluster(&mut sim, 100, &swim);
+
+        let joined = sim
+            .run_until(Duration::from_secs(120), |sim| {
+                views_agree(sim, &nodes, &[])
+            })
+            .expect("os nós não se descobriram");
+        assert!(
+            joined <= Duration::from_secs(60),
+            "descoberta em {:?}",
+            joined
+        );
+
+        // escritas concorrentes em nós diferentes
+        for addr in &nodes[..20] {
+            sim.apply(*addr, "visitas", Op::Increment { by: 1 })
+                .unwrap();
+        }
+        for (i, addr) in nodes[20..25].iter().enumerate() {
+            let op = Op::Set {
+                value: format!("v{}", i),
+            };
+            sim.apply(*addr, &format!("chave-{}", i), op).unwrap();
+        }
+        sim.apply(
+            nodes[50],
+            "cor",
+            Op::MvSet {
+                value: "azul".into(),
+            },
+        )
+        .unwrap();
+        sim.apply(
+            nodes[99],
+            "cor",
+            Op::MvSet {
+                value: "verde".into(),
+            },
+        )
+        .unwrap();
+
+        let spread = sim
+            .run_until(Duration::from_secs(60), |sim| converged(sim, &nodes))
+            .expect("o estado não convergiu");
+        assert!(
+            spread <= Duration::from_secs(20),
+            "convergência em {:?}",
+            spread
+        );
+        for addr in &nodes {
+            assert_eq!(value(&sim, *addr, "visitas"), "20");
+            assert_eq!(value(&sim, *addr, "cor"), "'azul' | 'verde'");
+            assert_eq!(value(&sim, *addr, "chave-4"), "'v4'");
+        }
+        assert!(sim.dropped > 0);
+    }
+
+    #[test]
+    fn failed_nodes_are_declared_dead_everywhere() {
+        let mut sim = Simulation::new(11, lossy());
+        let swim = SwimConfig::default();
+        let nodes = cluster(&mut sim, 100, &swim);
+        sim.run_until(Duration::from_secs(120), |sim| {
+            views_agree(sim, &nodes, &[])
+        })
+        .expect("os nós não se descobriram");
+
+        let crashed: Vec<SocketAddr> = nodes.iter().step_by(20).copied().collect();
+        for addr in &crashed {
+            sim.crash(*addr);
+        }
+        let live = sim.live();
+        assert_eq!(live.len(), 95);
+
+        // todos os vivos veem os derrubados mortos e nenhum outro nó morto
+        let detected = sim
+            .run_until(Duration::from_secs(60), |sim| {
+                views_agree(sim, &live, &crashed)
+            })
+            .expect("as falhas não foram detectadas");
+        assert!(
+            detected <= swim.suspicion_timeout + Duration::from_secs(10),
+            "detecção em {:?}",
+            detected
+        );
+
+        // e continua assim: perdas na rede não derrubam nós vivos
+        sim.run_for(Duration::from_secs(20));
+        assert!(views_agree(&sim, &live, &crashed));
+    }
+
+    #[test]
+    fn partitioned_sides_merge_after_healing() {
+        let mut sim = Simulation::new(3, lossy());
+        // a partição dura menos que o prazo de suspeita: os dois lados
+        // suspeitam um do outro, mas não se declaram mortos
+        let swim = SwimConfig {
+            suspicion_timeout: Duration::from_secs(30),
+            ..SwimConfig::default()
+        };
+        let nodes = cluster(&mut sim, 40, &swim);
+        sim.run_until(Duration::from_secs(120), |sim| {
+            views_agree(sim, &nodes, &[])
+        })
+        .expect("os nós não se descobriram");
+
+        let (left, right) = nodes.split_at(20);
+        sim.partition(&[left.to_vec(), right.to_vec()]);
+        sim.apply(
+            left[0],
+            "cor",
+            Op::MvSet {
+                value: "azul".into(),
+            },
+        )
+        .unwrap();
+        sim.apply(
+            right[0],
+            "cor",
+            Op::MvSet {
+                value: "verde".into(),
+            },
+        )
+        .unwrap();
+        sim.apply(
+            left[1],
+            "lado",
+            Op::Add {
+                element: "esquerdo".into(),
+            },
+        )
+        .unwrap();
+        sim.apply(
+            right[1],
+            "lado",
+            Op::Add {
+                element: "direito".into(),
+            },
+        )
+        .unwrap();
+
+        // cada lado converge sozinho
+        sim.run_until(Duration::from_secs(20), |sim| {
+            converged(sim, left) && converged(sim, right)
+        })
+        .expect("os lados não convergiram");
+        assert_eq!(value(&sim, left[5], "cor"), "'azul'");
+        assert_eq!(value(&sim, right[5], "cor"), "'verde'");
+
+        sim.heal();
+        sim.run_until(Duration::from_secs(30), |sim| {
+            converged(sim, &nodes) && views_agree(sim, &nodes, &[])
+        })
+        .expect("o cluster não se juntou depois da partição");
+        for addr in &nodes {
+            assert_eq!(value(&sim, *addr, "cor"), "'azul' | 'verde'");
+            assert_eq!(value(&sim, *addr, "lado"), "{'direito', 'esquerdo'}");
+        }
+    }
+
+    #[test]
+    fn the_same_seed_repeats_the_same_run() {
+        let run = |seed| {
+            let mut sim = Simulation::new(seed, lossy());
+            let nodes = cluster(&mut sim, 20, &SwimConfig::default());
+            for (i, addr) in nodes.iter().enumerate().step_by(3) {
+                sim.apply(*addr, &format!("k{}", i), Op::Increment { by: 2 })
+                    .unwrap();
+            }
+            let time = sim.run_until(Duration::from_secs(60), |sim| converged(sim, &nodes));
+            let bytes: Vec<u64> = nodes
+                .iter()
+                .map(|addr| sim.node(*addr).metrics().current.bytes_sent)
+                .collect();
+            (time, bytes, sim.delivered, sim.dropped)
+        };
+        assert_eq!(run(42), run(42));
+        assert_ne!(run(42), run(43));
+    }
+}
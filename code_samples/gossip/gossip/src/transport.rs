@@ -0,0 +1,22 @@
+//! Por onde os datagramas do protocolo saem. O protocolo não conhece o
+//! socket: recebe os datagramas que chegam e entrega os que saem a um
+//! `Transport`, que no nó de verdade é o socket UDP e, nos testes, a rede
+//! simulada.
+
+use std::io;
+use std::net::SocketAddr;
+use tokio::net::UdpSocket;
+
+pub trait Transport {
+    /// Envia um datagrama. Como no UDP, a entrega não é garantida; o erro é
+    /// só o do envio local.
+    fn send(&self, to: SocketAddr, bytes: &[u8]) -> io::Result<()>;
+}
+
+impl Transport for UdpSocket {
+    /// Envia sem esperar: se o buffer do socket estiver cheio, o datagrama
+    /// é perdido, como seria na rede.
+    fn send(&self, to: SocketAddr, bytes: &[u8]) -> io::Result<()> {
+        self.try_send_to(bytes, to).map(|_| ())
+    }
+}
@@ -0,0 +1,117 @@
+//! Rotas `/diff`, `/merge` e `/merge3`: comparam e juntam documentos
+//! enviados no corpo, sem alterar o JSON guardado pela API.
+
+use crate::diff::{self, ArrayDiff};
+use crate::merge::{self, merge_patch};
+use crate::pretty_print;
+use axum::{
+    extract::Json,
+    http::{header::CONTENT_TYPE, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+/// Formato da resposta de um diff
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffFormat {
+    /// Um JSON Patch (RFC 6902) que leva `from` a `to`
+    #[default]
+    Patch,
+    /// Texto, uma linha por mudança
+    Report,
+}
+
+/// Opções de um diff
+#[derive(Deserialize)]
+pub struct DiffOptions {
+    /// Campo que identifica os itens dos arrays (como `id`); sem ele, os
+    /// arrays são comparados por posição
+    pub array_key: Option<String>,
+    #[serde(default)]
+    pub format: DiffFormat,
+}
+
+/// O diff de `from` para `to` no formato pedido
+pub fn diff_response(from: &Value, to: &Value, options: DiffOptions) -> Response {
+    let arrays = match options.array_key {
+        Some(key) => ArrayDiff::ByKey(key),
+        None => ArrayDiff::ByIndex,
+    };
+    let changes = diff::diff(from, to, &arrays);
+    match options.format {
+        DiffFormat::Patch => (
+            StatusCode::OK,
+            pretty_print(&json!(diff::to_patch(&changes))),
+        )
+            .into_response(),
+        DiffFormat::Report => (
+            StatusCode::OK,
+            [(CONTENT_TYPE, "text/plain; charset=utf-8")],
+            diff::report(&changes),
+        )
+            .into_response(),
+    }
+}
+
+/// Verdadeiro se o corpo é um JSON Merge Patch (RFC 7396), e não um JSON
+/// Patch, pelo `Content-Type: application/merge-patch+json`
+pub fn is_merge_patch(headers: &HeaderMap) -> bool {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/merge-patch+json"))
+}
+
+#[derive(Deserialize)]
+pub struct DiffPayload {
+    from: Value,
+    to: Value,
+    #[serde(flatten)]
+    options: DiffOptions,
+}
+
+/// `{"from": ..., "to": ..., "array_key": "id", "format": "report"}`
+pub async fn diff_handler(Json(payload): Json<DiffPayload>) -> Response {
+    diff_response(&payload.from, &payload.to, payload.options)
+}
+
+#[derive(Deserialize)]
+pub struct MergePayload {
+    target: Value,
+    patch: Value,
+}
+
+/// Aplica o merge patch `patch` a `target` e devolve o resultado
+pub async fn merge_handler(Json(payload): Json<MergePayload>) -> impl IntoResponse {
+    let mut target = payload.target;
+    merge_patch(&mut target, &payload.patch);
+    (StatusCode::OK, pretty_print(&target))
+}
+
+#[derive(Deserialize)]
+pub struct Merge3Payload {
+    base: Value,
+    ours: Value,
+    theirs: Value,
+}
+
+/// Merge de três vias. Sem conflitos, responde 200 com `{"merged": ...}`;
+/// com conflitos, 409 com `merged` (ficando com `ours` nos pontos em
+/// conflito) e a lista `conflicts`
+pub async fn merge3_handler(Json(payload): Json<Merge3Payload>) -> impl IntoResponse {
+    let result = merge::merge3(&payload.base, &payload.ours, &payload.theirs);
+    if result.conflicts.is_empty() {
+        return (
+            StatusCode::OK,
+            pretty_print(&json!({ "merged": result.merged })),
+        );
+    }
+    let body = json!({
+        "error": format!("{} conflito(s)", result.conflicts.len()),
+        "merged": result.merged,
+        "conflicts": result.conflicts,
+    });
+    (StatusCode::CONFLICT, pretty_print(&body))
+}
@@ -0,0 +1,301 @@
+//! Diferença estrutural entre dois documentos JSON. O resultado é uma lista
+//! de mudanças que vira um JSON Patch (RFC 6902) ou um relatório legível.
+//!
+//! Arrays são comparados posição a posição ou, com `ArrayDiff::ByKey`,
+//! casando os objetos pelo valor de um campo (como `id`): assim um item
+//! inserido no meio aparece como um `add`, e não como uma troca de todos os
+//! itens seguintes.
+
+use crate::jsonpath::json_equal;
+use crate::patch::Operation;
+use crate::pointer::child;
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+
+/// Como comparar arrays
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum ArrayDiff {
+    /// O item `i` de um é comparado com o item `i` do outro
+    #[default]
+    ByIndex,
+    /// Os itens são objetos identificados pelo campo indicado. Se algum item
+    /// não tiver o campo, ou se houver valores repetidos, o array é
+    /// comparado por posição
+    ByKey(String),
+}
+
+/// Uma mudança, com o JSON Pointer de onde ela acontece. Os caminhos valem
+/// para o documento como ele está depois das mudanças anteriores da lista,
+/// como em um JSON Patch
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Added {
+        path: String,
+        value: Value,
+    },
+    Removed {
+        path: String,
+        old: Value,
+    },
+    Changed {
+        path: String,
+        old: Value,
+        new: Value,
+    },
+    /// Um item de array (`ByKey`) que mudou de posição
+    Moved {
+        from: String,
+        path: String,
+    },
+}
+
+/// As mudanças que levam `old` a `new`
+pub fn diff(old: &Value, new: &Value, arrays: &ArrayDiff) -> Vec<Change> {
+    let mut changes = Vec::new();
+    diff_at("", old, new, arrays, &mut changes);
+    changes
+}
+
+fn diff_at(path: &str, old: &Value, new: &Value, arrays: &ArrayDiff, out: &mut Vec<Change>) {
+    if json_equal(old, new) {
+        return;
+    }
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => diff_objects(path, old, new, arrays, out),
+        (Value::Array(old), Value::Array(new)) => match arrays {
+            ArrayDiff::ByKey(key) if keyed(old, key) && keyed(new, key) => {
+                diff_by_key(path, old, new, key, arrays, out)
+            }
+            _ => diff_by_index(path, old, new, arrays, out),
+        },
+        _ => out.push(Change::Changed {
+            path: path.to_string(),
+            old: old.clone(),
+            new: new.clone(),
+        }),
+    }
+}
+
+fn diff_objects(
+    path: &str,
+    old: &Map<String, Value>,
+    new: &Map<String, Value>,
+    arrays: &ArrayDiff,
+    out: &mut Vec<Change>,
+) {
+    for (key, old_value) in old {
+        match new.get(key) {
+            Some(new_value) => diff_at(&child(path, key), old_value, new_value, arrays, out),
+            None => out.push(Change::Removed {
+                path: child(path, key),
+                old: old_value.clone(),
+            }),
+        }
+    }
+    for (key, new_value) in new {
+        if !old.contains_key(key) {
+            out.push(Change::Added {
+                path: child(path, key),
+                value: new_value.clone(),
+            });
+        }
+    }
+}
+
+fn diff_by_index(
+    path: &str,
+    old: &[Value],
+    new: &[Value],
+    arrays: &ArrayDiff,
+    out: &mut Vec<Change>,
+) {
+    let common = old.len().min(new.len());
+    for i in 0..common {
+        diff_at(&child(path, &i.to_string()), &old[i], &new[i], arrays, out);
+    }
+    // remove do fim para o começo, para os índices continuarem valendo
+    for i in (common..old.len()).rev() {
+        out.push(Change::Removed {
+            path: child(path, &i.to_string()),
+            old: old[i].clone(),
+        });
+    }
+    for (i, value) in new.iter().enumerate().skip(common) {
+        out.push(Change::Added {
+            path: child(path, &i.to_string()),
+            value: value.clone(),
+        });
+    }
+}
+
+/// Identidade de um item: o texto JSON do campo-chave
+fn identity(item: &Value, key: &str) -> String {
+    item[key].to_string()
+}
+
+/// Verdadeiro se todos os itens são objetos com o campo, sem repetição
+fn keyed(items: &[Value], key: &str) -> bool {
+    let mut seen = HashSet::new();
+    items
+        .iter()
+        .all(|item| item.get(key).is_some() && seen.insert(identity(item, key)))
+}
+
+fn diff_by_key(
+    path: &str,
+    old: &[Value],
+    new: &[Value],
+    key: &str,
+    arrays: &ArrayDiff,
+    out: &mut Vec<Change>,
+) {
+    let wanted: HashSet<String> = new.iter().map(|item| identity(item, key)).collect();
+
+    // 1. itens que sumiram, do fim para o começo
+    let mut current: Vec<&Value> = old.iter().collect();
+    for i in (0..old.len()).rev() {
+        if !wanted.contains(&identity(&old[i], key)) {
+            out.push(Change::Removed {
+                path: child(path, &i.to_string()),
+                old: old[i].clone(),
+            });
+            current.remove(i);
+        }
+    }
+
+    // 2. na ordem nova: as posições antes de `i` já estão certas, então um
+    // item que existia está em `i` ou depois
+    for (i, target) in new.iter().enumerate() {
+        let item_path = child(path, &i.to_string());
+        let id = identity(target, key);
+        match current.iter().position(|item| identity(item, key) == id) {
+            Some(j) => {
+                if j != i {
+                    out.push(Change::Moved {
+                        from: child(path, &j.to_string()),
+                        path: item_path.clone(),
+                    });
+                    let item = current.remove(j);
+                    current.insert(i, item);
+                }
+                diff_at(&item_path, current[i], target, arrays, out);
+            }
+            None => {
+                out.push(Change::Added {
+                    path: item_path,
+                    value: target.clone(),
+                });
+                current.insert(i, target);
+            }
+        }
+    }
+}
+
+/// As mudanças como operações de JSON Patch
+pub fn to_patch(changes: &[Change]) -> Vec<Operation> {
+    changes
+        .iter()
+        .map(|change| match change.clone() {
+            Change::Added { path, value } => Operation::Add { path, value },
+            Change::Removed { path, .. } => Operation::Remove { path },
+            Change::Changed { path, new, .. } => Operation::Replace { path, value: new },
+            Change::Moved { from, path } => Operation::Move { from, path },
+        })
+        .collect()
+}
+
+/// Uma linha por mudança:
+///
+/// ```text
+/// ~ /idade: 30 -> 31
+/// + /hobbies/1: "leitura"
+/// - /endereco/rua: "Rua A"
+/// > /itens/2 -> /itens/0
+/// ```
+pub fn report(changes: &[Change]) -> String {
+    if changes.is_empty() {
+        return "sem diferenças\n".to_string();
+    }
+    let mut text = String::new();
+    for change in changes {
+        let line = match change {
+            Change::Added { path, value } => format!("+ {}: {}", display(path), value),
+            Change::Removed { path, old } => format!("- {}: {}", display(path), old),
+            Change::Changed { path, old, new } => {
+                format!("~ {}: {} -> {}", display(path), old, new)
+            }
+            Change::Moved { from, path } => format!("> {} -> {}", display(from), display(path)),
+        };
+        text.push_str(&line);
+        text.push('\n');
+    }
+    text
+}
+
+/// O ponteiro vazio é a raiz, que ficaria invisível no relatório
+fn display(path: &str) -> &str {
+    if path.is_empty() {
+        "(raiz)"
+    } else {
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patch;
+    use serde_json::json;
+
+    /// O patch gerado leva mesmo `old` a `new`
+    fn assert_roundtrip(old: &Value, new: &Value, arrays: &ArrayDiff) -> Vec<Change> {
+        let changes = diff(old, new, arrays);
+        let mut doc = old.clone();
+        patch::apply(&mut doc, &to_patch(&changes)).unwrap();
+        assert_eq!(&doc, new);
+        changes
+    }
+
+    #[test]
+    fn objects_and_arrays_by_index() {
+        let old = json!({"nome": "João", "idade": 30, "a/b": 1, "hobbies": ["x", "y", "z"]});
+        let new = json!({"nome": "João", "idade": 31.0, "a/b": 2, "hobbies": ["x"], "ativo": true});
+        let changes = assert_roundtrip(&old, &new, &ArrayDiff::ByIndex);
+        assert_eq!(
+            report(&changes),
+            "~ /a~1b: 1 -> 2\n- /hobbies/2: \"z\"\n- /hobbies/1: \"y\"\n~ /idade: 30 -> 31.0\n+ /ativo: true\n"
+        );
+        assert_eq!(
+            report(&diff(&json!(1), &json!(1.0), &ArrayDiff::ByIndex)),
+            "sem diferenças\n"
+        );
+        assert_roundtrip(
+            &json!([1, 2]),
+            &json!([1, 2, {"c": 3}]),
+            &ArrayDiff::ByIndex,
+        );
+        assert_roundtrip(&json!({"a": 1}), &json!([1]), &ArrayDiff::ByIndex);
+    }
+
+    #[test]
+    fn arrays_by_key() {
+        let by_id = ArrayDiff::ByKey("id".into());
+        let old = json!([{"id": 1, "v": "a"}, {"id": 2, "v": "b"}, {"id": 3, "v": "c"}]);
+        let new = json!([{"id": 4, "v": "d"}, {"id": 3, "v": "c"}, {"id": 1, "v": "A"}]);
+        let changes = assert_roundtrip(&old, &new, &by_id);
+        assert_eq!(
+            report(&changes),
+            "- /1: {\"id\":2,\"v\":\"b\"}\n+ /0: {\"id\":4,\"v\":\"d\"}\n> /2 -> /1\n~ /2/v: \"a\" -> \"A\"\n"
+        );
+
+        // um item inserido no começo não muda os outros
+        let old = json!({"itens": [{"id": "a"}, {"id": "b"}]});
+        let new = json!({"itens": [{"id": "z"}, {"id": "a"}, {"id": "b"}]});
+        assert_eq!(assert_roundtrip(&old, &new, &by_id).len(), 1);
+
+        // chave repetida: compara por posição
+        let old = json!([{"id": 1}, {"id": 1}]);
+        let new = json!([{"id": 1, "x": 0}]);
+        assert_roundtrip(&old, &new, &by_id);
+    }
+}
@@ -0,0 +1,423 @@
+//! Rotas `/docs/:id`: documentos com nome, gravados em disco pelo
+//! `DocumentStore`. Cada resposta traz o cabeçalho `ETag` com a versão do
+//! documento; quem envia `If-Match` com essa ETag só altera o documento se
+//! ninguém o alterou antes (senão recebe 412 e a versão atual).
+
+use crate::compare::{diff_response, is_merge_patch, DiffFormat, DiffOptions};
+use crate::store::{DocumentStore, Revision, StoreError};
+use crate::{
+    error_response, merge, parse_operations, patch, patch_error_response, pretty_print,
+    query_response, violations_response, AppState, GetQuery,
+};
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::{
+        header::{ETAG, IF_MATCH, IF_NONE_MATCH},
+        HeaderMap, StatusCode,
+    },
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::convert::Infallible;
+
+/// ETag forte a partir da versão: `"3"`
+fn etag(version: u64) -> String {
+    format!("\"{}\"", version)
+}
+
+/// Verdadeiro se o cabeçalho (`*` ou uma lista de ETags) aceita a versão
+fn header_matches(headers: &HeaderMap, name: &str, version: Option<u64>) -> bool {
+    let Some(value) = headers.get(name).and_then(|v| v.to_str().ok()) else {
+        return false;
+    };
+    let Some(version) = version else {
+        return false;
+    };
+    value
+        .split(',')
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag == etag(version))
+}
+
+/// Confere as pré-condições de uma alteração e devolve a resposta 412 se
+/// alguma falhar. Sem `If-Match`, a alteração é aceita; `If-None-Match: *`
+/// só aceita criar um documento novo
+fn precondition_failed(headers: &HeaderMap, current: Option<u64>) -> Option<Response> {
+    let fails_if_match =
+        headers.contains_key(IF_MATCH) && !header_matches(headers, IF_MATCH.as_str(), current);
+    let fails_if_none_match = header_matches(headers, IF_NONE_MATCH.as_str(), current);
+    if !fails_if_match && !fails_if_none_match {
+        return None;
+    }
+    let body = json!({
+        "error": "o documento foi alterado (ou criado) por outra requisição",
+        "current_version": current,
+    });
+    let mut response = (StatusCode::PRECONDITION_FAILED, pretty_print(&body)).into_response();
+    if let Some(version) = current {
+        response
+            .headers_mut()
+            .insert(ETAG, etag(version).parse().unwrap());
+    }
+    Some(response)
+}
+
+fn store_error_response(e: StoreError) -> Response {
+    let status = match e {
+        StoreError::SchemaViolation(violations) => {
+            return violations_response(&violations).into_response()
+        }
+        StoreError::InvalidId(_) | StoreError::InvalidSchema(_) => StatusCode::BAD_REQUEST,
+        StoreError::NotFound(_) | StoreError::RevisionNotFound(_) => StatusCode::NOT_FOUND,
+        StoreError::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    error_response(status, e).into_response()
+}
+
+/// Roda a alteração do store em uma thread de bloqueio: a gravação faz
+/// fsync e rename, e não deve parar o runtime enquanto segura o lock
+async fn write_store<F>(state: AppState, write: F) -> Response
+where
+    F: FnOnce(&mut DocumentStore) -> Response + Send + 'static,
+{
+    tokio::task::spawn_blocking(move || write(&mut state.store.lock().unwrap()))
+        .await
+        .unwrap_or_else(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, e).into_response())
+}
+
+/// O conteúdo da revisão, com a ETag
+fn revision_response(status: StatusCode, revision: &Revision) -> Response {
+    (
+        status,
+        [(ETAG, etag(revision.version))],
+        pretty_print(&revision.data),
+    )
+        .into_response()
+}
+
+/// Lista os documentos: `[{"id": "...", "version": 3}, ...]`
+pub async fn list_docs(State(state): State<AppState>) -> impl IntoResponse {
+    let store = state.store.lock().unwrap();
+    let docs: Vec<Value> = store
+        .list()
+        .into_iter()
+        .map(|(id, version)| json!({ "id": id, "version": version }))
+        .collect();
+    (StatusCode::OK, pretty_print(&json!(docs)))
+}
+
+/// Consulta o documento, aceitando `pointer` e `path` como em `GET /json`.
+/// Com `If-None-Match` igual à versão atual, responde 304 sem o corpo
+pub async fn get_doc(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<GetQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let store = state.store.lock().unwrap();
+    let revision = match store.get(&id) {
+        Ok(revision) => revision,
+        Err(e) => return store_error_response(e),
+    };
+    let tag = [(ETAG, etag(revision.version))];
+    if header_matches(&headers, IF_NONE_MATCH.as_str(), Some(revision.version)) {
+        return (StatusCode::NOT_MODIFIED, tag).into_response();
+    }
+    (tag, query_response(&revision.data, query)).into_response()
+}
+
+/// Cria (201) ou substitui (200) o documento inteiro
+pub async fn put_doc(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(data): Json<Value>,
+) -> Response {
+    write_store(state, move |store| {
+        let current = store.version(&id);
+        if let Some(response) = precondition_failed(&headers, current) {
+            return response;
+        }
+        let status = if current.is_some() {
+            StatusCode::OK
+        } else {
+            StatusCode::CREATED
+        };
+        match store.put(&id, data) {
+            Ok(revision) => revision_response(status, revision),
+            Err(e) => store_error_response(e),
+        }
+    })
+    .await
+}
+
+/// Aplica um JSON Patch (RFC 6902) ao documento, gerando uma nova revisão.
+/// Com `Content-Type: application/merge-patch+json`, aplica um JSON Merge
+/// Patch (RFC 7396)
+pub async fn patch_doc(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(patch): Json<Value>,
+) -> Response {
+    if is_merge_patch(&headers) {
+        return write_store(state, move |store| {
+            let Some(current) = store.version(&id) else {
+                return store_error_response(StoreError::NotFound(id));
+            };
+            if let Some(response) = precondition_failed(&headers, Some(current)) {
+                return response;
+            }
+            let edit = |data: &mut Value| {
+                merge::merge_patch(data, &patch);
+                Ok::<(), Infallible>(())
+            };
+            match store.modify(&id, "merge-patch", edit) {
+                Ok(Ok(revision)) => revision_response(StatusCode::OK, revision),
+                Ok(Err(never)) => match never {},
+                Err(e) => store_error_response(e),
+            }
+        })
+        .await;
+    }
+    let operations = match parse_operations(patch) {
+        Ok(operations) => operations,
+        Err(response) => return response.into_response(),
+    };
+    write_store(state, move |store| {
+        let Some(current) = store.version(&id) else {
+            return store_error_response(StoreError::NotFound(id));
+        };
+        if let Some(response) = precondition_failed(&headers, Some(current)) {
+            return response;
+        }
+        match store.modify(&id, "patch", |data| patch::apply(data, &operations)) {
+            Ok(Ok(revision)) => revision_response(StatusCode::OK, revision),
+            Ok(Err(e)) => patch_error_response(&e, &operations).into_response(),
+            Err(e) => store_error_response(e),
+        }
+    })
+    .await
+}
+
+/// Apaga o documento e o seu histórico
+pub async fn delete_doc(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    write_store(state, move |store| {
+        let Some(current) = store.version(&id) else {
+            return store_error_response(StoreError::NotFound(id));
+        };
+        if let Some(response) = precondition_failed(&headers, Some(current)) {
+            return response;
+        }
+        match store.delete(&id) {
+            Ok(()) => StatusCode::NO_CONTENT.into_response(),
+            Err(e) => store_error_response(e),
+        }
+    })
+    .await
+}
+
+/// Lista as revisões guardadas, sem o conteúdo:
+/// `[{"version": 1, "timestamp": 1700000000, "change": "create"}, ...]`
+pub async fn get_history(State(state): State<AppState>, Path(id): Path<String>) -> Response {
+    let store = state.store.lock().unwrap();
+    match store.history(&id) {
+        Ok(revisions) => {
+            let revisions: Vec<Value> = revisions
+                .iter()
+                .map(|r| json!({ "version": r.version, "timestamp": r.timestamp, "change": r.change }))
+                .collect();
+            (StatusCode::OK, pretty_print(&json!(revisions))).into_response()
+        }
+        Err(e) => store_error_response(e),
+    }
+}
+
+/// Revisões comparadas por `GET /docs/:id/diff`
+#[derive(Deserialize)]
+pub struct DiffQuery {
+    from: u64,
+    /// Sem `to`, compara com a versão atual
+    to: Option<u64>,
+    // sem `flatten`: na query string ele impede a leitura dos números
+    array_key: Option<String>,
+    #[serde(default)]
+    format: DiffFormat,
+}
+
+/// O que mudou entre duas revisões:
+/// `?from=1&to=3&array_key=id&format=report`
+pub async fn diff_doc(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<DiffQuery>,
+) -> Response {
+    let store = state.store.lock().unwrap();
+    let to = match query.to {
+        Some(version) => store.revision(&id, version),
+        None => store.get(&id),
+    };
+    match (store.revision(&id, query.from), to) {
+        (Ok(from), Ok(to)) => {
+            let options = DiffOptions {
+                array_key: query.array_key,
+                format: query.format,
+            };
+            diff_response(&from.data, &to.data, options)
+        }
+        (Err(e), _) | (_, Err(e)) => store_error_response(e),
+    }
+}
+
+/// O conteúdo de uma revisão anterior
+pub async fn get_revision(
+    State(state): State<AppState>,
+    Path((id, version)): Path<(String, u64)>,
+) -> Response {
+    let store = state.store.lock().unwrap();
+    match store.revision(&id, version) {
+        Ok(revision) => (StatusCode::OK, pretty_print(&revision.data)).into_response(),
+        Err(e) => store_error_response(e),
+    }
+}
+
+/// Volta o documento ao conteúdo de uma revisão anterior, gerando uma nova
+/// revisão (o histórico não é reescrito)
+pub async fn revert_doc(
+    State(state): State<AppState>,
+    Path((id, version)): Path<(String, u64)>,
+    headers: HeaderMap,
+) -> Response {
+    write_store(state, move |store| {
+        let Some(current) = store.version(&id) else {
+            return store_error_response(StoreError::NotFound(id));
+        };
+        if let Some(response) = precondition_failed(&headers, Some(current)) {
+            return response;
+        }
+        match store.revert(&id, version) {
+            Ok(revision) => revision_response(StatusCode::OK, revision),
+            Err(e) => store_error_response(e),
+        }
+    })
+    .await
+}
+
+/// Anexa um JSON Schema ao documento; as próximas revisões precisam segui-lo
+pub async fn put_schema(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(schema): Json<Value>,
+) -> Response {
+    let response = (StatusCode::OK, pretty_print(&schema)).into_response();
+    write_store(state, move |store| {
+        match store.set_schema(&id, Some(schema)) {
+            Ok(()) => response,
+            Err(e) => store_error_response(e),
+        }
+    })
+    .await
+}
+
+pub async fn get_schema(State(state): State<AppState>, Path(id): Path<String>) -> Response {
+    let store = state.store.lock().unwrap();
+    match store.schema(&id) {
+        Ok(Some(schema)) => (StatusCode::OK, pretty_print(schema)).into_response(),
+        Ok(None) => error_response(StatusCode::NOT_FOUND, "nenhum schema anexado").into_response(),
+        Err(e) => store_error_response(e),
+    }
+}
+
+pub async fn delete_schema(State(state): State<AppState>, Path(id): Path<String>) -> Response {
+    write_store(state, move |store| match store.set_schema(&id, None) {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => store_error_response(e),
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_state;
+
+    fn headers(name: &str, value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            axum::http::HeaderName::from_bytes(name.as_bytes()).unwrap(),
+            value.parse().unwrap(),
+        );
+        headers
+    }
+
+    async fn put(state: &AppState, headers: HeaderMap, data: Value) -> Response {
+        put_doc(
+            State(state.clone()),
+            Path("doc".to_string()),
+            headers,
+            Json(data),
+        )
+        .await
+    }
+
+    fn version(state: &AppState) -> Option<u64> {
+        state.store.lock().unwrap().version("doc")
+    }
+
+    #[tokio::test]
+    async fn if_match_accepts_only_the_current_version() {
+        let state = test_state("docs_if_match");
+        // If-Match: * exige que o documento exista
+        let response = put(&state, headers("if-match", "*"), json!(0)).await;
+        assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+        assert_eq!(version(&state), None);
+
+        put(&state, HeaderMap::new(), json!(1)).await;
+        let response = put(&state, headers("if-match", "\"1\""), json!(2)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[ETAG], "\"2\"");
+
+        // ETag velha: 412 com a versão atual, e nada é gravado
+        let response = put(&state, headers("if-match", "\"1\""), json!(3)).await;
+        assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+        assert_eq!(response.headers()[ETAG], "\"2\"");
+        assert_eq!(version(&state), Some(2));
+        assert_eq!(
+            state.store.lock().unwrap().get("doc").unwrap().data,
+            json!(2)
+        );
+    }
+
+    #[tokio::test]
+    async fn if_none_match_guards_creation_and_revalidates_reads() {
+        let state = test_state("docs_if_none_match");
+        let response = put(&state, headers("if-none-match", "*"), json!(1)).await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        // já existe: quem queria criar recebe 412
+        let response = put(&state, headers("if-none-match", "*"), json!(2)).await;
+        assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+        assert_eq!(version(&state), Some(1));
+
+        let get = |headers| {
+            get_doc(
+                State(state.clone()),
+                Path("doc".to_string()),
+                Query(GetQuery {
+                    pointer: None,
+                    path: None,
+                }),
+                headers,
+            )
+        };
+        let response = get(headers("if-none-match", "\"1\"")).await;
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[ETAG], "\"1\"");
+        let response = get(headers("if-none-match", "\"0\"")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,220 @@
+//! Eventos das alterações do JSON compartilhado. Cada alteração gravada
+//! recebe um número de sequência e vira um `Event` com o nome da operação e
+//! o JSON Patch (RFC 6902) que leva o documento anterior ao novo. Os últimos
+//! eventos ficam guardados, para quem reconecta continuar de onde parou.
+
+use crate::diff::{diff, to_patch, ArrayDiff};
+use crate::patch::Operation;
+use crate::pointer::Pointer;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::VecDeque;
+use tokio::sync::broadcast;
+
+/// Quantos eventos ficam guardados para a retomada
+pub const DEFAULT_EVENT_BUFFER: usize = 1000;
+
+/// Uma alteração do documento
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Event {
+    /// Começa em 1 e cresce de um em um, a cada alteração
+    pub seq: u64,
+    /// O que fez a alteração: `set`, `update`, `patch`...
+    pub operation: String,
+    pub patch: Vec<Operation>,
+}
+
+impl Event {
+    /// O evento só com as operações que tocam o valor em `pointer`: dentro
+    /// dele, nele mesmo ou em um valor que o contém. None se nenhuma toca.
+    /// Operações em outros itens de um array não contam, mesmo que mudem a
+    /// posição do item
+    pub fn restrict(&self, pointer: &Pointer) -> Option<Event> {
+        let patch: Vec<Operation> = self
+            .patch
+            .iter()
+            .filter(|operation| touches(operation, pointer))
+            .cloned()
+            .collect();
+        if patch.is_empty() {
+            return None;
+        }
+        Some(Event {
+            seq: self.seq,
+            operation: self.operation.clone(),
+            patch,
+        })
+    }
+}
+
+fn touches(operation: &Operation, pointer: &Pointer) -> bool {
+    let paths = match operation {
+        Operation::Move { from, path } | Operation::Copy { from, path } => vec![from, path],
+        Operation::Add { path, .. }
+        | Operation::Remove { path }
+        | Operation::Replace { path, .. }
+        | Operation::Test { path, .. } => vec![path],
+    };
+    paths
+        .into_iter()
+        .filter_map(|path| Pointer::parse(path).ok())
+        .any(|path| path == *pointer || path.is_inside(pointer) || pointer.is_inside(&path))
+}
+
+/// O que um assinante recebe ao se inscrever
+pub struct Subscription {
+    /// Os eventos depois do `since` pedido. None sem `since`, ou se algum
+    /// dos eventos já não está guardado: o assinante precisa então do
+    /// documento inteiro
+    pub missed: Option<Vec<Event>>,
+    /// O número do último evento publicado até a inscrição
+    pub last_seq: u64,
+    /// Os eventos publicados depois da inscrição
+    pub receiver: broadcast::Receiver<Event>,
+}
+
+/// Os últimos eventos e o canal para quem acompanha as alterações
+pub struct EventLog {
+    last_seq: u64,
+    recent: VecDeque<Event>,
+    capacity: usize,
+    sender: broadcast::Sender<Event>,
+}
+
+impl EventLog {
+    /// Guarda os últimos `capacity` eventos. É também o quanto um assinante
+    /// lento pode se atrasar antes de perder eventos
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        EventLog {
+            last_seq: 0,
+            recent: VecDeque::new(),
+            capacity,
+            sender,
+        }
+    }
+
+    /// O número do último evento publicado (0 antes do primeiro)
+    pub fn last_seq(&self) -> u64 {
+        self.last_seq
+    }
+
+    /// Registra a alteração de `old` para `new` e devolve o número do
+    /// evento. Uma alteração que não muda nada não gera evento
+    pub fn publish(&mut self, operation: &str, old: &Value, new: &Value) -> Option<u64> {
+        let patch = to_patch(&diff(old, new, &ArrayDiff::ByIndex));
+        if patch.is_empty() {
+            return None;
+        }
+        self.last_seq += 1;
+        let event = Event {
+            seq: self.last_seq,
+            operation: operation.to_string(),
+            patch,
+        };
+        while !self.recent.is_empty() && self.recent.len() >= self.capacity {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(event.clone());
+        // sem assinantes o envio falha, e não há nada a fazer
+        let _ = self.sender.send(event);
+        Some(self.last_seq)
+    }
+
+    /// Inscreve um assinante que já recebeu até o evento `since`. Um
+    /// `since` maior que o último evento (de antes de o servidor reiniciar,
+    /// por exemplo) conta como eventos perdidos
+    pub fn subscribe(&self, since: Option<u64>) -> Subscription {
+        let first_kept = self.recent.front().map_or(self.last_seq + 1, |e| e.seq);
+        let missed = match since {
+            Some(since) if since <= self.last_seq && since + 1 >= first_kept => Some(
+                self.recent
+                    .iter()
+                    .filter(|e| e.seq > since)
+                    .cloned()
+                    .collect(),
+            ),
+            _ => None,
+        };
+        Subscription {
+            missed,
+            last_seq: self.last_seq,
+            receiver: self.sender.subscribe(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn pointer(text: &str) -> Pointer {
+        Pointer::parse(text).unwrap()
+    }
+
+    #[test]
+    fn publishes_numbered_patches() {
+        let mut log = EventLog::new(2);
+        let mut receiver = log.subscribe(None).receiver;
+        assert_eq!(log.publish("set", &json!({}), &json!({})), None);
+        assert_eq!(log.publish("set", &json!({}), &json!({"a": 1})), Some(1));
+        assert_eq!(
+            receiver.try_recv().unwrap(),
+            Event {
+                seq: 1,
+                operation: "set".into(),
+                patch: vec![Operation::Add {
+                    path: "/a".into(),
+                    value: json!(1)
+                }],
+            }
+        );
+        log.publish("update", &json!({"a": 1}), &json!({"a": 2}));
+        log.publish("update", &json!({"a": 2}), &json!({"a": 3}));
+
+        // só os dois últimos estão guardados
+        let seqs = |since| {
+            log.subscribe(since)
+                .missed
+                .map(|events| events.iter().map(|e| e.seq).collect::<Vec<_>>())
+        };
+        assert_eq!(seqs(Some(1)), Some(vec![2, 3]));
+        assert_eq!(seqs(Some(3)), Some(vec![]));
+        assert_eq!(seqs(Some(0)), None);
+        assert_eq!(seqs(Some(9)), None);
+        assert_eq!(seqs(None), None);
+    }
+
+    #[test]
+    fn restricts_to_a_sub_path() {
+        let event = Event {
+            seq: 7,
+            operation: "patch".into(),
+            patch: vec![
+                Operation::Replace {
+                    path: "/endereco/rua".into(),
+                    value: json!("Rua B"),
+                },
+                Operation::Remove {
+                    path: "/idade".into(),
+                },
+                Operation::Move {
+                    from: "/a".into(),
+                    path: "/endereco/a".into(),
+                },
+            ],
+        };
+        let restricted = event.restrict(&pointer("/endereco")).unwrap();
+        assert_eq!(restricted.seq, 7);
+        assert_eq!(restricted.patch.len(), 2);
+        assert_eq!(
+            event.restrict(&pointer("/idade/x")).unwrap().patch,
+            vec![Operation::Remove {
+                path: "/idade".into()
+            }]
+        );
+        assert_eq!(event.restrict(&pointer("/nome")), None);
+        assert_eq!(event.restrict(&pointer("")).unwrap(), event);
+    }
+}
@@ -0,0 +1,704 @@
+//! Consultas JSONPath (RFC 9535) sobre o documento. Suporta:
+//!
+//! - `$`, `.nome`, `['nome']` e `.*` / `[*]`
+//! - índices (`[0]`, `[-1]`), fatias (`[1:3]`, `[::-1]`) e uniões (`[0,2]`)
+//! - descida recursiva (`$..nome`, `$..*`)
+//! - filtros: `[?@.preco < 10 && @.autor]`, com `==`, `!=`, `<`, `<=`, `>`,
+//!   `>=`, `&&`, `||`, `!` e parênteses. `@` é o elemento testado e `$` a raiz.
+//!   A forma antiga `[?(...)]` também é aceita
+
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Erro de sintaxe, com a posição (em caracteres) onde a leitura parou
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonPathError {
+    pub message: String,
+    pub position: usize,
+}
+
+impl fmt::Display for JsonPathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "JSONPath inválido na posição {}: {}",
+            self.position, self.message
+        )
+    }
+}
+
+impl std::error::Error for JsonPathError {}
+
+/// Uma consulta já interpretada, pronta para ser aplicada a vários documentos
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonPath {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Segment {
+    /// `..`: aplica os seletores ao nó e a todos os seus descendentes
+    descendant: bool,
+    selectors: Vec<Selector>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Selector {
+    Name(String),
+    Wildcard,
+    Index(i64),
+    Slice {
+        start: Option<i64>,
+        end: Option<i64>,
+        step: i64,
+    },
+    Filter(Filter),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Filter {
+    Or(Box<Filter>, Box<Filter>),
+    And(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+    /// `@.autor`: verdadeiro se o caminho existe
+    Exists(Operand),
+    Compare {
+        left: Operand,
+        op: Comparison,
+        right: Operand,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// Lado de uma comparação: um caminho singular a partir de `@` ou `$`, ou
+/// um literal
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    Current(Vec<Step>),
+    Root(Vec<Step>),
+    Literal(Value),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Name(String),
+    Index(i64),
+}
+
+impl JsonPath {
+    pub fn parse(path: &str) -> Result<Self, JsonPathError> {
+        let mut parser = Parser {
+            chars: path.chars().collect(),
+            pos: 0,
+        };
+        parser.expect('$')?;
+        let mut segments = Vec::new();
+        while !parser.at_end() {
+            segments.push(parser.segment()?);
+        }
+        Ok(JsonPath { segments })
+    }
+
+    /// Os valores encontrados, na ordem do documento
+    pub fn query<'a>(&self, doc: &'a Value) -> Vec<&'a Value> {
+        let mut nodes = vec![doc];
+        for segment in &self.segments {
+            let mut next = Vec::new();
+            for node in nodes {
+                if segment.descendant {
+                    let mut all = Vec::new();
+                    descendants(node, &mut all);
+                    for node in all {
+                        for selector in &segment.selectors {
+                            select(selector, node, doc, &mut next);
+                        }
+                    }
+                } else {
+                    for selector in &segment.selectors {
+                        select(selector, node, doc, &mut next);
+                    }
+                }
+            }
+            nodes = next;
+        }
+        nodes
+    }
+}
+
+/// O próprio nó e todos os descendentes, em pré-ordem
+fn descendants<'a>(node: &'a Value, out: &mut Vec<&'a Value>) {
+    out.push(node);
+    match node {
+        Value::Object(map) => map.values().for_each(|child| descendants(child, out)),
+        Value::Array(items) => items.iter().for_each(|child| descendants(child, out)),
+        _ => {}
+    }
+}
+
+/// Índice negativo conta a partir do fim
+fn normalize(index: i64, len: i64) -> i64 {
+    if index >= 0 {
+        index
+    } else {
+        len + index
+    }
+}
+
+fn select<'a>(selector: &Selector, node: &'a Value, root: &'a Value, out: &mut Vec<&'a Value>) {
+    match (selector, node) {
+        (Selector::Name(name), Value::Object(map)) => out.extend(map.get(name)),
+        (Selector::Wildcard, Value::Object(map)) => out.extend(map.values()),
+        (Selector::Wildcard, Value::Array(items)) => out.extend(items),
+        (Selector::Index(index), Value::Array(items)) => {
+            let index = normalize(*index, items.len() as i64);
+            if index >= 0 {
+                out.extend(items.get(index as usize));
+            }
+        }
+        (Selector::Slice { start, end, step }, Value::Array(items)) => {
+            let len = items.len() as i64;
+            let step = *step;
+            if step > 0 {
+                let lower = normalize(start.unwrap_or(0), len).clamp(0, len);
+                let upper = normalize(end.unwrap_or(len), len).clamp(0, len);
+                let mut i = lower;
+                while i < upper {
+                    out.push(&items[i as usize]);
+                    match i.checked_add(step) {
+                        Some(next) => i = next,
+                        None => break,
+                    }
+                }
+            } else if step < 0 {
+                let upper = normalize(start.unwrap_or(len - 1), len).clamp(-1, len - 1);
+                let lower = normalize(end.unwrap_or(-len - 1), len).clamp(-1, len - 1);
+                let mut i = upper;
+                while i > lower {
+                    out.push(&items[i as usize]);
+                    i += step;
+                }
+            }
+        }
+        (Selector::Filter(filter), Value::Object(map)) => {
+            out.extend(map.values().filter(|child| filter.matches(child, root)))
+        }
+        (Selector::Filter(filter), Value::Array(items)) => {
+            out.extend(items.iter().filter(|child| filter.matches(child, root)))
+        }
+        _ => {}
+    }
+}
+
+impl Filter {
+    fn matches(&self, current: &Value, root: &Value) -> bool {
+        match self {
+            Filter::Or(a, b) => a.matches(current, root) || b.matches(current, root),
+            Filter::And(a, b) => a.matches(current, root) && b.matches(current, root),
+            Filter::Not(inner) => !inner.matches(current, root),
+            Filter::Exists(operand) => operand.resolve(current, root).is_some(),
+            Filter::Compare { left, op, right } => {
+                let left = left.resolve(current, root);
+                let right = right.resolve(current, root);
+                match (left, right) {
+                    (Some(a), Some(b)) => compare(a, *op, b),
+                    // um caminho ausente só é igual a outro ausente
+                    (None, None) => matches!(op, Comparison::Eq | Comparison::Le | Comparison::Ge),
+                    _ => *op == Comparison::Ne,
+                }
+            }
+        }
+    }
+}
+
+impl Operand {
+    fn resolve<'a>(&'a self, current: &'a Value, root: &'a Value) -> Option<&'a Value> {
+        let (start, steps) = match self {
+            Operand::Literal(value) => return Some(value),
+            Operand::Current(steps) => (current, steps),
+            Operand::Root(steps) => (root, steps),
+        };
+        steps
+            .iter()
+            .try_fold(start, |node, step| match (step, node) {
+                (Step::Name(name), Value::Object(map)) => map.get(name),
+                (Step::Index(index), Value::Array(items)) => {
+                    let index = normalize(*index, items.len() as i64);
+                    usize::try_from(index).ok().and_then(|i| items.get(i))
+                }
+                _ => None,
+            })
+    }
+}
+
+/// Igualdade com números comparados pelo valor (1 == 1.0)
+pub fn json_equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => match (x.as_i64(), y.as_i64()) {
+            (Some(x), Some(y)) => x == y,
+            _ => match (x.as_u64(), y.as_u64()) {
+                (Some(x), Some(y)) => x == y,
+                _ => x.as_f64() == y.as_f64(),
+            },
+        },
+        (Value::Array(x), Value::Array(y)) => {
+            x.len() == y.len() && x.iter().zip(y).all(|(a, b)| json_equal(a, b))
+        }
+        (Value::Object(x), Value::Object(y)) => {
+            x.len() == y.len()
+                && x.iter()
+                    .all(|(key, a)| y.get(key).is_some_and(|b| json_equal(a, b)))
+        }
+        _ => a == b,
+    }
+}
+
+/// `<` e `>` só valem entre dois números ou dois textos
+fn compare(a: &Value, op: Comparison, b: &Value) -> bool {
+    let ordering = match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x.as_f64().partial_cmp(&y.as_f64()),
+        (Value::String(x), Value::String(y)) => Some(x.cmp(y)),
+        _ if json_equal(a, b) => Some(Ordering::Equal),
+        _ => None,
+    };
+    match op {
+        Comparison::Eq => json_equal(a, b),
+        Comparison::Ne => !json_equal(a, b),
+        Comparison::Lt => ordering == Some(Ordering::Less),
+        Comparison::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+        Comparison::Gt => ordering == Some(Ordering::Greater),
+        Comparison::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+    }
+}
+
+/// Parser descendente recursivo, caractere a caractere
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, JsonPathError> {
+        Err(JsonPathError {
+            message: message.into(),
+            position: self.pos,
+        })
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.chars.len()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        s.chars()
+            .enumerate()
+            .all(|(i, c)| self.chars.get(self.pos + i) == Some(&c))
+    }
+
+    fn eat(&mut self, s: &str) -> bool {
+        if self.starts_with(s) {
+            self.pos += s.chars().count();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), JsonPathError> {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            self.error(format!("esperado '{}'", c))
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn segment(&mut self) -> Result<Segment, JsonPathError> {
+        if self.eat("..") {
+            let selectors = match self.peek() {
+                Some('[') => self.bracket()?,
+                Some('*') => {
+                    self.pos += 1;
+                    vec![Selector::Wildcard]
+                }
+                _ => vec![Selector::Name(self.name()?)],
+            };
+            return Ok(Segment {
+                descendant: true,
+                selectors,
+            });
+        }
+        let selectors = if self.eat(".") {
+            if self.eat("*") {
+                vec![Selector::Wildcard]
+            } else {
+                vec![Selector::Name(self.name()?)]
+            }
+        } else if self.peek() == Some('[') {
+            self.bracket()?
+        } else {
+            return self.error("esperado '.', '..' ou '['");
+        };
+        Ok(Segment {
+            descendant: false,
+            selectors,
+        })
+    }
+
+    /// Nome depois de '.': letras, dígitos e '_', sem começar com dígito
+    fn name(&mut self) -> Result<String, JsonPathError> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+            self.pos += 1;
+        }
+        if self.pos == start || self.chars[start].is_ascii_digit() {
+            self.pos = start;
+            return self.error("esperado um nome");
+        }
+        Ok(self.chars[start..self.pos].iter().collect())
+    }
+
+    /// `[seletor, seletor, ...]`
+    fn bracket(&mut self) -> Result<Vec<Selector>, JsonPathError> {
+        self.expect('[')?;
+        let mut selectors = Vec::new();
+        loop {
+            self.skip_whitespace();
+            selectors.push(self.selector()?);
+            self.skip_whitespace();
+            if self.eat("]") {
+                return Ok(selectors);
+            }
+            if !self.eat(",") {
+                return self.error("esperado ',' ou ']'");
+            }
+        }
+    }
+
+    fn selector(&mut self) -> Result<Selector, JsonPathError> {
+        match self.peek() {
+            Some('\'' | '"') => Ok(Selector::Name(self.string()?)),
+            Some('*') => {
+                self.pos += 1;
+                Ok(Selector::Wildcard)
+            }
+            Some('?') => {
+                self.pos += 1;
+                self.skip_whitespace();
+                Ok(Selector::Filter(self.or()?))
+            }
+            _ => {
+                let start = self.optional_integer()?;
+                self.skip_whitespace();
+                if !self.eat(":") {
+                    return match start {
+                        Some(index) => Ok(Selector::Index(index)),
+                        None => self.error("esperado um seletor"),
+                    };
+                }
+                self.skip_whitespace();
+                let end = self.optional_integer()?;
+                self.skip_whitespace();
+                let mut step = 1;
+                if self.eat(":") {
+                    self.skip_whitespace();
+                    step = self.optional_integer()?.unwrap_or(1);
+                }
+                Ok(Selector::Slice { start, end, step })
+            }
+        }
+    }
+
+    fn optional_integer(&mut self) -> Result<Option<i64>, JsonPathError> {
+        let start = self.pos;
+        self.eat("-");
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        match text.as_str() {
+            "" => Ok(None),
+            _ => match text.parse() {
+                Ok(n) => Ok(Some(n)),
+                Err(_) => {
+                    self.pos = start;
+                    self.error("esperado um inteiro")
+                }
+            },
+        }
+    }
+
+    /// Texto entre aspas simples ou duplas, com os escapes do JSON
+    fn string(&mut self) -> Result<String, JsonPathError> {
+        let quote = self.peek().unwrap();
+        self.pos += 1;
+        let mut result = String::new();
+        loop {
+            let Some(c) = self.peek() else {
+                return self.error("texto sem a aspa final");
+            };
+            self.pos += 1;
+            if c == quote {
+                return Ok(result);
+            }
+            if c != '\\' {
+                result.push(c);
+                continue;
+            }
+            let escaped = match self.peek() {
+                Some(c @ ('\'' | '"' | '\\' | '/')) => c,
+                Some('b') => '\u{8}',
+                Some('f') => '\u{c}',
+                Some('n') => '\n',
+                Some('r') => '\r',
+                Some('t') => '\t',
+                Some('u') => {
+                    let hex: String = self.chars.iter().skip(self.pos + 1).take(4).collect();
+                    match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                        Some(c) if hex.len() == 4 => {
+                            self.pos += 4;
+                            c
+                        }
+                        _ => return self.error("escape \\u inválido"),
+                    }
+                }
+                _ => return self.error("escape inválido"),
+            };
+            self.pos += 1;
+            result.push(escaped);
+        }
+    }
+
+    fn or(&mut self) -> Result<Filter, JsonPathError> {
+        let mut left = self.and()?;
+        while self.eat("||") {
+            self.skip_whitespace();
+            left = Filter::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Filter, JsonPathError> {
+        let mut left = self.unary()?;
+        while self.eat("&&") {
+            self.skip_whitespace();
+            left = Filter::And(Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Filter, JsonPathError> {
+        let filter = if self.eat("!") {
+            self.skip_whitespace();
+            Filter::Not(Box::new(self.unary()?))
+        } else if self.eat("(") {
+            self.skip_whitespace();
+            let inner = self.or()?;
+            self.expect(')')?;
+            inner
+        } else {
+            self.comparison()?
+        };
+        self.skip_whitespace();
+        Ok(filter)
+    }
+
+    fn comparison(&mut self) -> Result<Filter, JsonPathError> {
+        let left = self.operand()?;
+        self.skip_whitespace();
+        let operators = [
+            ("==", Comparison::Eq),
+            ("!=", Comparison::Ne),
+            ("<=", Comparison::Le),
+            (">=", Comparison::Ge),
+            ("<", Comparison::Lt),
+            (">", Comparison::Gt),
+        ];
+        let Some(&(_, op)) = operators.iter().find(|(text, _)| self.eat(text)) else {
+            return match left {
+                Operand::Literal(_) => self.error("um literal sozinho não é um filtro"),
+                path => Ok(Filter::Exists(path)),
+            };
+        };
+        self.skip_whitespace();
+        let right = self.operand()?;
+        Ok(Filter::Compare { left, op, right })
+    }
+
+    fn operand(&mut self) -> Result<Operand, JsonPathError> {
+        match self.peek() {
+            Some('@') => {
+                self.pos += 1;
+                Ok(Operand::Current(self.steps()?))
+            }
+            Some('$') => {
+                self.pos += 1;
+                Ok(Operand::Root(self.steps()?))
+            }
+            Some('\'' | '"') => Ok(Operand::Literal(Value::String(self.string()?))),
+            _ => self.literal().map(Operand::Literal),
+        }
+    }
+
+    /// Caminho singular dentro de um filtro: `.nome`, `['nome']` e `[índice]`
+    fn steps(&mut self) -> Result<Vec<Step>, JsonPathError> {
+        let mut steps = Vec::new();
+        loop {
+            if self.peek() == Some('.') && !self.starts_with("..") {
+                self.pos += 1;
+                steps.push(Step::Name(self.name()?));
+            } else if self.eat("[") {
+                self.skip_whitespace();
+                let step = match self.peek() {
+                    Some('\'' | '"') => Step::Name(self.string()?),
+                    _ => match self.optional_integer()? {
+                        Some(index) => Step::Index(index),
+                        None => return self.error("esperado um nome ou índice"),
+                    },
+                };
+                self.skip_whitespace();
+                self.expect(']')?;
+                steps.push(step);
+            } else {
+                return Ok(steps);
+            }
+        }
+    }
+
+    /// Número, true, false ou null
+    fn literal(&mut self) -> Result<Value, JsonPathError> {
+        for (text, value) in [
+            ("true", Value::Bool(true)),
+            ("false", Value::Bool(false)),
+            ("null", Value::Null),
+        ] {
+            if self.eat(text) {
+                return Ok(value);
+            }
+        }
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'))
+        {
+            self.pos += 1;
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        match serde_json::from_str::<serde_json::Number>(&text) {
+            Ok(number) => Ok(Value::Number(number)),
+            Err(_) => {
+                self.pos = start;
+                self.error("esperado @, $ ou um literal")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn store() -> Value {
+        json!({
+            "store": {
+                "book": [
+                    {"category": "reference", "author": "Nigel Rees", "price": 8.95},
+                    {"category": "fiction", "author": "Evelyn Waugh", "price": 12.99},
+                    {"category": "fiction", "author": "Herman Melville", "price": 8.99, "isbn": "0-553-21311-3"},
+                    {"category": "fiction", "author": "J. R. R. Tolkien", "price": 22.99, "isbn": "0-395-19395-8"}
+                ],
+                "bicycle": {"color": "red", "price": 399}
+            }
+        })
+    }
+
+    fn query(path: &str) -> Vec<Value> {
+        let doc = store();
+        JsonPath::parse(path)
+            .unwrap()
+            .query(&doc)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn selectors() {
+        assert_eq!(query("$.store.bicycle.color"), vec![json!("red")]);
+        assert_eq!(query("$['store']['bicycle']['price']"), vec![json!(399)]);
+        assert_eq!(query("$.store.book[-1].price"), vec![json!(22.99)]);
+        assert_eq!(
+            query("$.store.book[0,2].price"),
+            vec![json!(8.95), json!(8.99)]
+        );
+        assert_eq!(
+            query("$.store.book[1:3].price"),
+            vec![json!(12.99), json!(8.99)]
+        );
+        assert_eq!(
+            query("$.store.book[::-2].price"),
+            vec![json!(22.99), json!(12.99)]
+        );
+        assert_eq!(
+            query("$.store.book[1::9223372036854775807].price"),
+            vec![json!(12.99)]
+        );
+        assert_eq!(query("$..author").len(), 4);
+        assert_eq!(query("$.store.*").len(), 2);
+        assert_eq!(query("$..price").len(), 5);
+        assert!(query("$.store.nada[0]").is_empty());
+    }
+
+    #[test]
+    fn filters() {
+        assert_eq!(
+            query("$.store.book[?@.price < 10].author"),
+            vec![json!("Nigel Rees"), json!("Herman Melville")]
+        );
+        assert_eq!(
+            query("$..book[?(@.isbn && @.price > 10)].author"),
+            vec![json!("J. R. R. Tolkien")]
+        );
+        assert_eq!(
+            query("$.store.book[?!@.isbn || @.category == 'reference'].price"),
+            vec![json!(8.95), json!(12.99)]
+        );
+        assert_eq!(
+            query("$.store.book[?@.price == 8.95].author"),
+            vec![json!("Nigel Rees")]
+        );
+        assert_eq!(
+            query("$.store.book[?@.price > $.store.bicycle.price]"),
+            Vec::<Value>::new()
+        );
+    }
+
+    #[test]
+    fn syntax_errors_report_the_position() {
+        assert_eq!(JsonPath::parse("store").unwrap_err().position, 0);
+        assert_eq!(JsonPath::parse("$.store[").unwrap_err().position, 8);
+        assert_eq!(JsonPath::parse("$.a[?@.b ==]").unwrap_err().position, 11);
+        assert!(JsonPath::parse("$.a['b").is_err());
+        assert!(json_equal(&json!({"a": [1]}), &json!({"a": [1.0]})));
+    }
+}
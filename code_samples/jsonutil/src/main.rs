@@ -1,22 +1,57 @@
+mod compare;
+mod diff;
+mod docs;
+mod events;
+mod jsonpath;
+mod merge;
+mod patch;
+mod pointer;
+mod schema;
+mod store;
+mod stream;
+
 use axum::{
-    extract::{Json, State},
-    http::StatusCode,
+    extract::{Json, Query, State},
+    http::{HeaderMap, StatusCode},
     response::IntoResponse,
     routing::{delete, get, post},
     Router,
 };
-use serde::{Deserialize, Serialize};
+use events::{EventLog, DEFAULT_EVENT_BUFFER};
+use jsonpath::JsonPath;
+use patch::{Operation, PatchError, PatchErrorKind};
+use pointer::{Pointer, PointerError};
+use schema::Violation;
+use serde::Deserialize;
 use serde_json::{json, Value};
 use std::{
     net::SocketAddr,
     sync::{Arc, Mutex},
 };
+use store::{DocumentStore, DEFAULT_MAX_HISTORY};
 
 /// Retorna o JSON formatado (pretty-print)
 fn pretty_print(json_data: &Value) -> String {
     serde_json::to_string_pretty(json_data).unwrap()
 }
 
+/// Resposta de erro no formato `{"error": "..."}`
+fn error_response(status: StatusCode, message: impl ToString) -> (StatusCode, String) {
+    (
+        status,
+        pretty_print(&json!({ "error": message.to_string() })),
+    )
+}
+
+/// Status HTTP para um erro de JSON Pointer
+fn pointer_status(error: &PointerError) -> StatusCode {
+    match error {
+        PointerError::NotFound(_) => StatusCode::NOT_FOUND,
+        PointerError::NotContainer(_) => StatusCode::CONFLICT,
+        _ => StatusCode::BAD_REQUEST,
+    }
+}
+
 /// Atualiza um atributo em um objeto JSON (se o atributo existir)
 fn update_attribute(json_data: &mut Value, attr: &str, new_value: Value) -> bool {
     if let Value::Object(map) = json_data {
@@ -44,10 +79,67 @@ fn remove_attribute(json_data: &mut Value, attr: &str) -> Option<Value> {
     }
 }
 
-/// Estado compartilhado da aplicação: nosso JSON, inicialmente vazio.
+/// Estado compartilhado da aplicação: nosso JSON, inicialmente vazio, o
+/// JSON Schema que ele deve seguir (opcional), os eventos das alterações do
+/// JSON e os documentos com nome de `/docs`, gravados em disco. Quem precisa
+/// de mais de um trava `data` antes de `schema` e de `events`.
 #[derive(Clone)]
 struct AppState {
     data: Arc<Mutex<Value>>,
+    schema: Arc<Mutex<Option<Value>>>,
+    events: Arc<Mutex<EventLog>>,
+    store: Arc<Mutex<DocumentStore>>,
+}
+
+/// Estado vazio para os testes das rotas, com os documentos em um
+/// diretório temporário exclusivo
+#[cfg(test)]
+fn test_state(name: &str) -> AppState {
+    let dir = std::env::temp_dir().join(format!("json_api_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    AppState {
+        data: Arc::new(Mutex::new(json!({}))),
+        schema: Arc::new(Mutex::new(None)),
+        events: Arc::new(Mutex::new(EventLog::new(DEFAULT_EVENT_BUFFER))),
+        store: Arc::new(Mutex::new(
+            DocumentStore::open(dir, DEFAULT_MAX_HISTORY).unwrap(),
+        )),
+    }
+}
+
+/// Resposta 422 com cada regra do schema que o documento não segue
+fn violations_response(violations: &[Violation]) -> (StatusCode, String) {
+    let body = json!({
+        "error": "o documento não segue o schema",
+        "violations": violations,
+    });
+    (StatusCode::UNPROCESSABLE_ENTITY, pretty_print(&body))
+}
+
+/// Grava o JSON alterado (`candidate`) se ele seguir o schema anexado. Se
+/// não seguir, o JSON atual fica como estava e a resposta lista as violações.
+/// Uma alteração gravada é avisada a quem acompanha `/json/events` e
+/// `/json/ws`, com o nome da `operation`
+fn commit_json(
+    state: &AppState,
+    data: &mut Value,
+    candidate: Value,
+    operation: &str,
+) -> (StatusCode, String) {
+    if let Some(schema) = state.schema.lock().unwrap().as_ref() {
+        if let Err(violations) = schema::validate(schema, &candidate) {
+            return violations_response(&violations);
+        }
+    }
+    // publicado com `data` ainda travado, para os eventos saírem na ordem
+    // das alterações
+    state
+        .events
+        .lock()
+        .unwrap()
+        .publish(operation, data, &candidate);
+    *data = candidate;
+    (StatusCode::OK, pretty_print(data))
 }
 
 /// Payload para configurar o JSON via POST
@@ -62,15 +154,45 @@ async fn set_json(
     Json(payload): Json<SetJsonPayload>,
 ) -> impl IntoResponse {
     let mut data = state.data.lock().unwrap();
-    *data = payload.json;
     // Retorna o JSON atual em pretty_print
-    (StatusCode::OK, pretty_print(&data))
+    commit_json(&state, &mut data, payload.json, "set")
 }
 
-/// Endpoint para consultar o JSON atual
-async fn get_json(State(state): State<AppState>) -> impl IntoResponse {
+/// Parâmetros opcionais da consulta: um JSON Pointer (`?pointer=/endereco/cidade`)
+/// ou uma expressão JSONPath (`?path=$.hobbies[0]`)
+#[derive(Deserialize)]
+struct GetQuery {
+    pointer: Option<String>,
+    path: Option<String>,
+}
+
+/// O documento inteiro, um valor dentro dele (pointer) ou a lista de
+/// valores que atendem a uma consulta JSONPath (path)
+fn query_response(data: &Value, query: GetQuery) -> (StatusCode, String) {
+    match (query.pointer, query.path) {
+        (None, None) => (StatusCode::OK, pretty_print(data)),
+        (Some(pointer), None) => match Pointer::parse(&pointer).and_then(|p| p.get(data)) {
+            Ok(value) => (StatusCode::OK, pretty_print(value)),
+            Err(e) => error_response(pointer_status(&e), e),
+        },
+        (None, Some(path)) => match JsonPath::parse(&path) {
+            Ok(path) => (StatusCode::OK, pretty_print(&json!(path.query(data)))),
+            Err(e) => error_response(StatusCode::BAD_REQUEST, e),
+        },
+        (Some(_), Some(_)) => error_response(
+            StatusCode::BAD_REQUEST,
+            "use apenas um dos parâmetros 'pointer' e 'path'",
+        ),
+    }
+}
+
+/// Endpoint para consultar o JSON atual (veja `GetQuery`)
+async fn get_json(
+    State(state): State<AppState>,
+    Query(query): Query<GetQuery>,
+) -> impl IntoResponse {
     let data = state.data.lock().unwrap();
-    (StatusCode::OK, pretty_print(&data))
+    query_response(&data, query)
 }
 
 /// Payload para atualizar um atributo
@@ -86,8 +208,9 @@ async fn update_attribute_handler(
     Json(payload): Json<UpdatePayload>,
 ) -> impl IntoResponse {
     let mut data = state.data.lock().unwrap();
-    if update_attribute(&mut *data, &payload.attribute, payload.value) {
-        (StatusCode::OK, pretty_print(&data))
+    let mut candidate = data.clone();
+    if update_attribute(&mut candidate, &payload.attribute, payload.value) {
+        commit_json(&state, &mut data, candidate, "update")
     } else {
         (StatusCode::BAD_REQUEST, pretty_print(&data))
     }
@@ -106,8 +229,9 @@ async fn insert_attribute_handler(
     Json(payload): Json<InsertPayload>,
 ) -> impl IntoResponse {
     let mut data = state.data.lock().unwrap();
-    insert_attribute(&mut *data, &payload.attribute, payload.value);
-    (StatusCode::OK, pretty_print(&data))
+    let mut candidate = data.clone();
+    insert_attribute(&mut candidate, &payload.attribute, payload.value);
+    commit_json(&state, &mut data, candidate, "insert")
 }
 
 /// Payload para remover um atributo
@@ -122,8 +246,9 @@ async fn remove_attribute_handler(
     Json(payload): Json<RemovePayload>,
 ) -> impl IntoResponse {
     let mut data = state.data.lock().unwrap();
-    let _ = remove_attribute(&mut *data, &payload.attribute);
-    (StatusCode::OK, pretty_print(&data))
+    let mut candidate = data.clone();
+    let _ = remove_attribute(&mut candidate, &payload.attribute);
+    commit_json(&state, &mut data, candidate, "remove")
 }
 
 /// Payload para inserir um atributo aninhado (dentro de um objeto existente)
@@ -140,30 +265,228 @@ async fn insert_nested_handler(
     Json(payload): Json<InsertNestedPayload>,
 ) -> impl IntoResponse {
     let mut data = state.data.lock().unwrap();
-    if let Some(parent_obj) = data.get_mut(&payload.parent) {
-        if let Value::Object(ref mut map) = parent_obj {
-            map.insert(payload.attribute, payload.value);
-            return (StatusCode::OK, pretty_print(&data));
-        }
+    let mut candidate = data.clone();
+    if let Some(Value::Object(map)) = candidate.get_mut(&payload.parent) {
+        map.insert(payload.attribute, payload.value);
+        return commit_json(&state, &mut data, candidate, "insert_nested");
     }
     (StatusCode::BAD_REQUEST, pretty_print(&data))
 }
 
+/// Payload para alterar o valor em um JSON Pointer
+#[derive(Deserialize)]
+struct PointerValuePayload {
+    pointer: String,
+    value: Value,
+}
+
+/// Payload para remover o valor em um JSON Pointer
+#[derive(Deserialize)]
+struct PointerPayload {
+    pointer: String,
+}
+
+/// Endpoint para substituir um valor que já existe, em qualquer nível
+async fn replace_pointer_handler(
+    State(state): State<AppState>,
+    Json(payload): Json<PointerValuePayload>,
+) -> impl IntoResponse {
+    let mut data = state.data.lock().unwrap();
+    let mut candidate = data.clone();
+    match Pointer::parse(&payload.pointer).and_then(|p| p.replace(&mut candidate, payload.value)) {
+        Ok(_) => commit_json(&state, &mut data, candidate, "pointer_replace"),
+        Err(e) => error_response(pointer_status(&e), e),
+    }
+}
+
+/// Endpoint para inserir um valor, em qualquer nível. Em arrays, insere na
+/// posição indicada (ou no fim, com `-`)
+async fn add_pointer_handler(
+    State(state): State<AppState>,
+    Json(payload): Json<PointerValuePayload>,
+) -> impl IntoResponse {
+    let mut data = state.data.lock().unwrap();
+    let mut candidate = data.clone();
+    match Pointer::parse(&payload.pointer).and_then(|p| p.add(&mut candidate, payload.value)) {
+        Ok(()) => commit_json(&state, &mut data, candidate, "pointer_add"),
+        Err(e) => error_response(pointer_status(&e), e),
+    }
+}
+
+/// Endpoint para remover um valor, em qualquer nível
+async fn remove_pointer_handler(
+    State(state): State<AppState>,
+    Json(payload): Json<PointerPayload>,
+) -> impl IntoResponse {
+    let mut data = state.data.lock().unwrap();
+    let mut candidate = data.clone();
+    match Pointer::parse(&payload.pointer).and_then(|p| p.remove(&mut candidate)) {
+        Ok(_) => commit_json(&state, &mut data, candidate, "pointer_remove"),
+        Err(e) => error_response(pointer_status(&e), e),
+    }
+}
+
+/// Endpoint para anexar um JSON Schema ao JSON. O JSON atual já precisa
+/// seguir o schema; a partir daí, toda alteração é validada antes de gravada
+async fn put_schema(
+    State(state): State<AppState>,
+    Json(new_schema): Json<Value>,
+) -> impl IntoResponse {
+    if let Err(e) = schema::check_schema(&new_schema) {
+        return error_response(StatusCode::BAD_REQUEST, e);
+    }
+    let data = state.data.lock().unwrap();
+    if let Err(violations) = schema::validate(&new_schema, &data) {
+        return violations_response(&violations);
+    }
+    let response = (StatusCode::OK, pretty_print(&new_schema));
+    *state.schema.lock().unwrap() = Some(new_schema);
+    response
+}
+
+/// Endpoint para consultar o schema anexado
+async fn get_schema(State(state): State<AppState>) -> impl IntoResponse {
+    match state.schema.lock().unwrap().as_ref() {
+        Some(schema) => (StatusCode::OK, pretty_print(schema)),
+        None => error_response(StatusCode::NOT_FOUND, "nenhum schema anexado"),
+    }
+}
+
+/// Endpoint para remover o schema; as alterações deixam de ser validadas
+async fn delete_schema(State(state): State<AppState>) -> impl IntoResponse {
+    *state.schema.lock().unwrap() = None;
+    StatusCode::NO_CONTENT
+}
+
+/// Lê o corpo de um PATCH: um array de operações. Uma operação malformada
+/// é reportada com a sua posição (`index`, a partir de 0)
+fn parse_operations(patch: Value) -> Result<Vec<Operation>, (StatusCode, String)> {
+    let Value::Array(items) = patch else {
+        return Err(error_response(
+            StatusCode::BAD_REQUEST,
+            "o patch deve ser um array de operações",
+        ));
+    };
+    let mut operations = Vec::with_capacity(items.len());
+    for (index, item) in items.into_iter().enumerate() {
+        match serde_json::from_value::<Operation>(item) {
+            Ok(operation) => operations.push(operation),
+            Err(e) => {
+                let body = json!({
+                    "error": format!("operação {} inválida: {}", index, e),
+                    "index": index,
+                });
+                return Err((StatusCode::BAD_REQUEST, pretty_print(&body)));
+            }
+        }
+    }
+    Ok(operations)
+}
+
+/// Resposta para um patch que falhou, indicando a operação
+fn patch_error_response(e: &PatchError, operations: &[Operation]) -> (StatusCode, String) {
+    // um `test` que falha é um conflito com o estado atual do documento
+    let status = match e.kind {
+        PatchErrorKind::TestFailed { .. } => StatusCode::CONFLICT,
+        _ => StatusCode::UNPROCESSABLE_ENTITY,
+    };
+    let body = json!({
+        "error": e.to_string(),
+        "index": e.index,
+        "operation": operations[e.index],
+    });
+    (status, pretty_print(&body))
+}
+
+/// Endpoint para aplicar um JSON Patch (RFC 6902). As operações são
+/// aplicadas todas ou nenhuma; em caso de falha, a resposta indica qual
+/// operação falhou e o documento não é alterado. Com
+/// `Content-Type: application/merge-patch+json`, o corpo é um JSON Merge
+/// Patch (RFC 7396)
+async fn patch_json(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(patch): Json<Value>,
+) -> impl IntoResponse {
+    if compare::is_merge_patch(&headers) {
+        let mut data = state.data.lock().unwrap();
+        let mut candidate = data.clone();
+        merge::merge_patch(&mut candidate, &patch);
+        return commit_json(&state, &mut data, candidate, "merge_patch");
+    }
+    let operations = match parse_operations(patch) {
+        Ok(operations) => operations,
+        Err(response) => return response,
+    };
+    let mut data = state.data.lock().unwrap();
+    let mut candidate = data.clone();
+    match patch::apply(&mut candidate, &operations) {
+        Ok(()) => commit_json(&state, &mut data, candidate, "patch"),
+        Err(e) => patch_error_response(&e, &operations),
+    }
+}
+
 #[tokio::main]
 async fn main() {
+    // Diretório dos documentos de /docs (padrão: ./data)
+    let data_dir = std::env::var("JSON_API_DATA_DIR").unwrap_or_else(|_| "data".to_string());
+    let store = match DocumentStore::open(&data_dir, DEFAULT_MAX_HISTORY) {
+        Ok(store) => store,
+        Err(e) => {
+            eprintln!("Não foi possível abrir '{}': {}", data_dir, e);
+            std::process::exit(1);
+        }
+    };
+
     // Estado inicial: objeto JSON vazio
     let state = AppState {
         data: Arc::new(Mutex::new(json!({}))),
+        schema: Arc::new(Mutex::new(None)),
+        events: Arc::new(Mutex::new(EventLog::new(DEFAULT_EVENT_BUFFER))),
+        store: Arc::new(Mutex::new(store)),
     };
 
     // Define as rotas da API
     let app = Router::new()
         .route("/json/set", post(set_json))
-        .route("/json", get(get_json))
+        .route("/json", get(get_json).patch(patch_json))
         .route("/json/update", post(update_attribute_handler))
         .route("/json/insert", post(insert_attribute_handler))
         .route("/json/remove", delete(remove_attribute_handler))
         .route("/json/insert_nested", post(insert_nested_handler))
+        .route(
+            "/json/pointer",
+            post(add_pointer_handler)
+                .put(replace_pointer_handler)
+                .delete(remove_pointer_handler),
+        )
+        .route("/json/events", get(stream::sse_handler))
+        .route("/json/ws", get(stream::ws_handler))
+        .route(
+            "/json/schema",
+            get(get_schema).put(put_schema).delete(delete_schema),
+        )
+        .route("/diff", post(compare::diff_handler))
+        .route("/merge", post(compare::merge_handler))
+        .route("/merge3", post(compare::merge3_handler))
+        .route("/docs", get(docs::list_docs))
+        .route(
+            "/docs/:id",
+            get(docs::get_doc)
+                .put(docs::put_doc)
+                .patch(docs::patch_doc)
+                .delete(docs::delete_doc),
+        )
+        .route(
+            "/docs/:id/schema",
+            get(docs::get_schema)
+                .put(docs::put_schema)
+                .delete(docs::delete_schema),
+        )
+        .route("/docs/:id/diff", get(docs::diff_doc))
+        .route("/docs/:id/history", get(docs::get_history))
+        .route("/docs/:id/history/:version", get(docs::get_revision))
+        .route("/docs/:id/revert/:version", post(docs::revert_doc))
         .with_state(state);
 
     let addr = SocketAddr::from(([127, 0, 0, 1], 3000));
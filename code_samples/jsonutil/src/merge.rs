@@ -0,0 +1,175 @@
+//! Junção de documentos: JSON Merge Patch (RFC 7396) e merge de três vias,
+//! que junta duas versões alteradas a partir de uma versão base comum.
+
+use crate::jsonpath::json_equal;
+use crate::pointer::child;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// Aplica um JSON Merge Patch: os campos do patch substituem os do
+/// documento, objetos são juntados campo a campo e `null` apaga o campo.
+/// Um patch que não é objeto substitui o documento inteiro
+pub fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    let Value::Object(target) = target else {
+        unreachable!()
+    };
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(key);
+        } else {
+            merge_patch(target.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+/// Um ponto alterado dos dois lados de formas diferentes. Um lado sem valor
+/// (o campo não existe) fica de fora da serialização
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Conflict {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ours: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub theirs: Option<Value>,
+}
+
+/// Resultado do merge de três vias. Nos conflitos, `merged` fica com o
+/// valor de `ours`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Merge {
+    pub merged: Value,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Junta `ours` e `theirs`, as duas derivadas de `base`: o que só um lado
+/// mudou é aceito, e objetos são juntados campo a campo. Arrays e valores
+/// simples alterados dos dois lados (de formas diferentes) são conflitos
+pub fn merge3(base: &Value, ours: &Value, theirs: &Value) -> Merge {
+    let mut conflicts = Vec::new();
+    let merged =
+        merge_at("", Some(base), Some(ours), Some(theirs), &mut conflicts).unwrap_or(Value::Null);
+    Merge { merged, conflicts }
+}
+
+fn same(a: Option<&Value>, b: Option<&Value>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => json_equal(a, b),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+/// `None` é um campo que não existe (ou que foi apagado)
+fn merge_at(
+    path: &str,
+    base: Option<&Value>,
+    ours: Option<&Value>,
+    theirs: Option<&Value>,
+    conflicts: &mut Vec<Conflict>,
+) -> Option<Value> {
+    if same(ours, theirs) || same(base, theirs) {
+        return ours.cloned();
+    }
+    if same(base, ours) {
+        return theirs.cloned();
+    }
+    if let (Some(Value::Object(ours)), Some(Value::Object(theirs))) = (ours, theirs) {
+        // um campo criado dos dois lados é juntado como se a base fosse `{}`
+        let empty = Map::new();
+        let base = match base {
+            Some(Value::Object(base)) => base,
+            _ => &empty,
+        };
+        let mut merged = Map::new();
+        let keys = ours
+            .keys()
+            .chain(theirs.keys().filter(|key| !ours.contains_key(*key)));
+        for key in keys {
+            let value = merge_at(
+                &child(path, key),
+                base.get(key),
+                ours.get(key),
+                theirs.get(key),
+                conflicts,
+            );
+            if let Some(value) = value {
+                merged.insert(key.clone(), value);
+            }
+        }
+        return Some(Value::Object(merged));
+    }
+    conflicts.push(Conflict {
+        path: path.to_string(),
+        base: base.cloned(),
+        ours: ours.cloned(),
+        theirs: theirs.cloned(),
+    });
+    ours.cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn merge_patch_follows_rfc_7396() {
+        let mut doc = json!({"a": "b", "c": {"d": "e", "f": "g"}, "l": [1, 2]});
+        merge_patch(
+            &mut doc,
+            &json!({"a": "z", "c": {"f": null, "n": {"x": 1}}, "l": [3]}),
+        );
+        assert_eq!(
+            doc,
+            json!({"a": "z", "c": {"d": "e", "n": {"x": 1}}, "l": [3]})
+        );
+        merge_patch(&mut doc, &json!(["inteiro"]));
+        assert_eq!(doc, json!(["inteiro"]));
+        merge_patch(&mut doc, &json!({"a": null}));
+        assert_eq!(doc, json!({}));
+    }
+
+    #[test]
+    fn three_way_merge_reports_conflicts() {
+        let base = json!({"nome": "João", "idade": 30, "cidade": "SP", "tags": ["a"]});
+        let ours = json!({"nome": "João Silva", "idade": 31, "tags": ["a", "b"]});
+        let theirs =
+            json!({"nome": "João", "idade": 32, "cidade": "RJ", "email": "j@x", "tags": ["a"]});
+        let result = merge3(&base, &ours, &theirs);
+        assert_eq!(
+            result.merged,
+            json!({"nome": "João Silva", "idade": 31, "tags": ["a", "b"], "email": "j@x"})
+        );
+        assert_eq!(
+            result.conflicts,
+            vec![
+                Conflict {
+                    path: "/idade".into(),
+                    base: Some(json!(30)),
+                    ours: Some(json!(31)),
+                    theirs: Some(json!(32)),
+                },
+                // apagado de um lado, alterado do outro
+                Conflict {
+                    path: "/cidade".into(),
+                    base: Some(json!("SP")),
+                    ours: None,
+                    theirs: Some(json!("RJ")),
+                },
+            ]
+        );
+
+        let clean = merge3(&json!({"a": 1}), &json!({"a": 1, "b": 2}), &json!({}));
+        assert_eq!(clean.merged, json!({"b": 2}));
+        assert!(clean.conflicts.is_empty());
+    }
+}
@@ -0,0 +1,190 @@
+//! JSON Patch (RFC 6902): uma lista de operações aplicada de uma vez.
+//! Se qualquer operação falhar, o documento fica como estava.
+
+use crate::jsonpath::json_equal;
+use crate::pointer::{Pointer, PointerError};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+/// Uma operação do patch; o campo `op` escolhe a variante
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum Operation {
+    Add {
+        path: String,
+        value: Value,
+    },
+    Remove {
+        path: String,
+    },
+    Replace {
+        path: String,
+        value: Value,
+    },
+    Move {
+        from: String,
+        path: String,
+    },
+    Copy {
+        from: String,
+        path: String,
+    },
+    /// Falha se o valor em `path` não for igual a `value`
+    Test {
+        path: String,
+        value: Value,
+    },
+}
+
+/// Por que uma operação falhou
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchErrorKind {
+    Pointer(PointerError),
+    TestFailed {
+        path: String,
+        actual: Value,
+    },
+    /// `move` para dentro do próprio valor sendo movido
+    MoveIntoItself,
+}
+
+/// Falha ao aplicar um patch: a posição da operação na lista e o motivo
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatchError {
+    pub index: usize,
+    pub kind: PatchErrorKind,
+}
+
+impl fmt::Display for PatchErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatchErrorKind::Pointer(e) => write!(f, "{}", e),
+            PatchErrorKind::TestFailed { path, actual } => {
+                write!(f, "teste falhou: '{}' vale {}", path, actual)
+            }
+            PatchErrorKind::MoveIntoItself => {
+                write!(f, "não é possível mover um valor para dentro dele mesmo")
+            }
+        }
+    }
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "operação {} falhou: {}", self.index, self.kind)
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+impl From<PointerError> for PatchErrorKind {
+    fn from(e: PointerError) -> Self {
+        PatchErrorKind::Pointer(e)
+    }
+}
+
+/// Aplica as operações em uma cópia do documento e só a grava no final,
+/// então um patch que falha não deixa alterações pela metade
+pub fn apply(doc: &mut Value, operations: &[Operation]) -> Result<(), PatchError> {
+    let mut copy = doc.clone();
+    for (index, operation) in operations.iter().enumerate() {
+        apply_one(&mut copy, operation).map_err(|kind| PatchError { index, kind })?;
+    }
+    *doc = copy;
+    Ok(())
+}
+
+fn apply_one(doc: &mut Value, operation: &Operation) -> Result<(), PatchErrorKind> {
+    match operation {
+        Operation::Add { path, value } => Pointer::parse(path)?.add(doc, value.clone())?,
+        Operation::Remove { path } => {
+            Pointer::parse(path)?.remove(doc)?;
+        }
+        Operation::Replace { path, value } => {
+            Pointer::parse(path)?.replace(doc, value.clone())?;
+        }
+        Operation::Move { from, path } => {
+            let from = Pointer::parse(from)?;
+            let path = Pointer::parse(path)?;
+            if path.is_inside(&from) {
+                return Err(PatchErrorKind::MoveIntoItself);
+            }
+            let value = from.remove(doc)?;
+            path.add(doc, value)?;
+        }
+        Operation::Copy { from, path } => {
+            let value = Pointer::parse(from)?.get(doc)?.clone();
+            Pointer::parse(path)?.add(doc, value)?;
+        }
+        Operation::Test { path, value } => {
+            let actual = Pointer::parse(path)?.get(doc)?;
+            if !json_equal(actual, value) {
+                return Err(PatchErrorKind::TestFailed {
+                    path: path.clone(),
+                    actual: actual.clone(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn operations(patch: Value) -> Vec<Operation> {
+        serde_json::from_value(patch).unwrap()
+    }
+
+    #[test]
+    fn applies_all_operations() {
+        let mut doc = json!({"nome": "João", "idade": 30, "hobbies": ["futebol"]});
+        let patch = operations(json!([
+            {"op": "test", "path": "/idade", "value": 30.0},
+            {"op": "replace", "path": "/idade", "value": 31},
+            {"op": "add", "path": "/hobbies/-", "value": "leitura"},
+            {"op": "copy", "from": "/nome", "path": "/apelido"},
+            {"op": "move", "from": "/hobbies", "path": "/interesses"},
+            {"op": "remove", "path": "/nome"}
+        ]));
+        apply(&mut doc, &patch).unwrap();
+        assert_eq!(
+            doc,
+            json!({"idade": 31, "apelido": "João", "interesses": ["futebol", "leitura"]})
+        );
+    }
+
+    #[test]
+    fn failure_leaves_the_document_untouched() {
+        let original = json!({"a": {"b": 1}});
+        let mut doc = original.clone();
+        let patch = operations(json!([
+            {"op": "remove", "path": "/a/b"},
+            {"op": "test", "path": "/a", "value": {"b": 2}}
+        ]));
+        let error = apply(&mut doc, &patch).unwrap_err();
+        assert_eq!(error.index, 1);
+        assert_eq!(
+            error.kind,
+            PatchErrorKind::TestFailed {
+                path: "/a".into(),
+                actual: json!({})
+            }
+        );
+        assert_eq!(doc, original);
+
+        let patch = operations(json!([{"op": "move", "from": "/a", "path": "/a/c"}]));
+        assert_eq!(
+            apply(&mut doc, &patch).unwrap_err().kind,
+            PatchErrorKind::MoveIntoItself
+        );
+        let patch = operations(json!([{"op": "replace", "path": "/x", "value": 1}]));
+        assert_eq!(
+            apply(&mut doc, &patch).unwrap_err().to_string(),
+            "operação 0 falhou: caminho não encontrado: '/x'"
+        );
+    }
+}
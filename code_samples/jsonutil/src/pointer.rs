@@ -0,0 +1,315 @@
+//! JSON Pointer (RFC 6901): endereça qualquer valor dentro de um documento,
+//! como `/endereco/cidade` ou `/hobbies/0`.
+
+use serde_json::Value;
+use std::fmt;
+
+/// Erros ao interpretar ou resolver um JSON Pointer
+#[derive(Debug, Clone, PartialEq)]
+pub enum PointerError {
+    /// O ponteiro não começa com '/' ou tem um escape diferente de `~0` e `~1`
+    Syntax(String),
+    /// O caminho não existe no documento
+    NotFound(String),
+    /// O segmento não é um índice válido para o array (ou está fora dos limites)
+    InvalidIndex(String),
+    /// O caminho tenta descer em um valor que não é objeto nem array
+    NotContainer(String),
+    /// A raiz do documento não pode ser removida
+    RemoveRoot,
+}
+
+impl fmt::Display for PointerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PointerError::Syntax(pointer) => write!(f, "ponteiro inválido: '{}'", pointer),
+            PointerError::NotFound(pointer) => write!(f, "caminho não encontrado: '{}'", pointer),
+            PointerError::InvalidIndex(pointer) => {
+                write!(f, "índice de array inválido: '{}'", pointer)
+            }
+            PointerError::NotContainer(pointer) => {
+                write!(f, "'{}' não é um objeto nem um array", pointer)
+            }
+            PointerError::RemoveRoot => write!(f, "não é possível remover a raiz do documento"),
+        }
+    }
+}
+
+impl std::error::Error for PointerError {}
+
+/// Um JSON Pointer já decodificado: a lista de segmentos, sem os escapes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pointer {
+    tokens: Vec<String>,
+}
+
+impl Pointer {
+    /// Interpreta um ponteiro. A string vazia aponta para o documento inteiro
+    pub fn parse(pointer: &str) -> Result<Self, PointerError> {
+        if pointer.is_empty() {
+            return Ok(Pointer { tokens: Vec::new() });
+        }
+        let Some(rest) = pointer.strip_prefix('/') else {
+            return Err(PointerError::Syntax(pointer.to_string()));
+        };
+        let tokens = rest
+            .split('/')
+            .map(|token| unescape(token).ok_or_else(|| PointerError::Syntax(pointer.to_string())))
+            .collect::<Result<_, _>>()?;
+        Ok(Pointer { tokens })
+    }
+
+    /// Verdadeiro se `self` está estritamente dentro de `other`
+    /// (`/a/b` está dentro de `/a`)
+    pub fn is_inside(&self, other: &Pointer) -> bool {
+        self.tokens.len() > other.tokens.len() && self.tokens.starts_with(&other.tokens)
+    }
+
+    /// O ponteiro para o pai e o último segmento; None para a raiz
+    fn split_last(&self) -> Option<(Pointer, &str)> {
+        let (last, parent) = self.tokens.split_last()?;
+        Some((
+            Pointer {
+                tokens: parent.to_vec(),
+            },
+            last,
+        ))
+    }
+
+    /// Ponteiro com os primeiros `len` segmentos, usado nas mensagens de erro
+    fn prefix(&self, len: usize) -> String {
+        Pointer {
+            tokens: self.tokens[..len].to_vec(),
+        }
+        .to_string()
+    }
+
+    pub fn get<'a>(&self, doc: &'a Value) -> Result<&'a Value, PointerError> {
+        let mut current = doc;
+        for (i, token) in self.tokens.iter().enumerate() {
+            current = match current {
+                Value::Object(map) => map.get(token),
+                Value::Array(items) => items.get(self.index(token, items.len(), i + 1)?),
+                _ => return Err(PointerError::NotContainer(self.prefix(i))),
+            }
+            .ok_or_else(|| PointerError::NotFound(self.prefix(i + 1)))?;
+        }
+        Ok(current)
+    }
+
+    pub fn get_mut<'a>(&self, doc: &'a mut Value) -> Result<&'a mut Value, PointerError> {
+        let mut current = doc;
+        for (i, token) in self.tokens.iter().enumerate() {
+            current = match current {
+                Value::Object(map) => map.get_mut(token),
+                Value::Array(items) => {
+                    let index = self.index(token, items.len(), i + 1)?;
+                    items.get_mut(index)
+                }
+                _ => return Err(PointerError::NotContainer(self.prefix(i))),
+            }
+            .ok_or_else(|| PointerError::NotFound(self.prefix(i + 1)))?;
+        }
+        Ok(current)
+    }
+
+    /// Índice de array: só dígitos, sem zeros à esquerda. `-` (depois do
+    /// último elemento) só é aceito ao inserir, e é tratado em `add`
+    fn index(&self, token: &str, len: usize, depth: usize) -> Result<usize, PointerError> {
+        let invalid = || PointerError::InvalidIndex(self.prefix(depth));
+        if token.is_empty()
+            || !token.bytes().all(|b| b.is_ascii_digit())
+            || (token.len() > 1 && token.starts_with('0'))
+        {
+            return Err(invalid());
+        }
+        let index: usize = token.parse().map_err(|_| invalid())?;
+        if index >= len {
+            return Err(PointerError::NotFound(self.prefix(depth)));
+        }
+        Ok(index)
+    }
+
+    /// Insere um valor (operação `add` da RFC 6902): em um objeto, cria ou
+    /// sobrescreve o membro; em um array, insere na posição, deslocando os
+    /// seguintes, ou no fim com `-`. Na raiz, substitui o documento
+    pub fn add(&self, doc: &mut Value, value: Value) -> Result<(), PointerError> {
+        let Some((parent, last)) = self.split_last() else {
+            *doc = value;
+            return Ok(());
+        };
+        match parent.get_mut(doc)? {
+            Value::Object(map) => {
+                map.insert(last.to_string(), value);
+            }
+            Value::Array(items) if last == "-" => items.push(value),
+            Value::Array(items) => {
+                // inserir logo depois do último elemento também é válido
+                let len = items.len();
+                let index = match self.index(last, len + 1, self.tokens.len()) {
+                    Ok(index) => index,
+                    Err(PointerError::NotFound(_)) => {
+                        return Err(PointerError::InvalidIndex(self.to_string()))
+                    }
+                    Err(e) => return Err(e),
+                };
+                items.insert(index, value);
+            }
+            _ => return Err(PointerError::NotContainer(parent.to_string())),
+        }
+        Ok(())
+    }
+
+    /// Remove o valor e o devolve; elementos de array seguintes são deslocados
+    pub fn remove(&self, doc: &mut Value) -> Result<Value, PointerError> {
+        let Some((parent, last)) = self.split_last() else {
+            return Err(PointerError::RemoveRoot);
+        };
+        match parent.get_mut(doc)? {
+            Value::Object(map) => map
+                .remove(last)
+                .ok_or_else(|| PointerError::NotFound(self.to_string())),
+            Value::Array(items) => {
+                let index = self.index(last, items.len(), self.tokens.len())?;
+                Ok(items.remove(index))
+            }
+            _ => Err(PointerError::NotContainer(parent.to_string())),
+        }
+    }
+
+    /// Substitui um valor que já existe e devolve o anterior
+    pub fn replace(&self, doc: &mut Value, value: Value) -> Result<Value, PointerError> {
+        let target = self.get_mut(doc)?;
+        Ok(std::mem::replace(target, value))
+    }
+}
+
+impl fmt::Display for Pointer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for token in &self.tokens {
+            write!(f, "/{}", token.replace('~', "~0").replace('/', "~1"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Acrescenta um segmento a um ponteiro, com os escapes: `child("/a", "b/c")`
+/// é `/a/b~1c`
+pub fn child(pointer: &str, token: &str) -> String {
+    format!(
+        "{}/{}",
+        pointer,
+        token.replace('~', "~0").replace('/', "~1")
+    )
+}
+
+/// Troca `~1` por `/` e `~0` por `~`, nessa ordem de leitura
+fn unescape(token: &str) -> Option<String> {
+    let mut result = String::with_capacity(token.len());
+    let mut chars = token.chars();
+    while let Some(c) = chars.next() {
+        if c != '~' {
+            result.push(c);
+            continue;
+        }
+        match chars.next()? {
+            '0' => result.push('~'),
+            '1' => result.push('/'),
+            _ => return None,
+        }
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn pointer(s: &str) -> Pointer {
+        Pointer::parse(s).unwrap()
+    }
+
+    #[test]
+    fn resolves_rfc_6901_examples() {
+        let doc = json!({
+            "foo": ["bar", "baz"], "": 0, "a/b": 1, "c%d": 2, "e^f": 3,
+            "g|h": 4, "i\\j": 5, "k\"l": 6, " ": 7, "m~n": 8
+        });
+        assert_eq!(pointer("").get(&doc), Ok(&doc));
+        assert_eq!(pointer("/foo/0").get(&doc), Ok(&json!("bar")));
+        assert_eq!(pointer("/").get(&doc), Ok(&json!(0)));
+        assert_eq!(pointer("/a~1b").get(&doc), Ok(&json!(1)));
+        assert_eq!(pointer("/m~0n").get(&doc), Ok(&json!(8)));
+        assert_eq!(pointer("/m~0n").to_string(), "/m~0n");
+
+        assert!(matches!(
+            Pointer::parse("foo"),
+            Err(PointerError::Syntax(_))
+        ));
+        assert!(matches!(
+            Pointer::parse("/a~2"),
+            Err(PointerError::Syntax(_))
+        ));
+        assert_eq!(
+            pointer("/foo/01").get(&doc),
+            Err(PointerError::InvalidIndex("/foo/01".into()))
+        );
+        assert_eq!(
+            pointer("/foo/2").get(&doc),
+            Err(PointerError::NotFound("/foo/2".into()))
+        );
+        assert_eq!(
+            pointer("/foo/0/x").get(&doc),
+            Err(PointerError::NotContainer("/foo/0".into()))
+        );
+    }
+
+    #[test]
+    fn add_remove_and_replace() {
+        let mut doc = json!({"hobbies": ["futebol", "música"], "endereco": {}});
+        pointer("/hobbies/1")
+            .add(&mut doc, json!("leitura"))
+            .unwrap();
+        pointer("/hobbies/-")
+            .add(&mut doc, json!("xadrez"))
+            .unwrap();
+        pointer("/hobbies/4").add(&mut doc, json!("fim")).unwrap();
+        pointer("/endereco/cidade")
+            .add(&mut doc, json!("São Paulo"))
+            .unwrap();
+        assert_eq!(
+            doc,
+            json!({
+                "hobbies": ["futebol", "leitura", "música", "xadrez", "fim"],
+                "endereco": {"cidade": "São Paulo"}
+            })
+        );
+        assert_eq!(
+            pointer("/hobbies/9").add(&mut doc, json!(0)),
+            Err(PointerError::InvalidIndex("/hobbies/9".into()))
+        );
+        assert_eq!(
+            pointer("/nada/x").add(&mut doc, json!(0)),
+            Err(PointerError::NotFound("/nada".into()))
+        );
+
+        assert_eq!(pointer("/hobbies/0").remove(&mut doc), Ok(json!("futebol")));
+        assert_eq!(
+            pointer("/endereco/rua").remove(&mut doc),
+            Err(PointerError::NotFound("/endereco/rua".into()))
+        );
+        assert_eq!(pointer("").remove(&mut doc), Err(PointerError::RemoveRoot));
+
+        assert_eq!(
+            pointer("/endereco/cidade").replace(&mut doc, json!("Rio")),
+            Ok(json!("São Paulo"))
+        );
+        assert!(pointer("/endereco/rua")
+            .replace(&mut doc, json!("A"))
+            .is_err());
+        assert!(pointer("/a/b").is_inside(&pointer("/a")));
+        assert_eq!(child("/a", "b/c~"), "/a/b~1c~0");
+        assert!(!pointer("/a").is_inside(&pointer("/a")));
+    }
+}
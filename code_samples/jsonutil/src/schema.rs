@@ -0,0 +1,106 @@
+//! Validação com JSON Schema, usando o crate `jsonschema`. O draft é o do
+//! `$schema` do próprio schema (2020-12, se não houver).
+
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+
+/// Uma regra do schema que o documento não segue
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Violation {
+    /// JSON Pointer do valor inválido no documento (`""` é a raiz)
+    pub instance_path: String,
+    /// JSON Pointer da regra no schema, como `/properties/idade/type`
+    pub schema_path: String,
+    pub message: String,
+}
+
+/// O schema em si é inválido e não pode ser usado
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidSchema(pub String);
+
+impl fmt::Display for InvalidSchema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "schema inválido: {}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidSchema {}
+
+/// Confere se o schema pode ser usado, antes de anexá-lo a um documento
+pub fn check_schema(schema: &Value) -> Result<(), InvalidSchema> {
+    jsonschema::validator_for(schema)
+        .map(|_| ())
+        .map_err(|e| InvalidSchema(e.to_string()))
+}
+
+/// Lista todas as violações, não só a primeira. O schema é compilado a cada
+/// chamada: assim o estado guarda só o JSON do schema, e os schemas desta
+/// API são pequenos
+pub fn validate(schema: &Value, instance: &Value) -> Result<(), Vec<Violation>> {
+    let validator = match jsonschema::validator_for(schema) {
+        Ok(validator) => validator,
+        // não acontece com schemas aceitos por `check_schema`
+        Err(e) => {
+            return Err(vec![Violation {
+                instance_path: String::new(),
+                schema_path: String::new(),
+                message: InvalidSchema(e.to_string()).to_string(),
+            }])
+        }
+    };
+    let violations: Vec<Violation> = validator
+        .iter_errors(instance)
+        .map(|e| Violation {
+            instance_path: e.instance_path().to_string(),
+            schema_path: e.schema_path().to_string(),
+            message: e.to_string(),
+        })
+        .collect();
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(violations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn lists_every_violation_with_its_path() {
+        let schema = json!({
+            "type": "object",
+            "required": ["nome"],
+            "properties": {
+                "nome": {"type": "string"},
+                "idade": {"type": "integer", "minimum": 0},
+                "hobbies": {"type": "array", "items": {"type": "string"}}
+            }
+        });
+        assert_eq!(
+            validate(&schema, &json!({"nome": "João", "idade": 30})),
+            Ok(())
+        );
+
+        let violations = validate(&schema, &json!({"idade": -1, "hobbies": ["a", 2]})).unwrap_err();
+        let mut paths: Vec<(&str, &str)> = violations
+            .iter()
+            .map(|v| (v.instance_path.as_str(), v.schema_path.as_str()))
+            .collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                ("", "/required"),
+                ("/hobbies/1", "/properties/hobbies/items/type"),
+                ("/idade", "/properties/idade/minimum"),
+            ]
+        );
+
+        assert!(check_schema(&schema).is_ok());
+        assert!(check_schema(&json!({"type": "inteiro"})).is_err());
+    }
+}
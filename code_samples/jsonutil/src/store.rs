@@ -0,0 +1,384 @@
+//! Documentos com nome, gravados em disco. Cada documento é um arquivo
+//! `<id>.json` com todas as suas revisões; a última é o conteúdo atual.
+//! Um documento pode ter um JSON Schema, e toda revisão nova precisa segui-lo.
+
+use crate::schema::{self, InvalidSchema, Violation};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    fmt, fs, io,
+    io::Write,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Quantas revisões são guardadas por documento, por padrão
+pub const DEFAULT_MAX_HISTORY: usize = 100;
+
+/// Erros do repositório de documentos
+#[derive(Debug)]
+pub enum StoreError {
+    /// Só letras, dígitos, '-' e '_', com até 64 caracteres
+    InvalidId(String),
+    NotFound(String),
+    /// A revisão pedida não existe (ou já saiu do histórico)
+    RevisionNotFound(u64),
+    /// O conteúdo novo não segue o schema do documento
+    SchemaViolation(Vec<Violation>),
+    InvalidSchema(InvalidSchema),
+    Io(io::Error),
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::InvalidId(id) => write!(f, "id de documento inválido: '{}'", id),
+            StoreError::NotFound(id) => write!(f, "documento não encontrado: '{}'", id),
+            StoreError::RevisionNotFound(version) => {
+                write!(f, "revisão {} não encontrada no histórico", version)
+            }
+            StoreError::SchemaViolation(violations) => write!(
+                f,
+                "o documento não segue o schema ({} violações)",
+                violations.len()
+            ),
+            StoreError::InvalidSchema(e) => write!(f, "{}", e),
+            StoreError::Io(e) => write!(f, "erro de armazenamento: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+impl From<io::Error> for StoreError {
+    fn from(e: io::Error) -> Self {
+        StoreError::Io(e)
+    }
+}
+
+/// Uma versão do documento
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Revision {
+    pub version: u64,
+    /// Segundos desde 1970 (UTC)
+    pub timestamp: u64,
+    /// O que gerou a revisão: "create", "replace", "patch", "revert to 3"...
+    pub change: String,
+    pub data: Value,
+}
+
+/// O conteúdo de um arquivo `<id>.json`: as revisões, da mais antiga para
+/// a mais nova (nunca fica vazio), e o schema, se houver
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredDocument {
+    revisions: Vec<Revision>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    schema: Option<Value>,
+}
+
+impl StoredDocument {
+    fn current(&self) -> &Revision {
+        self.revisions.last().expect("documento sem revisões")
+    }
+}
+
+/// Repositório de documentos. Mantém tudo em memória e grava cada alteração
+/// no diretório antes de confirmá-la
+pub struct DocumentStore {
+    dir: PathBuf,
+    max_history: usize,
+    docs: BTreeMap<String, StoredDocument>,
+}
+
+impl DocumentStore {
+    /// Abre (ou cria) o diretório e carrega os documentos que já existem
+    pub fn open(dir: impl Into<PathBuf>, max_history: usize) -> Result<Self, StoreError> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        let mut docs = BTreeMap::new();
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            let Some(id) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(".json"))
+            else {
+                continue;
+            };
+            if validate_id(id).is_err() {
+                continue;
+            }
+            let doc: StoredDocument = serde_json::from_slice(&fs::read(&path)?)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            if !doc.revisions.is_empty() {
+                docs.insert(id.to_string(), doc);
+            }
+        }
+        Ok(DocumentStore {
+            dir,
+            max_history: max_history.max(1),
+            docs,
+        })
+    }
+
+    /// Os ids e as versões atuais, em ordem alfabética
+    pub fn list(&self) -> Vec<(&str, u64)> {
+        self.docs
+            .iter()
+            .map(|(id, doc)| (id.as_str(), doc.current().version))
+            .collect()
+    }
+
+    /// A revisão atual do documento
+    pub fn get(&self, id: &str) -> Result<&Revision, StoreError> {
+        Ok(self.document(id)?.current())
+    }
+
+    /// Versão atual, ou None se o documento não existe
+    pub fn version(&self, id: &str) -> Option<u64> {
+        self.docs.get(id).map(|doc| doc.current().version)
+    }
+
+    /// Todas as revisões guardadas, da mais antiga para a mais nova
+    pub fn history(&self, id: &str) -> Result<&[Revision], StoreError> {
+        Ok(&self.document(id)?.revisions)
+    }
+
+    pub fn revision(&self, id: &str, version: u64) -> Result<&Revision, StoreError> {
+        self.document(id)?
+            .revisions
+            .iter()
+            .find(|revision| revision.version == version)
+            .ok_or(StoreError::RevisionNotFound(version))
+    }
+
+    /// Cria o documento ou grava uma nova revisão com o conteúdo dado
+    pub fn put(&mut self, id: &str, data: Value) -> Result<&Revision, StoreError> {
+        validate_id(id)?;
+        let change = if self.docs.contains_key(id) {
+            "replace"
+        } else {
+            "create"
+        };
+        self.commit(id, change.to_string(), data)
+    }
+
+    /// Grava uma nova revisão com o resultado de `edit`. Se `edit` falhar,
+    /// nada muda
+    pub fn modify<E>(
+        &mut self,
+        id: &str,
+        change: &str,
+        edit: impl FnOnce(&mut Value) -> Result<(), E>,
+    ) -> Result<Result<&Revision, E>, StoreError> {
+        let mut data = self.get(id)?.data.clone();
+        if let Err(e) = edit(&mut data) {
+            return Ok(Err(e));
+        }
+        self.commit(id, change.to_string(), data).map(Ok)
+    }
+
+    /// Volta ao conteúdo de uma revisão anterior. O histórico não é
+    /// apagado: a volta é registrada como uma nova revisão
+    pub fn revert(&mut self, id: &str, version: u64) -> Result<&Revision, StoreError> {
+        let data = self.revision(id, version)?.data.clone();
+        self.commit(id, format!("revert to {}", version), data)
+    }
+
+    pub fn schema(&self, id: &str) -> Result<Option<&Value>, StoreError> {
+        Ok(self.document(id)?.schema.as_ref())
+    }
+
+    /// Anexa (ou, com None, remove) o schema do documento. O conteúdo atual
+    /// já precisa seguir o schema novo
+    pub fn set_schema(&mut self, id: &str, new_schema: Option<Value>) -> Result<(), StoreError> {
+        let mut doc = self.document(id)?.clone();
+        if let Some(new_schema) = &new_schema {
+            schema::check_schema(new_schema).map_err(StoreError::InvalidSchema)?;
+            schema::validate(new_schema, &doc.current().data)
+                .map_err(StoreError::SchemaViolation)?;
+        }
+        doc.schema = new_schema;
+        self.save(id, doc)
+    }
+
+    pub fn delete(&mut self, id: &str) -> Result<(), StoreError> {
+        self.document(id)?;
+        fs::remove_file(self.path(id))?;
+        self.docs.remove(id);
+        Ok(())
+    }
+
+    fn document(&self, id: &str) -> Result<&StoredDocument, StoreError> {
+        self.docs
+            .get(id)
+            .ok_or_else(|| StoreError::NotFound(id.to_string()))
+    }
+
+    fn path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+
+    /// Confere o schema, acrescenta a revisão e grava
+    fn commit(&mut self, id: &str, change: String, data: Value) -> Result<&Revision, StoreError> {
+        let mut doc = self.docs.get(id).cloned().unwrap_or(StoredDocument {
+            revisions: Vec::new(),
+            schema: None,
+        });
+        if let Some(schema) = &doc.schema {
+            schema::validate(schema, &data).map_err(StoreError::SchemaViolation)?;
+        }
+        let version = doc.revisions.last().map_or(1, |r| r.version + 1);
+        doc.revisions.push(Revision {
+            version,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            change,
+            data,
+        });
+        let excess = doc.revisions.len().saturating_sub(self.max_history);
+        doc.revisions.drain(..excess);
+
+        self.save(id, doc)?;
+        Ok(self.docs[id].current())
+    }
+
+    /// Grava o arquivo e só então atualiza a memória
+    fn save(&mut self, id: &str, doc: StoredDocument) -> Result<(), StoreError> {
+        write_atomically(&self.path(id), &serde_json::to_vec_pretty(&doc).unwrap())?;
+        self.docs.insert(id.to_string(), doc);
+        Ok(())
+    }
+}
+
+fn validate_id(id: &str) -> Result<(), StoreError> {
+    let valid = !id.is_empty()
+        && id.len() <= 64
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_');
+    if valid {
+        Ok(())
+    } else {
+        Err(StoreError::InvalidId(id.to_string()))
+    }
+}
+
+/// Grava em um arquivo temporário no mesmo diretório e o renomeia por cima
+/// do original: quem lê vê o arquivo antigo ou o novo, nunca um pela metade
+fn write_atomically(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let tmp = path.with_extension("json.tmp");
+    let mut file = fs::File::create(&tmp)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Diretório vazio e exclusivo para cada teste
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("json_api_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn documents_survive_a_restart() {
+        let dir = temp_dir("restart");
+        let mut store = DocumentStore::open(&dir, DEFAULT_MAX_HISTORY).unwrap();
+        assert_eq!(
+            store
+                .put("pessoa", json!({"nome": "João"}))
+                .unwrap()
+                .version,
+            1
+        );
+        let revision = store
+            .modify("pessoa", "patch", |doc| {
+                doc["idade"] = json!(30);
+                Ok::<_, ()>(())
+            })
+            .unwrap()
+            .unwrap();
+        assert_eq!(revision.version, 2);
+        // uma edição que falha não gera revisão
+        assert_eq!(
+            store
+                .modify("pessoa", "patch", |_| Err("falhou"))
+                .unwrap()
+                .unwrap_err(),
+            "falhou"
+        );
+        store.put("outro", json!([])).unwrap();
+
+        let store = DocumentStore::open(&dir, DEFAULT_MAX_HISTORY).unwrap();
+        assert_eq!(store.list(), vec![("outro", 1), ("pessoa", 2)]);
+        assert_eq!(
+            store.get("pessoa").unwrap().data,
+            json!({"nome": "João", "idade": 30})
+        );
+        assert_eq!(store.history("pessoa").unwrap()[0].change, "create");
+        // nenhum arquivo temporário fica para trás
+        assert!(fs::read_dir(&dir).unwrap().all(|e| !e
+            .unwrap()
+            .path()
+            .to_string_lossy()
+            .ends_with(".tmp")));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn history_revert_and_delete() {
+        let dir = temp_dir("history");
+        let mut store = DocumentStore::open(&dir, 3).unwrap();
+        for i in 1..=4 {
+            store.put("contador", json!(i)).unwrap();
+        }
+        let versions: Vec<u64> = store
+            .history("contador")
+            .unwrap()
+            .iter()
+            .map(|r| r.version)
+            .collect();
+        assert_eq!(versions, vec![2, 3, 4]);
+        assert!(matches!(
+            store.revert("contador", 1),
+            Err(StoreError::RevisionNotFound(1))
+        ));
+
+        let revision = store.revert("contador", 2).unwrap();
+        assert_eq!((revision.version, &revision.data), (5, &json!(2)));
+        assert_eq!(revision.change, "revert to 2");
+
+        assert!(matches!(
+            store.put("../fora", json!(1)),
+            Err(StoreError::InvalidId(_))
+        ));
+        // com um schema, revisões que não o seguem são recusadas
+        let schema = json!({"type": "integer", "minimum": 0});
+        store.set_schema("contador", Some(schema.clone())).unwrap();
+        assert!(matches!(
+            store.put("contador", json!(-1)),
+            Err(StoreError::SchemaViolation(v)) if v[0].schema_path == "/minimum"
+        ));
+        assert!(matches!(
+            store.set_schema("contador", Some(json!({"type": "string"}))),
+            Err(StoreError::SchemaViolation(_))
+        ));
+        let mut store = DocumentStore::open(&dir, 3).unwrap();
+        assert_eq!(store.schema("contador").unwrap(), Some(&schema));
+        store.delete("contador").unwrap();
+        assert!(matches!(
+            store.get("contador"),
+            Err(StoreError::NotFound(_))
+        ));
+        assert!(fs::read_dir(&dir).unwrap().next().is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,250 @@
+//! Rotas `/json/events` (Server-Sent Events) e `/json/ws` (WebSocket): em
+//! vez de consultar `GET /json` de tempos em tempos, o cliente recebe um
+//! aviso a cada alteração do JSON.
+//!
+//! As duas aceitam `?pointer=/endereco`, para receber só as alterações que
+//! tocam esse valor, e `?since=42`, para continuar depois do evento 42 ao
+//! reconectar (no SSE, o navegador envia sozinho o cabeçalho
+//! `Last-Event-ID`, que vale mais que o `since` da URL: o `EventSource`
+//! reconecta à mesma URL). Sem `since`, ou se os eventos perdidos já não estão
+//! guardados, o primeiro aviso é um `snapshot` com o valor atual.
+
+use crate::events::Event;
+use crate::pointer::Pointer;
+use crate::{error_response, AppState};
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    http::{HeaderMap, StatusCode},
+    response::{
+        sse::{self, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+};
+use futures::{stream, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::convert::Infallible;
+use tokio::sync::broadcast::error::RecvError;
+
+#[derive(Deserialize)]
+pub struct StreamQuery {
+    pointer: Option<String>,
+    since: Option<u64>,
+}
+
+/// Um aviso enviado ao cliente; no WebSocket, o campo `type` diz qual é
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Notification {
+    /// O valor atual em `pointer` (`null` se ele não existe), depois do
+    /// evento `seq`
+    Snapshot {
+        seq: u64,
+        data: Value,
+    },
+    Change(Event),
+}
+
+impl Notification {
+    fn seq(&self) -> u64 {
+        match self {
+            Notification::Snapshot { seq, .. } => *seq,
+            Notification::Change(event) => event.seq,
+        }
+    }
+}
+
+fn parse_pointer(pointer: Option<String>) -> Result<Pointer, (StatusCode, String)> {
+    Pointer::parse(pointer.as_deref().unwrap_or(""))
+        .map_err(|e| error_response(StatusCode::BAD_REQUEST, e))
+}
+
+fn value_at(data: &Value, pointer: &Pointer) -> Value {
+    pointer.get(data).cloned().unwrap_or(Value::Null)
+}
+
+/// O valor atual, com o número do último evento
+fn snapshot(state: &AppState, pointer: &Pointer) -> Notification {
+    let data = state.data.lock().unwrap();
+    let seq = state.events.lock().unwrap().last_seq();
+    Notification::Snapshot {
+        seq,
+        data: value_at(&data, pointer),
+    }
+}
+
+/// Inscreve o cliente e monta a sequência de avisos: os eventos perdidos
+/// (ou um snapshot) e depois os novos, à medida que acontecem. Um cliente
+/// lento demais, que perde eventos, recebe um snapshot novo
+fn notifications(
+    state: AppState,
+    pointer: Pointer,
+    since: Option<u64>,
+) -> impl Stream<Item = Notification> {
+    let (first, receiver, last_seq) = {
+        // `data` travado: nenhuma alteração entre o snapshot e a inscrição
+        let data = state.data.lock().unwrap();
+        let subscription = state.events.lock().unwrap().subscribe(since);
+        let first: Vec<Notification> = match subscription.missed {
+            Some(missed) => missed
+                .iter()
+                .filter_map(|event| event.restrict(&pointer))
+                .map(Notification::Change)
+                .collect(),
+            None => vec![Notification::Snapshot {
+                seq: subscription.last_seq,
+                data: value_at(&data, &pointer),
+            }],
+        };
+        (first, subscription.receiver, subscription.last_seq)
+    };
+
+    let live = stream::unfold(
+        (receiver, state, pointer, last_seq),
+        |(mut receiver, state, pointer, mut last_seq)| async move {
+            loop {
+                match receiver.recv().await {
+                    // já coberto por um snapshot
+                    Ok(event) if event.seq <= last_seq => {}
+                    Ok(event) => {
+                        last_seq = event.seq;
+                        if let Some(event) = event.restrict(&pointer) {
+                            let notification = Notification::Change(event);
+                            return Some((notification, (receiver, state, pointer, last_seq)));
+                        }
+                    }
+                    Err(RecvError::Lagged(_)) => {
+                        let notification = snapshot(&state, &pointer);
+                        last_seq = notification.seq();
+                        return Some((notification, (receiver, state, pointer, last_seq)));
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        },
+    );
+    stream::iter(first).chain(live)
+}
+
+fn sse_event(notification: &Notification) -> sse::Event {
+    let name = match notification {
+        Notification::Snapshot { .. } => "snapshot",
+        Notification::Change(_) => "change",
+    };
+    sse::Event::default()
+        .event(name)
+        .id(notification.seq().to_string())
+        .json_data(notification)
+        .unwrap()
+}
+
+/// Server-Sent Events: `snapshot` e `change`, com o número do evento no `id`
+pub async fn sse_handler(
+    State(state): State<AppState>,
+    Query(query): Query<StreamQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let pointer = match parse_pointer(query.pointer) {
+        Ok(pointer) => pointer,
+        Err(response) => return response.into_response(),
+    };
+    // o cabeçalho é o último evento recebido; o `since` da URL, o da primeira conexão
+    let since = headers
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+        .or(query.since);
+    let events = notifications(state, pointer, since)
+        .map(|notification| Ok::<_, Infallible>(sse_event(&notification)));
+    Sse::new(events)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+/// WebSocket: cada aviso é uma mensagem de texto com o JSON
+pub async fn ws_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Query(query): Query<StreamQuery>,
+) -> Response {
+    let pointer = match parse_pointer(query.pointer) {
+        Ok(pointer) => pointer,
+        Err(response) => return response.into_response(),
+    };
+    ws.on_upgrade(move |socket| forward(socket, notifications(state, pointer, query.since)))
+}
+
+async fn forward(mut socket: WebSocket, notifications: impl Stream<Item = Notification>) {
+    let mut notifications = std::pin::pin!(notifications);
+    loop {
+        tokio::select! {
+            notification = notifications.next() => {
+                let Some(notification) = notification else { break };
+                let text = serde_json::to_string(&notification).unwrap();
+                if socket.send(Message::Text(text)).await.is_err() {
+                    break;
+                }
+            }
+            // o cliente não envia nada além de ping e close
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::HttpBody;
+    use serde_json::json;
+
+    /// Os `id` dos primeiros `count` eventos enviados pelo SSE
+    async fn sse_ids(response: Response, count: usize) -> Vec<u64> {
+        let mut body = response.into_body();
+        let mut text = String::new();
+        while text.matches("\n\n").count() < count {
+            let chunk = body.data().await.unwrap().unwrap();
+            text.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+        text.lines()
+            .filter_map(|line| line.strip_prefix("id:"))
+            .map(|id| id.parse().unwrap())
+            .take(count)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn sse_resumes_from_last_event_id_over_a_stale_since() {
+        let state = crate::test_state("sse_resume");
+        {
+            let mut events = state.events.lock().unwrap();
+            for n in 1..=4 {
+                events.publish("set", &json!({"n": n - 1}), &json!({"n": n}));
+            }
+        }
+        let query = |since| StreamQuery {
+            pointer: None,
+            since,
+        };
+
+        // reconexão: a URL ainda tem o since=1 da primeira conexão
+        let mut headers = HeaderMap::new();
+        headers.insert("last-event-id", "3".parse().unwrap());
+        let response = sse_handler(State(state.clone()), Query(query(Some(1))), headers).await;
+        assert_eq!(sse_ids(response, 1).await, vec![4]);
+
+        // primeira conexão: vale o since
+        let response = sse_handler(
+            State(state.clone()),
+            Query(query(Some(1))),
+            HeaderMap::new(),
+        )
+        .await;
+        assert_eq!(sse_ids(response, 3).await, vec![2, 3, 4]);
+    }
+}
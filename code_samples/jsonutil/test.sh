@@ -1,4 +1,64 @@
 curl -X POST -H "Content-Type: application/json" -d '{"json": {"nome": "João","idade": 30,"endereco": {"rua": "Rua A","cidade": "São Paulo"},"hobbies": ["futebol", "leitura", "música"]}}' http://localhost:3000/json/set 
 
 
-{"nome": "João","idade": 30,"endereco": {"rua": "Rua A","cidade": "São Paulo"},"hobbies": ["futebol", "leitura", "música"]}
\ No newline at end of file
+{"nome": "João","idade": 30,"endereco": {"rua": "Rua A","cidade": "São Paulo"},"hobbies": ["futebol", "leitura", "música"]}
+
+# Lê um valor com JSON Pointer (RFC 6901)
+curl "http://localhost:3000/json?pointer=/endereco/cidade"
+
+# Consulta JSONPath (RFC 9535)
+curl -G http://localhost:3000/json --data-urlencode 'path=$.hobbies[?@ != "leitura"]'
+
+# Insere (POST), substitui (PUT) e remove (DELETE) em qualquer nível
+curl -X POST -H "Content-Type: application/json" -d '{"pointer": "/hobbies/1", "value": "xadrez"}' http://localhost:3000/json/pointer
+curl -X PUT -H "Content-Type: application/json" -d '{"pointer": "/endereco/rua", "value": "Rua B"}' http://localhost:3000/json/pointer
+curl -X DELETE -H "Content-Type: application/json" -d '{"pointer": "/hobbies/0"}' http://localhost:3000/json/pointer
+
+# JSON Patch (RFC 6902): tudo ou nada; se o teste falhar, nada é alterado (409)
+curl -X PATCH -H "Content-Type: application/json-patch+json" -d '[{"op": "test", "path": "/idade", "value": 30}, {"op": "replace", "path": "/idade", "value": 31}, {"op": "move", "from": "/endereco/rua", "path": "/rua"}]' http://localhost:3000/json
+
+
+# Documentos com nome, gravados em ./data (ou em $JSON_API_DATA_DIR)
+curl -i -X PUT -H "Content-Type: application/json" -d '{"nome": "João", "idade": 30}' http://localhost:3000/docs/pessoa
+curl http://localhost:3000/docs
+
+# Com If-Match, a alteração só é aceita se ninguém alterou o documento antes (senão, 412)
+curl -i -X PATCH -H "Content-Type: application/json-patch+json" -H 'If-Match: "1"' -d '[{"op": "replace", "path": "/idade", "value": 31}]' http://localhost:3000/docs/pessoa
+
+# Histórico de revisões, uma revisão anterior e a volta para ela
+curl http://localhost:3000/docs/pessoa/history
+curl http://localhost:3000/docs/pessoa/history/1
+curl -X POST http://localhost:3000/docs/pessoa/revert/1
+
+
+# JSON Schema: toda alteração do JSON (set, update, insert, remove, insert_nested, pointer e PATCH)
+# passa a ser validada; uma alteração que não segue o schema recebe 422 com as violações
+curl -X PUT -H "Content-Type: application/json" -d '{"type": "object", "required": ["nome"], "properties": {"idade": {"type": "integer", "minimum": 0}}}' http://localhost:3000/json/schema
+curl -X POST -H "Content-Type: application/json" -d '{"attribute": "idade", "value": -5}' http://localhost:3000/json/update
+
+# O mesmo para os documentos com nome
+curl -X PUT -H "Content-Type: application/json" -d '{"properties": {"idade": {"type": "integer"}}}' http://localhost:3000/docs/pessoa/schema
+
+
+# Diff estrutural: como JSON Patch (padrão) ou relatório ("format": "report"); com
+# "array_key", os itens dos arrays são casados pelo campo em vez da posição
+curl -X POST -H "Content-Type: application/json" -d '{"from": {"idade": 30, "itens": [{"id": 1}, {"id": 2}]}, "to": {"idade": 31, "itens": [{"id": 2}, {"id": 1, "qtd": 3}]}, "array_key": "id", "format": "report"}' http://localhost:3000/diff
+
+# O que mudou entre duas revisões de um documento (sem "to", compara com a atual)
+curl "http://localhost:3000/docs/pessoa/diff?from=1&format=report"
+
+# JSON Merge Patch (RFC 7396): null apaga o campo
+curl -X POST -H "Content-Type: application/json" -d '{"target": {"nome": "João", "idade": 30}, "patch": {"idade": null, "cidade": "SP"}}' http://localhost:3000/merge
+curl -X PATCH -H "Content-Type: application/merge-patch+json" -d '{"endereco": {"cidade": "Rio de Janeiro"}}' http://localhost:3000/json
+
+# Merge de três vias: 409 com a lista de conflitos quando os dois lados mudam o mesmo valor
+curl -X POST -H "Content-Type: application/json" -d '{"base": {"idade": 30, "nome": "João"}, "ours": {"idade": 31, "nome": "João"}, "theirs": {"idade": 32, "nome": "João Silva"}}' http://localhost:3000/merge3
+
+
+# Avisos de alteração do JSON: um "snapshot" com o valor atual e um "change" (com o JSON Patch)
+# a cada alteração. "pointer" limita a um valor; "since" (ou Last-Event-ID) continua depois de um evento
+curl -N "http://localhost:3000/json/events?pointer=/endereco"
+curl -N -H "Last-Event-ID: 3" http://localhost:3000/json/events
+
+# O mesmo por WebSocket (ws://localhost:3000/json/ws?pointer=/endereco&since=3), por exemplo com o websocat
+websocat "ws://localhost:3000/json/ws?pointer=/endereco"
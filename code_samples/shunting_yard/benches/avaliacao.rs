@@ -0,0 +1,89 @@
+// Compara as três formas de avaliar a mesma fórmula com valores diferentes:
+// texto da RPN (`avaliar_rpn`), árvore sintática (`Expr`) e bytecode
+// (`Programa`). Execute com `cargo bench`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use shunting_yard::{avaliar_rpn, infixa_para_posfixa, Ambiente, Expr, Programa};
+
+const FORMULA: &str = "MAX(preco * (1 - desconto), custo * 1.2) + SIN(30) * 2^3 - -taxa / 100";
+const AVALIACOES: usize = 200_000;
+
+fn valores(i: usize) -> [f64; 4] {
+    let x = i as f64;
+    [
+        100.0 + x % 50.0,
+        (x % 7.0) / 100.0,
+        80.0 + x % 30.0,
+        x % 5.0,
+    ]
+}
+
+fn medir(nome: &str, mut avaliar: impl FnMut(&[f64; 4]) -> f64) -> f64 {
+    let inicio = Instant::now();
+    let mut soma = 0.0;
+    for i in 0..AVALIACOES {
+        soma += avaliar(black_box(&valores(i)));
+    }
+    let tempo = inicio.elapsed();
+    println!(
+        "{:<12} {:>10.2?} {:>8.1} ns/avaliação",
+        nome,
+        tempo,
+        por_avaliacao(tempo)
+    );
+    soma
+}
+
+fn por_avaliacao(tempo: Duration) -> f64 {
+    tempo.as_nanos() as f64 / AVALIACOES as f64
+}
+
+fn main() {
+    let nomes = ["preco", "desconto", "custo", "taxa"];
+    let mut ambiente = Ambiente::novo();
+    let rpn = infixa_para_posfixa(FORMULA).unwrap();
+    let expr = Expr::analisar(FORMULA).unwrap();
+    let programa = Programa::compilar(FORMULA, &ambiente).unwrap();
+    // ordem dos valores esperada pelo programa
+    let indices: Vec<usize> = programa
+        .variaveis()
+        .iter()
+        .map(|v| nomes.iter().position(|n| n == v).unwrap())
+        .collect();
+
+    println!("{}", FORMULA);
+    println!(
+        "{} avaliações, {} instruções",
+        AVALIACOES,
+        programa.instrucoes().len()
+    );
+
+    let mut texto = ambiente.clone();
+    let soma_rpn = medir("avaliar_rpn", |v| {
+        for (nome, valor) in nomes.iter().zip(v) {
+            texto.definir(nome, *valor);
+        }
+        avaliar_rpn(&rpn, &texto).unwrap()
+    });
+
+    let soma_arvore = medir("Expr", |v| {
+        for (nome, valor) in nomes.iter().zip(v) {
+            ambiente.definir(nome, *valor);
+        }
+        expr.avaliar(&ambiente).unwrap()
+    });
+
+    let mut pilha = Vec::new();
+    let mut ordenados = vec![0.0; indices.len()];
+    let soma_programa = medir("Programa", |v| {
+        for (destino, i) in ordenados.iter_mut().zip(&indices) {
+            *destino = v[*i];
+        }
+        programa.executar_com_pilha(&ordenados, &mut pilha)
+    });
+
+    assert_eq!(soma_rpn, soma_arvore);
+    assert_eq!(soma_rpn, soma_programa);
+}
@@ -0,0 +1,119 @@
+// Ambiente de avaliação: valores das variáveis e funções disponíveis
+
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::fmt;
+use std::sync::Arc;
+
+// Quantos argumentos uma função aceita
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aridade {
+    Exata(usize),
+    // Funções variádicas, como MAX(a, b, c)
+    AoMenos(usize),
+}
+
+impl Aridade {
+    pub fn aceita(&self, n: usize) -> bool {
+        match *self {
+            Aridade::Exata(e) => n == e,
+            Aridade::AoMenos(min) => n >= min,
+        }
+    }
+}
+
+impl fmt::Display for Aridade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Aridade::Exata(n) => write!(f, "{}", n),
+            Aridade::AoMenos(n) => write!(f, "ao menos {}", n),
+        }
+    }
+}
+
+pub type FuncaoNativa = Arc<dyn Fn(&[f64]) -> f64 + Send + Sync>;
+
+#[derive(Clone)]
+pub struct Funcao {
+    pub aridade: Aridade,
+    pub corpo: FuncaoNativa,
+}
+
+impl fmt::Debug for Funcao {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Funcao")
+            .field("aridade", &self.aridade)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Ambiente {
+    variaveis: HashMap<String, f64>,
+    funcoes: HashMap<String, Funcao>,
+}
+
+// Calcula o seno de um ângulo em graus
+fn seno_graus(valor: f64) -> f64 {
+    (valor * PI / 180.0).sin()
+}
+
+// Calcula o cosseno de um ângulo em graus
+fn cosseno_graus(valor: f64) -> f64 {
+    (valor * PI / 180.0).cos()
+}
+
+impl Ambiente {
+    // Ambiente com as funções padrão: EXP, LN, SQR, SIN e COS (em graus),
+    // MAX e MIN (variádicas)
+    pub fn novo() -> Self {
+        let mut ambiente = Ambiente::vazio();
+        ambiente
+            .registrar("EXP", Aridade::Exata(1), |a| a[0].exp())
+            .registrar("LN", Aridade::Exata(1), |a| a[0].ln())
+            .registrar("SQR", Aridade::Exata(1), |a| a[0].sqrt())
+            .registrar("SIN", Aridade::Exata(1), |a| seno_graus(a[0]))
+            .registrar("COS", Aridade::Exata(1), |a| cosseno_graus(a[0]))
+            .registrar("MAX", Aridade::AoMenos(1), |a| {
+                a.iter().copied().fold(f64::NEG_INFINITY, f64::max)
+            })
+            .registrar("MIN", Aridade::AoMenos(1), |a| {
+                a.iter().copied().fold(f64::INFINITY, f64::min)
+            });
+        ambiente
+    }
+
+    // Ambiente sem variáveis nem funções
+    pub fn vazio() -> Self {
+        Ambiente::default()
+    }
+
+    pub fn definir(&mut self, nome: &str, valor: f64) -> &mut Self {
+        self.variaveis.insert(nome.to_string(), valor);
+        self
+    }
+
+    pub fn variavel(&self, nome: &str) -> Option<f64> {
+        self.variaveis.get(nome).copied()
+    }
+
+    // Registra (ou substitui) uma função; `corpo` recebe os argumentos já
+    // avaliados, em ordem, e só é chamada com uma quantidade aceita por `aridade`
+    pub fn registrar<F>(&mut self, nome: &str, aridade: Aridade, corpo: F) -> &mut Self
+    where
+        F: Fn(&[f64]) -> f64 + Send + Sync + 'static,
+    {
+        self.funcoes.insert(
+            nome.to_string(),
+            Funcao {
+                aridade,
+                corpo: Arc::new(corpo),
+            },
+        );
+        self
+    }
+
+    pub fn funcao(&self, nome: &str) -> Option<&Funcao> {
+        self.funcoes.get(nome)
+    }
+}
@@ -0,0 +1,292 @@
+// Árvore sintática montada a partir da saída do Shunting Yard
+
+use std::fmt;
+
+use crate::ambiente::Ambiente;
+use crate::erro::ErroExpressao;
+use crate::lexico::tokenizar;
+use crate::sintatico::{para_rpn, precedencia, ItemRpn};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Numero(f64),
+    Variavel {
+        nome: String,
+        posicao: usize,
+    },
+    Negacao(Box<Expr>),
+    Binaria {
+        op: char,
+        esq: Box<Expr>,
+        dir: Box<Expr>,
+    },
+    Chamada {
+        nome: String,
+        args: Vec<Expr>,
+        posicao: usize,
+    },
+}
+
+// Aplica um operador binário
+pub fn aplicar_operador(op: char, a: f64, b: f64) -> f64 {
+    match op {
+        '+' => a + b,
+        '-' => a - b,
+        '*' => a * b,
+        '/' => a / b,
+        '^' => a.powf(b),
+        _ => unreachable!("operador desconhecido '{}'", op),
+    }
+}
+
+impl Expr {
+    // Precedência do nó ao ser impresso; folhas e chamadas nunca precisam de
+    // parênteses
+    fn precedencia(&self) -> i32 {
+        match self {
+            Expr::Negacao(_) => precedencia('~'),
+            Expr::Binaria { op, .. } => precedencia(*op),
+            _ => i32::MAX,
+        }
+    }
+
+    // Analisa uma expressão infixa
+    pub fn analisar(infixa: &str) -> Result<Expr, ErroExpressao> {
+        Expr::de_rpn(&para_rpn(&tokenizar(infixa)?)?)
+    }
+
+    // Monta a árvore empilhando subárvores, como na avaliação de RPN
+    pub fn de_rpn(rpn: &[ItemRpn]) -> Result<Expr, ErroExpressao> {
+        let mut pilha: Vec<Expr> = Vec::new();
+        for item in rpn {
+            let expr = match item {
+                ItemRpn::Numero(n) => Expr::Numero(*n),
+                ItemRpn::Variavel { nome, posicao } => Expr::Variavel {
+                    nome: nome.clone(),
+                    posicao: *posicao,
+                },
+                ItemRpn::Negacao { posicao } => {
+                    let a = pilha
+                        .pop()
+                        .ok_or(ErroExpressao::RpnInvalida { posicao: *posicao })?;
+                    Expr::Negacao(Box::new(a))
+                }
+                ItemRpn::Operador { op, posicao } => {
+                    if pilha.len() < 2 {
+                        return Err(ErroExpressao::RpnInvalida { posicao: *posicao });
+                    }
+                    let dir = pilha.pop().unwrap();
+                    let esq = pilha.pop().unwrap();
+                    Expr::Binaria {
+                        op: *op,
+                        esq: Box::new(esq),
+                        dir: Box::new(dir),
+                    }
+                }
+                ItemRpn::Funcao {
+                    nome,
+                    aridade,
+                    posicao,
+                } => {
+                    if pilha.len() < *aridade {
+                        return Err(ErroExpressao::RpnInvalida { posicao: *posicao });
+                    }
+                    let args = pilha.split_off(pilha.len() - aridade);
+                    Expr::Chamada {
+                        nome: nome.clone(),
+                        args,
+                        posicao: *posicao,
+                    }
+                }
+            };
+            pilha.push(expr);
+        }
+        match (pilha.pop(), pilha.is_empty()) {
+            (Some(expr), true) => Ok(expr),
+            _ => Err(ErroExpressao::RpnInvalida { posicao: 0 }),
+        }
+    }
+
+    // Avalia a expressão com as variáveis e funções do ambiente
+    pub fn avaliar(&self, ambiente: &Ambiente) -> Result<f64, ErroExpressao> {
+        match self {
+            Expr::Numero(n) => Ok(*n),
+            Expr::Variavel { nome, posicao } => {
+                ambiente
+                    .variavel(nome)
+                    .ok_or_else(|| ErroExpressao::VariavelIndefinida {
+                        nome: nome.clone(),
+                        posicao: *posicao,
+                    })
+            }
+            Expr::Negacao(a) => Ok(-a.avaliar(ambiente)?),
+            Expr::Binaria { op, esq, dir } => Ok(aplicar_operador(
+                *op,
+                esq.avaliar(ambiente)?,
+                dir.avaliar(ambiente)?,
+            )),
+            Expr::Chamada {
+                nome,
+                args,
+                posicao,
+            } => {
+                let funcao =
+                    ambiente
+                        .funcao(nome)
+                        .ok_or_else(|| ErroExpressao::FuncaoDesconhecida {
+                            nome: nome.clone(),
+                            posicao: *posicao,
+                        })?;
+                if !funcao.aridade.aceita(args.len()) {
+                    return Err(ErroExpressao::AridadeInvalida {
+                        nome: nome.clone(),
+                        esperada: funcao.aridade,
+                        recebida: args.len(),
+                        posicao: *posicao,
+                    });
+                }
+                let valores = args
+                    .iter()
+                    .map(|a| a.avaliar(ambiente))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok((funcao.corpo)(&valores))
+            }
+        }
+    }
+}
+
+// Imprime em notação infixa só com os parênteses necessários para que a
+// expressão seja lida de volta com a mesma estrutura
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Numero(n) => write!(f, "{}", n),
+            Expr::Variavel { nome, .. } => write!(f, "{}", nome),
+            Expr::Negacao(a) => {
+                if a.precedencia() < self.precedencia() {
+                    write!(f, "-({})", a)
+                } else {
+                    write!(f, "-{}", a)
+                }
+            }
+            Expr::Binaria { op, esq, dir } => {
+                let p = self.precedencia();
+                // '^' associa à direita; '-' e '/' não são associativos
+                let esq_entre_parenteses = esq.precedencia() < p
+                    || (esq.precedencia() == p && *op == '^')
+                    || (*op == '^' && matches!(**esq, Expr::Numero(n) if n < 0.0));
+                let dir_entre_parenteses = !matches!(**dir, Expr::Negacao(_))
+                    && (dir.precedencia() < p
+                        || (dir.precedencia() == p && matches!(op, '-' | '/')));
+                if esq_entre_parenteses {
+                    write!(f, "({})", esq)?;
+                } else {
+                    write!(f, "{}", esq)?;
+                }
+                if *op == '^' {
+                    write!(f, "^")?;
+                } else {
+                    write!(f, " {} ", op)?;
+                }
+                if dir_entre_parenteses {
+                    write!(f, "({})", dir)
+                } else {
+                    write!(f, "{}", dir)
+                }
+            }
+            Expr::Chamada { nome, args, .. } => {
+                write!(f, "{}(", nome)?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", arg)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ambiente::Aridade;
+
+    #[test]
+    fn variaveis_e_funcoes_do_usuario() {
+        let mut ambiente = Ambiente::novo();
+        ambiente.definir("preco", 120.0).definir("desconto", 0.1);
+        ambiente.registrar("ARRED", Aridade::Exata(2), |a| {
+            let fator = 10f64.powf(a[1]);
+            (a[0] * fator).round() / fator
+        });
+
+        let expr = Expr::analisar("ARRED(preco * (1 - desconto) / 7, 2)").unwrap();
+        assert_eq!(expr.avaliar(&ambiente), Ok(15.43));
+        let expr = Expr::analisar("MAX(1, preco, -desconto) + MIN(3, 2)").unwrap();
+        assert_eq!(expr.avaliar(&ambiente), Ok(122.0));
+    }
+
+    #[test]
+    fn impressao_com_parenteses_minimos() {
+        let casos = [
+            ("((a + b)) * c", "(a + b) * c"),
+            ("a - (b - c) + (d + e)", "a - (b - c) + d + e"),
+            ("a / (b * c) * (d / e)", "a / (b * c) * d / e"),
+            ("a^b^c", "a^b^c"),
+            ("(a^b)^c", "(a^b)^c"),
+            ("-(a + b) * -x^2", "-(a + b) * -x^2"),
+            ("(-a)^2 + 2^-x", "(-a)^2 + 2^-x"),
+            ("(-2)^2 - -3", "(-2)^2 - -3"),
+            ("MAX(1.5, (x), SIN(y))", "MAX(1.5, x, SIN(y))"),
+        ];
+        for (infixa, esperada) in casos {
+            let expr = Expr::analisar(infixa).unwrap();
+            let impressa = expr.to_string();
+            assert_eq!(impressa, esperada);
+            // lida de volta, a expressão tem o mesmo valor
+            let mut ambiente = Ambiente::novo();
+            for (nome, valor) in [("a", 1.5), ("b", 2.0), ("c", 3.0), ("d", 5.0)] {
+                ambiente.definir(nome, valor);
+            }
+            ambiente
+                .definir("e", 7.0)
+                .definir("x", 0.5)
+                .definir("y", 30.0);
+            assert_eq!(
+                Expr::analisar(&impressa).unwrap().avaliar(&ambiente),
+                expr.avaliar(&ambiente)
+            );
+        }
+    }
+
+    #[test]
+    fn erros_de_avaliacao() {
+        let ambiente = Ambiente::novo();
+        let avaliar = |s: &str| Expr::analisar(s).unwrap().avaliar(&ambiente);
+        assert_eq!(
+            avaliar("2 * taxa"),
+            Err(ErroExpressao::VariavelIndefinida {
+                nome: "taxa".into(),
+                posicao: 4
+            })
+        );
+        assert_eq!(
+            avaliar("SIN(3+4)*INVALID(2-1)"),
+            Err(ErroExpressao::FuncaoDesconhecida {
+                nome: "INVALID".into(),
+                posicao: 9
+            })
+        );
+        assert_eq!(
+            avaliar("SQR(1, 2)"),
+            Err(ErroExpressao::AridadeInvalida {
+                nome: "SQR".into(),
+                esperada: Aridade::Exata(1),
+                recebida: 2,
+                posicao: 0
+            })
+        );
+    }
+}
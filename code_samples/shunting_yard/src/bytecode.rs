@@ -0,0 +1,290 @@
+// Compilação da RPN para bytecode e máquina de pilha, para avaliar a mesma
+// expressão muitas vezes com valores diferentes nas variáveis
+
+use crate::ambiente::{Ambiente, Funcao};
+use crate::ast::aplicar_operador;
+use crate::erro::ErroExpressao;
+use crate::lexico::tokenizar;
+use crate::sintatico::{para_rpn, ItemRpn};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Instrucao {
+    // Empilha uma constante
+    Constante(f64),
+    // Empilha o valor da variável no índice dado (veja `Programa::variaveis`)
+    Variavel(usize),
+    Negacao,
+    // Operador binário: + - * / ^
+    Operador(char),
+    // Chama a função de índice `funcao` com os `aridade` valores do topo
+    Chamada { funcao: usize, aridade: usize },
+}
+
+// Expressão compilada. As funções são resolvidas no ambiente durante a
+// compilação e consideradas puras: chamadas só com argumentos constantes são
+// calculadas uma única vez, como os operadores
+#[derive(Debug, Clone)]
+pub struct Programa {
+    instrucoes: Vec<Instrucao>,
+    variaveis: Vec<String>,
+    funcoes: Vec<(String, Funcao)>,
+    tamanho_pilha: usize,
+}
+
+impl Programa {
+    // Compila uma expressão infixa
+    pub fn compilar(infixa: &str, ambiente: &Ambiente) -> Result<Programa, ErroExpressao> {
+        Programa::de_rpn(&para_rpn(&tokenizar(infixa)?)?, ambiente)
+    }
+
+    // Compila a saída do Shunting Yard, verificando funções, aridades e a
+    // altura da pilha para que a execução não precise verificar nada
+    pub fn de_rpn(rpn: &[ItemRpn], ambiente: &Ambiente) -> Result<Programa, ErroExpressao> {
+        let mut programa = Programa {
+            instrucoes: Vec::with_capacity(rpn.len()),
+            variaveis: Vec::new(),
+            funcoes: Vec::new(),
+            tamanho_pilha: 0,
+        };
+        let mut altura = 0;
+
+        for item in rpn {
+            // quantos valores a instrução desempilha, e a posição para erros
+            let (instrucao, consome, posicao) = match item {
+                ItemRpn::Numero(n) => (Instrucao::Constante(*n), 0, 0),
+                ItemRpn::Variavel { nome, posicao } => {
+                    (Instrucao::Variavel(programa.variavel(nome)), 0, *posicao)
+                }
+                ItemRpn::Negacao { posicao } => (Instrucao::Negacao, 1, *posicao),
+                ItemRpn::Operador { op, posicao } => (Instrucao::Operador(*op), 2, *posicao),
+                ItemRpn::Funcao {
+                    nome,
+                    aridade,
+                    posicao,
+                } => {
+                    let funcao =
+                        ambiente
+                            .funcao(nome)
+                            .ok_or_else(|| ErroExpressao::FuncaoDesconhecida {
+                                nome: nome.clone(),
+                                posicao: *posicao,
+                            })?;
+                    if !funcao.aridade.aceita(*aridade) {
+                        return Err(ErroExpressao::AridadeInvalida {
+                            nome: nome.clone(),
+                            esperada: funcao.aridade,
+                            recebida: *aridade,
+                            posicao: *posicao,
+                        });
+                    }
+                    let indice = programa.funcao(nome, funcao);
+                    let instrucao = Instrucao::Chamada {
+                        funcao: indice,
+                        aridade: *aridade,
+                    };
+                    (instrucao, *aridade, *posicao)
+                }
+            };
+            if altura < consome {
+                return Err(ErroExpressao::RpnInvalida { posicao });
+            }
+            altura = altura - consome + 1;
+            programa.tamanho_pilha = programa.tamanho_pilha.max(altura);
+            programa.emitir(instrucao, consome);
+        }
+
+        if altura != 1 {
+            return Err(ErroExpressao::RpnInvalida { posicao: 0 });
+        }
+        Ok(programa)
+    }
+
+    // Índice da variável, atribuído na ordem da primeira ocorrência
+    fn variavel(&mut self, nome: &str) -> usize {
+        match self.variaveis.iter().position(|v| v == nome) {
+            Some(i) => i,
+            None => {
+                self.variaveis.push(nome.to_string());
+                self.variaveis.len() - 1
+            }
+        }
+    }
+
+    fn funcao(&mut self, nome: &str, funcao: &Funcao) -> usize {
+        match self.funcoes.iter().position(|(f, _)| f == nome) {
+            Some(i) => i,
+            None => {
+                self.funcoes.push((nome.to_string(), funcao.clone()));
+                self.funcoes.len() - 1
+            }
+        }
+    }
+
+    // Acrescenta a instrução; se todos os operandos já são constantes, o
+    // resultado é calculado agora e vira uma constante (constant folding)
+    fn emitir(&mut self, instrucao: Instrucao, consome: usize) {
+        let inicio = self.instrucoes.len() - consome;
+        let constantes: Option<Vec<f64>> = self.instrucoes[inicio..]
+            .iter()
+            .map(|i| match i {
+                Instrucao::Constante(c) => Some(*c),
+                _ => None,
+            })
+            .collect();
+        match constantes {
+            Some(mut pilha) if consome > 0 => {
+                self.executar_instrucao(instrucao, &mut pilha, &[]);
+                self.instrucoes.truncate(inicio);
+                self.instrucoes.push(Instrucao::Constante(pilha[0]));
+            }
+            _ => self.instrucoes.push(instrucao),
+        }
+    }
+
+    pub fn instrucoes(&self) -> &[Instrucao] {
+        &self.instrucoes
+    }
+
+    // Nomes das variáveis, na ordem esperada em `executar`
+    pub fn variaveis(&self) -> &[String] {
+        &self.variaveis
+    }
+
+    pub fn indice(&self, nome: &str) -> Option<usize> {
+        self.variaveis.iter().position(|v| v == nome)
+    }
+
+    // Avalia o programa; `valores[i]` é o valor de `variaveis()[i]`.
+    // Entra em pânico se faltarem valores
+    pub fn executar(&self, valores: &[f64]) -> f64 {
+        let mut pilha = Vec::with_capacity(self.tamanho_pilha);
+        self.executar_com_pilha(valores, &mut pilha)
+    }
+
+    // Igual a `executar`, mas reaproveita a pilha entre avaliações
+    pub fn executar_com_pilha(&self, valores: &[f64], pilha: &mut Vec<f64>) -> f64 {
+        assert!(
+            valores.len() >= self.variaveis.len(),
+            "esperados {} valores, recebidos {}",
+            self.variaveis.len(),
+            valores.len()
+        );
+        pilha.clear();
+        for instrucao in &self.instrucoes {
+            self.executar_instrucao(*instrucao, pilha, valores);
+        }
+        pilha.pop().unwrap()
+    }
+
+    fn executar_instrucao(&self, instrucao: Instrucao, pilha: &mut Vec<f64>, valores: &[f64]) {
+        match instrucao {
+            Instrucao::Constante(c) => pilha.push(c),
+            Instrucao::Variavel(i) => pilha.push(valores[i]),
+            Instrucao::Negacao => {
+                let a = pilha.last_mut().unwrap();
+                *a = -*a;
+            }
+            Instrucao::Operador(op) => {
+                let b = pilha.pop().unwrap();
+                let a = pilha.last_mut().unwrap();
+                *a = aplicar_operador(op, *a, b);
+            }
+            Instrucao::Chamada { funcao, aridade } => {
+                let inicio = pilha.len() - aridade;
+                let valor = (self.funcoes[funcao].1.corpo)(&pilha[inicio..]);
+                pilha.truncate(inicio);
+                pilha.push(valor);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ambiente::Aridade;
+    use crate::ast::Expr;
+
+    #[test]
+    fn dobra_constantes() {
+        let ambiente = Ambiente::novo();
+        let programa = Programa::compilar("x * (2 + 3) - MAX(1, 4) ^ 0.5 + -y", &ambiente).unwrap();
+        assert_eq!(
+            programa.instrucoes(),
+            &[
+                Instrucao::Variavel(0),
+                Instrucao::Constante(5.0),
+                Instrucao::Operador('*'),
+                Instrucao::Constante(2.0),
+                Instrucao::Operador('-'),
+                Instrucao::Variavel(1),
+                Instrucao::Negacao,
+                Instrucao::Operador('+'),
+            ]
+        );
+        assert_eq!(programa.variaveis(), &["x", "y"]);
+        assert_eq!(programa.executar(&[3.0, 1.0]), 12.0);
+    }
+
+    #[test]
+    fn mesmo_resultado_da_arvore() {
+        let mut ambiente = Ambiente::novo();
+        ambiente.registrar("MEDIA", Aridade::AoMenos(1), |a| {
+            a.iter().sum::<f64>() / a.len() as f64
+        });
+        let expressoes = [
+            "3+4*2/(1-5)^2^3",
+            "SIN(a+4)*COS(2-b)",
+            "-a^2 + -(b - 1) * EXP(0)",
+            "MEDIA(a, b, a * b, 10) / SQR(b)",
+            "MIN(a) - MAX(b, 2, -a)",
+        ];
+        let mut pilha = Vec::new();
+        for infixa in expressoes {
+            let expr = Expr::analisar(infixa).unwrap();
+            let programa = Programa::compilar(infixa, &ambiente).unwrap();
+            for (a, b) in [(1.5, 4.0), (-2.0, 9.0), (0.0, 0.25)] {
+                ambiente.definir("a", a).definir("b", b);
+                let valores: Vec<f64> = programa
+                    .variaveis()
+                    .iter()
+                    .map(|nome| ambiente.variavel(nome).unwrap())
+                    .collect();
+                let esperado = expr.avaliar(&ambiente).unwrap();
+                assert_eq!(programa.executar_com_pilha(&valores, &mut pilha), esperado);
+            }
+        }
+    }
+
+    #[test]
+    fn erros_de_compilacao() {
+        let ambiente = Ambiente::novo();
+        assert_eq!(
+            Programa::compilar("x + TAN(1)", &ambiente).unwrap_err(),
+            ErroExpressao::FuncaoDesconhecida {
+                nome: "TAN".into(),
+                posicao: 4
+            }
+        );
+        assert_eq!(
+            Programa::compilar("SIN()", &ambiente).unwrap_err(),
+            ErroExpressao::AridadeInvalida {
+                nome: "SIN".into(),
+                esperada: Aridade::Exata(1),
+                recebida: 0,
+                posicao: 0
+            }
+        );
+        let rpn = [
+            ItemRpn::Numero(1.0),
+            ItemRpn::Operador {
+                op: '+',
+                posicao: 1,
+            },
+        ];
+        assert_eq!(
+            Programa::de_rpn(&rpn, &ambiente).unwrap_err(),
+            ErroExpressao::RpnInvalida { posicao: 1 }
+        );
+    }
+}
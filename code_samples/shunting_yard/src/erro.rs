@@ -0,0 +1,117 @@
+// Erros de análise e avaliação, sempre com a posição (índice do caractere)
+// onde o problema foi encontrado
+
+use std::fmt;
+
+use crate::ambiente::Aridade;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErroExpressao {
+    // Caractere que não faz parte da linguagem, como '$' ou '#'
+    CaractereInvalido {
+        caractere: char,
+        posicao: usize,
+    },
+    // Número mal formado, como "1.2.3"
+    NumeroInvalido {
+        texto: String,
+        posicao: usize,
+    },
+    // Faltou um operando: "3+", "3++4", "()"
+    OperandoEsperado {
+        posicao: usize,
+    },
+    // Dois operandos seguidos: "3 4", "2(3)"
+    OperadorEsperado {
+        posicao: usize,
+    },
+    ParentesesDesbalanceados {
+        posicao: usize,
+    },
+    // Vírgula fora da lista de argumentos de uma função
+    VirgulaInesperada {
+        posicao: usize,
+    },
+    VariavelIndefinida {
+        nome: String,
+        posicao: usize,
+    },
+    FuncaoDesconhecida {
+        nome: String,
+        posicao: usize,
+    },
+    AridadeInvalida {
+        nome: String,
+        esperada: Aridade,
+        recebida: usize,
+        posicao: usize,
+    },
+    // Função sem regra de derivação, como MAX ou uma função do usuário
+    DerivadaDesconhecida {
+        nome: String,
+        posicao: usize,
+    },
+    // Expressão em RPN que não deixa exatamente um valor na pilha
+    RpnInvalida {
+        posicao: usize,
+    },
+}
+
+impl ErroExpressao {
+    // Índice (em caracteres) do ponto da expressão onde o erro ocorreu
+    pub fn posicao(&self) -> usize {
+        match self {
+            ErroExpressao::CaractereInvalido { posicao, .. }
+            | ErroExpressao::NumeroInvalido { posicao, .. }
+            | ErroExpressao::OperandoEsperado { posicao }
+            | ErroExpressao::OperadorEsperado { posicao }
+            | ErroExpressao::ParentesesDesbalanceados { posicao }
+            | ErroExpressao::VirgulaInesperada { posicao }
+            | ErroExpressao::VariavelIndefinida { posicao, .. }
+            | ErroExpressao::FuncaoDesconhecida { posicao, .. }
+            | ErroExpressao::AridadeInvalida { posicao, .. }
+            | ErroExpressao::DerivadaDesconhecida { posicao, .. }
+            | ErroExpressao::RpnInvalida { posicao } => *posicao,
+        }
+    }
+}
+
+impl fmt::Display for ErroExpressao {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErroExpressao::CaractereInvalido { caractere, .. } => {
+                write!(f, "caractere inválido '{}'", caractere)
+            }
+            ErroExpressao::NumeroInvalido { texto, .. } => write!(f, "número inválido '{}'", texto),
+            ErroExpressao::OperandoEsperado { .. } => write!(f, "operando esperado"),
+            ErroExpressao::OperadorEsperado { .. } => write!(f, "operador esperado"),
+            ErroExpressao::ParentesesDesbalanceados { .. } => {
+                write!(f, "parênteses desbalanceados")
+            }
+            ErroExpressao::VirgulaInesperada { .. } => write!(f, "vírgula fora de uma função"),
+            ErroExpressao::VariavelIndefinida { nome, .. } => {
+                write!(f, "variável '{}' não definida", nome)
+            }
+            ErroExpressao::FuncaoDesconhecida { nome, .. } => {
+                write!(f, "função '{}' desconhecida", nome)
+            }
+            ErroExpressao::AridadeInvalida {
+                nome,
+                esperada,
+                recebida,
+                ..
+            } => write!(
+                f,
+                "função '{}' espera {} argumento(s), recebeu {}",
+                nome, esperada, recebida
+            ),
+            ErroExpressao::DerivadaDesconhecida { nome, .. } => {
+                write!(f, "não há regra de derivação para '{}'", nome)
+            }
+            ErroExpressao::RpnInvalida { .. } => write!(f, "expressão RPN inválida"),
+        }?;
+        write!(f, " na posição {}", self.posicao())
+    }
+}
+
+impl std::error::Error for ErroExpressao {}
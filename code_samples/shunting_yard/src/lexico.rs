@@ -0,0 +1,156 @@
+// Análise léxica: divide a expressão infixa em tokens com posição
+
+use crate::erro::ErroExpressao;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TipoToken {
+    Numero(f64),
+    // Nome de variável ou de função (quando seguido por '(')
+    Identificador(String),
+    // Operador binário: + - * / ^
+    Operador(char),
+    // '-' unário antes de algo que não é um número literal, como em -(x+1)
+    Negacao,
+    AbreParenteses,
+    FechaParenteses,
+    Virgula,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub tipo: TipoToken,
+    // Índice do primeiro caractere do token na expressão
+    pub posicao: usize,
+}
+
+// Verifica se um caractere é operador
+pub fn e_operador(c: char) -> bool {
+    c == '+' || c == '-' || c == '*' || c == '/' || c == '^'
+}
+
+fn e_digito(c: char) -> bool {
+    c.is_ascii_digit() || c == '.'
+}
+
+// Analisa a expressão e divide em tokens
+pub fn tokenizar(infixa: &str) -> Result<Vec<Token>, ErroExpressao> {
+    let mut tokens = Vec::new();
+    let mut esperando_operando = true; // Indica se estamos esperando um operando (usado para operadores unários)
+
+    let chars: Vec<char> = infixa.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let posicao = i;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        // '-' unário seguido de número vira um número negativo, como antes
+        let negativo =
+            c == '-' && esperando_operando && i + 1 < chars.len() && e_digito(chars[i + 1]);
+        if e_digito(c) || negativo {
+            let inicio = i;
+            i += 1;
+            while i < chars.len() && e_digito(chars[i]) {
+                i += 1;
+            }
+            let texto: String = chars[inicio..i].iter().collect();
+            let valor = texto
+                .parse::<f64>()
+                .map_err(|_| ErroExpressao::NumeroInvalido {
+                    texto: texto.clone(),
+                    posicao,
+                })?;
+            tokens.push(Token {
+                tipo: TipoToken::Numero(valor),
+                posicao,
+            });
+            esperando_operando = false;
+            continue;
+        }
+
+        let tipo = if c.is_alphabetic() || c == '_' {
+            let inicio = i;
+            while i + 1 < chars.len() && (chars[i + 1].is_alphanumeric() || chars[i + 1] == '_') {
+                i += 1;
+            }
+            esperando_operando = false;
+            TipoToken::Identificador(chars[inicio..=i].iter().collect())
+        } else if c == '-' && esperando_operando {
+            TipoToken::Negacao
+        } else if e_operador(c) {
+            esperando_operando = true;
+            TipoToken::Operador(c)
+        } else if c == '(' {
+            esperando_operando = true;
+            TipoToken::AbreParenteses
+        } else if c == ')' {
+            esperando_operando = false;
+            TipoToken::FechaParenteses
+        } else if c == ',' {
+            esperando_operando = true;
+            TipoToken::Virgula
+        } else {
+            return Err(ErroExpressao::CaractereInvalido {
+                caractere: c,
+                posicao,
+            });
+        };
+        tokens.push(Token { tipo, posicao });
+        i += 1;
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tipos(infixa: &str) -> Vec<TipoToken> {
+        tokenizar(infixa)
+            .unwrap()
+            .into_iter()
+            .map(|t| t.tipo)
+            .collect()
+    }
+
+    #[test]
+    fn menos_unario() {
+        assert_eq!(
+            tipos("1--5"),
+            vec![
+                TipoToken::Numero(1.0),
+                TipoToken::Operador('-'),
+                TipoToken::Numero(-5.0)
+            ]
+        );
+        assert_eq!(
+            tipos("-(x)"),
+            vec![
+                TipoToken::Negacao,
+                TipoToken::AbreParenteses,
+                TipoToken::Identificador("x".into()),
+                TipoToken::FechaParenteses
+            ]
+        );
+    }
+
+    #[test]
+    fn erros_com_posicao() {
+        assert_eq!(
+            tokenizar("2 + $"),
+            Err(ErroExpressao::CaractereInvalido {
+                caractere: '$',
+                posicao: 4
+            })
+        );
+        assert_eq!(
+            tokenizar("1.2.3"),
+            Err(ErroExpressao::NumeroInvalido {
+                texto: "1.2.3".into(),
+                posicao: 0
+            })
+        );
+    }
+}
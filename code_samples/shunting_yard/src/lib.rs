@@ -0,0 +1,129 @@
+/*
+Implementação do algoritmo Shunting Yard © 2024 por Cleuton Sampaio
+licenciado sob CC BY-SA 4.0. Para ver uma cópia desta licença,
+visite https://creativecommons.org/licenses/by-sa/4.0/
+*/
+
+// Biblioteca de expressões: tokens com posição, conversão para RPN pelo
+// Shunting Yard, árvore sintática com derivação e simplificação, bytecode
+// para avaliações repetidas, variáveis e funções registradas em um
+// Ambiente, e erros tipados
+
+pub mod ambiente;
+pub mod ast;
+pub mod bytecode;
+pub mod erro;
+pub mod lexico;
+pub mod simbolico;
+pub mod sintatico;
+
+pub use ambiente::{Ambiente, Aridade, Funcao};
+pub use ast::Expr;
+pub use bytecode::{Instrucao, Programa};
+pub use erro::ErroExpressao;
+pub use lexico::{tokenizar, TipoToken, Token};
+pub use sintatico::{para_rpn, ItemRpn};
+
+use ast::aplicar_operador;
+
+// Converte uma expressão infixa para o texto da RPN, com um espaço após
+// cada item ("3 4 + SIN ")
+pub fn infixa_para_posfixa(infixa: &str) -> Result<String, ErroExpressao> {
+    let rpn = para_rpn(&tokenizar(infixa)?)?;
+    let mut saida = String::new();
+    for item in rpn {
+        saida.push_str(&item.to_string());
+        saida.push(' ');
+    }
+    Ok(saida)
+}
+
+// Avalia uma expressão em notação polonesa reversa (RPN) no formato de
+// `infixa_para_posfixa`. Um nome sem ":n" é variável se estiver definido no
+// ambiente e, caso contrário, uma função de um argumento.
+pub fn avaliar_rpn(rpn: &str, ambiente: &Ambiente) -> Result<f64, ErroExpressao> {
+    let mut pilha: Vec<f64> = Vec::new();
+    let mut posicao = 0;
+
+    for parte in rpn.split(' ') {
+        let inicio = posicao;
+        posicao += parte.chars().count() + 1;
+        let token = parte.trim();
+        if token.is_empty() {
+            continue;
+        }
+        let invalida = ErroExpressao::RpnInvalida { posicao: inicio };
+        let mut chars = token.chars();
+        let primeiro = chars.next().unwrap();
+        let segundo = chars.next();
+
+        if primeiro.is_ascii_digit()
+            || (matches!(primeiro, '-' | '.')
+                && segundo.is_some_and(|c| c.is_ascii_digit() || c == '.'))
+        {
+            let valor = token
+                .parse::<f64>()
+                .map_err(|_| ErroExpressao::NumeroInvalido {
+                    texto: token.to_string(),
+                    posicao: inicio,
+                })?;
+            pilha.push(valor);
+        } else if token == "~" {
+            let a = pilha.pop().ok_or(invalida)?;
+            pilha.push(-a);
+        } else if segundo.is_none() && lexico::e_operador(primeiro) {
+            if pilha.len() < 2 {
+                return Err(invalida);
+            }
+            let b = pilha.pop().unwrap();
+            let a = pilha.pop().unwrap();
+            pilha.push(aplicar_operador(primeiro, a, b));
+        } else if primeiro.is_alphabetic() || primeiro == '_' {
+            let (nome, aridade) = match token.split_once(':') {
+                Some((nome, n)) => match n.parse::<usize>() {
+                    Ok(n) => (nome, n),
+                    Err(_) => return Err(invalida),
+                },
+                None => match ambiente.variavel(token) {
+                    Some(valor) => {
+                        pilha.push(valor);
+                        continue;
+                    }
+                    None => (token, 1),
+                },
+            };
+            let funcao =
+                ambiente
+                    .funcao(nome)
+                    .ok_or_else(|| ErroExpressao::FuncaoDesconhecida {
+                        nome: nome.to_string(),
+                        posicao: inicio,
+                    })?;
+            if !funcao.aridade.aceita(aridade) {
+                return Err(ErroExpressao::AridadeInvalida {
+                    nome: nome.to_string(),
+                    esperada: funcao.aridade,
+                    recebida: aridade,
+                    posicao: inicio,
+                });
+            }
+            if pilha.len() < aridade {
+                return Err(invalida);
+            }
+            let args = pilha.split_off(pilha.len() - aridade);
+            pilha.push((funcao.corpo)(&args));
+        } else {
+            return Err(ErroExpressao::CaractereInvalido {
+                caractere: primeiro,
+                posicao: inicio,
+            });
+        }
+    }
+
+    if pilha.len() != 1 {
+        return Err(ErroExpressao::RpnInvalida {
+            posicao: rpn.chars().count(),
+        });
+    }
+    Ok(pilha.pop().unwrap())
+}
@@ -0,0 +1,481 @@
+// Derivação simbólica e simplificação algébrica da árvore sintática
+
+use crate::ambiente::Aridade;
+use crate::ast::{aplicar_operador, Expr};
+use crate::erro::ErroExpressao;
+
+// SIN e COS recebem graus: d/dx SIN(x) = π/180 * COS(x)
+const GRAUS: f64 = std::f64::consts::PI / 180.0;
+
+fn num(n: f64) -> Expr {
+    Expr::Numero(n)
+}
+
+fn bin(op: char, esq: Expr, dir: Expr) -> Expr {
+    Expr::Binaria {
+        op,
+        esq: Box::new(esq),
+        dir: Box::new(dir),
+    }
+}
+
+fn chamada(nome: &str, arg: Expr, posicao: usize) -> Expr {
+    Expr::Chamada {
+        nome: nome.to_string(),
+        args: vec![arg],
+        posicao,
+    }
+}
+
+// Compara a estrutura de duas expressões, ignorando as posições
+fn equivalentes(a: &Expr, b: &Expr) -> bool {
+    match (a, b) {
+        (Expr::Numero(x), Expr::Numero(y)) => x == y,
+        (Expr::Variavel { nome: x, .. }, Expr::Variavel { nome: y, .. }) => x == y,
+        (Expr::Negacao(x), Expr::Negacao(y)) => equivalentes(x, y),
+        (
+            Expr::Binaria {
+                op: o1,
+                esq: e1,
+                dir: d1,
+            },
+            Expr::Binaria {
+                op: o2,
+                esq: e2,
+                dir: d2,
+            },
+        ) => o1 == o2 && equivalentes(e1, e2) && equivalentes(d1, d2),
+        (
+            Expr::Chamada {
+                nome: n1, args: a1, ..
+            },
+            Expr::Chamada {
+                nome: n2, args: a2, ..
+            },
+        ) => n1 == n2 && a1.len() == a2.len() && a1.iter().zip(a2).all(|(x, y)| equivalentes(x, y)),
+        _ => false,
+    }
+}
+
+// Escreve `c` como p/q com q pequeno, para imprimir "2 * x / 3" em vez de
+// "0.6666666666666666 * x"
+fn fracao(c: f64) -> Option<(f64, f64)> {
+    (1..=100).map(f64::from).find_map(|q| {
+        let p = c * q;
+        ((p - p.round()).abs() < 1e-9 * p.abs().max(1.0)).then(|| (p.round(), q))
+    })
+}
+
+// Fator de um produto: (base, expoente), com expoente negativo no denominador
+type Fator = (Expr, f64);
+
+// Termo de uma soma: (coeficiente, fatores)
+type Termo = (f64, Vec<Fator>);
+
+// Achata um produto em coeficiente numérico e fatores
+fn fatores(e: &Expr) -> Termo {
+    fn coletar(e: &Expr, expoente: f64, coeficiente: &mut f64, fatores: &mut Vec<Fator>) {
+        match e {
+            Expr::Binaria { op: '*', esq, dir } => {
+                coletar(esq, expoente, coeficiente, fatores);
+                coletar(dir, expoente, coeficiente, fatores);
+            }
+            Expr::Binaria { op: '/', esq, dir } => {
+                coletar(esq, expoente, coeficiente, fatores);
+                coletar(dir, -expoente, coeficiente, fatores);
+            }
+            Expr::Negacao(a) => {
+                *coeficiente = -*coeficiente;
+                coletar(a, expoente, coeficiente, fatores);
+            }
+            // uma divisão por zero fica como está
+            Expr::Numero(n) if *n != 0.0 || expoente > 0.0 => *coeficiente *= n.powf(expoente),
+            Expr::Numero(_) => adicionar_fator(fatores, e.clone(), expoente),
+            _ => match e.passo() {
+                s @ (Expr::Binaria { op: '*' | '/', .. } | Expr::Negacao(_) | Expr::Numero(_)) => {
+                    coletar(&s, expoente, coeficiente, fatores)
+                }
+                Expr::Binaria { op: '^', esq, dir } => match *dir {
+                    Expr::Numero(k) => adicionar_fator(fatores, *esq, k * expoente),
+                    dir => adicionar_fator(fatores, bin('^', *esq, dir), expoente),
+                },
+                s => adicionar_fator(fatores, s, expoente),
+            },
+        }
+    }
+
+    let mut coeficiente = 1.0;
+    let mut lista = Vec::new();
+    coletar(e, 1.0, &mut coeficiente, &mut lista);
+    (coeficiente, lista)
+}
+
+// x * x vira x^2 e x / x desaparece
+fn adicionar_fator(fatores: &mut Vec<Fator>, base: Expr, expoente: f64) {
+    match fatores.iter_mut().find(|(b, _)| equivalentes(b, &base)) {
+        Some((_, e)) => *e += expoente,
+        None => fatores.push((base, expoente)),
+    }
+}
+
+// Termos semelhantes têm os mesmos fatores, em qualquer ordem: x * y e y * x
+fn mesmos_fatores(f: &[Fator], g: &[Fator]) -> bool {
+    f.len() == g.len()
+        && f.iter()
+            .all(|(base, k)| g.iter().any(|(b, e)| e == k && equivalentes(b, base)))
+}
+
+fn montar_produto(coeficiente: f64, fatores: &[Fator]) -> Expr {
+    let potencia = |base: &Expr, k: f64| {
+        if k == 1.0 {
+            base.clone()
+        } else {
+            bin('^', base.clone(), num(k))
+        }
+    };
+    if coeficiente == 0.0 || fatores.iter().all(|(_, k)| *k == 0.0) {
+        return num(coeficiente + 0.0);
+    }
+    let (p, q) = fracao(coeficiente.abs()).unwrap_or((coeficiente.abs(), 1.0));
+    let mut numerador = Vec::new();
+    let mut denominador = Vec::new();
+    if p != 1.0 {
+        numerador.push(num(p));
+    }
+    if q != 1.0 {
+        denominador.push(num(q));
+    }
+    for (base, k) in fatores {
+        if *k > 0.0 {
+            numerador.push(potencia(base, *k));
+        } else if *k < 0.0 {
+            denominador.push(potencia(base, -k));
+        }
+    }
+    if numerador.is_empty() {
+        numerador.push(num(1.0));
+    }
+    // o sinal vai no primeiro fator: -2 * x, -x * y
+    if coeficiente < 0.0 {
+        numerador[0] = match &numerador[0] {
+            Expr::Numero(n) => num(-n),
+            primeiro => Expr::Negacao(Box::new(primeiro.clone())),
+        };
+    }
+    let produto = |lista: Vec<Expr>| lista.into_iter().reduce(|a, b| bin('*', a, b));
+    let numerador = produto(numerador).unwrap();
+    match produto(denominador) {
+        Some(denominador) => bin('/', numerador, denominador),
+        None => numerador,
+    }
+}
+
+// Achata uma soma em constante e termos, com termos semelhantes já somados
+fn termos(e: &Expr) -> (f64, Vec<Termo>) {
+    fn coletar(e: &Expr, sinal: f64, constante: &mut f64, termos: &mut Vec<Termo>) {
+        match e {
+            Expr::Binaria { op: '+', esq, dir } => {
+                coletar(esq, sinal, constante, termos);
+                coletar(dir, sinal, constante, termos);
+            }
+            Expr::Binaria { op: '-', esq, dir } => {
+                coletar(esq, sinal, constante, termos);
+                coletar(dir, -sinal, constante, termos);
+            }
+            Expr::Negacao(a) => coletar(a, -sinal, constante, termos),
+            Expr::Numero(n) => *constante += sinal * n,
+            _ => match e.passo() {
+                s @ (Expr::Binaria { op: '+' | '-', .. } | Expr::Negacao(_) | Expr::Numero(_)) => {
+                    coletar(&s, sinal, constante, termos)
+                }
+                s => {
+                    let (c, mut f) = fatores(&s);
+                    f.retain(|(_, k)| *k != 0.0);
+                    if f.is_empty() {
+                        *constante += sinal * c;
+                    } else {
+                        match termos.iter_mut().find(|(_, g)| mesmos_fatores(g, &f)) {
+                            Some((soma, _)) => *soma += sinal * c,
+                            None => termos.push((sinal * c, f)),
+                        }
+                    }
+                }
+            },
+        }
+    }
+
+    let mut constante = 0.0;
+    let mut lista = Vec::new();
+    coletar(e, 1.0, &mut constante, &mut lista);
+    (constante, lista)
+}
+
+fn montar_soma(constante: f64, termos: &[Termo]) -> Expr {
+    let mut soma: Option<Expr> = None;
+    for (c, f) in termos.iter().filter(|(c, _)| *c != 0.0) {
+        soma = Some(match soma {
+            None => montar_produto(*c, f),
+            Some(s) => {
+                let op = if *c < 0.0 { '-' } else { '+' };
+                bin(op, s, montar_produto(c.abs(), f))
+            }
+        });
+    }
+    match soma {
+        None => num(constante + 0.0),
+        Some(s) if constante > 0.0 => bin('+', s, num(constante)),
+        Some(s) if constante < 0.0 => bin('-', s, num(-constante)),
+        Some(s) => s,
+    }
+}
+
+impl Expr {
+    // Verifica se a expressão depende da variável
+    pub fn contem(&self, variavel: &str) -> bool {
+        match self {
+            Expr::Numero(_) => false,
+            Expr::Variavel { nome, .. } => nome == variavel,
+            Expr::Negacao(a) => a.contem(variavel),
+            Expr::Binaria { esq, dir, .. } => esq.contem(variavel) || dir.contem(variavel),
+            Expr::Chamada { args, .. } => args.iter().any(|a| a.contem(variavel)),
+        }
+    }
+
+    // Derivada em relação a `variavel`, já simplificada. Conhece os
+    // operadores e as funções EXP, LN, SQR, SIN e COS (estas em graus)
+    pub fn derivar(&self, variavel: &str) -> Result<Expr, ErroExpressao> {
+        Ok(self.derivada(variavel)?.simplificar())
+    }
+
+    fn derivada(&self, x: &str) -> Result<Expr, ErroExpressao> {
+        if !self.contem(x) {
+            return Ok(num(0.0));
+        }
+        Ok(match self {
+            Expr::Numero(_) => num(0.0),
+            Expr::Variavel { .. } => num(1.0),
+            Expr::Negacao(a) => Expr::Negacao(Box::new(a.derivada(x)?)),
+            Expr::Binaria { op, esq, dir } => {
+                let (u, v) = (esq.as_ref().clone(), dir.as_ref().clone());
+                let (du, dv) = (esq.derivada(x)?, dir.derivada(x)?);
+                match op {
+                    '+' | '-' => bin(*op, du, dv),
+                    '*' => bin('+', bin('*', du, v), bin('*', u, dv)),
+                    '/' => bin(
+                        '/',
+                        bin('-', bin('*', du, v.clone()), bin('*', u, dv)),
+                        bin('^', v, num(2.0)),
+                    ),
+                    // expoente constante: (u^n)' = n * u^(n-1) * u'
+                    '^' if !dir.contem(x) => bin(
+                        '*',
+                        bin('*', v.clone(), bin('^', u, bin('-', v, num(1.0)))),
+                        du,
+                    ),
+                    // (u^v)' = u^v * (v' * LN(u) + v * u' / u)
+                    _ => bin(
+                        '*',
+                        self.clone(),
+                        bin(
+                            '+',
+                            bin('*', dv, chamada("LN", u.clone(), 0)),
+                            bin('/', bin('*', v, du), u),
+                        ),
+                    ),
+                }
+            }
+            Expr::Chamada {
+                nome,
+                args,
+                posicao,
+            } => {
+                let desconhecida = || ErroExpressao::DerivadaDesconhecida {
+                    nome: nome.clone(),
+                    posicao: *posicao,
+                };
+                if !matches!(nome.as_str(), "EXP" | "LN" | "SQR" | "SIN" | "COS") {
+                    return Err(desconhecida());
+                }
+                if args.len() != 1 {
+                    return Err(ErroExpressao::AridadeInvalida {
+                        nome: nome.clone(),
+                        esperada: Aridade::Exata(1),
+                        recebida: args.len(),
+                        posicao: *posicao,
+                    });
+                }
+                let u = &args[0];
+                // regra da cadeia: f(u)' = f'(u) * u'
+                let externa = match nome.as_str() {
+                    "EXP" => self.clone(),
+                    "LN" => bin('/', num(1.0), u.clone()),
+                    "SQR" => bin('/', num(1.0), bin('*', num(2.0), self.clone())),
+                    "SIN" => bin('*', num(GRAUS), chamada("COS", u.clone(), *posicao)),
+                    "COS" => Expr::Negacao(Box::new(bin(
+                        '*',
+                        num(GRAUS),
+                        chamada("SIN", u.clone(), *posicao),
+                    ))),
+                    _ => return Err(desconhecida()),
+                };
+                bin('*', externa, u.derivada(x)?)
+            }
+        })
+    }
+
+    // Simplifica a expressão: calcula as partes constantes, elimina
+    // identidades (x + 0, x * 1, x^1, --x) e junta termos semelhantes
+    // (x + 2 * x = 3 * x, x * x = x^2). Chamadas de função não são
+    // calculadas, pois dependem do ambiente
+    pub fn simplificar(&self) -> Expr {
+        let mut atual = self.passo();
+        for _ in 0..8 {
+            let proxima = atual.passo();
+            if equivalentes(&proxima, &atual) {
+                break;
+            }
+            atual = proxima;
+        }
+        atual
+    }
+
+    fn passo(&self) -> Expr {
+        match self {
+            Expr::Numero(_) | Expr::Variavel { .. } => self.clone(),
+            Expr::Negacao(_) | Expr::Binaria { op: '+' | '-', .. } => {
+                let (constante, lista) = termos(self);
+                montar_soma(constante, &lista)
+            }
+            Expr::Binaria { op: '*' | '/', .. } => {
+                let (coeficiente, lista) = fatores(self);
+                montar_produto(coeficiente, &lista)
+            }
+            Expr::Binaria { op, esq, dir } => {
+                let (base, expoente) = (esq.passo(), dir.passo());
+                match (base, expoente) {
+                    (Expr::Numero(a), Expr::Numero(b)) => num(aplicar_operador(*op, a, b)),
+                    (_, Expr::Numero(0.0)) => num(1.0),
+                    (base, Expr::Numero(1.0)) => base,
+                    (Expr::Numero(1.0), _) => num(1.0),
+                    // (x^a)^n = x^(a*n) para n inteiro
+                    (Expr::Binaria { op: '^', esq, dir }, Expr::Numero(n)) if n.fract() == 0.0 => {
+                        match *dir {
+                            Expr::Numero(a) => bin('^', *esq, num(a * n)),
+                            dir => bin('^', bin('^', *esq, dir), num(n)),
+                        }
+                    }
+                    (base, expoente) => bin(*op, base, expoente),
+                }
+            }
+            Expr::Chamada {
+                nome,
+                args,
+                posicao,
+            } => Expr::Chamada {
+                nome: nome.clone(),
+                args: args.iter().map(Expr::passo).collect(),
+                posicao: *posicao,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ambiente::Ambiente;
+
+    fn simplificada(infixa: &str) -> String {
+        Expr::analisar(infixa).unwrap().simplificar().to_string()
+    }
+
+    fn derivada(infixa: &str, variavel: &str) -> Result<String, ErroExpressao> {
+        Ok(Expr::analisar(infixa)?.derivar(variavel)?.to_string())
+    }
+
+    #[test]
+    fn simplificacao() {
+        let casos = [
+            ("x + 0", "x"),
+            ("1 * x / 1", "x"),
+            ("x * 0 + y", "y"),
+            ("x^1 + y^0", "x + 1"),
+            ("--x", "x"),
+            ("(2 + 3) * x - 4 / 2", "5 * x - 2"),
+            ("2 * x + 3 * x - x", "4 * x"),
+            ("x - x", "0"),
+            ("x * x * x / x", "x^2"),
+            ("x / 3 + x / 3", "2 * x / 3"),
+            ("-x + 1", "-x + 1"),
+            ("y * 2 * x - x * y", "y * x"),
+            ("(x^2)^3 * x", "x^7"),
+            ("SIN(2 * 15) + 0", "SIN(30)"),
+        ];
+        for (infixa, esperada) in casos {
+            assert_eq!(simplificada(infixa), esperada, "{}", infixa);
+        }
+    }
+
+    #[test]
+    fn derivadas() {
+        let casos = [
+            ("x^3 + 2 * x", "3 * x^2 + 2"),
+            ("x * x - 4 * x + 1", "2 * x - 4"),
+            ("EXP(2 * x)", "2 * EXP(2 * x)"),
+            ("x / y", "1 / y"),
+            ("SQR(x)", "1 / (2 * SQR(x))"),
+            ("2^x", "2^x * LN(2)"),
+            ("x^x", "x^x * (LN(x) + 1)"),
+            ("SIN(x)", "0.017453292519943295 * COS(x)"),
+            ("MAX(y, 1) * x", "MAX(y, 1)"),
+        ];
+        for (infixa, esperada) in casos {
+            assert_eq!(derivada(infixa, "x").unwrap(), esperada, "{}", infixa);
+        }
+        assert_eq!(derivada("x / y", "y").unwrap(), "-x / y^2");
+        assert_eq!(
+            derivada("1 + MAX(x, 1)", "x"),
+            Err(ErroExpressao::DerivadaDesconhecida {
+                nome: "MAX".into(),
+                posicao: 4
+            })
+        );
+    }
+
+    // Compara a derivada com a diferença finita central
+    #[test]
+    fn derivadas_numericas() {
+        let expressoes = [
+            "COS(x^2) * SIN(3 * x)",
+            "-x^3 / (1 + x^2) - 4",
+            "EXP(-x / 2) * LN(x + 3)",
+            "SQR(x * x + 1) ^ 1.5",
+            "x^SIN(x) - (2 * x - 1) / (x - 5)",
+        ];
+        let mut ambiente = Ambiente::novo();
+        let mut valor = |e: &Expr, x: f64| {
+            ambiente.definir("x", x);
+            e.avaliar(&ambiente).unwrap()
+        };
+        let h = 1e-6;
+        for infixa in expressoes {
+            let expr = Expr::analisar(infixa).unwrap();
+            let derivada = expr.derivar("x").unwrap();
+            // a derivada impressa é lida de volta com o mesmo valor
+            let relida = Expr::analisar(&derivada.to_string()).unwrap();
+            for x in [0.5, 1.3, 2.7] {
+                let esperada = (valor(&expr, x + h) - valor(&expr, x - h)) / (2.0 * h);
+                let calculada = valor(&derivada, x);
+                assert!(
+                    (calculada - esperada).abs() < 1e-5 * esperada.abs().max(1.0),
+                    "{} em x = {}: {} != {}",
+                    derivada,
+                    x,
+                    calculada,
+                    esperada
+                );
+                assert!((valor(&relida, x) - calculada).abs() < 1e-9 * calculada.abs().max(1.0));
+            }
+        }
+    }
+}
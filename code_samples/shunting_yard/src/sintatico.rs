@@ -0,0 +1,302 @@
+// Análise sintática: algoritmo Shunting Yard, de tokens infixos para RPN
+
+use std::fmt;
+
+use crate::erro::ErroExpressao;
+use crate::lexico::{TipoToken, Token};
+
+// Um item da expressão em notação polonesa reversa
+#[derive(Debug, Clone, PartialEq)]
+pub enum ItemRpn {
+    Numero(f64),
+    Variavel {
+        nome: String,
+        posicao: usize,
+    },
+    Operador {
+        op: char,
+        posicao: usize,
+    },
+    Negacao {
+        posicao: usize,
+    },
+    Funcao {
+        nome: String,
+        aridade: usize,
+        posicao: usize,
+    },
+}
+
+// Formato textual usado por `infixa_para_posfixa` e lido por `avaliar_rpn`:
+// a negação é '~' e funções com aridade diferente de 1 levam o número de
+// argumentos após ':' (MAX:3)
+impl fmt::Display for ItemRpn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ItemRpn::Numero(n) => write!(f, "{}", n),
+            ItemRpn::Variavel { nome, .. } => write!(f, "{}", nome),
+            ItemRpn::Operador { op, .. } => write!(f, "{}", op),
+            ItemRpn::Negacao { .. } => write!(f, "~"),
+            ItemRpn::Funcao {
+                nome, aridade: 1, ..
+            } => write!(f, "{}", nome),
+            ItemRpn::Funcao { nome, aridade, .. } => write!(f, "{}:{}", nome, aridade),
+        }
+    }
+}
+
+// Retorna a precedência do operador
+pub fn precedencia(op: char) -> i32 {
+    match op {
+        '+' | '-' => 1,
+        '*' | '/' => 2,
+        '~' => 3,
+        '^' => 4,
+        _ => 0,
+    }
+}
+
+// Elementos da pilha de operadores
+enum NaPilha {
+    Operador {
+        op: char,
+        posicao: usize,
+    },
+    Negacao {
+        posicao: usize,
+    },
+    Funcao {
+        nome: String,
+        posicao: usize,
+    },
+    // `argumentos` só existe nos parênteses de uma chamada de função
+    AbreParenteses {
+        posicao: usize,
+        argumentos: Option<usize>,
+    },
+}
+
+impl NaPilha {
+    fn precedencia(&self) -> Option<i32> {
+        match self {
+            NaPilha::Operador { op, .. } => Some(precedencia(*op)),
+            NaPilha::Negacao { .. } => Some(precedencia('~')),
+            _ => None,
+        }
+    }
+
+    fn para_rpn(self) -> ItemRpn {
+        match self {
+            NaPilha::Operador { op, posicao } => ItemRpn::Operador { op, posicao },
+            NaPilha::Negacao { posicao } => ItemRpn::Negacao { posicao },
+            // funções e parênteses nunca são desempilhados diretamente
+            NaPilha::Funcao { nome, posicao } => ItemRpn::Funcao {
+                nome,
+                aridade: 1,
+                posicao,
+            },
+            NaPilha::AbreParenteses { posicao, .. } => {
+                unreachable!("parêntese na posição {} enviado para a saída", posicao)
+            }
+        }
+    }
+}
+
+// Desempilha operadores para a saída até encontrar um '(' (que fica na pilha)
+fn desempilhar_ate_parenteses(operadores: &mut Vec<NaPilha>, saida: &mut Vec<ItemRpn>) -> bool {
+    while let Some(topo) = operadores.pop() {
+        if let NaPilha::AbreParenteses { .. } = topo {
+            operadores.push(topo);
+            return true;
+        }
+        saida.push(topo.para_rpn());
+    }
+    false
+}
+
+// Converte tokens infixos para RPN usando o algoritmo de Shunting Yard,
+// validando a sintaxe ao longo do caminho
+pub fn para_rpn(tokens: &[Token]) -> Result<Vec<ItemRpn>, ErroExpressao> {
+    let mut operadores: Vec<NaPilha> = Vec::new();
+    let mut saida = Vec::new();
+    let mut esperando_operando = true;
+    let mut anterior: Option<&TipoToken> = None;
+
+    for (i, token) in tokens.iter().enumerate() {
+        let posicao = token.posicao;
+        match &token.tipo {
+            TipoToken::Numero(_) | TipoToken::Identificador(_) if !esperando_operando => {
+                return Err(ErroExpressao::OperadorEsperado { posicao });
+            }
+            TipoToken::Numero(n) => {
+                saida.push(ItemRpn::Numero(*n));
+                esperando_operando = false;
+            }
+            TipoToken::Identificador(nome) => {
+                let chamada = matches!(
+                    tokens.get(i + 1).map(|t| &t.tipo),
+                    Some(TipoToken::AbreParenteses)
+                );
+                if chamada {
+                    // Token é uma função
+                    operadores.push(NaPilha::Funcao {
+                        nome: nome.clone(),
+                        posicao,
+                    });
+                } else {
+                    saida.push(ItemRpn::Variavel {
+                        nome: nome.clone(),
+                        posicao,
+                    });
+                    esperando_operando = false;
+                }
+            }
+            TipoToken::AbreParenteses => {
+                if !esperando_operando {
+                    return Err(ErroExpressao::OperadorEsperado { posicao });
+                }
+                let funcao = matches!(operadores.last(), Some(NaPilha::Funcao { .. }))
+                    && matches!(anterior, Some(TipoToken::Identificador(_)));
+                operadores.push(NaPilha::AbreParenteses {
+                    posicao,
+                    argumentos: funcao.then_some(0),
+                });
+            }
+            TipoToken::Virgula => {
+                if esperando_operando {
+                    return Err(ErroExpressao::OperandoEsperado { posicao });
+                }
+                desempilhar_ate_parenteses(&mut operadores, &mut saida);
+                match operadores.last_mut() {
+                    Some(NaPilha::AbreParenteses {
+                        argumentos: Some(n),
+                        ..
+                    }) => *n += 1,
+                    _ => return Err(ErroExpressao::VirgulaInesperada { posicao }),
+                }
+                esperando_operando = true;
+            }
+            TipoToken::FechaParenteses => {
+                // "F()" é a única forma aceita de fechar sem operando
+                let vazio = matches!(anterior, Some(TipoToken::AbreParenteses));
+                if esperando_operando && !vazio {
+                    return Err(ErroExpressao::OperandoEsperado { posicao });
+                }
+                if !desempilhar_ate_parenteses(&mut operadores, &mut saida) {
+                    return Err(ErroExpressao::ParentesesDesbalanceados { posicao });
+                }
+                match operadores.pop() {
+                    Some(NaPilha::AbreParenteses {
+                        argumentos: Some(n),
+                        ..
+                    }) => {
+                        let aridade = if vazio { 0 } else { n + 1 };
+                        if let Some(NaPilha::Funcao { nome, posicao }) = operadores.pop() {
+                            saida.push(ItemRpn::Funcao {
+                                nome,
+                                aridade,
+                                posicao,
+                            });
+                        }
+                    }
+                    Some(NaPilha::AbreParenteses { posicao, .. }) if vazio => {
+                        return Err(ErroExpressao::OperandoEsperado {
+                            posicao: posicao + 1,
+                        });
+                    }
+                    _ => {}
+                }
+                esperando_operando = false;
+            }
+            TipoToken::Negacao => {
+                operadores.push(NaPilha::Negacao { posicao });
+            }
+            TipoToken::Operador(op) => {
+                if esperando_operando {
+                    return Err(ErroExpressao::OperandoEsperado { posicao });
+                }
+                let direita = *op == '^';
+                while let Some(p) = operadores.last().and_then(NaPilha::precedencia) {
+                    let q = precedencia(*op);
+                    if p > q || (p == q && !direita) {
+                        saida.push(operadores.pop().unwrap().para_rpn());
+                    } else {
+                        break;
+                    }
+                }
+                operadores.push(NaPilha::Operador { op: *op, posicao });
+                esperando_operando = true;
+            }
+        }
+        anterior = Some(&token.tipo);
+    }
+
+    if esperando_operando {
+        let posicao = tokens.last().map_or(0, |t| t.posicao + 1);
+        return Err(ErroExpressao::OperandoEsperado { posicao });
+    }
+    // Esvazia a pilha de operadores
+    while let Some(topo) = operadores.pop() {
+        if let NaPilha::AbreParenteses { posicao, .. } = topo {
+            return Err(ErroExpressao::ParentesesDesbalanceados { posicao });
+        }
+        saida.push(topo.para_rpn());
+    }
+    Ok(saida)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexico::tokenizar;
+
+    fn rpn(infixa: &str) -> Result<String, ErroExpressao> {
+        let itens = para_rpn(&tokenizar(infixa)?)?;
+        Ok(itens
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(" "))
+    }
+
+    #[test]
+    fn funcoes_e_argumentos() {
+        assert_eq!(rpn("MAX(a, b*2, c)").unwrap(), "a b 2 * c MAX:3");
+        assert_eq!(rpn("SIN(x)^2").unwrap(), "x SIN 2 ^");
+        assert_eq!(rpn("PI()").unwrap(), "PI:0");
+        assert_eq!(rpn("-x^2 * -(y)").unwrap(), "x 2 ^ ~ y ~ *");
+    }
+
+    #[test]
+    fn erros_de_sintaxe() {
+        assert_eq!(
+            rpn("3++4"),
+            Err(ErroExpressao::OperandoEsperado { posicao: 2 })
+        );
+        assert_eq!(
+            rpn("3 4"),
+            Err(ErroExpressao::OperadorEsperado { posicao: 2 })
+        );
+        assert_eq!(
+            rpn("(3+4"),
+            Err(ErroExpressao::ParentesesDesbalanceados { posicao: 0 })
+        );
+        assert_eq!(
+            rpn("3+4)"),
+            Err(ErroExpressao::ParentesesDesbalanceados { posicao: 3 })
+        );
+        assert_eq!(
+            rpn("(1, 2)"),
+            Err(ErroExpressao::VirgulaInesperada { posicao: 2 })
+        );
+        assert_eq!(
+            rpn("()"),
+            Err(ErroExpressao::OperandoEsperado { posicao: 1 })
+        );
+        assert_eq!(
+            rpn("2*"),
+            Err(ErroExpressao::OperandoEsperado { posicao: 2 })
+        );
+        assert_eq!(rpn(""), Err(ErroExpressao::OperandoEsperado { posicao: 0 }));
+    }
+}